@@ -0,0 +1,13 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use uni_stg::NamingRules;
+
+fuzz_target!(|name: &str| {
+    for rules in [NamingRules::AwsS3, NamingRules::GoogleCloud] {
+        if rules.problems(name).is_empty() {
+            assert!((3..=222).contains(&name.len()));
+            assert!(name.is_ascii());
+        }
+    }
+});
@@ -0,0 +1,9 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|key: &str| {
+    let encoded = uni_stg::encode_key(key);
+    assert!(encoded.is_ascii());
+    assert!(encoded.len() >= key.len());
+});
@@ -0,0 +1,8 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|range: (Option<u64>, Option<u64>)| {
+    let header = uni_stg::range_header(range.0, range.1);
+    assert_eq!(header.is_none(), range.0.is_none() && range.1.is_none());
+});
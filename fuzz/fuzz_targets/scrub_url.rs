@@ -0,0 +1,10 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use uni_stg::scrub_url;
+
+fuzz_target!(|url: &str| {
+    let scrubbed = scrub_url(url);
+    assert_eq!(scrub_url(&scrubbed), scrubbed);
+    assert_eq!(scrubbed.split('&').count(), url.split('&').count());
+});
@@ -0,0 +1,11 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use uni_stg::StorageUri;
+
+fuzz_target!(|uri: &str| {
+    if let Ok(parsed) = StorageUri::parse(uri) {
+        assert!(uri.len() >= parsed.scheme.len() + 3 + parsed.bucket.len() + parsed.key.len());
+        assert!(!parsed.bucket.contains('/'));
+    }
+});
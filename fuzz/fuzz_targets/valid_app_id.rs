@@ -0,0 +1,10 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|app_id: &str| {
+    if uni_stg::valid_app_id(app_id) {
+        assert!(app_id.len() <= 50);
+        assert!(!app_id.contains(char::is_whitespace));
+    }
+});
@@ -0,0 +1,22 @@
+use std::time::SystemTime;
+
+/// Retrieval speed (and price) of a restore, Deep Archive does not support Expedited
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum RetrievalTier {
+    /// Minutes (Glacier Flexible Retrieval only)
+    Expedited,
+    /// Hours (up to 12 for Deep Archive)
+    Standard,
+    /// Cheapest, up to 48 hours for Deep Archive
+    Bulk,
+}
+
+/// State of the temporary copy of an archived object
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RestoreStatus {
+    /// No restore was requested (or the restored copy expired)
+    NotRequested,
+    InProgress,
+    /// The restored copy can be downloaded until it expires
+    Restored { expires: Option<SystemTime> },
+}
@@ -1,21 +1,98 @@
+use std::collections::HashMap;
+use std::pin::pin;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use aws_config::{BehaviorVersion, SdkConfig};
+use aws_config::profile::ProfileFileCredentialsProvider;
+use aws_runtime::env_config::file::{EnvConfigFileKind, EnvConfigFiles};
+use aws_credential_types::provider::error::CredentialsError;
+use aws_credential_types::provider::{ProvideCredentials, SharedCredentialsProvider};
 use aws_sdk_s3::{Client, Config};
-use aws_sdk_s3::config::http::HttpResponse;
-use aws_sdk_s3::error::SdkError;
-use aws_sdk_s3::operation::copy_object::{CopyObjectError, CopyObjectOutput};
+use aws_sdk_s3::config::{AppName, Region};
+use aws_sdk_s3::config::retry::{ClassifyRetry, RetryAction, RetryConfig};
+use aws_sdk_s3::config::interceptors::InterceptorContext;
+use aws_smithy_types::retry::ErrorKind as RetryKind;
+use aws_sdk_s3::config::timeout::TimeoutConfig;
+use aws_sdk_s3::primitives::{DateTime, DateTimeFormat};
+use aws_sdk_s3::config::http::{HttpRequest, HttpResponse};
+use aws_sdk_s3::error::{ProvideErrorMetadata, SdkError};
+use aws_sdk_s3::operation::abort_multipart_upload::AbortMultipartUploadError;
+use aws_sdk_s3::operation::complete_multipart_upload::CompleteMultipartUploadError;
+use aws_sdk_s3::operation::copy_object::CopyObjectError;
 use aws_sdk_s3::operation::create_bucket::CreateBucketError;
+use aws_sdk_s3::operation::create_multipart_upload::CreateMultipartUploadError;
 use aws_sdk_s3::operation::delete_bucket::{DeleteBucketError};
 use aws_sdk_s3::operation::delete_object::DeleteObjectError;
+use aws_sdk_s3::operation::delete_objects::DeleteObjectsError;
+use aws_sdk_s3::operation::get_bucket_cors::GetBucketCorsError;
+use aws_sdk_s3::operation::get_bucket_encryption::GetBucketEncryptionError;
+use aws_sdk_s3::operation::get_object_acl::{GetObjectAclError, GetObjectAclOutput};
+use aws_sdk_s3::operation::get_object_tagging::GetObjectTaggingError;
+use aws_sdk_s3::operation::get_bucket_lifecycle_configuration::GetBucketLifecycleConfigurationError;
+use aws_sdk_s3::operation::put_bucket_lifecycle_configuration::PutBucketLifecycleConfigurationError;
+use aws_sdk_s3::operation::delete_bucket_lifecycle::DeleteBucketLifecycleError;
+use aws_sdk_s3::operation::get_bucket_policy::GetBucketPolicyError;
+use aws_sdk_s3::operation::put_bucket_policy::PutBucketPolicyError;
+use aws_sdk_s3::operation::delete_bucket_policy::DeleteBucketPolicyError;
+use aws_sdk_s3::operation::put_object_retention::PutObjectRetentionError;
+use aws_sdk_s3::operation::put_object_legal_hold::PutObjectLegalHoldError;
+use aws_sdk_s3::operation::get_bucket_tagging::GetBucketTaggingError;
+use aws_sdk_s3::operation::put_bucket_tagging::PutBucketTaggingError;
+use aws_sdk_s3::operation::delete_bucket_tagging::DeleteBucketTaggingError;
 use aws_sdk_s3::operation::get_bucket_location::GetBucketLocationError;
-use aws_sdk_s3::operation::get_object::{GetObjectError, GetObjectOutput};
+use aws_sdk_s3::operation::get_bucket_versioning::GetBucketVersioningError;
+use aws_sdk_s3::operation::get_object::GetObjectError;
+use aws_sdk_s3::operation::head_bucket::HeadBucketError;
+use aws_sdk_s3::operation::head_object::{HeadObjectError, HeadObjectOutput};
 use aws_sdk_s3::operation::list_buckets::ListBucketsError;
+use aws_sdk_s3::operation::list_object_versions::ListObjectVersionsError;
+use aws_sdk_s3::operation::list_objects_v2::ListObjectsV2Error;
 use aws_sdk_s3::operation::put_object::{PutObjectError, PutObjectOutput};
-use aws_sdk_s3::types::Bucket;
-use crate::{ClientBucket, ClientError, ClientInterface, ClientObject, EmptyReqRes, ReqRes};
+use aws_sdk_s3::operation::put_object::builders::PutObjectFluentBuilder;
+use aws_sdk_s3::operation::put_object_acl::PutObjectAclError;
+use aws_sdk_s3::operation::put_object_tagging::PutObjectTaggingError;
+use aws_sdk_s3::operation::restore_object::RestoreObjectError;
+use aws_sdk_s3::operation::upload_part::UploadPartError;
+use aws_sdk_s3::operation::upload_part_copy::UploadPartCopyError;
+use aws_sdk_s3::error::{BoxError, BuildError};
+use aws_sdk_s3::config::interceptors::{BeforeTransmitInterceptorContextMut, FinalizerInterceptorContextMut};
+use aws_sdk_s3::config::{ConfigBag, Intercept, RuntimeComponents};
+use aws_sdk_s3::presigning::{PresigningConfig, PresigningConfigError};
+use aws_sdk_s3::types::{AccessControlPolicy, Bucket, BucketVersioningStatus, ChecksumAlgorithm, ChecksumMode, CompletedMultipartUpload, CompletedPart, Delete, ExpirationStatus, Grant, MetadataDirective, ObjectCannedAcl, GlacierJobParameters, ObjectIdentifier, ObjectLockLegalHoldStatus, Permission, RestoreRequest, StorageClass, Tag, Tagging, Tier, Type};
+use aws_sdk_s3::types::Error as S3Error;
+#[cfg(feature = "cassette")]
+use aws_smithy_runtime_api::client::http::{HttpClient, HttpConnector, HttpConnectorFuture, HttpConnectorSettings, SharedHttpClient, SharedHttpConnector};
+use aws_smithy_runtime_api::client::orchestrator::OrchestratorError;
+use aws_smithy_runtime_api::client::result::ConnectorError;
+use aws_smithy_runtime_api::client::retries::RequestAttempts;
+#[cfg(feature = "cassette")]
+use aws_smithy_types::body::SdkBody;
+#[cfg(feature = "cassette")]
+use aws_smithy_types::byte_stream::ByteStream;
+#[cfg(feature = "cassette")]
+use crate::{Cassette, CassetteMode, Interaction};
+use aws_sdk_s3::types::Grantee as S3Grantee;
+use aws_sdk_s3::types::Object as S3Object;
+use aws_sdk_s3::types::LifecycleRule as S3LifecycleRule;
+use aws_sdk_s3::types::{AbortIncompleteMultipartUpload, BucketLifecycleConfiguration, LifecycleExpiration, LifecycleRuleFilter, Transition};
+use aws_sdk_s3::types::{ObjectLockLegalHold, ObjectLockMode, ObjectLockRetention, ObjectLockRetentionMode};
+use aws_sdk_s3::types::CorsRule as S3CorsRule;
+use bytes::Bytes;
+use futures::{stream, Stream, StreamExt, TryStreamExt};
+use hmac::Mac;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use crate::checksum::{confirm, verify_crc32c, Checksums};
+use crate::defaults::parse_retry_after;
+use crate::guess_content_type;
+use crate::{encoding, post};
+use crate::{encode_key, valid_app_id, range_header, AccessControl, AclGrant, AclPermission, BucketNameProblem, BucketPolicy, BucketSpec, CopyOptions, ObjectLock, Retention, RetentionMode, CheckpointPart, ClientBucket, ClientError, ClientInterface, ClientObject, Context, CorsRule, Cursor, DownloadOptions, EmptyReqRes, ErrorContext, Grantee, LifecycleAction, LifecycleRule, ListPage, MetadataChanges, NamingRules, ObjectAcl, ObjectVersion, PostConstraints, PresignedPost, ReqRes, ComplianceRecord, CopyProgress, Defaults, RestoreStatus, RetrievalTier, SignedUrlMethod, SignedUrlOptions, CONCURRENCY, UploadCheckpoint, UploadOptions, WritePrecondition, DEFAULT_LEASE};
 
 macro_rules! aws_error_enum_and_impls {
     (
         $enum_name:ident, $client_error_variant:ident, $base_error:ident, $response_ty:ty, {
             $($variant:ident => $error_ty:ty),* $(,)?
+        }, {
+            $($extra_variant:ident => $extra_ty:ty : $extra_code:path),* $(,)?
         }
     ) => {
         // Define the AWSError enum
@@ -24,12 +101,71 @@ macro_rules! aws_error_enum_and_impls {
             $(
                 $variant($error_ty),
             )*
+            $(
+                $extra_variant($extra_ty),
+            )*
+        }
+
+        impl $enum_name {
+            /// Error code reported by the service (example: 'NoSuchKey')
+            pub fn code(&self) -> Option<&str> {
+                match self {
+                    $(
+                        $enum_name::$variant(e) => e.code(),
+                    )*
+                    $(
+                        $enum_name::$extra_variant(e) => $extra_code(e),
+                    )*
+                }
+            }
+        }
+
+        impl std::fmt::Display for $enum_name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                match self {
+                    $(
+                        $enum_name::$variant(e) => std::fmt::Display::fmt(e, f),
+                    )*
+                    $(
+                        $enum_name::$extra_variant(e) => write!(f, "{e:?}"),
+                    )*
+                }
+            }
+        }
+
+        impl std::error::Error for $enum_name {
+            fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+                match self {
+                    $(
+                        $enum_name::$variant(e) => Some(e),
+                    )*
+                    $enum_name::TrnErr(e) => Some(e.source.as_ref()),
+                    _ => None
+                }
+            }
         }
 
+        $(
+            impl From<$extra_ty> for $base_error {
+                fn from(value: $extra_ty) -> Self {
+                    $base_error::$client_error_variant(Box::new($enum_name::$extra_variant(value)))
+                }
+            }
+        )*
+
         $(
             impl From<SdkError<$error_ty, $response_ty>> for $base_error {
                 fn from(value: SdkError<$error_ty, $response_ty>) -> Self {
-                    $base_error::$client_error_variant($enum_name::$variant(value.into_service_error()))
+                    let attempts = attempts_of(&value);
+                    let timeout = match &value {
+                        SdkError::TimeoutError(_) => Some(true),
+                        SdkError::DispatchFailure(e) => Some(e.is_timeout()),
+                        _ => None
+                    };
+                    match timeout {
+                        Some(timeout) => $base_error::from(TransportError { timeout, source: Box::new(value) }),
+                        None => $base_error::$client_error_variant(Box::new($enum_name::$variant(value.into_service_error())))
+                    }.attempted(attempts)
                 }
             }
         )*
@@ -50,9 +186,105 @@ aws_error_enum_and_impls!(
         CreObjErr => CreateBucketError,
         PutObjErr => PutObjectError,
         LstBucErr => ListBucketsError,
+        GetVerErr => GetBucketVersioningError,
+        GetLcyErr => GetBucketLifecycleConfigurationError,
+        GetCorErr => GetBucketCorsError,
+        CreMulErr => CreateMultipartUploadError,
+        UplPrtErr => UploadPartError,
+        CmpMulErr => CompleteMultipartUploadError,
+        AbtMulErr => AbortMultipartUploadError,
+        HeaObjErr => HeadObjectError,
+        HeaBucErr => HeadBucketError,
+        DelObsErr => DeleteObjectsError,
+        LstObjErr => ListObjectsV2Error,
+        LstVerErr => ListObjectVersionsError,
+        GetAclErr => GetObjectAclError,
+        GetEncErr => GetBucketEncryptionError,
+        UplCpyErr => UploadPartCopyError,
+        RstObjErr => RestoreObjectError,
+        GetTagErr => GetObjectTaggingError,
+        PutAclErr => PutObjectAclError,
+        PutTagErr => PutObjectTaggingError,
+        PutLcyErr => PutBucketLifecycleConfigurationError,
+        DelLcyErr => DeleteBucketLifecycleError,
+        GetPolErr => GetBucketPolicyError,
+        PutPolErr => PutBucketPolicyError,
+        DelPolErr => DeleteBucketPolicyError,
+        PutRetErr => PutObjectRetentionError,
+        PutHldErr => PutObjectLegalHoldError,
+        GetBTgErr => GetBucketTaggingError,
+        PutBTgErr => PutBucketTaggingError,
+        DelBTgErr => DeleteBucketTaggingError,
+    },
+    {
+        // Errors which are not returned by a request
+        BuildErr => BuildError : no_code,
+        DelKeyErr => S3Error : S3Error::code,
+        PreCfgErr => PresigningConfigError : no_code,
+        CredErr => CredentialsError : no_code,
+        TrnErr => TransportError : no_code,
     }
 );
 
+/// A request which got no response, because it timed out or could not be sent
+#[derive(Debug)]
+pub struct TransportError {
+    pub timeout: bool,
+    pub source: BoxError
+}
+
+fn no_code<T>(_: &T) -> Option<&str> {
+    None
+}
+
+/// Maximum number of keys per DeleteObjects request
+const DELETE_BATCH_SIZE: usize = 1000;
+
+impl AWSError {
+    /// Whether the object or bucket does not exist
+    pub fn is_not_found(&self) -> bool {
+        matches!(self.code(), Some("NotFound" | "NoSuchKey" | "NoSuchBucket"))
+    }
+
+    /// Whether the credentials lack the permission for the request
+    pub fn is_permission_denied(&self) -> bool {
+        matches!(self.code(), Some("AccessDenied" | "AllAccessDisabled" | "Forbidden"))
+    }
+
+    pub fn is_throttled(&self) -> bool {
+        matches!(self.code(), Some("SlowDown" | "Throttling" | "ThrottlingException" | "RequestLimitExceeded" | "TooManyRequests"))
+    }
+
+    /// Whether the request did not complete in time
+    pub fn is_timeout(&self) -> bool {
+        match self {
+            AWSError::TrnErr(e) => e.timeout,
+            _ => matches!(self.code(), Some("RequestTimeout"))
+        }
+    }
+
+    /// Whether the request could not be sent (connection or transport failure)
+    pub fn is_network(&self) -> bool {
+        matches!(self, AWSError::TrnErr(e) if !e.timeout)
+    }
+
+    /// Whether AWS failed to handle the request
+    pub fn is_server_error(&self) -> bool {
+        matches!(self.code(), Some("InternalError" | "ServiceUnavailable" | "InternalFailure"))
+    }
+
+    /// Whether the bucket exists already (owned by anyone)
+    pub fn is_already_exists(&self) -> bool {
+        matches!(self.code(), Some("BucketAlreadyExists" | "BucketAlreadyOwnedByYou"))
+    }
+
+    /// Whether a condition (example: If-Match) of the request does not hold
+    /// Conflicting concurrent conditional writes count as well
+    pub fn is_precondition_failed(&self) -> bool {
+        matches!(self.code(), Some("PreconditionFailed" | "ConditionalRequestConflict"))
+    }
+}
+
 pub struct AWSBucket {
     bucket_name: String,
     location: Option<String>
@@ -74,36 +306,768 @@ impl ClientBucket for AWSBucket {
 
 impl From<Bucket> for AWSBucket {
     fn from(value: Bucket) -> Self {
-        AWSBucket {bucket_name: value.name.clone().unwrap(), location: value.bucket_region}
+        AWSBucket {bucket_name: value.name.unwrap_or_default(), location: value.bucket_region}
+    }
+}
+
+impl From<S3CorsRule> for CorsRule {
+    fn from(value: S3CorsRule) -> Self {
+        CorsRule {
+            origins: value.allowed_origins,
+            methods: value.allowed_methods,
+            response_headers: value.expose_headers.unwrap_or_default(),
+            max_age_seconds: value.max_age_seconds
+        }
     }
 }
 
+impl From<RetrievalTier> for Tier {
+    fn from(value: RetrievalTier) -> Self {
+        match value {
+            RetrievalTier::Expedited => Tier::Expedited,
+            RetrievalTier::Standard => Tier::Standard,
+            RetrievalTier::Bulk => Tier::Bulk,
+        }
+    }
+}
+
+impl From<ObjectAcl> for ObjectCannedAcl {
+    fn from(value: ObjectAcl) -> Self {
+        match value {
+            ObjectAcl::Private => ObjectCannedAcl::Private,
+            ObjectAcl::PublicRead => ObjectCannedAcl::PublicRead,
+            ObjectAcl::AuthenticatedRead => ObjectCannedAcl::AuthenticatedRead,
+            ObjectAcl::BucketOwnerRead => ObjectCannedAcl::BucketOwnerRead,
+            ObjectAcl::BucketOwnerFullControl => ObjectCannedAcl::BucketOwnerFullControl,
+        }
+    }
+}
+
+/// Grants with permissions other than READ and FULL_CONTROL are left out
+fn grant_from(grant: Grant) -> Option<AclGrant> {
+    let permission = match grant.permission? {
+        Permission::Read => AclPermission::Read,
+        Permission::FullControl => AclPermission::FullControl,
+        _ => return None
+    };
+    let grantee = grant.grantee?;
+    let grantee = match (grantee.r#type, grantee.uri, grantee.id, grantee.email_address) {
+        (Type::Group, Some(uri), _, _) if uri.ends_with("/global/AllUsers") => Grantee::AllUsers,
+        (Type::Group, Some(uri), _, _) if uri.ends_with("/global/AuthenticatedUsers") => Grantee::AuthenticatedUsers,
+        (Type::Group, Some(uri), _, _) => Grantee::Id(uri),
+        (Type::AmazonCustomerByEmail, _, _, Some(email)) => Grantee::Email(email),
+        (_, _, Some(id), _) => Grantee::Id(id),
+        _ => return None
+    };
+    Some(AclGrant { grantee, permission })
+}
+
+/// IDs starting with 'http' are group URIs, others canonical user IDs
+fn grant_to(grant: AclGrant) -> Result<Grant, BuildError> {
+    let grantee = match grant.grantee {
+        Grantee::AllUsers => S3Grantee::builder().r#type(Type::Group).uri("http://acs.amazonaws.com/groups/global/AllUsers"),
+        Grantee::AuthenticatedUsers => S3Grantee::builder().r#type(Type::Group).uri("http://acs.amazonaws.com/groups/global/AuthenticatedUsers"),
+        Grantee::Email(email) => S3Grantee::builder().r#type(Type::AmazonCustomerByEmail).email_address(email),
+        Grantee::Id(uri) if uri.starts_with("http") => S3Grantee::builder().r#type(Type::Group).uri(uri),
+        Grantee::Id(id) => S3Grantee::builder().r#type(Type::CanonicalUser).id(id),
+    }.build()?;
+    let permission = match grant.permission {
+        AclPermission::Read => Permission::Read,
+        AclPermission::FullControl => Permission::FullControl,
+    };
+    Ok(Grant::builder().grantee(grantee).permission(permission).build())
+}
+
+/// Grant headers which recreate the ACL of an object in its copy, copies are private otherwise
+#[derive(Clone, Default)]
+struct CopiedGrants {
+    full_control: Option<String>,
+    read: Option<String>,
+    read_acp: Option<String>,
+    write_acp: Option<String>,
+}
+
+impl CopiedGrants {
+    /// Empty if only the owner has access, buckets with ACLs disabled reject grant headers
+    fn of(acl: GetObjectAclOutput) -> Self {
+        let owner = acl.owner.and_then(|t| t.id);
+        let grants = acl.grants.unwrap_or_default();
+        let mut copied = Self::default();
+        if grants.iter().all(|t| t.permission == Some(Permission::FullControl) && t.grantee.as_ref().and_then(|t| t.id.as_ref()) == owner.as_ref()) {
+            return copied
+        }
+        for grant in grants {
+            let Some(grantee) = grant.grantee else { continue };
+            let grantee = match (grantee.r#type, grantee.uri, grantee.id, grantee.email_address) {
+                (Type::Group, Some(uri), _, _) => format!("uri=\"{uri}\""),
+                (Type::AmazonCustomerByEmail, _, _, Some(email)) => format!("emailAddress=\"{email}\""),
+                (_, _, Some(id), _) => format!("id=\"{id}\""),
+                _ => continue
+            };
+            let header = match grant.permission {
+                Some(Permission::FullControl) => &mut copied.full_control,
+                Some(Permission::Read) => &mut copied.read,
+                Some(Permission::ReadAcp) => &mut copied.read_acp,
+                Some(Permission::WriteAcp) => &mut copied.write_acp,
+                _ => continue
+            };
+            *header = Some(match header.take() {
+                Some(others) => format!("{others}, {grantee}"),
+                None => grantee
+            });
+        }
+        copied
+    }
+}
+
+/// S3 lifecycle rules may bundle expiration, transitions and multipart aborts, these are split into separate rules
+fn lifecycle_rules_from(rule: S3LifecycleRule) -> Vec<LifecycleRule> {
+    if rule.status != ExpirationStatus::Enabled {
+        return vec![]
+    }
+    let prefix = rule.filter.as_ref().and_then(|t| t.prefix.clone());
+    let mut rules = vec![];
+    if let Some(days) = rule.expiration.as_ref().and_then(|t| t.days) {
+        rules.push(LifecycleRule {prefix: prefix.clone(), age_days: Some(days as u32), action: LifecycleAction::Delete});
+    }
+    for transition in rule.transitions.unwrap_or_default() {
+        let storage_class = transition.storage_class.map(|t| t.as_str().to_string()).unwrap_or_default();
+        rules.push(LifecycleRule {prefix: prefix.clone(), age_days: transition.days.map(|t| t as u32), action: LifecycleAction::SetStorageClass(storage_class)});
+    }
+    if let Some(abort) = rule.abort_incomplete_multipart_upload {
+        rules.push(LifecycleRule {prefix, age_days: abort.days_after_initiation.map(|t| t as u32), action: LifecycleAction::AbortIncompleteMultipart});
+    }
+    rules
+}
+
+/// Every rule becomes a separate S3 rule (identified by its position), rules without an age apply after 0 days
+fn lifecycle_rule_to(index: usize, rule: LifecycleRule) -> Result<S3LifecycleRule, BuildError> {
+    let days = rule.age_days.unwrap_or(0) as i32;
+    let builder = S3LifecycleRule::builder().id(format!("uni-stg-{index}")).status(ExpirationStatus::Enabled)
+        .filter(LifecycleRuleFilter::builder().prefix(rule.prefix.unwrap_or_default()).build());
+    match rule.action {
+        LifecycleAction::Delete => builder.expiration(LifecycleExpiration::builder().days(days).build()),
+        LifecycleAction::SetStorageClass(storage_class) => builder.transitions(Transition::builder().days(days).storage_class(storage_class.as_str().into()).build()),
+        LifecycleAction::AbortIncompleteMultipart => builder.abort_incomplete_multipart_upload(AbortIncompleteMultipartUpload::builder().days_after_initiation(days).build()),
+    }.build()
+}
+
+/// Minimum size of all but the last part of a multipart upload
+const MIN_PART_SIZE: u64 = 5 * 1024 * 1024;
+/// Largest object which can be copied with a single request
+const MAX_COPY_SIZE: u64 = 5 * 1024 * 1024 * 1024;
+/// Size of the parts used when copying larger objects (grown if the object would need more than MAX_PARTS parts)
+const COPY_PART_SIZE: u64 = 512 * 1024 * 1024;
+const MAX_PARTS: u64 = 10_000;
+
+/// Virtual-hosted URL of a bucket, path-style for bucket names with dots (which do not match the TLS certificate)
+fn bucket_url(bucket_name: &str, region: &str) -> String {
+    if bucket_name.contains('.') {
+        return format!("https://s3.{region}.amazonaws.com/{bucket_name}")
+    }
+    format!("https://{bucket_name}.s3.{region}.amazonaws.com")
+}
+
+fn region_of(client: &Client) -> String {
+    client.config().region().map(|t| t.to_string()).unwrap_or("us-east-1".to_string())
+}
+
+fn hmac_sha256(key: &[u8], data: &str) -> Vec<u8> {
+    encoding::mac(key, data.as_bytes()).finalize().into_bytes().to_vec()
+}
+
+/// Adds headers to a request before it is presigned, so they are part of the signature
+fn signed_headers(headers: HashMap<String, String>) -> impl Fn(&mut HttpRequest) + Send + Sync + 'static {
+    move |req| for (name, value) in &headers {
+        let _ = req.headers_mut().try_insert(name.clone(), value.clone());
+    }
+}
+
+/// Whether a request was sent to the wrong region
+fn redirected(e: &ClientError) -> bool {
+    matches!(e.inner(), ClientError::AWSClient(e) if matches!(e.code(), Some("PermanentRedirect" | "AuthorizationHeaderMalformed")))
+}
+
+/// The strongest of the additional checksums S3 reports, prefixed with its algorithm
+fn checksum_of(sha256: &Option<String>, sha1: &Option<String>, crc32c: &Option<String>, crc32: &Option<String>) -> Option<String> {
+    [("sha256", sha256), ("sha1", sha1), ("crc32c", crc32c), ("crc32", crc32)].into_iter()
+        .find_map(|(algorithm, value)| value.as_ref().map(|t| format!("{algorithm}:{t}")))
+}
+
+fn system_time(value: Option<DateTime>) -> SystemTime {
+    value.and_then(|t| SystemTime::try_from(t).ok()).unwrap_or(UNIX_EPOCH)
+}
+
+/// Source and destination of a server-side copy
+#[derive(Clone)]
+struct CopyRoute {
+    src_bucket: String,
+    src_object: String,
+    dest_bucket: String,
+    dest_object: String,
+}
+
+/// The object to copy
+#[derive(Clone)]
+struct CopySource {
+    head: HeadObjectOutput,
+    grants: CopiedGrants,
+}
+
+/// A multipart copy which was started
+struct MultipartCopy {
+    client: Client,
+    copy_source: String,
+    bucket: String,
+    key: String,
+    upload_id: String,
+}
+
+impl MultipartCopy {
+    /// Copies the `index`th part, returns it with its size
+    async fn part(&self, index: u64, part_size: u64, size: u64) -> ReqRes<(CompletedPart, u64)> {
+        let start = index * part_size;
+        let end = (start + part_size).min(size) - 1;
+        let e_tag = self.client.upload_part_copy().bucket(&self.bucket).key(&self.key).upload_id(&self.upload_id)
+            .part_number(index as i32 + 1).copy_source(&self.copy_source).copy_source_range(format!("bytes={start}-{end}"))
+            .send().await.context("copy_object", &self.bucket, Some(&self.key))?
+            .copy_part_result.and_then(|t| t.e_tag);
+        Ok((CompletedPart::builder().set_e_tag(e_tag).part_number(index as i32 + 1).build(), end - start + 1))
+    }
+
+    async fn complete(&self, parts: Vec<CompletedPart>) -> EmptyReqRes {
+        self.client.complete_multipart_upload().bucket(&self.bucket).key(&self.key).upload_id(&self.upload_id)
+            .multipart_upload(CompletedMultipartUpload::builder().set_parts(Some(parts)).build()).send().await
+            .context("copy_object", &self.bucket, Some(&self.key))?;
+        Ok(())
+    }
+
+    /// Best effort, the original error is more useful to the caller
+    async fn abort(&self) {
+        let _ = self.client.abort_multipart_upload().bucket(&self.bucket).key(&self.key).upload_id(&self.upload_id).send().await;
+    }
+}
+
+/// Server-side copy with `client`, using part copies if the object is too large for a single request
+/// The headers and metadata of `source` are given to the multipart upload as part copies do not carry them over, its grants are given to either
+/// Yields the progress after every copied part, the last item reports the finished copy
+fn copy_parts(client: Client, route: CopyRoute, source: CopySource, storage_class: Option<StorageClass>) -> impl Stream<Item = ReqRes<CopyProgress>> {
+    let CopyRoute { src_bucket, src_object, dest_bucket, dest_object } = route;
+    let CopySource { head, grants } = source;
+    let size = head.content_length.unwrap_or(0) as u64;
+    let copy_source = format!("{src_bucket}/{}", encode_key(&src_object));
+    stream::once(async move {
+        if size <= MAX_COPY_SIZE {
+            client.copy_object().bucket(&dest_bucket).key(&dest_object).copy_source(&copy_source).set_storage_class(storage_class)
+                .set_grant_full_control(grants.full_control).set_grant_read(grants.read)
+                .set_grant_read_acp(grants.read_acp).set_grant_write_acp(grants.write_acp)
+                .send().await.context("copy_object", &dest_bucket, Some(&dest_object))?;
+            return Ok(stream::iter([Ok(CopyProgress::finished(size))]).left_stream())
+        }
+        let part_size = COPY_PART_SIZE.max(size.div_ceil(MAX_PARTS));
+        let upload_id = client.create_multipart_upload().bucket(&dest_bucket).key(&dest_object)
+            .set_metadata(head.metadata)
+            .set_content_type(head.content_type)
+            .set_cache_control(head.cache_control)
+            .set_content_encoding(head.content_encoding)
+            .set_content_disposition(head.content_disposition)
+            .set_content_language(head.content_language)
+            .set_storage_class(storage_class)
+            .set_grant_full_control(grants.full_control).set_grant_read(grants.read)
+            .set_grant_read_acp(grants.read_acp).set_grant_write_acp(grants.write_acp)
+            .send().await.context("copy_object", &dest_bucket, Some(&dest_object))?.upload_id.unwrap_or_default();
+        let copy = Arc::new(MultipartCopy { client, copy_source, bucket: dest_bucket, key: dest_object, upload_id });
+        let parts = stream::iter(0..size.div_ceil(part_size)).map({
+            let copy = copy.clone();
+            move |index| {
+                let copy = copy.clone();
+                async move { copy.part(index, part_size, size).await }
+            }
+        }).buffered(CONCURRENCY);
+        // None once the copy is finished or failed
+        let progress = stream::unfold(Some((Box::pin(parts), vec![], 0)), move |state| {
+            let copy = copy.clone();
+            async move {
+                let (mut parts, mut completed, copied) = state?;
+                match parts.next().await {
+                    Some(Ok((part, bytes))) => {
+                        completed.push(part);
+                        let progress = CopyProgress { total_bytes: size, bytes_copied: copied + bytes, rewrite_token: None, done: false };
+                        Some((Ok(progress), Some((parts, completed, copied + bytes))))
+                    }
+                    Some(Err(e)) => {
+                        copy.abort().await;
+                        Some((Err(e), None))
+                    }
+                    None => match copy.complete(completed).await {
+                        Ok(()) => Some((Ok(CopyProgress::finished(size)), None)),
+                        Err(e) => {
+                            copy.abort().await;
+                            Some((Err(e), None))
+                        }
+                    }
+                }
+            }
+        });
+        Ok(progress.right_stream())
+    }).flat_map(|t| match t {
+        Ok(progress) => progress.left_stream(),
+        Err(e) => stream::iter([Err(e)]).right_stream()
+    })
+}
+
+/// Reads up to `size` bytes, less are only returned at the end of the reader
+async fn read_chunk(r: &mut (impl AsyncRead + Unpin), size: usize) -> std::io::Result<Vec<u8>> {
+    let mut buf = Vec::with_capacity(size);
+    (&mut *r).take(size as u64).read_to_end(&mut buf).await?;
+    Ok(buf)
+}
+
 pub struct AWSConfig {
-    config: Config
+    config: Config,
+    /// Kept apart from `config`, the S3 config does not expose its credentials provider
+    credentials: Option<SharedCredentialsProvider>
+}
+
+impl AWSConfig {
+    fn from_sdk(sdk: &SdkConfig) -> Self {
+        Self { config: Config::from(sdk), credentials: sdk.credentials_provider() }
+    }
+
+    /// Loads region and credentials from the default provider chain (environment, profile, IMDS, ...)
+    pub async fn standard_auth() -> Self {
+        Self::from_sdk(&aws_config::load_defaults(BehaviorVersion::latest()).await)
+    }
+
+    /// Sends unsigned requests, useful for public buckets (example: public datasets) when no credentials are configured
+    pub async fn anonymous(region: String) -> Self {
+        Self::from_sdk(&aws_config::defaults(BehaviorVersion::latest()).region(Region::new(region)).no_credentials().load().await)
+    }
+
+    /// Same as `anonymous`, but the region is loaded from the default provider chain
+    pub async fn anonymous_default_region() -> Self {
+        Self::from_sdk(&aws_config::defaults(BehaviorVersion::latest()).no_credentials().load().await)
+    }
+
+    /// Signs requests with a profile of an AWS shared credentials file (example: '~/.aws/credentials'), the profile is taken from
+    /// `AWS_PROFILE` ('default' if unset) and the region is loaded from the default provider chain
+    pub async fn from_credentials_file(path: &str) -> Self {
+        let files = EnvConfigFiles::builder().with_file(EnvConfigFileKind::Credentials, path).build();
+        let credentials = ProfileFileCredentialsProvider::builder().profile_files(files).build();
+        Self::from_sdk(&aws_config::defaults(BehaviorVersion::latest()).credentials_provider(credentials).load().await)
+    }
+
+    /// Signs requests with a static access key (example: the root user of a local MinIO)
+    pub async fn from_keys(region: String, access_key_id: &str, secret_access_key: &str) -> Self {
+        let credentials = aws_credential_types::Credentials::new(access_key_id, secret_access_key, None, None, "uni-stg");
+        Self::from_sdk(&aws_config::defaults(BehaviorVersion::latest()).region(Region::new(region)).credentials_provider(credentials).load().await)
+    }
+
+    /// Identifies the application in the user agent of every request (example: 'my-service')
+    /// Overrides `Defaults::user_agent_suffix`
+    pub fn with_app_id(mut self, app_id: &str) -> ReqRes<Self> {
+        let app_name = AppName::new(app_id.to_string()).ok().filter(|_| valid_app_id(app_id))
+            .ok_or_else(|| ClientError::Unsupported("application identifiers are limited to 50 letters, digits and !#$%&'*+-.^_`|~")
+                .with_context(ErrorContext::new("with_app_id", None, None)))?;
+        self.config = self.config.to_builder().app_name(app_name).build();
+        Ok(self)
+    }
+
+    /// Overrides the region of the config (example: 'eu-central-1')
+    pub fn with_region(mut self, region: &str) -> Self {
+        self.config = self.config.to_builder().region(Region::new(region.to_string())).build();
+        self
+    }
+
+    /// Sends requests to an S3 compatible endpoint (example: 'http://localhost:9000'), with path-style addressing
+    pub fn with_endpoint(mut self, endpoint: &str) -> Self {
+        self.config = self.config.to_builder().endpoint_url(endpoint).force_path_style(true).build();
+        self
+    }
+
+    /// Sends `x-amz-request-payer: requester` with every request, so requester-pays buckets (example: public datasets) are accessible
+    /// The transfer and request costs are billed to the account of the credentials
+    pub fn with_requester_pays(mut self) -> Self {
+        self.config = self.config.to_builder().interceptor(RequesterPays).build();
+        self
+    }
+
+    /// Records the requests to `cassette` or replays them from it (see `Cassette`)
+    /// Recording sends the requests with the HTTP client of the config
+    #[cfg(feature = "cassette")]
+    pub fn with_cassette(mut self, cassette: Cassette) -> Self {
+        let inner = self.config.http_client();
+        self.config = self.config.to_builder().http_client(Recorder { cassette, inner }).build();
+        self
+    }
+}
+
+/// HTTP client which records responses to a cassette or answers requests from it (see `AWSConfig::with_cassette`)
+#[cfg(feature = "cassette")]
+#[derive(Debug, Clone)]
+struct Recorder {
+    cassette: Cassette,
+    inner: Option<SharedHttpClient>,
+}
+
+#[cfg(feature = "cassette")]
+impl HttpClient for Recorder {
+    fn http_connector(&self, settings: &HttpConnectorSettings, components: &RuntimeComponents) -> SharedHttpConnector {
+        let inner = self.inner.as_ref().map(|t| t.http_connector(settings, components));
+        SharedHttpConnector::new(RecordingConnector { cassette: self.cassette.clone(), inner })
+    }
+}
+
+#[cfg(feature = "cassette")]
+#[derive(Debug)]
+struct RecordingConnector {
+    cassette: Cassette,
+    inner: Option<SharedHttpConnector>,
+}
+
+#[cfg(feature = "cassette")]
+fn response_of(status: u16, headers: Vec<(String, String)>, body: Bytes) -> Result<HttpResponse, ConnectorError> {
+    let status = status.try_into().map_err(|e| ConnectorError::other(Box::new(e), None))?;
+    let mut response = HttpResponse::new(status, SdkBody::from(body));
+    for (name, value) in headers {
+        let _ = response.headers_mut().try_insert(name, value);
+    }
+    Ok(response)
+}
+
+#[cfg(feature = "cassette")]
+impl HttpConnector for RecordingConnector {
+    fn call(&self, request: HttpRequest) -> HttpConnectorFuture {
+        let cassette = self.cassette.clone();
+        let inner = self.inner.clone();
+        HttpConnectorFuture::new(async move {
+            let method = request.method().to_string();
+            let url = request.uri().to_string();
+            match cassette.mode() {
+                CassetteMode::Replay => {
+                    let interaction = cassette.take(&method, &url)
+                        .ok_or_else(|| ConnectorError::other(format!("no recorded response for {method} {url}").into(), None))?;
+                    let body = interaction.body();
+                    response_of(interaction.status, interaction.headers, body)
+                }
+                CassetteMode::Record => {
+                    let inner = inner.ok_or_else(|| ConnectorError::other("the config has no HTTP client to record with".into(), None))?;
+                    let response = inner.call(request).await?;
+                    let status = response.status().as_u16();
+                    let headers: Vec<(String, String)> = response.headers().iter().map(|(name, value)| (name.to_string(), value.to_string())).collect();
+                    let body = ByteStream::new(response.into_body()).collect().await.map_err(|e| ConnectorError::io(Box::new(e)))?.into_bytes();
+                    cassette.push(Interaction::new(method, &url, status, headers.clone(), &body));
+                    response_of(status, headers, body)
+                }
+            }
+        })
+    }
+}
+
+/// Adds the requester-pays header before requests are signed (see `AWSConfig::with_requester_pays`)
+#[derive(Debug)]
+struct RequesterPays;
+
+impl Intercept for RequesterPays {
+    fn name(&self) -> &'static str {
+        "RequesterPays"
+    }
+
+    fn modify_before_signing(&self, context: &mut BeforeTransmitInterceptorContextMut<'_>, _: &RuntimeComponents, _: &mut ConfigBag) -> Result<(), BoxError> {
+        context.request_mut().headers_mut().insert("x-amz-request-payer", "requester");
+        Ok(())
+    }
+}
+
+/// Lets retries of throttled or unavailable requests wait as long as their Retry-After header asks (up to `RetryPolicy::max_backoff`)
+#[derive(Debug)]
+struct RetryAfter {
+    max_backoff: Duration
+}
+
+impl ClassifyRetry for RetryAfter {
+    fn classify_retry(&self, ctx: &InterceptorContext) -> RetryAction {
+        let Some(response) = ctx.response().filter(|t| matches!(t.status().as_u16(), 429 | 503)) else {
+            return RetryAction::NoActionIndicated
+        };
+        match response.headers().get("retry-after").and_then(parse_retry_after) {
+            Some(delay) => RetryAction::retryable_error_with_explicit_delay(RetryKind::ThrottlingError, delay.min(self.max_backoff)),
+            None => RetryAction::NoActionIndicated
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        "RetryAfter"
+    }
+}
+
+/// Number of attempts of a retried request which failed, added to its last response by `CountAttempts`
+#[derive(Debug, Clone, Copy)]
+struct Attempts(u32);
+
+/// Failure of the last attempt of a retried request which got no response (see `CountAttempts`)
+#[derive(Debug)]
+struct Retried {
+    attempts: u32,
+    source: BoxError
+}
+
+impl std::fmt::Display for Retried {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(&self.source, f)
+    }
+}
+
+impl std::error::Error for Retried {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(self.source.as_ref())
+    }
+}
+
+/// Records the attempts of retried requests which failed, so they reach `ErrorContext::attempt` (see `attempts_of`)
+#[derive(Debug)]
+struct CountAttempts;
+
+impl Intercept for CountAttempts {
+    fn name(&self) -> &'static str {
+        "CountAttempts"
+    }
+
+    fn modify_before_completion(&self, context: &mut FinalizerInterceptorContextMut<'_>, _: &RuntimeComponents, cfg: &mut ConfigBag) -> Result<(), BoxError> {
+        let attempts = cfg.load::<RequestAttempts>().map(|t| t.attempts()).unwrap_or(1);
+        if attempts <= 1 || !matches!(context.output_or_error(), Some(Err(_))) {
+            return Ok(())
+        }
+        if let Some(response) = context.response_mut() {
+            response.add_extension(Attempts(attempts));
+            return Ok(())
+        }
+        // Without a response, the error (which can only be borrowed) is wrapped with the same kind
+        if let Some(Err(e)) = context.output_or_error_mut() {
+            let timeout = e.is_timeout_error();
+            let connector = e.as_connector_error().map(|t| (t.is_timeout(), t.is_io(), t.is_user(), t.as_other()));
+            if !timeout && connector.is_none() {
+                return Ok(())
+            }
+            let source = Box::new(Retried { attempts, source: std::mem::replace(e, OrchestratorError::other("retried")).into() });
+            *e = match connector {
+                None => OrchestratorError::timeout(source),
+                Some((true, ..)) => OrchestratorError::connector(ConnectorError::timeout(source)),
+                Some((_, true, ..)) => OrchestratorError::connector(ConnectorError::io(source)),
+                Some((_, _, true, _)) => OrchestratorError::connector(ConnectorError::user(source)),
+                Some((.., kind)) => OrchestratorError::connector(ConnectorError::other(source, kind))
+            };
+        }
+        Ok(())
+    }
+}
+
+/// Attempts of a failed request, 1 unless `CountAttempts` recorded more
+fn attempts_of<E: std::error::Error + 'static>(error: &SdkError<E, HttpResponse>) -> u32 {
+    if let Some(attempts) = error.raw_response().and_then(|t| t.extension::<Attempts>()) {
+        return attempts.0
+    }
+    let mut source = std::error::Error::source(error);
+    while let Some(e) = source {
+        if let Some(retried) = e.downcast_ref::<Retried>() {
+            return retried.attempts
+        }
+        source = e.source();
+    }
+    1
 }
 
+/// Client for AWS-S3 (and compatible services), it is `Send + Sync` and cheap to clone
+/// Clones share the connection pool, credentials and settings, so one configured client can serve all request handlers.
+#[derive(Clone)]
 pub struct AWSClient {
-    client: Client
+    client: Client,
+    /// Credentials the client signs with, `None` for unsigned requests (used for presigned POST policies)
+    credentials: Option<SharedCredentialsProvider>,
+    /// Size of the parts used when uploading from a reader
+    part_size: usize,
+    /// See `Defaults::strict_checksums`
+    strict_checksums: bool,
+    /// See `Defaults::detect_content_type`
+    detect_content_type: bool
 }
 
+impl AWSClient {
+    /// Server-side copy with `client` (see `copy_parts`)
+    async fn copy_with(&self, client: &Client, route: &CopyRoute, source: &CopySource, storage_class: Option<StorageClass>) -> EmptyReqRes {
+        let mut progress = pin!(copy_parts(client.clone(), route.clone(), source.clone(), storage_class));
+        while let Some(progress) = progress.next().await {
+            progress?;
+        }
+        Ok(())
+    }
+
+    /// Copies within and across buckets (see `copy_object`), with the storage class of the copy if given
+    async fn copy(&self, src_bucket: String, src_object: String, dest_bucket: String, dest_object: String, storage_class: Option<StorageClass>) -> ReqRes<AWSObject> {
+        if storage_class.is_none() && src_bucket == dest_bucket && src_object == dest_object {
+            return Err(ClientError::Unsupported("AWS-S3 cannot copy an object onto itself without changing it")
+                .with_context(ErrorContext::new("copy_object", Some(&src_bucket), Some(&src_object))))
+        }
+        let source = self.source(&src_bucket, &src_object).await?;
+        let route = CopyRoute { src_bucket, src_object, dest_bucket: dest_bucket.clone(), dest_object: dest_object.clone() };
+        let client = match self.copy_with(&self.client, &route, &source, storage_class.clone()).await {
+            Err(e) if redirected(&e) => {
+                let client = self.regional_client(&dest_bucket).await?;
+                self.copy_with(&client, &route, &source, storage_class).await?;
+                client
+            }
+            result => {
+                result?;
+                self.client.clone()
+            }
+        };
+        let object = client.head_object().bucket(&dest_bucket).key(&dest_object).send().await.context("copy_object", &dest_bucket, Some(&dest_object))?;
+        Ok(AWSObject {object, bucket: dest_bucket, key: dest_object})
+    }
+
+    /// Headers and grants of the object to copy, asking the bucket's region if the request was sent to the wrong one
+    async fn source(&self, src_bucket: &str, src_object: &str) -> ReqRes<CopySource> {
+        let (client, head) = match self.client.head_object().bucket(src_bucket).key(src_object).send().await.context("copy_object", src_bucket, Some(src_object)) {
+            Err(e) if redirected(&e) => {
+                let client = self.regional_client(src_bucket).await?;
+                let head = client.head_object().bucket(src_bucket).key(src_object).send().await.context("copy_object", src_bucket, Some(src_object))?;
+                (client, head)
+            }
+            head => (self.client.clone(), head?)
+        };
+        let acl = client.get_object_acl().bucket(src_bucket).key(src_object).send().await.context("copy_object", src_bucket, Some(src_object))?;
+        Ok(CopySource { head, grants: CopiedGrants::of(acl) })
+    }
+
+    /// Client for the region a bucket is located in
+    async fn regional_client(&self, bucket_name: &str) -> ReqRes<Client> {
+        let location = self.client.get_bucket_location().bucket(bucket_name).send().await.context("get_bucket", bucket_name, None)?.location_constraint;
+        // No constraint means us-east-1, 'EU' is the legacy name of eu-west-1
+        let region = match location.as_ref().map(|t| t.as_str()) {
+            None | Some("") => "us-east-1",
+            Some("EU") => "eu-west-1",
+            Some(region) => region,
+        };
+        Ok(Client::from_conf(self.client.config().to_builder().region(Region::new(region.to_string())).build()))
+    }
+
+    /// Inherits the process-wide `Defaults`
+    pub fn new(config: AWSConfig) -> Self {
+        Self::with_defaults(config, Defaults::get())
+    }
+
+    /// Uses `defaults` instead of the process-wide ones
+    /// A user agent suffix which is not a valid AWS app name (see `AppName`) is ignored, as is one of a config with an own app id
+    pub fn with_defaults(config: AWSConfig, defaults: Defaults) -> Self {
+        let retry = RetryConfig::standard().with_max_attempts(defaults.retry.max_attempts.max(1))
+            .with_initial_backoff(defaults.retry.initial_backoff).with_max_backoff(defaults.retry.max_backoff);
+        // The `set_*` methods of the builders work on references
+        let mut timeouts = TimeoutConfig::builder();
+        timeouts.set_connect_timeout(defaults.connect_timeout).set_operation_timeout(defaults.operation_timeout);
+        let config_credentials = config.credentials;
+        let app_name = config.config.app_name().cloned().or_else(|| defaults.user_agent_suffix.and_then(|t| AppName::new(t).ok()));
+        let mut builder = config.config.to_builder().retry_config(retry).retry_classifier(RetryAfter { max_backoff: defaults.retry.max_backoff })
+            .interceptor(CountAttempts).timeout_config(timeouts.build());
+        builder.set_app_name(app_name);
+        let config = builder.build();
+        Self { client: Client::from_conf(config), credentials: config_credentials, part_size: defaults.part_size.max(MIN_PART_SIZE as usize), strict_checksums: defaults.strict_checksums,
+               detect_content_type: defaults.detect_content_type }
+    }
+
+    /// Adds the checksums of `data` to a PUT in strict mode
+    fn checksummed(&self, req: PutObjectFluentBuilder, data: &[u8]) -> (PutObjectFluentBuilder, Option<Checksums>) {
+        if !self.strict_checksums {
+            return (req, None)
+        }
+        let checksums = Checksums::of(data);
+        (req.content_md5(&checksums.md5).checksum_crc32_c(&checksums.crc32c), Some(checksums))
+    }
+
+    /// Content-Type guessed for an upload without one (see `Defaults::detect_content_type`)
+    fn guessed_content_type(&self, key: &str, data: &[u8]) -> Option<String> {
+        self.detect_content_type.then(|| guess_content_type(key, data)).flatten()
+    }
+
+    /// Multipart uploads carry a CRC32C per part in strict mode
+    fn checksum_algorithm(&self) -> Option<ChecksumAlgorithm> {
+        self.strict_checksums.then_some(ChecksumAlgorithm::Crc32C)
+    }
+}
+
+/// Object metadata as returned by a HEAD request
 pub struct AWSObject {
-    object: GetObjectOutput,
-    bucket: String
+    object: HeadObjectOutput,
+    bucket: String,
+    key: String
 }
 
+/// Object as returned by a PUT request, which only reports checksums and the ETag, the rest is taken from the upload
 pub struct AWSObjectPut {
     object: PutObjectOutput,
-    bucket: String
+    bucket: String,
+    key: String,
+    size: u64,
+    /// Options the object was uploaded with, including the guessed content type
+    options: UploadOptions
 }
 
-pub struct AWSObjectCopy {
-    object: CopyObjectOutput,
+/// Object as returned by a listing
+pub struct AWSListedObject {
+    object: S3Object,
     bucket: String
 }
 
+impl ClientObject for AWSListedObject {
+    fn size(&self) -> u64 {
+        self.object.size.map(|t| {t as u64}).unwrap_or(0)
+    }
+
+    fn bucket_name(&self) -> String {
+        self.bucket.clone()
+    }
+
+    fn id(&self) -> String {
+        self.object.e_tag.clone().unwrap_or_default()
+    }
+
+    fn name(&self) -> String {
+        self.object.key.clone().unwrap_or_default()
+    }
+
+    fn content_type(&self) -> Option<String> {
+        None
+    }
+
+    fn last_modified(&self) -> Option<SystemTime> {
+        self.object.last_modified.and_then(|t| SystemTime::try_from(t).ok())
+    }
+
+    fn etag(&self) -> Option<String> {
+        self.object.e_tag.clone()
+    }
+
+    fn storage_class(&self) -> Option<String> {
+        self.object.storage_class.as_ref().map(|t| t.as_str().to_string())
+    }
+
+    /// Listings do not include metadata
+    fn custom_metadata(&self) -> HashMap<String, String> {
+        HashMap::new()
+    }
+
+    /// Listings only include the checksum algorithm
+    fn checksum(&self) -> Option<String> {
+        None
+    }
+
+    fn version(&self) -> Option<String> {
+        self.etag()
+    }
+}
+
 impl ClientObject for AWSObjectPut {
     fn size(&self) -> u64 {
-        self.object.size.map(|t| {t as u64}).unwrap()
+        self.size
     }
 
     fn bucket_name(&self) -> String {
@@ -111,21 +1075,46 @@ impl ClientObject for AWSObjectPut {
     }
 
     fn id(&self) -> String {
-        self.object.e_tag.clone().unwrap()
+        self.object.e_tag.clone().unwrap_or_default()
     }
 
     fn name(&self) -> String {
-        self.id()
+        self.key.clone()
     }
 
     fn content_type(&self) -> Option<String> {
+        self.options.content_type.clone()
+    }
+
+    fn last_modified(&self) -> Option<SystemTime> {
         None
     }
+
+    fn etag(&self) -> Option<String> {
+        self.object.e_tag.clone()
+    }
+
+    /// Objects uploaded without a storage class are standard ones
+    fn storage_class(&self) -> Option<String> {
+        Some(self.options.storage_class.clone().unwrap_or_else(|| "STANDARD".to_string()))
+    }
+
+    fn custom_metadata(&self) -> HashMap<String, String> {
+        self.options.custom_metadata.clone()
+    }
+
+    fn checksum(&self) -> Option<String> {
+        checksum_of(&self.object.checksum_sha256, &self.object.checksum_sha1, &self.object.checksum_crc32_c, &self.object.checksum_crc32)
+    }
+
+    fn version(&self) -> Option<String> {
+        self.etag()
+    }
 }
 
 impl ClientObject for AWSObject {
     fn size(&self) -> u64 {
-        self.object.content_length.map(|t| {t as u64}).unwrap()
+        self.object.content_length.map(|t| {t as u64}).unwrap_or_default()
     }
 
     fn bucket_name(&self) -> String {
@@ -133,99 +1122,775 @@ impl ClientObject for AWSObject {
     }
 
     fn id(&self) -> String {
-        self.object.e_tag.clone().unwrap()
+        self.object.e_tag.clone().unwrap_or_default()
     }
 
     fn name(&self) -> String {
-        self.id()
+        self.key.clone()
     }
 
     fn content_type(&self) -> Option<String> {
         self.object.content_type.clone()
     }
+
+    fn last_modified(&self) -> Option<SystemTime> {
+        self.object.last_modified.and_then(|t| SystemTime::try_from(t).ok())
+    }
+
+    fn etag(&self) -> Option<String> {
+        self.object.e_tag.clone()
+    }
+
+    /// HEAD omits the storage class for standard objects
+    fn storage_class(&self) -> Option<String> {
+        Some(self.object.storage_class.as_ref().map(|t| t.as_str()).unwrap_or("STANDARD").to_string())
+    }
+
+    fn custom_metadata(&self) -> HashMap<String, String> {
+        self.object.metadata.clone().unwrap_or_default()
+    }
+
+    /// Only reported for objects uploaded with a checksum algorithm
+    fn checksum(&self) -> Option<String> {
+        checksum_of(&self.object.checksum_sha256, &self.object.checksum_sha1, &self.object.checksum_crc32_c, &self.object.checksum_crc32)
+    }
+
+    fn version(&self) -> Option<String> {
+        self.etag()
+    }
+}
+
+impl AWSClient {
+    /// Like `upload_from_reader`, but always uses a multipart upload and persists its progress
+    /// (see `UploadCheckpoint`) after every part, so another worker can finish it with `resume_upload`
+    pub async fn checkpointed_upload_from_reader(&self, bucket_name: String, object_name: String, owner: String, mut r: impl AsyncRead + Unpin) -> EmptyReqRes {
+        let first = read_chunk(&mut r, self.part_size).await.context("checkpointed_upload_from_reader", &bucket_name, Some(&object_name))?;
+        let upload_id = self.client.create_multipart_upload().bucket(&bucket_name).key(&object_name).set_checksum_algorithm(self.checksum_algorithm())
+            .set_content_type(self.guessed_content_type(&object_name, &first)).send().await
+            .context("checkpointed_upload_from_reader", &bucket_name, Some(&object_name))?.upload_id.unwrap_or_default();
+        let mut checkpoint = UploadCheckpoint::new(bucket_name, object_name, upload_id, owner);
+        checkpoint.checksums = self.strict_checksums;
+        checkpoint.save(self).await?;
+        self.finish_multipart(&mut checkpoint, Some(first), r, true).await
+    }
+
+    /// Continues an upload adopted through `UploadCheckpoint::adopt`
+    /// `r` has to be positioned at `checkpoint.uploaded_bytes()` of the original data
+    pub async fn resume_upload(&self, mut checkpoint: UploadCheckpoint, r: impl AsyncRead + Unpin) -> EmptyReqRes {
+        self.finish_multipart(&mut checkpoint, None, r, true).await
+    }
+
+    /// Uploads the remaining parts (starting with `first` if given) and completes the upload
+    /// Checkpointed uploads are left intact on failure so they can be resumed, others are aborted
+    async fn finish_multipart(&self, checkpoint: &mut UploadCheckpoint, first: Option<Vec<u8>>, mut r: impl AsyncRead + Unpin, persist: bool) -> EmptyReqRes {
+        let (bucket_name, object_name, upload_id) = (checkpoint.bucket.clone(), checkpoint.object.clone(), checkpoint.upload_id.clone());
+        let result: EmptyReqRes = async {
+            let mut chunk = match first {
+                Some(first) => first,
+                None => read_chunk(&mut r, self.part_size).await.context("upload_part", &bucket_name, Some(&object_name))?
+            };
+            while !chunk.is_empty() {
+                let part_number = checkpoint.parts.len() as i32 + 1;
+                let size = chunk.len() as u64;
+                let checksums = checkpoint.checksums.then(|| Checksums::of(&chunk));
+                let part = self.client.upload_part().bucket(&bucket_name).key(&object_name).upload_id(&upload_id).part_number(part_number)
+                    .set_content_md5(checksums.as_ref().map(|t| t.md5.clone())).set_checksum_crc32_c(checksums.as_ref().map(|t| t.crc32c.clone()))
+                    .body(chunk.into()).send().await.context("upload_part", &bucket_name, Some(&object_name))?;
+                confirm(checksums.as_ref(), part.checksum_crc32_c.as_deref()).context("upload_part", &bucket_name, Some(&object_name))?;
+                checkpoint.parts.push(CheckpointPart { part_number, e_tag: part.e_tag, size, checksum: part.checksum_crc32_c });
+                if persist {
+                    checkpoint.renew(DEFAULT_LEASE);
+                    checkpoint.save(self).await?;
+                }
+                chunk = read_chunk(&mut r, self.part_size).await.context("upload_part", &bucket_name, Some(&object_name))?;
+            }
+            let parts = checkpoint.parts.iter().map(|t| CompletedPart::builder().set_e_tag(t.e_tag.clone()).set_checksum_crc32_c(t.checksum.clone())
+                .part_number(t.part_number).build()).collect();
+            self.client.complete_multipart_upload().bucket(&bucket_name).key(&object_name).upload_id(&upload_id)
+                .multipart_upload(CompletedMultipartUpload::builder().set_parts(Some(parts)).build()).send().await
+                .context("complete_multipart_upload", &bucket_name, Some(&object_name))?;
+            if persist {
+                checkpoint.remove(self).await?;
+            }
+            Ok(())
+        }.await;
+        if result.is_err() && !persist {
+            // Best effort, the original error is more useful to the caller
+            let _ = self.client.abort_multipart_upload().bucket(&bucket_name).key(&object_name).upload_id(&upload_id).send().await;
+        }
+        result
+    }
 }
 
 impl ClientInterface for AWSClient {
-    async fn static_download_object(&self, bucket_name: String, object_name: String, starting: Option<u64>, ending: Option<u64>) -> ReqRes<Vec<u8>> {
-        let range = match (starting, ending) {
-            (Some(s), Some(e)) => Some(format!("bytes={}-{}", s, e)),
-            (Some(s), None)    => Some(format!("bytes={}-", s)),
-            (None, Some(e))    => Some(format!("bytes=-{}", e)),
-            (None, None)       => None,
-        };
-        let mut builder = self.client.get_object().bucket(&bucket_name).if_match(object_name);
+    /// Strict mode verifies complete downloads (see `download_with_options`)
+    async fn static_download_bytes(&self, bucket_name: String, object_name: String, starting: Option<u64>, ending: Option<u64>) -> ReqRes<Bytes> {
+        if self.strict_checksums {
+            return self.download_with_options(bucket_name, object_name, starting, ending, DownloadOptions::default()).await
+        }
+        let range = range_header(starting, ending);
+        let builder = self.client.get_object().bucket(&bucket_name).key(&object_name);
         Ok(if let Some(range) = range {
-            builder.range(range).send().await?.body.collect().await.unwrap().to_vec()
+            builder.range(range).send().await
         } else {
-            builder.send().await?.body.collect().await.unwrap().to_vec()
-        })
+            builder.send().await
+        }.context("static_download_object", &bucket_name, Some(&object_name))?.body.collect().await.map_err(std::io::Error::from)
+            .context("static_download_object", &bucket_name, Some(&object_name))?.into_bytes())
+    }
+
+    /// Strict mode verifies complete downloads against the CRC32C stored with the object (objects uploaded in strict mode)
+    async fn download_with_options(&self, bucket_name: String, object_name: String, starting: Option<u64>, ending: Option<u64>, options: DownloadOptions) -> ReqRes<Bytes> {
+        let verify = self.strict_checksums && starting.is_none() && ending.is_none();
+        let object = self.client.get_object().bucket(&bucket_name).key(&object_name).set_range(range_header(starting, ending))
+            .set_checksum_mode(verify.then_some(ChecksumMode::Enabled))
+            .set_if_match(options.if_match).set_if_none_match(options.if_none_match)
+            .set_if_modified_since(options.if_modified_since.map(DateTime::from)).set_version_id(options.version).send().await
+            // A 304 response has no body, so it has no error code
+            .map_err(|e| match e.raw_response().map(|t| t.status().as_u16()) {
+                Some(304) => ClientError::NotModified,
+                _ => ClientError::from(e)
+            }).context("download_with_options", &bucket_name, Some(&object_name))?;
+        let stored = object.checksum_crc32_c.filter(|_| verify);
+        let data = object.body.collect().await.map_err(std::io::Error::from).context("download_with_options", &bucket_name, Some(&object_name))?.into_bytes();
+        verify_crc32c(&data, stored.as_deref()).context("download_with_options", &bucket_name, Some(&object_name))?;
+        Ok(data)
     }
 
     /// Uploads an object
-    /// Note: The content type of the returned object will always return None
-    async fn static_upload_object(&self, bucket_name: String, object_name: String, data: Vec<u8>) -> ReqRes<impl ClientObject> {
-        let object = self.client.put_object().bucket(&bucket_name).key(object_name).body(data.into()).send().await?;
-        Ok(AWSObjectPut {object, bucket: bucket_name})
+    async fn static_upload_bytes(&self, bucket_name: String, object_name: String, data: Bytes) -> ReqRes<impl ClientObject> {
+        let (req, checksums) = self.checksummed(self.client.put_object().bucket(&bucket_name).key(&object_name), &data);
+        let content_type = self.guessed_content_type(&object_name, &data);
+        let size = data.len() as u64;
+        let object = req.body(data.into()).set_content_type(content_type.clone()).send().await.context("static_upload_object", &bucket_name, Some(&object_name))?;
+        confirm(checksums.as_ref(), object.checksum_crc32_c.as_deref()).context("static_upload_object", &bucket_name, Some(&object_name))?;
+        Ok(AWSObjectPut {object, bucket: bucket_name, key: object_name, size, options: UploadOptions { content_type, ..Default::default() }})
+    }
+
+    async fn upload_with_options(&self, bucket_name: String, object_name: String, data: Bytes, mut options: UploadOptions) -> ReqRes<impl ClientObject> {
+        let (req, checksums) = self.checksummed(self.client.put_object().bucket(&bucket_name).key(&object_name), &data);
+        options.content_type = options.content_type.or_else(|| self.guessed_content_type(&object_name, &data));
+        let size = data.len() as u64;
+        let object = req.body(data.into())
+            .set_content_type(options.content_type.clone()).set_cache_control(options.cache_control.clone()).set_content_encoding(options.content_encoding.clone())
+            .set_metadata((!options.custom_metadata.is_empty()).then(|| options.custom_metadata.clone()))
+            .set_storage_class(options.storage_class.as_deref().map(StorageClass::from))
+            .set_acl(options.acl.map(ObjectCannedAcl::from))
+            .set_if_none_match(matches!(options.precondition, Some(WritePrecondition::DoesNotExist)).then(|| "*".to_string()))
+            .set_if_match(match &options.precondition {
+                Some(WritePrecondition::Matches(etag)) => Some(etag.clone()),
+                _ => None
+            }).send().await
+            .context("upload_with_options", &bucket_name, Some(&object_name))?;
+        confirm(checksums.as_ref(), object.checksum_crc32_c.as_deref()).context("upload_with_options", &bucket_name, Some(&object_name))?;
+        Ok(AWSObjectPut {object, bucket: bucket_name, key: object_name, size, options})
+    }
+
+    async fn download_to_writer(&self, bucket_name: String, object_name: String, starting: Option<u64>, ending: Option<u64>, mut w: impl AsyncWrite + Unpin + Send) -> ReqRes<u64> {
+        let object = self.client.get_object().bucket(&bucket_name).key(&object_name).set_range(range_header(starting, ending)).send().await
+            .context("download_to_writer", &bucket_name, Some(&object_name))?;
+        let written = tokio::io::copy(&mut object.body.into_async_read(), &mut w).await.context("download_to_writer", &bucket_name, Some(&object_name))?;
+        w.flush().await.context("download_to_writer", &bucket_name, Some(&object_name))?;
+        Ok(written)
+    }
+
+    /// Readers which fit into a single part are uploaded with one request, larger ones use a multipart upload
+    async fn upload_from_reader(&self, bucket_name: String, object_name: String, mut r: impl AsyncRead + Unpin + Send + Sync + 'static) -> EmptyReqRes {
+        let first = read_chunk(&mut r, self.part_size).await.context("upload_from_reader", &bucket_name, Some(&object_name))?;
+        if first.len() < self.part_size {
+            let (req, checksums) = self.checksummed(self.client.put_object().bucket(&bucket_name).key(&object_name), &first);
+            let content_type = self.guessed_content_type(&object_name, &first);
+            let object = req.body(first.into()).set_content_type(content_type).send().await.context("upload_from_reader", &bucket_name, Some(&object_name))?;
+            return confirm(checksums.as_ref(), object.checksum_crc32_c.as_deref()).context("upload_from_reader", &bucket_name, Some(&object_name))
+        }
+        let upload_id = self.client.create_multipart_upload().bucket(&bucket_name).key(&object_name).set_checksum_algorithm(self.checksum_algorithm())
+            .set_content_type(self.guessed_content_type(&object_name, &first)).send().await
+            .context("upload_from_reader", &bucket_name, Some(&object_name))?.upload_id.unwrap_or_default();
+        let mut checkpoint = UploadCheckpoint::new(bucket_name, object_name, upload_id, String::new());
+        checkpoint.checksums = self.strict_checksums;
+        self.finish_multipart(&mut checkpoint, Some(first), r, false).await
+    }
+
+    /// Objects of at least 5 MiB are extended with a multipart upload whose first part is a server-side copy of the object,
+    /// smaller ones are downloaded and uploaded again together with the new data
+    /// The headers, custom metadata and storage class of the object are kept
+    async fn append(&self, bucket_name: String, object_name: String, data: Bytes) -> EmptyReqRes {
+        let head = match self.client.head_object().bucket(&bucket_name).key(&object_name).send().await.context("append", &bucket_name, Some(&object_name)) {
+            Ok(head) => head,
+            Err(e) if e.is_not_found() => HeadObjectOutput::builder().build(),
+            Err(e) => return Err(e)
+        };
+        let size = head.content_length.unwrap_or(0) as u64;
+        if size < MIN_PART_SIZE {
+            let mut combined = if size > 0 {
+                self.static_download_bytes(bucket_name.clone(), object_name.clone(), None, None).await?.to_vec()
+            } else {
+                vec![]
+            };
+            combined.extend_from_slice(&data);
+            let (req, checksums) = self.checksummed(self.client.put_object().bucket(&bucket_name).key(&object_name), &combined);
+            let content_type = head.content_type.or_else(|| self.guessed_content_type(&object_name, &combined));
+            let object = req.body(combined.into())
+                .set_metadata(head.metadata)
+                .set_content_type(content_type)
+                .set_cache_control(head.cache_control)
+                .set_content_encoding(head.content_encoding)
+                .set_content_disposition(head.content_disposition)
+                .set_content_language(head.content_language)
+                .set_storage_class(head.storage_class)
+                .send().await.context("append", &bucket_name, Some(&object_name))?;
+            return confirm(checksums.as_ref(), object.checksum_crc32_c.as_deref()).context("append", &bucket_name, Some(&object_name))
+        }
+        let upload_id = self.client.create_multipart_upload().bucket(&bucket_name).key(&object_name).set_checksum_algorithm(self.checksum_algorithm())
+            .set_metadata(head.metadata)
+            .set_content_type(head.content_type.or_else(|| self.guessed_content_type(&object_name, &[])))
+            .set_cache_control(head.cache_control)
+            .set_content_encoding(head.content_encoding)
+            .set_content_disposition(head.content_disposition)
+            .set_content_language(head.content_language)
+            .set_storage_class(head.storage_class)
+            .send().await.context("append", &bucket_name, Some(&object_name))?.upload_id.unwrap_or_default();
+        let result: EmptyReqRes = async {
+            let copied = self.client.upload_part_copy().bucket(&bucket_name).key(&object_name).upload_id(&upload_id).part_number(1)
+                .copy_source(format!("{bucket_name}/{}", encode_key(&object_name))).send().await
+                .context("append", &bucket_name, Some(&object_name))?.copy_part_result;
+            let checksums = self.strict_checksums.then(|| Checksums::of(&data));
+            let appended = self.client.upload_part().bucket(&bucket_name).key(&object_name).upload_id(&upload_id).part_number(2)
+                .set_content_md5(checksums.as_ref().map(|t| t.md5.clone())).set_checksum_crc32_c(checksums.as_ref().map(|t| t.crc32c.clone()))
+                .body(data.into()).send().await.context("append", &bucket_name, Some(&object_name))?;
+            confirm(checksums.as_ref(), appended.checksum_crc32_c.as_deref()).context("append", &bucket_name, Some(&object_name))?;
+            let parts = vec![
+                CompletedPart::builder().set_e_tag(copied.as_ref().and_then(|t| t.e_tag.clone())).set_checksum_crc32_c(copied.and_then(|t| t.checksum_crc32_c)).part_number(1).build(),
+                CompletedPart::builder().set_e_tag(appended.e_tag).set_checksum_crc32_c(appended.checksum_crc32_c).part_number(2).build(),
+            ];
+            self.client.complete_multipart_upload().bucket(&bucket_name).key(&object_name).upload_id(&upload_id)
+                .multipart_upload(CompletedMultipartUpload::builder().set_parts(Some(parts)).build()).send().await
+                .context("append", &bucket_name, Some(&object_name))?;
+            Ok(())
+        }.await;
+        if result.is_err() {
+            // Best effort, the original error is more useful to the caller
+            let _ = self.client.abort_multipart_upload().bucket(&bucket_name).key(&object_name).upload_id(&upload_id).send().await;
+        }
+        result
+    }
+
+    /// Presigned request, headers with invalid names or values are left out
+    async fn signed_url(&self, bucket_name: String, object_name: String, options: SignedUrlOptions) -> ReqRes<String> {
+        let config = PresigningConfig::expires_in(options.expires_in).context("signed_url", &bucket_name, Some(&object_name))?;
+        let headers = signed_headers(options.extra_headers);
+        let presigned = match options.method {
+            SignedUrlMethod::Get => self.client.get_object().bucket(&bucket_name).key(&object_name)
+                .set_response_content_type(options.content_type)
+                .set_response_content_disposition(options.response_content_disposition)
+                .customize().mutate_request(headers).presigned(config).await
+                .context("signed_url", &bucket_name, Some(&object_name))?,
+            SignedUrlMethod::Put => self.client.put_object().bucket(&bucket_name).key(&object_name)
+                .set_content_type(options.content_type)
+                .customize().mutate_request(headers).presigned(config).await
+                .context("signed_url", &bucket_name, Some(&object_name))?,
+            SignedUrlMethod::Head => self.client.head_object().bucket(&bucket_name).key(&object_name)
+                .set_response_content_type(options.content_type)
+                .set_response_content_disposition(options.response_content_disposition)
+                .customize().mutate_request(headers).presigned(config).await
+                .context("signed_url", &bucket_name, Some(&object_name))?,
+            SignedUrlMethod::Delete => self.client.delete_object().bucket(&bucket_name).key(&object_name)
+                .customize().mutate_request(headers).presigned(config).await
+                .context("signed_url", &bucket_name, Some(&object_name))?,
+        };
+        Ok(presigned.uri().to_string())
     }
 
-    /// AWS S3 provides no URL for uploading objects. An empty string is returned.
-    async fn url_upload_object(&self, _: String, _: String) -> ReqRes<String> {
-        Ok("".to_string())
+    /// Uses the public-read canned ACL, which is rejected by buckets with ACLs disabled (object ownership enforced)
+    /// (those have to grant public read access with a bucket policy instead)
+    async fn upload_public(&self, bucket_name: String, object_name: String, data: Bytes, content_type: Option<String>, cache_control: String) -> EmptyReqRes {
+        let (req, checksums) = self.checksummed(self.client.put_object().bucket(&bucket_name).key(&object_name), &data);
+        let object = req.body(data.into()).acl(ObjectCannedAcl::PublicRead)
+            .set_content_type(content_type).cache_control(cache_control).send().await
+            .context("upload_public", &bucket_name, Some(&object_name))?;
+        confirm(checksums.as_ref(), object.checksum_crc32_c.as_deref()).context("upload_public", &bucket_name, Some(&object_name))
     }
 
-    /// Creates a download URL
-    /// Note: I don't know if this is correct
-    async fn url_download_object(&self, bucket_name: String, object_name: String) -> ReqRes<String> {
-        Ok(format!("https://{bucket_name}.s3.amazonaws.com/{object_name}"))
+    /// Virtual-hosted URL in the client's region (the bucket is expected to be located there, see `regional_client`)
+    /// Buckets with dots in their name use a path-style URL, their virtual-hosted name does not match the TLS certificate
+    fn public_url(&self, bucket_name: &str, object_name: &str) -> String {
+        format!("{}/{}", bucket_url(bucket_name, &region_of(&self.client)), encode_key(object_name))
+    }
+
+    /// Signature V4 POST policy for the bucket's region, signed with the current credentials of the client
+    /// (the form stops working once temporary credentials expire)
+    async fn presigned_post(&self, bucket: String, key_prefix: String, constraints: PostConstraints) -> ReqRes<PresignedPost> {
+        let client = self.regional_client(&bucket).await?;
+        let region = region_of(&client);
+        let credentials = match &self.credentials {
+            Some(provider) => provider.provide_credentials().await.context("presigned_post", &bucket, Some(&key_prefix))?,
+            None => return Err(ClientError::Unsupported("presigned POST policies require credentials")
+                .with_context(ErrorContext::new("presigned_post", Some(&bucket), Some(&key_prefix))))
+        };
+        let now = SystemTime::now();
+        let (timestamp, date) = post::signing_time(now);
+        let mut fields = HashMap::from([
+            ("x-amz-algorithm".to_string(), "AWS4-HMAC-SHA256".to_string()),
+            ("x-amz-credential".to_string(), format!("{}/{date}/{region}/s3/aws4_request", credentials.access_key_id())),
+            ("x-amz-date".to_string(), timestamp),
+        ]);
+        if let Some(token) = credentials.session_token() {
+            fields.insert("x-amz-security-token".to_string(), token.to_string());
+        }
+        let policy = post::policy(now + constraints.expires_in, post::conditions(&bucket, &key_prefix, &constraints, &fields));
+        let signing_key = [date.as_str(), region.as_str(), "s3", "aws4_request"].into_iter()
+            .fold(format!("AWS4{}", credentials.secret_access_key()).into_bytes(), |key, t| hmac_sha256(&key, t));
+        fields.insert("x-amz-signature".to_string(), encoding::hex(&hmac_sha256(&signing_key, &policy)));
+        fields.insert("policy".to_string(), policy);
+        fields.insert("key".to_string(), format!("{key_prefix}${{filename}}"));
+        Ok(PresignedPost { url: bucket_url(&bucket, &region), fields })
     }
 
     async fn remove_bucket(&self, bucket: String) -> EmptyReqRes {
-        self.client.delete_bucket().bucket(bucket).send().await?;
+        self.client.delete_bucket().bucket(&bucket).send().await.context("remove_bucket", &bucket, None)?;
         Ok(())
     }
 
     async fn remove_object(&self, bucket_name: String, object_name: String) -> EmptyReqRes {
-        self.client.delete_object().bucket(bucket_name).key(object_name).send().await?;
+        self.client.delete_object().bucket(&bucket_name).key(&object_name).send().await.context("remove_object", &bucket_name, Some(&object_name))?;
         Ok(())
     }
 
     async fn create_bucket(&self, bucket_name: String) -> ReqRes<impl ClientBucket> {
-        let location = self.client.create_bucket().bucket(bucket_name).send().await?.location;
+        let location = self.client.create_bucket().bucket(&bucket_name).send().await.context("create_bucket", &bucket_name, None)?.location;
         Ok(AWSBucket {bucket_name, location})
     }
 
     /// Copy an object from one object of bucket to another
-    /// Note: AWS-S3 only supports copying within the same bucket
+    /// Copies within and across buckets (and regions, the request is sent to the destination bucket's region)
+    /// Objects larger than 5 GiB are copied with a multipart upload made of server-side part copies
+    /// The grants of the source are kept (this requires the s3:GetObjectAcl permission)
+    /// Copying an object onto itself fails with `ClientError::Unsupported`
     async fn copy_object(&self, src_bucket: String, src_object: String, dest_bucket: String, dest_object: String) -> ReqRes<impl ClientObject> {
-        assert_eq!(src_bucket, dest_bucket, "Source and destination buckets must be the same on AWS-S3");
-        self.client.copy_object().bucket(src_bucket).key(dest_object).copy_source(src_object).send().await?.copy_object_result.unwrap();
-        self.get_object(dest_bucket, dest_object)
+        self.copy(src_bucket, src_object, dest_bucket, dest_object, None).await
     }
 
-    async fn list_buckets(&self, max_results: Option<u32>) -> ReqRes<Vec<impl ClientBucket>> {
-        let builder = self.client.list_buckets();
-        Ok((if let Some(max_results) = max_results {
-            builder.max_buckets(max_results as i32).send().await
-        } else {
-            builder.send().await
-        })?.buckets.and_then(|t| { t.into_iter().map(|t1| {t1.into()}).collect() }).unwrap())
+    /// A copy onto itself is only allowed with a storage class
+    async fn copy_object_with_options(&self, src_bucket: String, src_object: String, dest_bucket: String, dest_object: String, options: CopyOptions) -> ReqRes<impl ClientObject> {
+        self.copy(src_bucket, src_object, dest_bucket, dest_object, options.storage_class.map(|t| StorageClass::from(t.as_str()))).await
+    }
+
+    async fn restore_object(&self, bucket_name: String, object_name: String, days: u32, tier: RetrievalTier) -> EmptyReqRes {
+        let parameters = GlacierJobParameters::builder().tier(tier.into()).build().context("restore_object", &bucket_name, Some(&object_name))?;
+        let request = RestoreRequest::builder().days(days as i32).glacier_job_parameters(parameters).build();
+        match self.client.restore_object().bucket(&bucket_name).key(&object_name).restore_request(request).send().await
+            .context("restore_object", &bucket_name, Some(&object_name)) {
+            Err(e) if matches!(e.inner(), ClientError::AWSClient(e) if e.code() == Some("RestoreAlreadyInProgress")) => Ok(()),
+            result => result.map(|_| ())
+        }
+    }
+
+    async fn restore_status(&self, bucket_name: String, object_name: String) -> ReqRes<RestoreStatus> {
+        let head = self.client.head_object().bucket(&bucket_name).key(&object_name).send().await
+            .context("restore_status", &bucket_name, Some(&object_name))?;
+        // Example: 'ongoing-request="false", expiry-date="Fri, 21 Dec 2012 00:00:00 GMT"'
+        let Some(restore) = head.restore else {
+            return Ok(RestoreStatus::NotRequested)
+        };
+        if restore.contains("ongoing-request=\"true\"") {
+            return Ok(RestoreStatus::InProgress)
+        }
+        let expires = restore.split_once("expiry-date=\"").and_then(|(_, t)| t.split('"').next())
+            .and_then(|t| DateTime::from_str(t, DateTimeFormat::HttpDate).ok())
+            .and_then(|t| SystemTime::try_from(t).ok());
+        Ok(RestoreStatus::Restored { expires })
+    }
+
+    /// Yields the progress after every copied part of a multipart copy (objects larger than 5 GiB), smaller objects are copied with a single request
+    /// Note: A stream which is dropped before its end leaves an incomplete multipart upload behind
+    fn copy_object_progress(&self, src_bucket: String, src_object: String, dest_bucket: String, dest_object: String) -> impl Stream<Item = ReqRes<CopyProgress>> + Send {
+        stream::once(async move {
+            if src_bucket == dest_bucket && src_object == dest_object {
+                return Err(ClientError::Unsupported("AWS-S3 cannot copy an object onto itself without changing it")
+                    .with_context(ErrorContext::new("copy_object_progress", Some(&src_bucket), Some(&src_object))))
+            }
+            let source = self.source(&src_bucket, &src_object).await?;
+            let client = self.regional_client(&dest_bucket).await?;
+            Ok(copy_parts(client, CopyRoute { src_bucket, src_object, dest_bucket, dest_object }, source, None))
+        }).flat_map(|t| match t {
+            Ok(progress) => progress.left_stream(),
+            Err(e) => stream::iter([Err(e)]).right_stream()
+        })
+    }
+
+    /// Copies the object onto itself with replaced metadata (the data is not transferred), fails if the object changed in between
+    /// The grants of the object are sent with the copy, which would be private otherwise
+    /// Note: Only objects up to 5 GiB can be copied with a single request, reading the ACL requires the s3:GetObjectAcl permission
+    async fn update_object_metadata(&self, bucket_name: String, object_name: String, changes: MetadataChanges) -> ReqRes<impl ClientObject> {
+        let head = self.client.head_object().bucket(&bucket_name).key(&object_name).send().await
+            .context("update_object_metadata", &bucket_name, Some(&object_name))?;
+        let grants = CopiedGrants::of(self.client.get_object_acl().bucket(&bucket_name).key(&object_name).send().await
+            .context("update_object_metadata", &bucket_name, Some(&object_name))?);
+        // Replacing the metadata drops all headers which are not sent again
+        self.client.copy_object().bucket(&bucket_name).key(&object_name).copy_source(format!("{bucket_name}/{}", encode_key(&object_name)))
+            .set_copy_source_if_match(head.e_tag.clone()).metadata_directive(MetadataDirective::Replace)
+            .set_metadata(Some(changes.merge_custom(head.metadata.unwrap_or_default())))
+            .set_content_type(changes.content_type.or(head.content_type))
+            .set_cache_control(changes.cache_control.or(head.cache_control))
+            .set_content_encoding(changes.content_encoding.or(head.content_encoding))
+            .set_content_disposition(changes.content_disposition.or(head.content_disposition))
+            .set_content_language(head.content_language)
+            .set_storage_class(head.storage_class)
+            .set_server_side_encryption(head.server_side_encryption)
+            .set_ssekms_key_id(head.ssekms_key_id)
+            .set_grant_full_control(grants.full_control).set_grant_read(grants.read)
+            .set_grant_read_acp(grants.read_acp).set_grant_write_acp(grants.write_acp)
+            .send().await.context("update_object_metadata", &bucket_name, Some(&object_name))?;
+        let object = self.client.head_object().bucket(&bucket_name).key(&object_name).send().await
+            .context("update_object_metadata", &bucket_name, Some(&object_name))?;
+        Ok(AWSObject {object, bucket: bucket_name, key: object_name})
+    }
+
+    async fn get_object_tags(&self, bucket_name: String, object_name: String) -> ReqRes<HashMap<String, String>> {
+        let res = self.client.get_object_tagging().bucket(&bucket_name).key(&object_name).send().await
+            .context("get_object_tags", &bucket_name, Some(&object_name))?;
+        Ok(res.tag_set.into_iter().map(|t| (t.key, t.value)).collect())
+    }
+
+    /// Note: S3 allows up to 10 tags per object
+    async fn set_object_tags(&self, bucket_name: String, object_name: String, tags: HashMap<String, String>) -> EmptyReqRes {
+        let tag_set = tags.into_iter().map(|(key, value)| Tag::builder().key(key).value(value).build())
+            .collect::<Result<Vec<_>, _>>().context("set_object_tags", &bucket_name, Some(&object_name))?;
+        let tagging = Tagging::builder().set_tag_set(Some(tag_set)).build().context("set_object_tags", &bucket_name, Some(&object_name))?;
+        self.client.put_object_tagging().bucket(&bucket_name).key(&object_name).tagging(tagging).send().await
+            .context("set_object_tags", &bucket_name, Some(&object_name))?;
+        Ok(())
+    }
+
+    /// Note: READ_ACP and WRITE_ACP grants are left out, buckets with ACLs disabled (object ownership enforced) only report the owner
+    async fn get_object_acl(&self, bucket_name: String, object_name: String) -> ReqRes<Vec<AclGrant>> {
+        let res = self.client.get_object_acl().bucket(&bucket_name).key(&object_name).send().await
+            .context("get_object_acl", &bucket_name, Some(&object_name))?;
+        Ok(res.grants.unwrap_or_default().into_iter().filter_map(grant_from).collect())
+    }
+
+    /// Grants are sent as an access control policy together with a FULL_CONTROL grant of the current owner
+    /// Note: Buckets with ACLs disabled (object ownership enforced) reject every ACL other than private / bucket-owner-full-control
+    async fn set_object_acl(&self, bucket_name: String, object_name: String, acl: AccessControl) -> EmptyReqRes {
+        let req = self.client.put_object_acl().bucket(&bucket_name).key(&object_name);
+        let req = match acl {
+            AccessControl::Canned(acl) => req.acl(acl.into()),
+            AccessControl::Grants(grants) => {
+                let owner = self.client.get_object_acl().bucket(&bucket_name).key(&object_name).send().await
+                    .context("set_object_acl", &bucket_name, Some(&object_name))?.owner;
+                let mut grants = grants.into_iter().map(grant_to).collect::<Result<Vec<_>, _>>()
+                    .context("set_object_acl", &bucket_name, Some(&object_name))?;
+                if let Some(id) = owner.as_ref().and_then(|t| t.id.clone()) {
+                    grants.push(grant_to(AclGrant { grantee: Grantee::Id(id), permission: AclPermission::FullControl })
+                        .context("set_object_acl", &bucket_name, Some(&object_name))?);
+                }
+                req.access_control_policy(AccessControlPolicy::builder().set_owner(owner).set_grants(Some(grants)).build())
+            }
+        };
+        req.send().await.context("set_object_acl", &bucket_name, Some(&object_name))?;
+        Ok(())
+    }
+
+    async fn list_buckets(&self, max_results: Option<u32>, page_token: Option<Cursor>) -> ReqRes<ListPage<impl ClientBucket>> {
+        let res = self.client.list_buckets().set_max_buckets(max_results.map(|t| t as i32)).set_continuation_token(page_token.map(Cursor::into_token)).send().await
+            .map_err(|e| ClientError::from(e).with_context(ErrorContext::new("list_buckets", None, None)))?;
+        Ok(ListPage {
+            items: res.buckets.unwrap_or_default().into_iter().map(AWSBucket::from).collect(),
+            prefixes: vec![],
+            next_token: res.continuation_token.map(Cursor::new)
+        })
     }
 
     async fn get_bucket(&self, bucket_name: String) -> ReqRes<impl ClientBucket> {
-        Ok(AWSBucket {bucket_name, location: Some(self.client.get_bucket_location().bucket(bucket_name).send().await?.location_constraint.unwrap().as_str().to_string()) })
+        let location = self.client.get_bucket_location().bucket(&bucket_name).send().await.context("get_bucket", &bucket_name, None)?
+            .location_constraint.map(|t| t.as_str().to_string());
+        Ok(AWSBucket {bucket_name, location})
     }
 
+    /// Same as `stat_object`
     async fn get_object(&self, bucket_name: String, object_name: String) -> ReqRes<impl ClientObject> {
-        let object = self.client.get_object().bucket(&bucket_name).if_match(object_name).send().await?;
-        Ok(AWSObject {object, bucket: bucket_name})
+        self.stat_object(bucket_name, object_name).await
+    }
+
+    async fn stat_object(&self, bucket_name: String, object_name: String) -> ReqRes<impl ClientObject> {
+        let object = self.client.head_object().bucket(&bucket_name).key(&object_name).send().await.context("stat_object", &bucket_name, Some(&object_name))?;
+        Ok(AWSObject {object, bucket: bucket_name, key: object_name})
+    }
+
+    /// Note: The content type of the returned objects will always return None
+    async fn list_objects(&self, bucket_name: String, max_results: Option<u32>, page_token: Option<Cursor>) -> ReqRes<ListPage<impl ClientObject>> {
+        let res = self.client.list_objects_v2().bucket(&bucket_name).set_max_keys(max_results.map(|t| t as i32))
+            .set_continuation_token(page_token.map(Cursor::into_token)).send().await
+            .context("list_objects", &bucket_name, None)?;
+        Ok(ListPage {
+            items: res.contents.unwrap_or_default().into_iter().map(|object| AWSListedObject {object, bucket: bucket_name.clone()}).collect(),
+            prefixes: vec![],
+            next_token: res.next_continuation_token.map(Cursor::new)
+        })
+    }
+
+    async fn list_prefix(&self, bucket_name: String, prefix: String, page_token: Option<Cursor>) -> ReqRes<ListPage<impl ClientObject>> {
+        let res = self.client.list_objects_v2().bucket(&bucket_name).prefix(&prefix).set_continuation_token(page_token.map(Cursor::into_token)).send().await
+            .context("list_prefix", &bucket_name, Some(&prefix))?;
+        Ok(ListPage {
+            items: res.contents.unwrap_or_default().into_iter().map(|object| AWSListedObject {object, bucket: bucket_name.clone()}).collect(),
+            prefixes: vec![],
+            next_token: res.next_continuation_token.map(Cursor::new)
+        })
+    }
+
+    async fn list_delimited(&self, bucket_name: String, prefix: String, delimiter: String, page_token: Option<Cursor>) -> ReqRes<ListPage<impl ClientObject>> {
+        let res = self.client.list_objects_v2().bucket(&bucket_name).prefix(&prefix).delimiter(delimiter).set_continuation_token(page_token.map(Cursor::into_token)).send().await
+            .context("list_delimited", &bucket_name, Some(&prefix))?;
+        Ok(ListPage {
+            items: res.contents.unwrap_or_default().into_iter().map(|object| AWSListedObject {object, bucket: bucket_name.clone()}).collect(),
+            prefixes: res.common_prefixes.unwrap_or_default().into_iter().filter_map(|t| t.prefix).collect(),
+            next_token: res.next_continuation_token.map(Cursor::new)
+        })
+    }
+
+    async fn list_after(&self, bucket_name: String, prefix: String, start_after: String, max_results: u32) -> ReqRes<Vec<impl ClientObject>> {
+        let res = self.client.list_objects_v2().bucket(&bucket_name).prefix(&prefix).start_after(start_after).max_keys(max_results as i32).send().await
+            .context("list_after", &bucket_name, Some(&prefix))?;
+        Ok(res.contents.unwrap_or_default().into_iter().map(|object| AWSListedObject {object, bucket: bucket_name.clone()}).collect())
+    }
+
+    /// The cursor combines the key and version ID markers, separated by a NUL character
+    async fn list_versions(&self, bucket_name: String, prefix: String, page_token: Option<Cursor>) -> ReqRes<ListPage<ObjectVersion>> {
+        let page_token = page_token.map(Cursor::into_token);
+        let (key_marker, version_marker) = match page_token.as_deref().and_then(|t| t.split_once('\0')) {
+            Some((key, version)) => (Some(key.to_string()), Some(version.to_string()).filter(|t| !t.is_empty())),
+            None => (None, None)
+        };
+        let res = self.client.list_object_versions().bucket(&bucket_name).prefix(&prefix)
+            .set_key_marker(key_marker).set_version_id_marker(version_marker).send().await
+            .context("list_versions", &bucket_name, Some(&prefix))?;
+        let mut items: Vec<ObjectVersion> = res.versions.unwrap_or_default().into_iter().map(|t| ObjectVersion {
+            bucket: bucket_name.clone(),
+            name: t.key.unwrap_or_default(),
+            version: t.version_id.unwrap_or_default(),
+            size: t.size.map(|t| t as u64).unwrap_or(0),
+            created: system_time(t.last_modified),
+            superseded: None,
+            delete_marker: false,
+        }).collect();
+        items.extend(res.delete_markers.unwrap_or_default().into_iter().map(|t| ObjectVersion {
+            bucket: bucket_name.clone(),
+            name: t.key.unwrap_or_default(),
+            version: t.version_id.unwrap_or_default(),
+            size: 0,
+            created: system_time(t.last_modified),
+            superseded: None,
+            delete_marker: true,
+        }));
+        let next_token = if res.is_truncated == Some(true) {
+            Some(Cursor::new(format!("{}\0{}", res.next_key_marker.unwrap_or_default(), res.next_version_id_marker.unwrap_or_default())))
+        } else {
+            None
+        };
+        Ok(ListPage {items, prefixes: vec![], next_token})
+    }
+
+    /// Versions larger than 5 GiB cannot be restored with a single copy request and fail
+    async fn restore_object_version(&self, bucket_name: String, object_name: String, version: String) -> ReqRes<impl ClientObject> {
+        let copy_source = format!("{bucket_name}/{}?versionId={}", encode_key(&object_name), encode_key(&version));
+        let res = self.client.copy_object().bucket(&bucket_name).key(&object_name).copy_source(copy_source).send().await
+            .context("restore_object_version", &bucket_name, Some(&object_name))?;
+        let object = self.client.head_object().bucket(&bucket_name).key(&object_name).set_version_id(res.version_id).send().await
+            .context("restore_object_version", &bucket_name, Some(&object_name))?;
+        Ok(AWSObject {object, bucket: bucket_name, key: object_name})
+    }
+
+    async fn get_object_lock(&self, bucket_name: String, object_name: String) -> ReqRes<ObjectLock> {
+        let head = self.client.head_object().bucket(&bucket_name).key(&object_name).send().await
+            .context("get_object_lock", &bucket_name, Some(&object_name))?;
+        let mode = match head.object_lock_mode {
+            Some(ObjectLockMode::Compliance) => RetentionMode::Compliance,
+            _ => RetentionMode::Governance
+        };
+        Ok(ObjectLock {
+            legal_hold: head.object_lock_legal_hold_status == Some(ObjectLockLegalHoldStatus::On),
+            retention: head.object_lock_retain_until_date.map(|t| Retention { mode, retain_until: system_time(Some(t)) }),
+        })
+    }
+
+    async fn set_object_retention(&self, bucket_name: String, object_name: String, retention: Option<Retention>) -> EmptyReqRes {
+        let lock = match retention {
+            Some(retention) => ObjectLockRetention::builder()
+                .mode(match retention.mode {
+                    RetentionMode::Governance => ObjectLockRetentionMode::Governance,
+                    RetentionMode::Compliance => ObjectLockRetentionMode::Compliance,
+                })
+                .retain_until_date(DateTime::from(retention.retain_until)).build(),
+            None => ObjectLockRetention::builder().build()
+        };
+        self.client.put_object_retention().bucket(&bucket_name).key(&object_name).retention(lock)
+            .set_bypass_governance_retention(retention.is_none().then_some(true)).send().await
+            .context("set_object_retention", &bucket_name, Some(&object_name))?;
+        Ok(())
+    }
+
+    async fn set_legal_hold(&self, bucket_name: String, object_name: String, on: bool) -> EmptyReqRes {
+        let status = if on { ObjectLockLegalHoldStatus::On } else { ObjectLockLegalHoldStatus::Off };
+        self.client.put_object_legal_hold().bucket(&bucket_name).key(&object_name).legal_hold(ObjectLockLegalHold::builder().status(status).build())
+            .send().await.context("set_legal_hold", &bucket_name, Some(&object_name))?;
+        Ok(())
+    }
+
+    /// Issues a HEAD and an ACL request per object
+    async fn compliance_records(&self, bucket_name: String, page_token: Option<Cursor>) -> ReqRes<ListPage<ComplianceRecord>> {
+        let res = self.client.list_objects_v2().bucket(&bucket_name).set_continuation_token(page_token.map(Cursor::into_token)).send().await
+            .context("compliance_records", &bucket_name, None)?;
+        let keys = res.contents.unwrap_or_default().into_iter().filter_map(|t| t.key);
+        let bucket_name = &bucket_name;
+        let items = stream::iter(keys).map(|key| async move {
+            let head = self.client.head_object().bucket(bucket_name).key(&key).send().await
+                .context("compliance_records", bucket_name, Some(&key))?;
+            let grants = self.client.get_object_acl().bucket(bucket_name).key(&key).send().await
+                .context("compliance_records", bucket_name, Some(&key))?.grants.unwrap_or_default();
+            Ok::<_, ClientError>(ComplianceRecord {
+                // HEAD omits the storage class for standard objects
+                storage_class: Some(head.storage_class.map(|t| t.as_str().to_string()).unwrap_or("STANDARD".to_string())),
+                encryption: head.server_side_encryption.map(|t| t.as_str().to_string()),
+                public: grants.iter().filter_map(|t| t.grantee.as_ref().and_then(|t| t.uri.as_deref()))
+                    .any(|t| t.ends_with("/global/AllUsers") || t.ends_with("/global/AuthenticatedUsers")),
+                legal_hold: head.object_lock_legal_hold_status == Some(ObjectLockLegalHoldStatus::On),
+                retain_until: head.object_lock_retain_until_date.map(|t| system_time(Some(t))),
+                name: key,
+            })
+        }).buffered(CONCURRENCY).try_collect().await?;
+        Ok(ListPage {items, prefixes: vec![], next_token: res.next_continuation_token.map(Cursor::new)})
+    }
+
+    /// Deletes up to 1000 keys per DeleteObjects request
+    async fn remove_objects(&self, bucket_name: String, keys: Vec<String>) -> ReqRes<Vec<(String, EmptyReqRes)>> {
+        let mut results = Vec::with_capacity(keys.len());
+        for chunk in keys.chunks(DELETE_BATCH_SIZE) {
+            let objects = chunk.iter().map(|t| ObjectIdentifier::builder().key(t).build()).collect::<Result<Vec<_>, _>>()
+                .context("remove_objects", &bucket_name, None)?;
+            let delete = Delete::builder().set_objects(Some(objects)).quiet(true).build().context("remove_objects", &bucket_name, None)?;
+            let errors = self.client.delete_objects().bucket(&bucket_name).delete(delete).send().await
+                .context("remove_objects", &bucket_name, None)?.errors.unwrap_or_default();
+            results.extend(chunk.iter().map(|key| {
+                let result = match errors.iter().find(|t| t.key() == Some(key.as_str())) {
+                    Some(e) => Err(e.clone()).context("remove_objects", &bucket_name, Some(key)),
+                    None => Ok(())
+                };
+                (key.clone(), result)
+            }));
+        }
+        Ok(results)
+    }
+
+    /// Uses a HEAD request, as the bucket location is not needed
+    async fn bucket_exists(&self, bucket_name: String) -> ReqRes<bool> {
+        match self.client.head_bucket().bucket(&bucket_name).send().await.context("bucket_exists", &bucket_name, None) {
+            Ok(_) => Ok(true),
+            Err(e) if e.is_not_found() => Ok(false),
+            Err(e) => Err(e)
+        }
+    }
+
+    /// Missing lifecycle or CORS configurations are reported as empty
+    async fn get_bucket_spec(&self, bucket_name: String) -> ReqRes<BucketSpec> {
+        let versioning = self.client.get_bucket_versioning().bucket(&bucket_name).send().await.context("get_bucket_spec", &bucket_name, None)?.status;
+        let lifecycle_rules = match self.client.get_bucket_lifecycle_configuration().bucket(&bucket_name).send().await {
+            Ok(out) => out.rules.unwrap_or_default().into_iter().flat_map(lifecycle_rules_from).collect(),
+            Err(e) if e.as_service_error().and_then(|t| t.code()) == Some("NoSuchLifecycleConfiguration") => vec![],
+            Err(e) => return Err(e).context("get_bucket_spec", &bucket_name, None)
+        };
+        let cors = match self.client.get_bucket_cors().bucket(&bucket_name).send().await {
+            Ok(out) => out.cors_rules.unwrap_or_default().into_iter().map(|t| t.into()).collect(),
+            Err(e) if e.as_service_error().and_then(|t| t.code()) == Some("NoSuchCORSConfiguration") => vec![],
+            Err(e) => return Err(e).context("get_bucket_spec", &bucket_name, None)
+        };
+        let encryption = match self.client.get_bucket_encryption().bucket(&bucket_name).send().await {
+            Ok(out) => out.server_side_encryption_configuration.and_then(|t| t.rules.into_iter().next())
+                .and_then(|t| t.apply_server_side_encryption_by_default)
+                .map(|t| t.sse_algorithm.as_str().to_string()),
+            Err(e) if e.as_service_error().and_then(|t| t.code()) == Some("ServerSideEncryptionConfigurationNotFoundError") => None,
+            Err(e) => return Err(e).context("get_bucket_spec", &bucket_name, None)
+        };
+        Ok(BucketSpec {
+            versioning: Some(versioning == Some(BucketVersioningStatus::Enabled)),
+            lifecycle_rules: Some(lifecycle_rules),
+            cors: Some(cors),
+            encryption,
+        })
+    }
+
+    /// A missing lifecycle configuration is reported as empty
+    async fn get_lifecycle_rules(&self, bucket_name: String) -> ReqRes<Vec<LifecycleRule>> {
+        match self.client.get_bucket_lifecycle_configuration().bucket(&bucket_name).send().await {
+            Ok(out) => Ok(out.rules.unwrap_or_default().into_iter().flat_map(lifecycle_rules_from).collect()),
+            Err(e) if e.as_service_error().and_then(|t| t.code()) == Some("NoSuchLifecycleConfiguration") => Ok(vec![]),
+            Err(e) => Err(e).context("get_lifecycle_rules", &bucket_name, None)
+        }
+    }
+
+    /// S3 rejects empty lifecycle configurations, so an empty list deletes the configuration
+    async fn set_lifecycle_rules(&self, bucket_name: String, rules: Vec<LifecycleRule>) -> EmptyReqRes {
+        if rules.is_empty() {
+            self.client.delete_bucket_lifecycle().bucket(&bucket_name).send().await.context("set_lifecycle_rules", &bucket_name, None)?;
+            return Ok(())
+        }
+        let rules = rules.into_iter().enumerate().map(|(i, t)| lifecycle_rule_to(i, t)).collect::<Result<Vec<_>, _>>()
+            .context("set_lifecycle_rules", &bucket_name, None)?;
+        let configuration = BucketLifecycleConfiguration::builder().set_rules(Some(rules)).build().context("set_lifecycle_rules", &bucket_name, None)?;
+        self.client.put_bucket_lifecycle_configuration().bucket(&bucket_name).lifecycle_configuration(configuration).send().await
+            .context("set_lifecycle_rules", &bucket_name, None)?;
+        Ok(())
+    }
+
+    async fn get_bucket_policy(&self, bucket_name: String) -> ReqRes<Option<BucketPolicy>> {
+        let policy = match self.client.get_bucket_policy().bucket(&bucket_name).send().await {
+            Ok(out) => out.policy,
+            Err(e) if e.as_service_error().and_then(|t| t.code()) == Some("NoSuchBucketPolicy") => None,
+            Err(e) => return Err(e).context("get_bucket_policy", &bucket_name, None)
+        };
+        match policy {
+            Some(policy) => Ok(Some(BucketPolicy::S3(serde_json::from_str(&policy).context("get_bucket_policy", &bucket_name, None)?))),
+            None => Ok(None)
+        }
     }
 
-    async fn list_objects(&self, bucket_name: String, max_results: Option<u32>) -> ReqRes<Vec<impl ClientObject>> {
-        let builder = self.client.list_objects().bucket(bucket_name);
-        if let Some(max_results) = max_results {
-            builder.max_keys(max_results as i32).send()
+    /// A `Value::Null` document deletes the bucket policy
+    async fn set_bucket_policy(&self, bucket_name: String, policy: BucketPolicy) -> EmptyReqRes {
+        let BucketPolicy::S3(document) = policy else {
+            return Err(ClientError::Unsupported("AWS-S3 bucket policies are JSON documents")
+                .with_context(ErrorContext::new("set_bucket_policy", Some(&bucket_name), None)))
+        };
+        if document.is_null() {
+            self.client.delete_bucket_policy().bucket(&bucket_name).send().await.context("set_bucket_policy", &bucket_name, None)?;
         } else {
-            builder.send()
+            self.client.put_bucket_policy().bucket(&bucket_name).policy(document.to_string()).send().await
+                .context("set_bucket_policy", &bucket_name, None)?;
+        }
+        Ok(())
+    }
+
+    /// A bucket without tags is reported as empty
+    async fn get_bucket_labels(&self, bucket_name: String) -> ReqRes<HashMap<String, String>> {
+        match self.client.get_bucket_tagging().bucket(&bucket_name).send().await {
+            Ok(out) => Ok(out.tag_set.into_iter().map(|t| (t.key, t.value)).collect()),
+            Err(e) if e.as_service_error().and_then(|t| t.code()) == Some("NoSuchTagSet") => Ok(HashMap::new()),
+            Err(e) => Err(e).context("get_bucket_labels", &bucket_name, None)
         }
     }
+
+    /// Note: S3 allows up to 50 tags per bucket, an empty map deletes all tags
+    async fn set_bucket_labels(&self, bucket_name: String, labels: HashMap<String, String>) -> EmptyReqRes {
+        if labels.is_empty() {
+            self.client.delete_bucket_tagging().bucket(&bucket_name).send().await.context("set_bucket_labels", &bucket_name, None)?;
+            return Ok(())
+        }
+        let tag_set = labels.into_iter().map(|(key, value)| Tag::builder().key(key).value(value).build())
+            .collect::<Result<Vec<_>, _>>().context("set_bucket_labels", &bucket_name, None)?;
+        let tagging = Tagging::builder().set_tag_set(Some(tag_set)).build().context("set_bucket_labels", &bucket_name, None)?;
+        self.client.put_bucket_tagging().bucket(&bucket_name).tagging(tagging).send().await.context("set_bucket_labels", &bucket_name, None)?;
+        Ok(())
+    }
+
+    fn bucket_name_problems(&self, bucket_name: &str) -> Vec<BucketNameProblem> {
+        NamingRules::AwsS3.problems(bucket_name)
+    }
 }
\ No newline at end of file
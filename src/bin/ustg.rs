@@ -0,0 +1,73 @@
+//! Minimal command line client: lists and prints objects of any enabled provider
+//! `--no-sign` sends unsigned requests, so public datasets (example: 's3://landsat-pds/', 'gs://gcp-public-data-landsat/')
+//! can be browsed without any credentials configured
+
+use std::process::ExitCode;
+use futures::{pin_mut, StreamExt};
+use tokio::io::AsyncWriteExt;
+use uni_stg::{ClientInterface, ClientObject, Credentials, EmptyReqRes, ListOptions, StorageConfig, StorageUri};
+
+const USAGE: &str = "usage: ustg [--no-sign] [--region <region>] ls <uri>
+       ustg [--no-sign] [--region <region>] cat <uri>";
+
+struct Args {
+    credentials: Credentials,
+    region: Option<String>,
+    command: String,
+    uri: String,
+}
+
+fn parse(args: impl Iterator<Item = String>) -> Option<Args> {
+    let mut credentials = Credentials::Standard;
+    let mut region = None;
+    let mut positional = vec![];
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--no-sign" => credentials = Credentials::Anonymous,
+            "--region" => region = Some(args.next()?),
+            _ if arg.starts_with("--") => return None,
+            _ => positional.push(arg)
+        }
+    }
+    let [command, uri] = <[String; 2]>::try_from(positional).ok()?;
+    Some(Args { credentials, region, command, uri })
+}
+
+async fn run(args: Args) -> EmptyReqRes {
+    let uri = StorageUri::parse(&args.uri)?;
+    let config = StorageConfig { provider: uri.scheme.clone(), region: args.region, credentials: args.credentials, ..Default::default() };
+    let client = config.build().await?;
+    match args.command.as_str() {
+        "ls" => {
+            let options = ListOptions { prefix: Some(uri.key), ..Default::default() };
+            let objects = client.list_objects_stream(uri.bucket, options);
+            pin_mut!(objects);
+            while let Some(object) = objects.next().await {
+                let object = object?;
+                println!("{:>12}  {}", object.size(), object.name());
+            }
+        }
+        _ => {
+            let mut stdout = tokio::io::stdout();
+            client.download_to_writer(uri.bucket, uri.key, None, None, &mut stdout).await?;
+            stdout.flush().await?;
+        }
+    }
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let Some(args) = parse(std::env::args().skip(1)).filter(|t| matches!(t.command.as_str(), "ls" | "cat")) else {
+        eprintln!("{USAGE}");
+        return ExitCode::from(2)
+    };
+    match run(args).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("ustg: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
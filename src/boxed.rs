@@ -0,0 +1,561 @@
+use std::collections::HashMap;
+use std::time::SystemTime;
+use bytes::Bytes;
+use futures::future::BoxFuture;
+use futures::stream::BoxStream;
+use futures::{FutureExt, Stream, StreamExt};
+use tokio::io::{AsyncRead, AsyncWrite};
+use crate::{AccessControl, AclGrant, BucketNameProblem, BucketPolicy, BucketSpec, ClientBucket, ClientInterface, ClientObject, ComplianceRecord, CopyOptions, CopyProgress, Cursor, DownloadOptions, EmptyReqRes, LifecycleRule, ListAttributes, ListPage, MetadataChanges, ObjectLock, ObjectVersion, PostConstraints, PresignedPost, ReqRes, RestoreStatus, Retention, RetrievalTier, SignedUrlOptions, UploadOptions};
+
+/// An object returned by a `DynClient`
+pub type BoxedObject<'a> = Box<dyn ClientObject + 'a>;
+/// A bucket returned by a `DynClient`
+pub type BoxedBucket<'a> = Box<dyn ClientBucket + 'a>;
+
+impl<T: ClientObject + ?Sized> ClientObject for Box<T> {
+    fn size(&self) -> u64 {
+        (**self).size()
+    }
+
+    fn bucket_name(&self) -> String {
+        (**self).bucket_name()
+    }
+
+    fn id(&self) -> String {
+        (**self).id()
+    }
+
+    fn name(&self) -> String {
+        (**self).name()
+    }
+
+    fn content_type(&self) -> Option<String> {
+        (**self).content_type()
+    }
+
+    fn last_modified(&self) -> Option<SystemTime> {
+        (**self).last_modified()
+    }
+
+    fn etag(&self) -> Option<String> {
+        (**self).etag()
+    }
+
+    fn storage_class(&self) -> Option<String> {
+        (**self).storage_class()
+    }
+
+    fn custom_metadata(&self) -> HashMap<String, String> {
+        (**self).custom_metadata()
+    }
+
+    fn checksum(&self) -> Option<String> {
+        (**self).checksum()
+    }
+
+    fn version(&self) -> Option<String> {
+        (**self).version()
+    }
+}
+
+impl<T: ClientBucket + ?Sized> ClientBucket for Box<T> {
+    fn id(&self) -> String {
+        (**self).id()
+    }
+
+    fn name(&self) -> String {
+        (**self).name()
+    }
+
+    fn location(&self) -> Option<String> {
+        (**self).location()
+    }
+}
+
+fn object<'a>(res: ReqRes<impl ClientObject + 'a>) -> ReqRes<BoxedObject<'a>> {
+    res.map(|t| Box::new(t) as BoxedObject<'a>)
+}
+
+fn bucket<'a>(res: ReqRes<impl ClientBucket + 'a>) -> ReqRes<BoxedBucket<'a>> {
+    res.map(|t| Box::new(t) as BoxedBucket<'a>)
+}
+
+fn objects<'a>(res: ReqRes<ListPage<impl ClientObject + 'a>>) -> ReqRes<ListPage<BoxedObject<'a>>> {
+    res.map(|page| ListPage {
+        items: page.items.into_iter().map(|t| Box::new(t) as BoxedObject<'a>).collect(),
+        prefixes: page.prefixes,
+        next_token: page.next_token
+    })
+}
+
+/// Object safe version of `ClientInterface`, implemented for every client
+///
+/// Futures and streams are boxed, objects and buckets are returned as `BoxedObject` / `BoxedBucket`.
+/// Only the required methods (and defaults which clients override) are part of it,
+/// the other defaults of `BoxedClient` are built on top of them.
+pub trait DynClient: Send + Sync {
+    fn static_download_bytes(&self, bucket: String, object_id: String, starting: Option<u64>, ending: Option<u64>) -> BoxFuture<'_, ReqRes<Bytes>>;
+    fn static_upload_bytes(&self, bucket: String, object_id: String, data: Bytes) -> BoxFuture<'_, ReqRes<BoxedObject<'_>>>;
+    fn upload_with_options(&self, bucket: String, object_id: String, data: Bytes, options: UploadOptions) -> BoxFuture<'_, ReqRes<BoxedObject<'_>>>;
+    fn download_with_options(&self, bucket: String, object_id: String, starting: Option<u64>, ending: Option<u64>, options: DownloadOptions) -> BoxFuture<'_, ReqRes<Bytes>>;
+    fn download_to_writer<'a>(&'a self, bucket: String, object_id: String, starting: Option<u64>, ending: Option<u64>, w: Box<dyn AsyncWrite + Unpin + Send + 'a>) -> BoxFuture<'a, ReqRes<u64>>;
+    fn upload_from_reader(&self, bucket: String, object_id: String, r: Box<dyn AsyncRead + Unpin + Send + Sync>) -> BoxFuture<'_, EmptyReqRes>;
+    fn signed_url(&self, bucket: String, object_id: String, options: SignedUrlOptions) -> BoxFuture<'_, ReqRes<String>>;
+    fn presigned_post(&self, bucket: String, key_prefix: String, constraints: PostConstraints) -> BoxFuture<'_, ReqRes<PresignedPost>>;
+    fn remove_bucket(&self, bucket: String) -> BoxFuture<'_, EmptyReqRes>;
+    fn remove_object(&self, bucket: String, object_id: String) -> BoxFuture<'_, EmptyReqRes>;
+    fn create_bucket(&self, bucket: String) -> BoxFuture<'_, ReqRes<BoxedBucket<'_>>>;
+    fn copy_object(&self, src_bucket: String, src_object: String, dest_bucket: String, dest_object: String) -> BoxFuture<'_, ReqRes<BoxedObject<'_>>>;
+    fn copy_object_with_options(&self, src_bucket: String, src_object: String, dest_bucket: String, dest_object: String, options: CopyOptions) -> BoxFuture<'_, ReqRes<BoxedObject<'_>>>;
+    fn copy_object_progress(&self, src_bucket: String, src_object: String, dest_bucket: String, dest_object: String) -> BoxStream<'_, ReqRes<CopyProgress>>;
+    fn restore_object(&self, bucket_name: String, object_name: String, days: u32, tier: RetrievalTier) -> BoxFuture<'_, EmptyReqRes>;
+    fn restore_status(&self, bucket_name: String, object_name: String) -> BoxFuture<'_, ReqRes<RestoreStatus>>;
+    fn update_object_metadata(&self, bucket: String, object_id: String, changes: MetadataChanges) -> BoxFuture<'_, ReqRes<BoxedObject<'_>>>;
+    fn get_object_tags(&self, bucket: String, object_id: String) -> BoxFuture<'_, ReqRes<HashMap<String, String>>>;
+    fn set_object_tags(&self, bucket: String, object_id: String, tags: HashMap<String, String>) -> BoxFuture<'_, EmptyReqRes>;
+    fn get_object_acl(&self, bucket: String, object_id: String) -> BoxFuture<'_, ReqRes<Vec<AclGrant>>>;
+    fn set_object_acl(&self, bucket: String, object_id: String, acl: AccessControl) -> BoxFuture<'_, EmptyReqRes>;
+    fn append(&self, bucket: String, object_id: String, data: Bytes) -> BoxFuture<'_, EmptyReqRes>;
+    fn upload_public(&self, bucket: String, object_id: String, data: Bytes, content_type: Option<String>, cache_control: String) -> BoxFuture<'_, EmptyReqRes>;
+    fn public_url(&self, bucket: &str, object_id: &str) -> String;
+    fn list_buckets(&self, max_results: Option<u32>, page_token: Option<Cursor>) -> BoxFuture<'_, ReqRes<ListPage<BoxedBucket<'_>>>>;
+    fn get_bucket(&self, bucket_name: String) -> BoxFuture<'_, ReqRes<BoxedBucket<'_>>>;
+    fn get_object(&self, bucket_name: String, object_name: String) -> BoxFuture<'_, ReqRes<BoxedObject<'_>>>;
+    fn stat_object(&self, bucket_name: String, object_name: String) -> BoxFuture<'_, ReqRes<BoxedObject<'_>>>;
+    fn list_objects(&self, bucket_name: String, max_results: Option<u32>, page_token: Option<Cursor>) -> BoxFuture<'_, ReqRes<ListPage<BoxedObject<'_>>>>;
+    fn list_prefix(&self, bucket_name: String, prefix: String, page_token: Option<Cursor>) -> BoxFuture<'_, ReqRes<ListPage<BoxedObject<'_>>>>;
+    fn list_projected(&self, bucket_name: String, prefix: String, attributes: ListAttributes, page_token: Option<Cursor>) -> BoxFuture<'_, ReqRes<ListPage<BoxedObject<'_>>>>;
+    fn list_delimited(&self, bucket_name: String, prefix: String, delimiter: String, page_token: Option<Cursor>) -> BoxFuture<'_, ReqRes<ListPage<BoxedObject<'_>>>>;
+    fn list_after(&self, bucket_name: String, prefix: String, start_after: String, max_results: u32) -> BoxFuture<'_, ReqRes<Vec<BoxedObject<'_>>>>;
+    fn list_versions(&self, bucket_name: String, prefix: String, page_token: Option<Cursor>) -> BoxFuture<'_, ReqRes<ListPage<ObjectVersion>>>;
+    fn restore_object_version(&self, bucket_name: String, object_name: String, version: String) -> BoxFuture<'_, ReqRes<BoxedObject<'_>>>;
+    fn remove_objects(&self, bucket_name: String, keys: Vec<String>) -> BoxFuture<'_, ReqRes<Vec<(String, EmptyReqRes)>>>;
+    fn bucket_exists(&self, bucket_name: String) -> BoxFuture<'_, ReqRes<bool>>;
+    fn compliance_records(&self, bucket_name: String, page_token: Option<Cursor>) -> BoxFuture<'_, ReqRes<ListPage<ComplianceRecord>>>;
+    fn get_bucket_spec(&self, bucket_name: String) -> BoxFuture<'_, ReqRes<BucketSpec>>;
+    fn get_lifecycle_rules(&self, bucket_name: String) -> BoxFuture<'_, ReqRes<Vec<LifecycleRule>>>;
+    fn set_lifecycle_rules(&self, bucket_name: String, rules: Vec<LifecycleRule>) -> BoxFuture<'_, EmptyReqRes>;
+    fn get_object_lock(&self, bucket_name: String, object_name: String) -> BoxFuture<'_, ReqRes<ObjectLock>>;
+    fn set_object_retention(&self, bucket_name: String, object_name: String, retention: Option<Retention>) -> BoxFuture<'_, EmptyReqRes>;
+    fn set_legal_hold(&self, bucket_name: String, object_name: String, on: bool) -> BoxFuture<'_, EmptyReqRes>;
+    fn get_bucket_labels(&self, bucket_name: String) -> BoxFuture<'_, ReqRes<HashMap<String, String>>>;
+    fn set_bucket_labels(&self, bucket_name: String, labels: HashMap<String, String>) -> BoxFuture<'_, EmptyReqRes>;
+    fn get_bucket_policy(&self, bucket_name: String) -> BoxFuture<'_, ReqRes<Option<BucketPolicy>>>;
+    fn set_bucket_policy(&self, bucket_name: String, policy: BucketPolicy) -> BoxFuture<'_, EmptyReqRes>;
+    fn bucket_name_problems(&self, bucket_name: &str) -> Vec<BucketNameProblem>;
+}
+
+impl<C: ClientInterface> DynClient for C {
+    fn static_download_bytes(&self, bucket: String, object_id: String, starting: Option<u64>, ending: Option<u64>) -> BoxFuture<'_, ReqRes<Bytes>> {
+        ClientInterface::static_download_bytes(self, bucket, object_id, starting, ending).boxed()
+    }
+
+    fn static_upload_bytes(&self, bucket: String, object_id: String, data: Bytes) -> BoxFuture<'_, ReqRes<BoxedObject<'_>>> {
+        ClientInterface::static_upload_bytes(self, bucket, object_id, data).map(object).boxed()
+    }
+
+    fn upload_with_options(&self, bucket: String, object_id: String, data: Bytes, options: UploadOptions) -> BoxFuture<'_, ReqRes<BoxedObject<'_>>> {
+        ClientInterface::upload_with_options(self, bucket, object_id, data, options).map(object).boxed()
+    }
+
+    fn download_with_options(&self, bucket: String, object_id: String, starting: Option<u64>, ending: Option<u64>, options: DownloadOptions) -> BoxFuture<'_, ReqRes<Bytes>> {
+        ClientInterface::download_with_options(self, bucket, object_id, starting, ending, options).boxed()
+    }
+
+    fn download_to_writer<'a>(&'a self, bucket: String, object_id: String, starting: Option<u64>, ending: Option<u64>, w: Box<dyn AsyncWrite + Unpin + Send + 'a>) -> BoxFuture<'a, ReqRes<u64>> {
+        ClientInterface::download_to_writer(self, bucket, object_id, starting, ending, w).boxed()
+    }
+
+    fn upload_from_reader(&self, bucket: String, object_id: String, r: Box<dyn AsyncRead + Unpin + Send + Sync>) -> BoxFuture<'_, EmptyReqRes> {
+        ClientInterface::upload_from_reader(self, bucket, object_id, r).boxed()
+    }
+
+    fn signed_url(&self, bucket: String, object_id: String, options: SignedUrlOptions) -> BoxFuture<'_, ReqRes<String>> {
+        ClientInterface::signed_url(self, bucket, object_id, options).boxed()
+    }
+
+    fn presigned_post(&self, bucket: String, key_prefix: String, constraints: PostConstraints) -> BoxFuture<'_, ReqRes<PresignedPost>> {
+        ClientInterface::presigned_post(self, bucket, key_prefix, constraints).boxed()
+    }
+
+    fn remove_bucket(&self, bucket: String) -> BoxFuture<'_, EmptyReqRes> {
+        ClientInterface::remove_bucket(self, bucket).boxed()
+    }
+
+    fn remove_object(&self, bucket: String, object_id: String) -> BoxFuture<'_, EmptyReqRes> {
+        ClientInterface::remove_object(self, bucket, object_id).boxed()
+    }
+
+    fn create_bucket(&self, bucket: String) -> BoxFuture<'_, ReqRes<BoxedBucket<'_>>> {
+        ClientInterface::create_bucket(self, bucket).map(self::bucket).boxed()
+    }
+
+    fn copy_object(&self, src_bucket: String, src_object: String, dest_bucket: String, dest_object: String) -> BoxFuture<'_, ReqRes<BoxedObject<'_>>> {
+        ClientInterface::copy_object(self, src_bucket, src_object, dest_bucket, dest_object).map(object).boxed()
+    }
+
+    fn copy_object_with_options(&self, src_bucket: String, src_object: String, dest_bucket: String, dest_object: String, options: CopyOptions) -> BoxFuture<'_, ReqRes<BoxedObject<'_>>> {
+        ClientInterface::copy_object_with_options(self, src_bucket, src_object, dest_bucket, dest_object, options).map(object).boxed()
+    }
+
+    fn copy_object_progress(&self, src_bucket: String, src_object: String, dest_bucket: String, dest_object: String) -> BoxStream<'_, ReqRes<CopyProgress>> {
+        ClientInterface::copy_object_progress(self, src_bucket, src_object, dest_bucket, dest_object).boxed()
+    }
+
+    fn restore_object(&self, bucket_name: String, object_name: String, days: u32, tier: RetrievalTier) -> BoxFuture<'_, EmptyReqRes> {
+        ClientInterface::restore_object(self, bucket_name, object_name, days, tier).boxed()
+    }
+
+    fn restore_status(&self, bucket_name: String, object_name: String) -> BoxFuture<'_, ReqRes<RestoreStatus>> {
+        ClientInterface::restore_status(self, bucket_name, object_name).boxed()
+    }
+
+    fn update_object_metadata(&self, bucket: String, object_id: String, changes: MetadataChanges) -> BoxFuture<'_, ReqRes<BoxedObject<'_>>> {
+        ClientInterface::update_object_metadata(self, bucket, object_id, changes).map(object).boxed()
+    }
+
+    fn get_object_tags(&self, bucket: String, object_id: String) -> BoxFuture<'_, ReqRes<HashMap<String, String>>> {
+        ClientInterface::get_object_tags(self, bucket, object_id).boxed()
+    }
+
+    fn set_object_tags(&self, bucket: String, object_id: String, tags: HashMap<String, String>) -> BoxFuture<'_, EmptyReqRes> {
+        ClientInterface::set_object_tags(self, bucket, object_id, tags).boxed()
+    }
+
+    fn get_object_acl(&self, bucket: String, object_id: String) -> BoxFuture<'_, ReqRes<Vec<AclGrant>>> {
+        ClientInterface::get_object_acl(self, bucket, object_id).boxed()
+    }
+
+    fn set_object_acl(&self, bucket: String, object_id: String, acl: AccessControl) -> BoxFuture<'_, EmptyReqRes> {
+        ClientInterface::set_object_acl(self, bucket, object_id, acl).boxed()
+    }
+
+    fn append(&self, bucket: String, object_id: String, data: Bytes) -> BoxFuture<'_, EmptyReqRes> {
+        ClientInterface::append(self, bucket, object_id, data).boxed()
+    }
+
+    fn upload_public(&self, bucket: String, object_id: String, data: Bytes, content_type: Option<String>, cache_control: String) -> BoxFuture<'_, EmptyReqRes> {
+        ClientInterface::upload_public(self, bucket, object_id, data, content_type, cache_control).boxed()
+    }
+
+    fn public_url(&self, bucket: &str, object_id: &str) -> String {
+        ClientInterface::public_url(self, bucket, object_id)
+    }
+
+    fn list_buckets(&self, max_results: Option<u32>, page_token: Option<Cursor>) -> BoxFuture<'_, ReqRes<ListPage<BoxedBucket<'_>>>> {
+        ClientInterface::list_buckets(self, max_results, page_token).map(|res| res.map(|page| ListPage {
+            items: page.items.into_iter().map(|t| Box::new(t) as BoxedBucket<'_>).collect(),
+            prefixes: page.prefixes,
+            next_token: page.next_token
+        })).boxed()
+    }
+
+    fn get_bucket(&self, bucket_name: String) -> BoxFuture<'_, ReqRes<BoxedBucket<'_>>> {
+        ClientInterface::get_bucket(self, bucket_name).map(bucket).boxed()
+    }
+
+    fn get_object(&self, bucket_name: String, object_name: String) -> BoxFuture<'_, ReqRes<BoxedObject<'_>>> {
+        ClientInterface::get_object(self, bucket_name, object_name).map(object).boxed()
+    }
+
+    fn stat_object(&self, bucket_name: String, object_name: String) -> BoxFuture<'_, ReqRes<BoxedObject<'_>>> {
+        ClientInterface::stat_object(self, bucket_name, object_name).map(object).boxed()
+    }
+
+    fn list_objects(&self, bucket_name: String, max_results: Option<u32>, page_token: Option<Cursor>) -> BoxFuture<'_, ReqRes<ListPage<BoxedObject<'_>>>> {
+        ClientInterface::list_objects(self, bucket_name, max_results, page_token).map(objects).boxed()
+    }
+
+    fn list_prefix(&self, bucket_name: String, prefix: String, page_token: Option<Cursor>) -> BoxFuture<'_, ReqRes<ListPage<BoxedObject<'_>>>> {
+        ClientInterface::list_prefix(self, bucket_name, prefix, page_token).map(objects).boxed()
+    }
+
+    fn list_projected(&self, bucket_name: String, prefix: String, attributes: ListAttributes, page_token: Option<Cursor>) -> BoxFuture<'_, ReqRes<ListPage<BoxedObject<'_>>>> {
+        ClientInterface::list_projected(self, bucket_name, prefix, attributes, page_token).map(objects).boxed()
+    }
+
+    fn list_delimited(&self, bucket_name: String, prefix: String, delimiter: String, page_token: Option<Cursor>) -> BoxFuture<'_, ReqRes<ListPage<BoxedObject<'_>>>> {
+        ClientInterface::list_delimited(self, bucket_name, prefix, delimiter, page_token).map(objects).boxed()
+    }
+
+    fn list_after(&self, bucket_name: String, prefix: String, start_after: String, max_results: u32) -> BoxFuture<'_, ReqRes<Vec<BoxedObject<'_>>>> {
+        ClientInterface::list_after(self, bucket_name, prefix, start_after, max_results)
+            .map(|res| res.map(|items| items.into_iter().map(|t| Box::new(t) as BoxedObject<'_>).collect())).boxed()
+    }
+
+    fn list_versions(&self, bucket_name: String, prefix: String, page_token: Option<Cursor>) -> BoxFuture<'_, ReqRes<ListPage<ObjectVersion>>> {
+        ClientInterface::list_versions(self, bucket_name, prefix, page_token).boxed()
+    }
+
+    fn restore_object_version(&self, bucket_name: String, object_name: String, version: String) -> BoxFuture<'_, ReqRes<BoxedObject<'_>>> {
+        ClientInterface::restore_object_version(self, bucket_name, object_name, version).map(object).boxed()
+    }
+
+    fn remove_objects(&self, bucket_name: String, keys: Vec<String>) -> BoxFuture<'_, ReqRes<Vec<(String, EmptyReqRes)>>> {
+        ClientInterface::remove_objects(self, bucket_name, keys).boxed()
+    }
+
+    fn bucket_exists(&self, bucket_name: String) -> BoxFuture<'_, ReqRes<bool>> {
+        ClientInterface::bucket_exists(self, bucket_name).boxed()
+    }
+
+    fn compliance_records(&self, bucket_name: String, page_token: Option<Cursor>) -> BoxFuture<'_, ReqRes<ListPage<ComplianceRecord>>> {
+        ClientInterface::compliance_records(self, bucket_name, page_token).boxed()
+    }
+
+    fn get_bucket_spec(&self, bucket_name: String) -> BoxFuture<'_, ReqRes<BucketSpec>> {
+        ClientInterface::get_bucket_spec(self, bucket_name).boxed()
+    }
+
+    fn get_lifecycle_rules(&self, bucket_name: String) -> BoxFuture<'_, ReqRes<Vec<LifecycleRule>>> {
+        ClientInterface::get_lifecycle_rules(self, bucket_name).boxed()
+    }
+
+    fn set_lifecycle_rules(&self, bucket_name: String, rules: Vec<LifecycleRule>) -> BoxFuture<'_, EmptyReqRes> {
+        ClientInterface::set_lifecycle_rules(self, bucket_name, rules).boxed()
+    }
+
+    fn get_object_lock(&self, bucket_name: String, object_name: String) -> BoxFuture<'_, ReqRes<ObjectLock>> {
+        ClientInterface::get_object_lock(self, bucket_name, object_name).boxed()
+    }
+
+    fn set_object_retention(&self, bucket_name: String, object_name: String, retention: Option<Retention>) -> BoxFuture<'_, EmptyReqRes> {
+        ClientInterface::set_object_retention(self, bucket_name, object_name, retention).boxed()
+    }
+
+    fn set_legal_hold(&self, bucket_name: String, object_name: String, on: bool) -> BoxFuture<'_, EmptyReqRes> {
+        ClientInterface::set_legal_hold(self, bucket_name, object_name, on).boxed()
+    }
+
+    fn get_bucket_labels(&self, bucket_name: String) -> BoxFuture<'_, ReqRes<HashMap<String, String>>> {
+        ClientInterface::get_bucket_labels(self, bucket_name).boxed()
+    }
+
+    fn set_bucket_labels(&self, bucket_name: String, labels: HashMap<String, String>) -> BoxFuture<'_, EmptyReqRes> {
+        ClientInterface::set_bucket_labels(self, bucket_name, labels).boxed()
+    }
+
+    fn get_bucket_policy(&self, bucket_name: String) -> BoxFuture<'_, ReqRes<Option<BucketPolicy>>> {
+        ClientInterface::get_bucket_policy(self, bucket_name).boxed()
+    }
+
+    fn set_bucket_policy(&self, bucket_name: String, policy: BucketPolicy) -> BoxFuture<'_, EmptyReqRes> {
+        ClientInterface::set_bucket_policy(self, bucket_name, policy).boxed()
+    }
+
+    fn bucket_name_problems(&self, bucket_name: &str) -> Vec<BucketNameProblem> {
+        ClientInterface::bucket_name_problems(self, bucket_name)
+    }
+}
+
+/// A client of any provider behind a `dyn DynClient`, so clients can be stored in structs and registries without generics
+/// It implements `ClientInterface` itself, objects and buckets it returns are boxed (see `BoxedObject`)
+pub struct BoxedClient(Box<dyn DynClient>);
+
+impl BoxedClient {
+    pub fn new(client: impl ClientInterface + 'static) -> Self {
+        Self(Box::new(client))
+    }
+
+    pub fn inner(&self) -> &dyn DynClient {
+        &*self.0
+    }
+}
+
+impl ClientInterface for BoxedClient {
+    async fn static_download_bytes(&self, bucket: String, object_id: String, starting: Option<u64>, ending: Option<u64>) -> ReqRes<Bytes> {
+        self.0.static_download_bytes(bucket, object_id, starting, ending).await
+    }
+
+    async fn static_upload_bytes(&self, bucket: String, object_id: String, data: Bytes) -> ReqRes<impl ClientObject> {
+        self.0.static_upload_bytes(bucket, object_id, data).await
+    }
+
+    async fn upload_with_options(&self, bucket: String, object_id: String, data: Bytes, options: UploadOptions) -> ReqRes<impl ClientObject> {
+        self.0.upload_with_options(bucket, object_id, data, options).await
+    }
+
+    async fn download_with_options(&self, bucket: String, object_id: String, starting: Option<u64>, ending: Option<u64>, options: DownloadOptions) -> ReqRes<Bytes> {
+        self.0.download_with_options(bucket, object_id, starting, ending, options).await
+    }
+
+    async fn download_to_writer(&self, bucket: String, object_id: String, starting: Option<u64>, ending: Option<u64>, w: impl AsyncWrite + Unpin + Send) -> ReqRes<u64> {
+        self.0.download_to_writer(bucket, object_id, starting, ending, Box::new(w)).await
+    }
+
+    async fn upload_from_reader(&self, bucket: String, object_id: String, r: impl AsyncRead + Unpin + Send + Sync + 'static) -> EmptyReqRes {
+        self.0.upload_from_reader(bucket, object_id, Box::new(r)).await
+    }
+
+    async fn signed_url(&self, bucket: String, object_id: String, options: SignedUrlOptions) -> ReqRes<String> {
+        self.0.signed_url(bucket, object_id, options).await
+    }
+
+    async fn presigned_post(&self, bucket: String, key_prefix: String, constraints: PostConstraints) -> ReqRes<PresignedPost> {
+        self.0.presigned_post(bucket, key_prefix, constraints).await
+    }
+
+    async fn remove_bucket(&self, bucket: String) -> EmptyReqRes {
+        self.0.remove_bucket(bucket).await
+    }
+
+    async fn remove_object(&self, bucket: String, object_id: String) -> EmptyReqRes {
+        self.0.remove_object(bucket, object_id).await
+    }
+
+    async fn create_bucket(&self, bucket: String) -> ReqRes<impl ClientBucket> {
+        self.0.create_bucket(bucket).await
+    }
+
+    async fn copy_object(&self, src_bucket: String, src_object: String, dest_bucket: String, dest_object: String) -> ReqRes<impl ClientObject> {
+        self.0.copy_object(src_bucket, src_object, dest_bucket, dest_object).await
+    }
+
+    async fn copy_object_with_options(&self, src_bucket: String, src_object: String, dest_bucket: String, dest_object: String, options: CopyOptions) -> ReqRes<impl ClientObject> {
+        self.0.copy_object_with_options(src_bucket, src_object, dest_bucket, dest_object, options).await
+    }
+
+    fn copy_object_progress(&self, src_bucket: String, src_object: String, dest_bucket: String, dest_object: String) -> impl Stream<Item = ReqRes<CopyProgress>> + Send {
+        self.0.copy_object_progress(src_bucket, src_object, dest_bucket, dest_object)
+    }
+
+    async fn restore_object(&self, bucket_name: String, object_name: String, days: u32, tier: RetrievalTier) -> EmptyReqRes {
+        self.0.restore_object(bucket_name, object_name, days, tier).await
+    }
+
+    async fn restore_status(&self, bucket_name: String, object_name: String) -> ReqRes<RestoreStatus> {
+        self.0.restore_status(bucket_name, object_name).await
+    }
+
+    async fn update_object_metadata(&self, bucket: String, object_id: String, changes: MetadataChanges) -> ReqRes<impl ClientObject> {
+        self.0.update_object_metadata(bucket, object_id, changes).await
+    }
+
+    async fn get_object_tags(&self, bucket: String, object_id: String) -> ReqRes<HashMap<String, String>> {
+        self.0.get_object_tags(bucket, object_id).await
+    }
+
+    async fn set_object_tags(&self, bucket: String, object_id: String, tags: HashMap<String, String>) -> EmptyReqRes {
+        self.0.set_object_tags(bucket, object_id, tags).await
+    }
+
+    async fn get_object_acl(&self, bucket: String, object_id: String) -> ReqRes<Vec<AclGrant>> {
+        self.0.get_object_acl(bucket, object_id).await
+    }
+
+    async fn set_object_acl(&self, bucket: String, object_id: String, acl: AccessControl) -> EmptyReqRes {
+        self.0.set_object_acl(bucket, object_id, acl).await
+    }
+
+    async fn append(&self, bucket: String, object_id: String, data: Bytes) -> EmptyReqRes {
+        self.0.append(bucket, object_id, data).await
+    }
+
+    async fn upload_public(&self, bucket: String, object_id: String, data: Bytes, content_type: Option<String>, cache_control: String) -> EmptyReqRes {
+        self.0.upload_public(bucket, object_id, data, content_type, cache_control).await
+    }
+
+    fn public_url(&self, bucket: &str, object_id: &str) -> String {
+        self.0.public_url(bucket, object_id)
+    }
+
+    async fn list_buckets(&self, max_results: Option<u32>, page_token: Option<Cursor>) -> ReqRes<ListPage<impl ClientBucket>> {
+        self.0.list_buckets(max_results, page_token).await
+    }
+
+    async fn get_bucket(&self, bucket_name: String) -> ReqRes<impl ClientBucket> {
+        self.0.get_bucket(bucket_name).await
+    }
+
+    async fn get_object(&self, bucket_name: String, object_name: String) -> ReqRes<impl ClientObject> {
+        self.0.get_object(bucket_name, object_name).await
+    }
+
+    async fn stat_object(&self, bucket_name: String, object_name: String) -> ReqRes<impl ClientObject> {
+        self.0.stat_object(bucket_name, object_name).await
+    }
+
+    async fn list_objects(&self, bucket_name: String, max_results: Option<u32>, page_token: Option<Cursor>) -> ReqRes<ListPage<impl ClientObject>> {
+        self.0.list_objects(bucket_name, max_results, page_token).await
+    }
+
+    async fn list_prefix(&self, bucket_name: String, prefix: String, page_token: Option<Cursor>) -> ReqRes<ListPage<impl ClientObject>> {
+        self.0.list_prefix(bucket_name, prefix, page_token).await
+    }
+
+    async fn list_projected(&self, bucket_name: String, prefix: String, attributes: ListAttributes, page_token: Option<Cursor>) -> ReqRes<ListPage<impl ClientObject>> {
+        self.0.list_projected(bucket_name, prefix, attributes, page_token).await
+    }
+
+    async fn list_delimited(&self, bucket_name: String, prefix: String, delimiter: String, page_token: Option<Cursor>) -> ReqRes<ListPage<impl ClientObject>> {
+        self.0.list_delimited(bucket_name, prefix, delimiter, page_token).await
+    }
+
+    async fn list_after(&self, bucket_name: String, prefix: String, start_after: String, max_results: u32) -> ReqRes<Vec<impl ClientObject>> {
+        self.0.list_after(bucket_name, prefix, start_after, max_results).await
+    }
+
+    async fn list_versions(&self, bucket_name: String, prefix: String, page_token: Option<Cursor>) -> ReqRes<ListPage<ObjectVersion>> {
+        self.0.list_versions(bucket_name, prefix, page_token).await
+    }
+
+    async fn restore_object_version(&self, bucket_name: String, object_name: String, version: String) -> ReqRes<impl ClientObject> {
+        self.0.restore_object_version(bucket_name, object_name, version).await
+    }
+
+    async fn remove_objects(&self, bucket_name: String, keys: Vec<String>) -> ReqRes<Vec<(String, EmptyReqRes)>> {
+        self.0.remove_objects(bucket_name, keys).await
+    }
+
+    async fn bucket_exists(&self, bucket_name: String) -> ReqRes<bool> {
+        self.0.bucket_exists(bucket_name).await
+    }
+
+    async fn compliance_records(&self, bucket_name: String, page_token: Option<Cursor>) -> ReqRes<ListPage<ComplianceRecord>> {
+        self.0.compliance_records(bucket_name, page_token).await
+    }
+
+    async fn get_bucket_spec(&self, bucket_name: String) -> ReqRes<BucketSpec> {
+        self.0.get_bucket_spec(bucket_name).await
+    }
+
+    async fn get_lifecycle_rules(&self, bucket_name: String) -> ReqRes<Vec<LifecycleRule>> {
+        self.0.get_lifecycle_rules(bucket_name).await
+    }
+
+    async fn set_lifecycle_rules(&self, bucket_name: String, rules: Vec<LifecycleRule>) -> EmptyReqRes {
+        self.0.set_lifecycle_rules(bucket_name, rules).await
+    }
+
+    async fn get_object_lock(&self, bucket_name: String, object_name: String) -> ReqRes<ObjectLock> {
+        self.0.get_object_lock(bucket_name, object_name).await
+    }
+
+    async fn set_object_retention(&self, bucket_name: String, object_name: String, retention: Option<Retention>) -> EmptyReqRes {
+        self.0.set_object_retention(bucket_name, object_name, retention).await
+    }
+
+    async fn set_legal_hold(&self, bucket_name: String, object_name: String, on: bool) -> EmptyReqRes {
+        self.0.set_legal_hold(bucket_name, object_name, on).await
+    }
+
+    async fn get_bucket_labels(&self, bucket_name: String) -> ReqRes<HashMap<String, String>> {
+        self.0.get_bucket_labels(bucket_name).await
+    }
+
+    async fn set_bucket_labels(&self, bucket_name: String, labels: HashMap<String, String>) -> EmptyReqRes {
+        self.0.set_bucket_labels(bucket_name, labels).await
+    }
+
+    async fn get_bucket_policy(&self, bucket_name: String) -> ReqRes<Option<BucketPolicy>> {
+        self.0.get_bucket_policy(bucket_name).await
+    }
+
+    async fn set_bucket_policy(&self, bucket_name: String, policy: BucketPolicy) -> EmptyReqRes {
+        self.0.set_bucket_policy(bucket_name, policy).await
+    }
+
+    fn bucket_name_problems(&self, bucket_name: &str) -> Vec<BucketNameProblem> {
+        self.0.bucket_name_problems(bucket_name)
+    }
+}
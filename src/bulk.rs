@@ -0,0 +1,46 @@
+use crate::{ClientError, EmptyReqRes};
+
+/// Outcome of an operation applied to many objects
+#[derive(Default)]
+pub struct BulkReport {
+    /// Number of objects the operation succeeded on
+    pub succeeded: u64,
+    /// Objects the operation failed on
+    pub failed: Vec<(String, ClientError)>,
+}
+
+impl BulkReport {
+    /// Adds per-key results to the report
+    pub fn extend(&mut self, results: Vec<(String, EmptyReqRes)>) {
+        for (key, result) in results {
+            match result {
+                Ok(()) => self.succeeded += 1,
+                Err(e) => self.failed.push((key, e))
+            }
+        }
+    }
+
+    /// Whether the operation succeeded on every object
+    pub fn is_complete(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+/// Number and total size of the objects below a prefix
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PrefixStats {
+    pub objects: u64,
+    pub bytes: u64,
+}
+
+impl PrefixStats {
+    pub(crate) fn add(&mut self, size: u64) {
+        self.objects += 1;
+        self.bytes += size;
+    }
+
+    pub(crate) fn merge(&mut self, other: PrefixStats) {
+        self.objects += other.objects;
+        self.bytes += other.bytes;
+    }
+}
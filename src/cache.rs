@@ -0,0 +1,444 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use bytes::Bytes;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
+use crate::delegate::delegate;
+use crate::encoding::{hex, slice_range};
+use crate::{CacheValidator, ClientInterface, ClientObject, Context, CopyOptions, DownloadOptions, EmptyReqRes, MetadataChanges, ReqRes, UploadOptions};
+
+/// Stored next to every cached object, so the cache survives restarts
+#[derive(Serialize, Deserialize)]
+struct Meta {
+    bucket: String,
+    key: String,
+    etag: String,
+    size: u64,
+}
+
+struct Entry {
+    etag: String,
+    size: u64,
+    /// Name of the files (without extension)
+    file: String,
+    /// Value of `Index::clock` when the entry was last read
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct Index {
+    entries: HashMap<(String, String), Entry>,
+    total: u64,
+    clock: u64,
+}
+
+/// Numbers the temporary files of this process
+static TEMPORARY: AtomicU64 = AtomicU64::new(0);
+
+/// Name of the cache files of an object
+fn file_name(bucket: &str, key: &str) -> String {
+    hex(&Sha256::new().chain_update(bucket).chain_update([0u8]).chain_update(key).finalize())
+}
+
+/// Wraps a client and caches downloaded objects on local disk, for workloads which read the same objects repeatedly
+///
+/// Cached objects are validated with a conditional request (`If-None-Match` with their ETag) before every read,
+/// so reads stay consistent but save the transfer. Objects are only cached once their content is pinned to an ETag,
+/// the least recently read ones are evicted when the cache grows beyond its size limit.
+/// Writes through this client invalidate the cached copy once they completed, ranged reads are only served from objects which are cached already
+/// and conditional or versioned downloads bypass the cache.
+pub struct CachedClient<C: ClientInterface> {
+    inner: C,
+    dir: PathBuf,
+    max_bytes: u64,
+    index: Mutex<Index>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl<C: ClientInterface> CachedClient<C> {
+    /// Uses (and creates) the cache directory `dir`, objects cached there by an earlier instance are reused
+    /// Objects larger than `max_bytes` are never cached
+    pub async fn open(inner: C, dir: impl AsRef<Path>, max_bytes: u64) -> ReqRes<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        tokio::fs::create_dir_all(&dir).await?;
+        let mut index = Index::default();
+        let mut files = tokio::fs::read_dir(&dir).await?;
+        while let Some(file) = files.next_entry().await? {
+            let path = file.path();
+            // Left behind by a crash while storing
+            if path.extension().is_some_and(|t| t == "tmp") {
+                let _ = tokio::fs::remove_file(&path).await;
+            }
+            // Left behind by a crash (or a failed write) between storing the data and its meta file
+            if path.extension().is_some_and(|t| t == "data") && !tokio::fs::try_exists(path.with_extension("meta")).await.unwrap_or(true) {
+                let _ = tokio::fs::remove_file(&path).await;
+            }
+            if path.extension().is_none_or(|t| t != "meta") {
+                continue
+            }
+            let meta: Option<Meta> = tokio::fs::read(&path).await.ok().and_then(|t| serde_json::from_slice(&t).ok());
+            let data_len = tokio::fs::metadata(path.with_extension("data")).await.map(|t| t.len()).ok();
+            match meta {
+                Some(meta) if data_len == Some(meta.size) => {
+                    let file = file_name(&meta.bucket, &meta.key);
+                    index.total += meta.size;
+                    index.entries.insert((meta.bucket, meta.key), Entry { etag: meta.etag, size: meta.size, file, last_used: 0 });
+                }
+                // Partially written or corrupt
+                _ => {
+                    let _ = tokio::fs::remove_file(&path).await;
+                    let _ = tokio::fs::remove_file(path.with_extension("data")).await;
+                }
+            }
+        }
+        let client = Self { inner, dir, max_bytes, index: Mutex::new(index), hits: AtomicU64::new(0), misses: AtomicU64::new(0) };
+        client.evict().await;
+        Ok(client)
+    }
+
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    /// Number of reads which were answered from the cache
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// Number of reads which downloaded the object
+    pub fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+
+    /// Total size of the cached objects
+    pub fn cached_bytes(&self) -> u64 {
+        self.index().total
+    }
+
+    /// Removes every cached object
+    pub async fn clear(&self) {
+        let files: Vec<String> = {
+            let mut index = self.index();
+            index.total = 0;
+            index.entries.drain().map(|(_, t)| t.file).collect()
+        };
+        for file in files {
+            self.remove_files(&file).await;
+        }
+    }
+
+    fn index(&self) -> std::sync::MutexGuard<'_, Index> {
+        self.index.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn path(&self, file: &str, extension: &str) -> PathBuf {
+        self.dir.join(file).with_extension(extension)
+    }
+
+    /// Best effort, files which cannot be removed are overwritten or cleaned up by the next `open`
+    async fn remove_files(&self, file: &str) {
+        let _ = tokio::fs::remove_file(self.path(file, "meta")).await;
+        let _ = tokio::fs::remove_file(self.path(file, "data")).await;
+    }
+
+    /// Drops the cached copy of an object
+    async fn forget(&self, bucket: &str, key: &str) {
+        let removed = {
+            let mut index = self.index();
+            let removed = index.entries.remove(&(bucket.to_string(), key.to_string()));
+            if let Some(entry) = &removed {
+                index.total -= entry.size;
+            }
+            removed
+        };
+        if let Some(entry) = removed {
+            self.remove_files(&entry.file).await;
+        }
+    }
+
+    /// Removes the least recently used objects until the cache fits its size limit
+    async fn evict(&self) {
+        let mut evicted = vec![];
+        {
+            let mut index = self.index();
+            while index.total > self.max_bytes {
+                let Some(oldest) = index.entries.iter().min_by_key(|(_, t)| t.last_used).map(|(k, _)| k.clone()) else {
+                    break
+                };
+                if let Some(entry) = index.entries.remove(&oldest) {
+                    index.total -= entry.size;
+                    evicted.push(entry.file);
+                }
+            }
+        }
+        for file in evicted {
+            self.remove_files(&file).await;
+        }
+    }
+
+    /// ETag and file of the cached copy, marking it as used
+    fn cached(&self, bucket: &str, key: &str) -> Option<(String, String)> {
+        let mut index = self.index();
+        index.clock += 1;
+        let clock = index.clock;
+        let entry = index.entries.get_mut(&(bucket.to_string(), key.to_string()))?;
+        entry.last_used = clock;
+        Some((entry.etag.clone(), entry.file.clone()))
+    }
+
+    async fn store(&self, bucket: String, key: String, etag: String, data: &[u8]) -> EmptyReqRes {
+        let file = file_name(&bucket, &key);
+        self.forget(&bucket, &key).await;
+        // Written under a temporary name first, so a crash never leaves a truncated object behind,
+        // which is unique, so concurrent stores (also of other processes) do not write into the same file
+        let temporary = self.path(&format!("{file}.{}-{}", std::process::id(), TEMPORARY.fetch_add(1, Ordering::Relaxed)), "tmp");
+        let mut out = tokio::fs::File::create(&temporary).await?;
+        out.write_all(data).await?;
+        out.flush().await?;
+        tokio::fs::rename(&temporary, self.path(&file, "data")).await?;
+        let meta = Meta { bucket: bucket.clone(), key: key.clone(), etag: etag.clone(), size: data.len() as u64 };
+        if let Err(e) = tokio::fs::write(self.path(&file, "meta"), serde_json::to_vec(&meta)?).await {
+            self.remove_files(&file).await;
+            return Err(e.into())
+        }
+        {
+            let mut index = self.index();
+            index.clock += 1;
+            let last_used = index.clock;
+            index.total += meta.size;
+            // Another read of the same object may have stored it meanwhile (into the same files)
+            if let Some(replaced) = index.entries.insert((bucket, key), Entry { etag, size: meta.size, file, last_used }) {
+                index.total -= replaced.size;
+            }
+        }
+        self.evict().await;
+        Ok(())
+    }
+
+    /// Reads a whole object, from the cache if the cached copy is current
+    async fn read(&self, bucket: String, key: String) -> ReqRes<Bytes> {
+        if let Some((etag, file)) = self.cached(&bucket, &key) {
+            match self.inner.download_if_modified(bucket.clone(), key.clone(), CacheValidator::ETag(etag)).await {
+                Ok(None) => match tokio::fs::read(self.path(&file, "data")).await {
+                    Ok(data) => {
+                        self.hits.fetch_add(1, Ordering::Relaxed);
+                        return Ok(data.into())
+                    }
+                    // Removed behind the cache's back, downloaded again below
+                    Err(_) => self.forget(&bucket, &key).await
+                },
+                // Changed, the new version is cached by the next read once its ETag is known
+                Ok(Some(data)) => {
+                    self.forget(&bucket, &key).await;
+                    self.misses.fetch_add(1, Ordering::Relaxed);
+                    return Ok(data.into())
+                }
+                Err(e) => {
+                    if e.is_not_found() {
+                        self.forget(&bucket, &key).await;
+                    }
+                    return Err(e)
+                }
+            }
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let object = self.inner.stat_object(bucket.clone(), key.clone()).await?;
+        let Some(etag) = object.etag().filter(|_| object.size() <= self.max_bytes) else {
+            return self.inner.static_download_bytes(bucket, key, None, None).await
+        };
+        // Pinned to the ETag, so the cached data always belongs to it
+        let options = DownloadOptions { if_match: Some(etag.clone()), ..Default::default() };
+        let data = match self.inner.download_with_options(bucket.clone(), key.clone(), None, None, options).await {
+            Err(e) if e.is_precondition_failed() => return self.inner.static_download_bytes(bucket, key, None, None).await,
+            data => data?
+        };
+        // A full disk only costs the cache
+        let _ = self.store(bucket, key, etag, &data).await;
+        Ok(data)
+    }
+}
+
+impl<C: ClientInterface> ClientInterface for CachedClient<C> {
+    async fn static_download_bytes(&self, bucket: String, object_id: String, starting: Option<u64>, ending: Option<u64>) -> ReqRes<Bytes> {
+        let ranged = starting.is_some() || ending.is_some();
+        if ranged && self.cached(&bucket, &object_id).is_none() {
+            return self.inner.static_download_bytes(bucket, object_id, starting, ending).await
+        }
+        Ok(slice_range(self.read(bucket, object_id).await?, starting, ending))
+    }
+
+    /// Conditional and versioned downloads bypass the cache
+    async fn download_with_options(&self, bucket: String, object_id: String, starting: Option<u64>, ending: Option<u64>, options: DownloadOptions) -> ReqRes<Bytes> {
+        if options.is_conditional() || options.version.is_some() {
+            return self.inner.download_with_options(bucket, object_id, starting, ending, options).await
+        }
+        self.static_download_bytes(bucket, object_id, starting, ending).await
+    }
+
+    /// Objects which are not cached yet are streamed without caching them
+    async fn download_to_writer(&self, bucket: String, object_id: String, starting: Option<u64>, ending: Option<u64>, mut w: impl AsyncWrite + Unpin + Send) -> ReqRes<u64> {
+        if self.cached(&bucket, &object_id).is_none() {
+            return self.inner.download_to_writer(bucket, object_id, starting, ending, w).await
+        }
+        let data = self.static_download_bytes(bucket.clone(), object_id.clone(), starting, ending).await?;
+        w.write_all(&data).await.context("download_to_writer", &bucket, Some(&object_id))?;
+        w.flush().await.context("download_to_writer", &bucket, Some(&object_id))?;
+        Ok(data.len() as u64)
+    }
+
+    async fn static_upload_bytes(&self, bucket: String, object_id: String, data: Bytes) -> ReqRes<impl ClientObject> {
+        let written = self.inner.static_upload_bytes(bucket.clone(), object_id.clone(), data).await;
+        self.forget(&bucket, &object_id).await;
+        written
+    }
+
+    async fn upload_with_options(&self, bucket: String, object_id: String, data: Bytes, options: UploadOptions) -> ReqRes<impl ClientObject> {
+        let written = self.inner.upload_with_options(bucket.clone(), object_id.clone(), data, options).await;
+        self.forget(&bucket, &object_id).await;
+        written
+    }
+
+    async fn upload_from_reader(&self, bucket: String, object_id: String, r: impl AsyncRead + Unpin + Send + Sync + 'static) -> EmptyReqRes {
+        let written = self.inner.upload_from_reader(bucket.clone(), object_id.clone(), r).await;
+        self.forget(&bucket, &object_id).await;
+        written
+    }
+
+    async fn append(&self, bucket: String, object_id: String, data: Bytes) -> EmptyReqRes {
+        let written = self.inner.append(bucket.clone(), object_id.clone(), data).await;
+        self.forget(&bucket, &object_id).await;
+        written
+    }
+
+    async fn upload_public(&self, bucket: String, object_id: String, data: Bytes, content_type: Option<String>, cache_control: String) -> EmptyReqRes {
+        let written = self.inner.upload_public(bucket.clone(), object_id.clone(), data, content_type, cache_control).await;
+        self.forget(&bucket, &object_id).await;
+        written
+    }
+
+    async fn remove_object(&self, bucket: String, object_id: String) -> EmptyReqRes {
+        let written = self.inner.remove_object(bucket.clone(), object_id.clone()).await;
+        self.forget(&bucket, &object_id).await;
+        written
+    }
+
+    async fn remove_objects(&self, bucket_name: String, keys: Vec<String>) -> ReqRes<Vec<(String, EmptyReqRes)>> {
+        let removed = self.inner.remove_objects(bucket_name.clone(), keys.clone()).await;
+        for key in &keys {
+            self.forget(&bucket_name, key).await;
+        }
+        removed
+    }
+
+    async fn copy_object(&self, src_bucket: String, src_object: String, dest_bucket: String, dest_object: String) -> ReqRes<impl ClientObject> {
+        let written = self.inner.copy_object(src_bucket, src_object, dest_bucket.clone(), dest_object.clone()).await;
+        self.forget(&dest_bucket, &dest_object).await;
+        written
+    }
+
+    async fn copy_object_with_options(&self, src_bucket: String, src_object: String, dest_bucket: String, dest_object: String, options: CopyOptions) -> ReqRes<impl ClientObject> {
+        let written = self.inner.copy_object_with_options(src_bucket, src_object, dest_bucket.clone(), dest_object.clone(), options).await;
+        self.forget(&dest_bucket, &dest_object).await;
+        written
+    }
+
+    /// Drops the cached destination with every update, the last one is sent once the copy completed
+    fn copy_object_progress(&self, src_bucket: String, src_object: String, dest_bucket: String, dest_object: String) -> impl futures::Stream<Item = ReqRes<crate::CopyProgress>> + Send {
+        self.inner.copy_object_progress(src_bucket, src_object, dest_bucket.clone(), dest_object.clone()).then(move |progress| {
+            let (bucket, key) = (dest_bucket.clone(), dest_object.clone());
+            async move {
+                self.forget(&bucket, &key).await;
+                progress
+            }
+        })
+    }
+
+    async fn update_object_metadata(&self, bucket: String, object_id: String, changes: MetadataChanges) -> ReqRes<impl ClientObject> {
+        let written = self.inner.update_object_metadata(bucket.clone(), object_id.clone(), changes).await;
+        self.forget(&bucket, &object_id).await;
+        written
+    }
+
+    async fn restore_object_version(&self, bucket_name: String, object_name: String, version: String) -> ReqRes<impl ClientObject> {
+        let written = self.inner.restore_object_version(bucket_name.clone(), object_name.clone(), version).await;
+        self.forget(&bucket_name, &object_name).await;
+        written
+    }
+
+    delegate!(inner;
+        get_object_tags, set_object_tags, get_object_acl, set_object_acl, public_url, signed_url, presigned_post, remove_bucket, create_bucket, restore_object, restore_status,
+        list_buckets, get_bucket, get_object, stat_object, list_objects, list_prefix, list_projected, list_delimited, list_after, list_versions,
+        bucket_exists, bucket_name_problems, compliance_records, get_bucket_spec, get_lifecycle_rules, set_lifecycle_rules, get_bucket_policy, set_bucket_policy, get_bucket_labels, set_bucket_labels,
+        get_object_lock, set_object_retention, set_legal_hold
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::in_memory::InMemoryClient;
+    use super::*;
+
+    async fn cached(name: &str, max_bytes: u64) -> (CachedClient<InMemoryClient>, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("uni-stg-cache-{}-{name}", std::process::id()));
+        let _ = tokio::fs::remove_dir_all(&dir).await;
+        (CachedClient::open(InMemoryClient::with_bucket("bucket"), &dir, max_bytes).await.unwrap(), dir)
+    }
+
+    async fn read(client: &CachedClient<InMemoryClient>, key: &str) -> Bytes {
+        client.static_download_bytes("bucket".to_string(), key.to_string(), None, None).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn validates_cached_objects() {
+        let (client, dir) = cached("validates", 1024).await;
+        client.static_upload_bytes("bucket".to_string(), "key".to_string(), Bytes::from_static(b"first")).await.unwrap();
+        assert_eq!(read(&client, "key").await, "first");
+        assert_eq!(read(&client, "key").await, "first");
+        assert_eq!((client.misses(), client.hits()), (1, 1));
+        // Written behind the cache's back, the ETag no longer matches
+        client.inner().static_upload_bytes("bucket".to_string(), "key".to_string(), Bytes::from_static(b"second!")).await.unwrap();
+        assert_eq!(read(&client, "key").await, "second!");
+        assert_eq!(read(&client, "key").await, "second!");
+        assert_eq!(client.cached_bytes(), 7);
+        client.remove_object("bucket".to_string(), "key".to_string()).await.unwrap();
+        assert_eq!(client.cached_bytes(), 0);
+        let _ = tokio::fs::remove_dir_all(dir).await;
+    }
+
+    #[tokio::test]
+    async fn counts_concurrent_stores_once() {
+        let (client, dir) = cached("concurrent", 1024).await;
+        client.inner().static_upload_bytes("bucket".to_string(), "key".to_string(), Bytes::from_static(b"data")).await.unwrap();
+        let (a, b) = futures::join!(read(&client, "key"), read(&client, "key"));
+        assert_eq!((a, b), (Bytes::from_static(b"data"), Bytes::from_static(b"data")));
+        assert_eq!((client.misses(), client.cached_bytes()), (2, 4));
+        let _ = tokio::fs::remove_dir_all(dir).await;
+    }
+
+    #[tokio::test]
+    async fn evicts_least_recently_read() {
+        let (client, dir) = cached("evicts", 10).await;
+        for key in ["a", "b", "c"] {
+            client.inner().static_upload_bytes("bucket".to_string(), key.to_string(), Bytes::from_static(b"four")).await.unwrap();
+        }
+        read(&client, "a").await;
+        read(&client, "b").await;
+        read(&client, "a").await;
+        read(&client, "c").await;
+        assert_eq!(client.cached_bytes(), 8);
+        let hits = client.hits();
+        read(&client, "a").await;
+        read(&client, "c").await;
+        assert_eq!(client.hits(), hits + 2);
+        // Reopened from the files on disk
+        drop(client);
+        let reopened = CachedClient::open(InMemoryClient::with_bucket("bucket"), &dir, 10).await.unwrap();
+        assert_eq!(reopened.cached_bytes(), 8);
+        let _ = tokio::fs::remove_dir_all(dir).await;
+    }
+}
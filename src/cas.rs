@@ -0,0 +1,408 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use bytes::{Bytes, BytesMut};
+use futures::{stream, StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use crate::publish::content_hash;
+use crate::{ClientError, ClientInterface, ClientObject, Context, DownloadOptions, EmptyReqRes, ErrorContext, ListOptions, ReqRes, WritePrecondition, CONCURRENCY};
+
+/// Default prefix of content-addressed stores (see `CasStore::with_prefix`)
+pub const CAS_PREFIX: &str = ".uni-stg/cas/";
+/// Attempts of a compare-and-swap update of a reference count or manifest before it fails
+const CAS_ATTEMPTS: usize = 16;
+/// How long `CasStore::sweep` holds a blob while it removes it, writers which need the blob wait until it is released
+/// or the lease passed
+pub const SWEEP_LEASE: Duration = Duration::from_secs(60);
+/// Interval in which writers check whether a sweep released a blob
+const SWEEP_POLL: Duration = Duration::from_millis(100);
+
+/// Hex SHA-256 of data, the address of a blob
+pub fn content_digest(data: &[u8]) -> String {
+    content_hash(data)
+}
+
+/// Content of a reference count object
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+enum Refs {
+    Count(u64),
+    /// Written by `CasStore::sweep` while it removes the blob, held until this time (in unix milliseconds)
+    Sweeping { sweep_until: u64 },
+}
+
+fn unix_millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).map(|t| t.as_millis() as u64).unwrap_or(0)
+}
+
+/// Named list of blobs, its content is the concatenation of the blobs
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CasManifest {
+    pub name: String,
+    /// Digests of the blobs
+    pub blobs: Vec<String>,
+    pub size: u64,
+}
+
+/// Stores blobs under their content digest, so identical content (build artifacts, container layers) is stored once,
+/// and names them with manifests which count the references to their blobs
+///
+/// Below the prefix, blobs are stored at 'blobs/<digest>', reference counts at 'refs/<digest>' and manifests at 'manifests/<name>'.
+/// Reference counts and manifests are updated with compare-and-swap writes, so stores can be shared by concurrent writers.
+/// Blobs whose last manifest was replaced or removed are kept until `sweep` removes them, so a writer which references
+/// them again meanwhile never loses them.
+/// Note: Blobs uploaded with `put_blob` are not referenced until they are committed. A manifest removed while another writer
+/// commits the same name may leave its references behind, which only keeps blobs alive.
+/// A corrupt reference count fails every operation on its blob with `ClientError::Serialization`.
+pub struct CasStore<C: ClientInterface> {
+    client: C,
+    bucket: String,
+    prefix: String,
+}
+
+impl<C: ClientInterface> CasStore<C> {
+    /// A store below `CAS_PREFIX` in `bucket`
+    pub fn new(client: C, bucket: String) -> Self {
+        Self { client, bucket, prefix: CAS_PREFIX.to_string() }
+    }
+
+    /// Stores with different prefixes do not share blobs
+    pub fn with_prefix(mut self, prefix: String) -> Self {
+        self.prefix = prefix;
+        self
+    }
+
+    pub fn client(&self) -> &C {
+        &self.client
+    }
+
+    fn key(&self, kind: &str, name: &str) -> String {
+        format!("{}{kind}/{name}", self.prefix)
+    }
+
+    /// Reads an object together with the precondition which only overwrites this version of it and when it was written
+    async fn read_versioned(&self, key: &str) -> ReqRes<(Option<Bytes>, WritePrecondition, Option<SystemTime>)> {
+        let object = match self.client.stat_object(self.bucket.clone(), key.to_string()).await {
+            Ok(object) => object,
+            Err(e) if e.is_not_found() => return Ok((None, WritePrecondition::DoesNotExist, None)),
+            Err(e) => return Err(e)
+        };
+        let Some(version) = object.version() else {
+            return Err(ClientError::Unsupported("content-addressed stores need versioned (conditional) writes")
+                .with_context(ErrorContext::new("cas", Some(&self.bucket), Some(key))))
+        };
+        // Pinned to the version, so a concurrent write makes the following conditional write fail instead
+        let options = DownloadOptions { if_match: object.etag(), ..Default::default() };
+        let data = self.client.download_with_options(self.bucket.clone(), key.to_string(), None, None, options).await?;
+        Ok((Some(data), WritePrecondition::Matches(version), object.last_modified()))
+    }
+
+    /// Whether a conditional write lost against a concurrent one and should be retried
+    fn conflicted(e: &ClientError) -> bool {
+        e.is_precondition_failed() || e.is_already_exists()
+    }
+
+    /// A missing reference count is 0, a corrupt one fails
+    fn parse_refs(&self, key: &str, data: Option<Bytes>) -> ReqRes<Refs> {
+        match data {
+            Some(data) => serde_json::from_slice(&data).context("cas_count", &self.bucket, Some(key)),
+            None => Ok(Refs::Count(0))
+        }
+    }
+
+    /// Adds `delta` to the reference count of a blob and returns the previous count
+    /// Waits while a sweep holds the blob, a blob whose sweep lease passed counts as unreferenced (and may be gone)
+    async fn count(&self, digest: &str, delta: i64) -> ReqRes<u64> {
+        let key = self.key("refs", digest);
+        let mut attempts = 0;
+        while attempts < CAS_ATTEMPTS {
+            let (data, precondition, _) = match self.read_versioned(&key).await {
+                Err(e) if Self::conflicted(&e) => {
+                    attempts += 1;
+                    continue
+                }
+                read => read?
+            };
+            let current = match self.parse_refs(&key, data)? {
+                Refs::Count(count) => count,
+                Refs::Sweeping { sweep_until } => match sweep_until.saturating_sub(unix_millis(SystemTime::now())) {
+                    0 => 0,
+                    remaining => {
+                        tokio::time::sleep(SWEEP_POLL.min(Duration::from_millis(remaining))).await;
+                        continue
+                    }
+                }
+            };
+            let next = current.saturating_add_signed(delta);
+            match self.client.upload_if_match(self.bucket.clone(), key.clone(), next.to_string().into(), precondition).await {
+                Ok(_) => return Ok(current),
+                Err(e) if Self::conflicted(&e) => attempts += 1,
+                Err(e) => return Err(e)
+            }
+        }
+        Err(ClientError::PreconditionFailed.with_context(ErrorContext::new("cas_count", Some(&self.bucket), Some(&key))))
+    }
+
+    /// Removes a reference to each blob, blobs left without references are removed by `sweep`
+    async fn release(&self, digests: Vec<String>) -> EmptyReqRes {
+        for digest in digests {
+            self.count(&digest, -1).await?;
+        }
+        Ok(())
+    }
+
+    /// Adds a reference to each blob, fails (without adding any) if one of them does not exist
+    async fn acquire(&self, digests: &[String]) -> EmptyReqRes {
+        for (i, digest) in digests.iter().enumerate() {
+            // Checked after counting, a blob which had no references may have been removed meanwhile
+            let (counted, missing) = match self.count(digest, 1).await {
+                Ok(_) => (i + 1, self.client.stat_object(self.bucket.clone(), self.key("blobs", digest)).await.err()),
+                Err(e) => (i, Some(e))
+            };
+            if let Some(e) = missing {
+                self.release(digests[..counted].to_vec()).await?;
+                return Err(e)
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the manifest of `name` (or an empty one if None) and returns the replaced one
+    async fn swap_manifest(&self, name: &str, manifest: Option<&CasManifest>) -> ReqRes<Option<CasManifest>> {
+        let key = self.key("manifests", name);
+        let empty = CasManifest { name: name.to_string(), blobs: vec![], size: 0 };
+        let data: Bytes = serde_json::to_vec(manifest.unwrap_or(&empty))?.into();
+        for _ in 0..CAS_ATTEMPTS {
+            let (previous, precondition, _) = match self.read_versioned(&key).await {
+                Err(e) if Self::conflicted(&e) => continue,
+                read => read?
+            };
+            let previous = previous.map(|t| serde_json::from_slice(&t)).transpose().context("cas_manifest", &self.bucket, Some(&key))?;
+            match self.client.upload_if_match(self.bucket.clone(), key.clone(), data.clone(), precondition).await {
+                Ok(_) => return Ok(previous),
+                Err(e) if Self::conflicted(&e) => continue,
+                Err(e) => return Err(e)
+            }
+        }
+        Err(ClientError::PreconditionFailed.with_context(ErrorContext::new("cas_manifest", Some(&self.bucket), Some(&key))))
+    }
+
+    /// Digest of a blob and whether it was uploaded (false if it was stored already)
+    async fn store_blob(&self, data: Bytes) -> ReqRes<(String, bool)> {
+        let digest = content_digest(&data);
+        match self.client.upload_if_absent(self.bucket.clone(), self.key("blobs", &digest), data).await {
+            Ok(_) => Ok((digest, true)),
+            Err(e) if e.is_already_exists() => Ok((digest, false)),
+            Err(e) => Err(e)
+        }
+    }
+
+    /// Stores a blob (unless it is stored already) and returns its digest
+    pub async fn put_blob(&self, data: Bytes) -> ReqRes<String> {
+        Ok(self.store_blob(data).await?.0)
+    }
+
+    /// Fails with `ClientError::ChecksumMismatch` if the stored data does not match its digest
+    pub async fn get_blob(&self, digest: &str) -> ReqRes<Bytes> {
+        let key = self.key("blobs", digest);
+        let data = self.client.static_download_bytes(self.bucket.clone(), key.clone(), None, None).await?;
+        let actual = content_digest(&data);
+        if actual != digest {
+            return Err(ClientError::ChecksumMismatch { expected: digest.to_string(), actual: Some(actual) }
+                .with_context(ErrorContext::new("get_blob", Some(&self.bucket), Some(&key))))
+        }
+        Ok(data)
+    }
+
+    /// Names a list of already stored blobs (see `put_blob`), replacing the previous manifest of `name`
+    pub async fn commit(&self, name: &str, blobs: Vec<String>) -> ReqRes<CasManifest> {
+        let sizes: Vec<u64> = stream::iter(&blobs).map(|digest| async move {
+            Ok::<_, ClientError>(self.client.stat_object(self.bucket.clone(), self.key("blobs", digest)).await?.size())
+        }).buffered(CONCURRENCY).try_collect().await?;
+        self.acquire(&blobs).await?;
+        let manifest = CasManifest { name: name.to_string(), blobs, size: sizes.iter().sum() };
+        match self.swap_manifest(name, Some(&manifest)).await {
+            Ok(previous) => self.release(previous.map(|t| t.blobs).unwrap_or_default()).await?,
+            Err(e) => {
+                self.release(manifest.blobs).await?;
+                return Err(e)
+            }
+        }
+        Ok(manifest)
+    }
+
+    /// Stores `data` as a single blob named `name`, replacing the previous manifest of `name`
+    pub async fn put(&self, name: &str, data: Bytes) -> ReqRes<CasManifest> {
+        let (digest, uploaded) = self.store_blob(data.clone()).await?;
+        if self.count(&digest, 1).await? == 0 && !uploaded {
+            // The blob had no references, so it may have been removed after it was found
+            self.client.static_upload_bytes(self.bucket.clone(), self.key("blobs", &digest), data.clone()).await?;
+        }
+        let manifest = CasManifest { name: name.to_string(), blobs: vec![digest], size: data.len() as u64 };
+        match self.swap_manifest(name, Some(&manifest)).await {
+            Ok(previous) => self.release(previous.map(|t| t.blobs).unwrap_or_default()).await?,
+            Err(e) => {
+                self.release(manifest.blobs).await?;
+                return Err(e)
+            }
+        }
+        Ok(manifest)
+    }
+
+    pub async fn manifest(&self, name: &str) -> ReqRes<CasManifest> {
+        let key = self.key("manifests", name);
+        let data = self.client.static_download_bytes(self.bucket.clone(), key.clone(), None, None).await?;
+        serde_json::from_slice(&data).context("cas_manifest", &self.bucket, Some(&key))
+    }
+
+    /// The content of `name`, the concatenation of its blobs
+    pub async fn get(&self, name: &str) -> ReqRes<Bytes> {
+        let manifest = self.manifest(name).await?;
+        let blobs: Vec<Bytes> = stream::iter(&manifest.blobs).map(|digest| self.get_blob(digest)).buffered(CONCURRENCY).try_collect().await?;
+        let mut data = BytesMut::with_capacity(manifest.size as usize);
+        for blob in blobs {
+            data.extend_from_slice(&blob);
+        }
+        Ok(data.freeze())
+    }
+
+    /// Removes the manifest of `name` and releases its blobs (see `sweep`)
+    pub async fn remove(&self, name: &str) -> EmptyReqRes {
+        // Emptied first, so the references are released exactly once even if removals race
+        let previous = self.swap_manifest(name, None).await?;
+        self.client.remove_object(self.bucket.clone(), self.key("manifests", name)).await?;
+        self.release(previous.map(|t| t.blobs).unwrap_or_default()).await
+    }
+
+    /// Number of manifests referencing a blob (0 while a sweep holds it)
+    pub async fn references(&self, digest: &str) -> ReqRes<u64> {
+        let key = self.key("refs", digest);
+        let refs = match self.client.static_download_bytes(self.bucket.clone(), key.clone(), None, None).await {
+            Ok(data) => self.parse_refs(&key, Some(data))?,
+            Err(e) if e.is_not_found() => Refs::Count(0),
+            Err(e) => return Err(e)
+        };
+        match refs {
+            Refs::Count(count) => Ok(count),
+            Refs::Sweeping { .. } => Ok(0)
+        }
+    }
+
+    /// Removes the blobs which had no references for at least `grace` and returns their digests
+    /// Blobs are stored before they are referenced, so `grace` has to exceed the time between `put_blob` and `commit`.
+    /// Each blob is held with a `SWEEP_LEASE` in its reference count while it is removed, writers which reference it
+    /// meanwhile wait for the sweep and upload the blob again (`commit` fails with a not-found error instead).
+    pub async fn sweep(&self, grace: Duration) -> ReqRes<Vec<String>> {
+        let prefix = self.key("blobs", "");
+        let options = ListOptions { prefix: Some(prefix.clone()), ..Default::default() };
+        let blobs: Vec<(String, Option<SystemTime>)> = self.client.list_objects_stream(self.bucket.clone(), options)
+            .map_ok(|t| (t.name()[prefix.len()..].to_string(), t.last_modified()))
+            .try_collect().await?;
+        let mut removed = vec![];
+        for (digest, stored) in blobs {
+            if self.sweep_blob(&digest, stored, grace).await? {
+                removed.push(digest);
+            }
+        }
+        Ok(removed)
+    }
+
+    /// Removes a blob if it had no references for at least `grace`, returns whether it did
+    async fn sweep_blob(&self, digest: &str, stored: Option<SystemTime>, grace: Duration) -> ReqRes<bool> {
+        let key = self.key("refs", digest);
+        let (data, precondition, counted) = match self.read_versioned(&key).await {
+            Err(e) if Self::conflicted(&e) => return Ok(false),
+            read => read?
+        };
+        let now = SystemTime::now();
+        let idle_since = match self.parse_refs(&key, data)? {
+            // A blob which was never referenced is as old as its upload
+            Refs::Count(0) => counted.or(stored),
+            // Taken over from a sweep which did not finish within its lease
+            Refs::Sweeping { sweep_until } if sweep_until <= unix_millis(now) => counted,
+            _ => return Ok(false)
+        };
+        if idle_since.is_none_or(|t| now.duration_since(t).unwrap_or_default() < grace) {
+            return Ok(false)
+        }
+        let lease = now + SWEEP_LEASE;
+        let sweeping = serde_json::to_vec(&Refs::Sweeping { sweep_until: unix_millis(lease) })?;
+        let held = match self.client.upload_if_match(self.bucket.clone(), key.clone(), sweeping.into(), precondition).await {
+            Err(e) if Self::conflicted(&e) => return Ok(false),
+            written => written?
+        };
+        let Some(version) = held.version() else {
+            return Err(ClientError::Unsupported("content-addressed stores need versioned (conditional) writes")
+                .with_context(ErrorContext::new("cas_sweep", Some(&self.bucket), Some(&key))))
+        };
+        // Only removed with half of the lease left, so the removal cannot land after a writer took the blob over
+        let removed = match SystemTime::now() + SWEEP_LEASE / 2 < lease {
+            true => match self.client.remove_object(self.bucket.clone(), self.key("blobs", digest)).await {
+                Err(e) if !e.is_not_found() => Err(e),
+                _ => Ok(true)
+            },
+            false => Ok(false)
+        };
+        // Released conditionally, a writer which took over the lease meanwhile keeps its count
+        match self.client.upload_if_match(self.bucket.clone(), key, 0.to_string().into(), WritePrecondition::Matches(version)).await {
+            Err(e) if Self::conflicted(&e) => {}
+            written => {
+                written?;
+            }
+        }
+        removed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::in_memory::InMemoryClient;
+    use super::*;
+
+    fn store() -> CasStore<InMemoryClient> {
+        CasStore::new(InMemoryClient::with_bucket("bucket"), "bucket".to_string())
+    }
+
+    #[tokio::test]
+    async fn counts_references() {
+        let store = store();
+        let (layer, other) = (Bytes::from_static(b"layer"), Bytes::from_static(b"other"));
+        let digest = store.put("a", layer.clone()).await.unwrap().blobs.remove(0);
+        store.put("b", layer.clone()).await.unwrap();
+        assert_eq!(store.references(&digest).await.unwrap(), 2);
+        store.remove("a").await.unwrap();
+        assert_eq!(store.references(&digest).await.unwrap(), 1);
+        assert!(store.sweep(Duration::ZERO).await.unwrap().is_empty());
+        store.put("b", other.clone()).await.unwrap();
+        assert_eq!(store.references(&digest).await.unwrap(), 0);
+        // Kept within the grace period
+        assert!(store.sweep(Duration::from_secs(3600)).await.unwrap().is_empty());
+        assert_eq!(store.sweep(Duration::ZERO).await.unwrap(), vec![digest.clone()]);
+        assert!(store.get_blob(&digest).await.unwrap_err().is_not_found());
+        assert_eq!(store.get("b").await.unwrap(), other);
+        // Put again after it was swept
+        store.put("a", layer.clone()).await.unwrap();
+        assert_eq!(store.get("a").await.unwrap(), layer);
+        assert_eq!(store.references(&digest).await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn sweeps_never_remove_referenced_blobs() {
+        let store = store();
+        let layer = Bytes::from_static(b"layer");
+        // Each round starts the sweep a little later
+        for delay in 0..100 {
+            store.put("a", layer.clone()).await.unwrap();
+            store.remove("a").await.unwrap();
+            let sweep = async {
+                for _ in 0..delay {
+                    tokio::task::yield_now().await;
+                }
+                store.sweep(Duration::ZERO).await
+            };
+            let (put, swept) = futures::join!(store.put("b", layer.clone()), sweep);
+            put.unwrap();
+            swept.unwrap();
+            assert_eq!(store.get("b").await.unwrap(), layer, "delay {delay}");
+            store.remove("b").await.unwrap();
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+use crate::{EmptyReqRes, ReqRes};
+
+/// Environment variable which makes `Cassette::from_env` record (when set to 'record') instead of replaying
+pub const RECORD_VAR: &str = "UNI_STG_RECORD";
+
+/// Query parameters whose values are replaced before an interaction is stored
+const SECRET_PARAMS: [&str; 7] = ["x-amz-signature", "x-amz-credential", "x-amz-security-token", "x-goog-signature", "x-goog-credential", "access_token", "key"];
+/// Response headers which are not stored
+#[cfg(any(feature = "google_cloud", feature = "aws_s3"))]
+const SECRET_HEADERS: [&str; 3] = ["set-cookie", "authorization", "x-amz-security-token"];
+const REDACTED: &str = "REDACTED";
+
+/// A recorded HTTP exchange, requests are identified by their method and (scrubbed) URL
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Interaction {
+    pub method: String,
+    pub url: String,
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    /// Base64 encoded response body
+    pub body: String,
+}
+
+impl Interaction {
+    #[cfg(any(feature = "google_cloud", feature = "aws_s3"))]
+    pub(crate) fn new(method: String, url: &str, status: u16, headers: impl IntoIterator<Item = (String, String)>, body: &[u8]) -> Self {
+        let headers = headers.into_iter().filter(|(name, _)| !SECRET_HEADERS.contains(&name.to_ascii_lowercase().as_str())).collect();
+        Self { method, url: scrub_url(url), status, headers, body: STANDARD.encode(body) }
+    }
+
+    /// Undecodable bodies (edited fixtures) are replayed empty
+    pub fn body(&self) -> Bytes {
+        STANDARD.decode(&self.body).map(Bytes::from).unwrap_or_default()
+    }
+}
+
+/// Replaces the values of credentials (signatures, tokens, API keys) in the query of `url`
+pub fn scrub_url(url: &str) -> String {
+    let Some((base, query)) = url.split_once('?') else {
+        return url.to_string()
+    };
+    let query: Vec<String> = query.split('&').map(|param| match param.split_once('=') {
+        Some((name, _)) if SECRET_PARAMS.contains(&name.to_ascii_lowercase().as_str()) => format!("{name}={REDACTED}"),
+        _ => param.to_string()
+    }).collect();
+    format!("{base}?{}", query.join("&"))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CassetteMode {
+    /// Requests are sent to the provider and the exchanges are kept until `Cassette::save`
+    Record,
+    /// Requests are answered from the fixture, a request which was not recorded fails
+    Replay,
+}
+
+#[derive(Debug, Default)]
+struct Tape {
+    interactions: Vec<Interaction>,
+    /// Whether each interaction was replayed already
+    used: Vec<bool>,
+}
+
+/// Records the HTTP traffic of a client to a JSON fixture and replays it offline (see `GoogleCloudConfig::with_cassette`
+/// and `AWSConfig::with_cassette`), so integration tests run in CI without cloud access
+///
+/// Credentials in URLs and cookies are scrubbed before they are stored, request headers and bodies are not stored at all.
+/// Requests are matched by method and URL in the order they were recorded, so tests have to use fixed object names.
+/// Note: Replaying clients should be created with anonymous configs, the credentials of the others are still refreshed over the network.
+#[derive(Debug, Clone)]
+pub struct Cassette {
+    path: PathBuf,
+    mode: CassetteMode,
+    tape: Arc<Mutex<Tape>>,
+}
+
+impl Cassette {
+    /// Starts an empty recording which is written to `path` by `save`
+    pub fn record(path: impl AsRef<Path>) -> Self {
+        Self { path: path.as_ref().to_path_buf(), mode: CassetteMode::Record, tape: Arc::default() }
+    }
+
+    /// Fails if the fixture can not be read or parsed
+    pub async fn replay(path: impl AsRef<Path>) -> ReqRes<Self> {
+        let interactions: Vec<Interaction> = serde_json::from_slice(&tokio::fs::read(path.as_ref()).await?)?;
+        let tape = Tape { used: vec![false; interactions.len()], interactions };
+        Ok(Self { path: path.as_ref().to_path_buf(), mode: CassetteMode::Replay, tape: Arc::new(Mutex::new(tape)) })
+    }
+
+    /// Records if `RECORD_VAR` is 'record', replays otherwise (the usual setup of CI)
+    pub async fn from_env(path: impl AsRef<Path>) -> ReqRes<Self> {
+        match std::env::var(RECORD_VAR).as_deref() {
+            Ok("record") => Ok(Self::record(path)),
+            _ => Self::replay(path).await
+        }
+    }
+
+    pub fn mode(&self) -> CassetteMode {
+        self.mode
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn tape(&self) -> std::sync::MutexGuard<'_, Tape> {
+        self.tape.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Writes the recorded interactions to the fixture, does nothing when replaying
+    pub async fn save(&self) -> EmptyReqRes {
+        if self.mode == CassetteMode::Replay {
+            return Ok(())
+        }
+        let json = serde_json::to_vec_pretty(&self.tape().interactions)?;
+        tokio::fs::write(&self.path, json).await?;
+        Ok(())
+    }
+
+    /// Number of recorded interactions which were not replayed (yet)
+    pub fn unused(&self) -> usize {
+        self.tape().used.iter().filter(|t| !**t).count()
+    }
+
+    #[cfg(any(feature = "google_cloud", feature = "aws_s3"))]
+    pub(crate) fn push(&self, interaction: Interaction) {
+        let mut tape = self.tape();
+        tape.interactions.push(interaction);
+        tape.used.push(true);
+    }
+
+    /// The first interaction of this request which was not replayed yet
+    #[cfg(any(feature = "google_cloud", feature = "aws_s3"))]
+    pub(crate) fn take(&self, method: &str, url: &str) -> Option<Interaction> {
+        let url = scrub_url(url);
+        let mut tape = self.tape();
+        let i = (0..tape.interactions.len()).find(|&i| !tape.used[i] && tape.interactions[i].method == method && tape.interactions[i].url == url)?;
+        tape.used[i] = true;
+        Some(tape.interactions[i].clone())
+    }
+}
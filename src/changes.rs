@@ -0,0 +1,104 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+use futures::{stream, Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use crate::{ClientInterface, ClientObject, Context, ListOptions, ReqRes};
+
+/// Prefix of the objects in which change feeds persist their last snapshot
+pub const CHANGES_PREFIX: &str = ".uni-stg/changes/";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum ChangeKind {
+    Created,
+    Updated,
+    Deleted,
+}
+
+/// A change of an object between two snapshots
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Change {
+    pub kind: ChangeKind,
+    pub name: String,
+    /// Version (`ClientObject::id`) after the change, the last known version of deleted objects
+    pub version: String,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Entry {
+    version: String,
+    size: u64,
+}
+
+/// Emulates change notifications by diffing listings against the previous snapshot
+///
+/// The snapshot is stored in the bucket (below `CHANGES_PREFIX`), so a feed continues where the last run stopped.
+/// Every poll lists all objects below the prefix, changes between two polls are merged (an object created and deleted in between is never reported).
+pub struct ChangeFeed {
+    bucket: String,
+    prefix: String,
+    state_key: String,
+}
+
+impl ChangeFeed {
+    /// `name` identifies the feed, feeds with different names keep separate snapshots
+    pub fn new(bucket: String, prefix: String, name: &str) -> Self {
+        Self { bucket, prefix, state_key: format!("{CHANGES_PREFIX}{name}.json") }
+    }
+
+    async fn load(&self, client: &impl ClientInterface) -> ReqRes<Option<BTreeMap<String, Entry>>> {
+        match client.static_download_bytes(self.bucket.clone(), self.state_key.clone(), None, None).await {
+            Ok(data) => Ok(Some(serde_json::from_slice(&data).context("change_feed", &self.bucket, Some(&self.state_key))?)),
+            Err(e) if e.is_not_found() => Ok(None),
+            Err(e) => Err(e)
+        }
+    }
+
+    /// Lists the objects, returns the changes since the last poll and persists the new snapshot
+    /// The first poll of a feed only records the snapshot and reports no changes, unless `initial_created` is set
+    pub async fn poll(&self, client: &impl ClientInterface, initial_created: bool) -> ReqRes<Vec<Change>> {
+        let previous = self.load(client).await?;
+        let first = previous.is_none();
+        let mut previous = previous.unwrap_or_default();
+        let mut current = BTreeMap::new();
+        let options = ListOptions { prefix: Some(self.prefix.clone()), ..Default::default() };
+        let mut objects = std::pin::pin!(client.list_objects_stream(self.bucket.clone(), options));
+        while let Some(object) = objects.next().await {
+            let object = object?;
+            if object.name() != self.state_key {
+                current.insert(object.name(), Entry { version: object.id(), size: object.size() });
+            }
+        }
+        let mut changes = vec![];
+        for (name, entry) in &current {
+            let kind = match previous.remove(name) {
+                None => ChangeKind::Created,
+                Some(old) if old.version != entry.version => ChangeKind::Updated,
+                Some(_) => continue
+            };
+            changes.push(Change { kind, name: name.clone(), version: entry.version.clone(), size: entry.size });
+        }
+        changes.extend(previous.into_iter().map(|(name, entry)| Change { kind: ChangeKind::Deleted, name, version: entry.version, size: entry.size }));
+        let state = serde_json::to_vec(&current).context("change_feed", &self.bucket, Some(&self.state_key))?;
+        client.static_upload_bytes(self.bucket.clone(), self.state_key.clone(), state.into()).await?;
+        if first && !initial_created {
+            return Ok(vec![])
+        }
+        Ok(changes)
+    }
+
+    /// Polls every interval (forever), yielding the changes of every poll
+    /// A failed poll yields the error, the next poll retries from the same snapshot
+    pub fn stream<'a>(&'a self, client: &'a impl ClientInterface, interval: Duration) -> impl Stream<Item = ReqRes<Change>> + 'a {
+        stream::unfold(true, move |first| async move {
+            if !first {
+                tokio::time::sleep(interval).await;
+            }
+            let changes = match self.poll(client, false).await {
+                Ok(changes) => changes.into_iter().map(Ok).collect(),
+                Err(e) => vec![Err(e)]
+            };
+            Some((stream::iter(changes), false))
+        }).flatten()
+    }
+}
@@ -0,0 +1,133 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+use crate::{ClientError, ClientInterface, ClientObject, DownloadOptions, EmptyReqRes, ErrorContext, ReqRes, UploadOptions, WritePrecondition};
+
+/// Prefix (inside the target bucket) under which upload checkpoints are stored
+pub const CHECKPOINT_PREFIX: &str = ".uni-stg/uploads/";
+
+/// Lease duration given to the owner of a checkpoint, renewed on every saved part
+pub const DEFAULT_LEASE: Duration = Duration::from_secs(300);
+
+/// A part which has already been uploaded
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckpointPart {
+    pub part_number: i32,
+    pub e_tag: Option<String>,
+    pub size: u64,
+    /// Base64 CRC32C of the part, for uploads with checksums
+    #[serde(default)]
+    pub checksum: Option<String>,
+}
+
+/// Persisted state of a multipart upload, stored next to the object it belongs to
+/// so that another worker can adopt the upload once the owner's lease ran out
+/// Adoption and every save are conditional writes of the checkpoint, so of two workers adopting at the same instant only one succeeds
+/// and an owner whose lease ran out cannot overwrite the checkpoint of the worker which adopted its upload
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UploadCheckpoint {
+    pub bucket: String,
+    pub object: String,
+    /// Provider specific ID of the upload
+    pub upload_id: String,
+    pub parts: Vec<CheckpointPart>,
+    /// Identifies the worker currently holding the lease
+    pub owner: String,
+    /// Unix timestamp (seconds) at which the lease expires
+    pub lease_expires: u64,
+    /// Whether the upload was created with CRC32C checksums, all of its parts have to carry one
+    #[serde(default)]
+    pub checksums: bool,
+    /// Version of the stored checkpoint this one was loaded from or last saved as
+    #[serde(skip)]
+    version: Option<String>,
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|t| t.as_secs()).unwrap_or(0)
+}
+
+impl UploadCheckpoint {
+    pub fn new(bucket: String, object: String, upload_id: String, owner: String) -> Self {
+        let mut checkpoint = Self { bucket, object, upload_id, parts: vec![], owner, lease_expires: 0, checksums: false, version: None };
+        checkpoint.renew(DEFAULT_LEASE);
+        checkpoint
+    }
+
+    /// Key of the checkpoint object inside the bucket
+    pub fn key(object: &str) -> String {
+        format!("{CHECKPOINT_PREFIX}{object}")
+    }
+
+    /// Number of bytes that have already been uploaded
+    /// A resuming reader has to start at this offset
+    pub fn uploaded_bytes(&self) -> u64 {
+        self.parts.iter().map(|t| t.size).sum()
+    }
+
+    pub fn lease_expired(&self) -> bool {
+        self.lease_expires <= now()
+    }
+
+    /// Extends the lease of the current owner
+    pub fn renew(&mut self, lease: Duration) {
+        self.lease_expires = now() + lease.as_secs();
+    }
+
+    /// Writes the checkpoint to its bucket, only if the stored one did not change since it was loaded or last saved
+    /// Fails with `ClientError::PreconditionFailed` once another worker adopted the upload, the caller has to stop uploading parts
+    /// The first save of a new checkpoint, and every save on a provider without versions, is unconditional
+    pub async fn save(&mut self, client: &impl ClientInterface) -> EmptyReqRes {
+        let key = Self::key(&self.object);
+        let options = UploadOptions { precondition: self.version.clone().map(WritePrecondition::Matches), ..Default::default() };
+        let stored = match client.upload_with_options(self.bucket.clone(), key.clone(), serde_json::to_vec(self)?.into(), options).await {
+            Err(e) if e.is_precondition_failed() => return Err(ClientError::PreconditionFailed
+                .with_context(ErrorContext::new("save_checkpoint", Some(&self.bucket), Some(&key)))),
+            stored => stored?
+        };
+        self.version = stored.version();
+        Ok(())
+    }
+
+    /// Reads the checkpoint of an object
+    pub async fn load(client: &impl ClientInterface, bucket: String, object: &str) -> ReqRes<Self> {
+        let key = Self::key(object);
+        let stat = client.stat_object(bucket.clone(), key.clone()).await?;
+        // Pinned to the version, so the data matches the version conditional saves are checked against
+        let options = DownloadOptions { if_match: stat.etag(), ..Default::default() };
+        let data = client.download_with_options(bucket, key, None, None, options).await?;
+        let mut checkpoint: Self = serde_json::from_slice(&data)?;
+        checkpoint.version = stat.version();
+        Ok(checkpoint)
+    }
+
+    /// Removes the checkpoint, done once the upload completed
+    pub async fn remove(&self, client: &impl ClientInterface) -> EmptyReqRes {
+        client.remove_object(self.bucket.clone(), Self::key(&self.object)).await
+    }
+
+    /// Takes over an upload whose owner's lease ran out
+    /// Returns None if the lease is still held by another worker or another worker adopted it first
+    /// Requires versioned (conditional) writes, fails with `ClientError::Unsupported` otherwise
+    pub async fn adopt(client: &impl ClientInterface, bucket: String, object: &str, owner: String) -> ReqRes<Option<Self>> {
+        let mut checkpoint = match Self::load(client, bucket.clone(), object).await {
+            // A checkpoint saved in between
+            Err(e) if e.is_precondition_failed() => return Ok(None),
+            checkpoint => checkpoint?
+        };
+        if checkpoint.version.is_none() {
+            return Err(ClientError::Unsupported("adopting uploads requires versioned (conditional) writes")
+                .with_context(ErrorContext::new("adopt", Some(&bucket), Some(&Self::key(object)))))
+        }
+        if checkpoint.owner != owner && !checkpoint.lease_expired() {
+            return Ok(None)
+        }
+        checkpoint.owner = owner;
+        checkpoint.renew(DEFAULT_LEASE);
+        match checkpoint.save(client).await {
+            Ok(()) => Ok(Some(checkpoint)),
+            // Lost against another worker
+            Err(e) if e.is_precondition_failed() => Ok(None),
+            Err(e) => Err(e)
+        }
+    }
+}
@@ -0,0 +1,49 @@
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use md5::{Digest, Md5};
+use crate::{ClientError, EmptyReqRes};
+
+/// Checksums sent with an upload in strict mode (see `Defaults::strict_checksums`), base64 encoded as both providers expect them
+pub(crate) struct Checksums {
+    pub md5: String,
+    pub crc32c: String,
+}
+
+/// Base64 of the big-endian CRC32C
+pub(crate) fn encode_crc32c(crc: u32) -> String {
+    STANDARD.encode(crc.to_be_bytes())
+}
+
+impl Checksums {
+    pub fn of(data: &[u8]) -> Self {
+        Self { md5: STANDARD.encode(Md5::digest(data)), crc32c: encode_crc32c(crc32c::crc32c(data)) }
+    }
+}
+
+/// Fails unless the provider reported the expected (base64) CRC32C
+pub(crate) fn confirm_crc32c(expected: &str, actual: Option<&str>) -> EmptyReqRes {
+    match actual {
+        Some(actual) if actual == expected => Ok(()),
+        actual => Err(ClientError::ChecksumMismatch { expected: expected.to_string(), actual: actual.map(str::to_string) })
+    }
+}
+
+/// Fails if downloaded data does not match the (base64) CRC32C stored with the object
+/// Composite checksums of multipart uploads ('...-3') cannot be verified and are skipped
+pub(crate) fn verify_crc32c(data: &[u8], stored: Option<&str>) -> EmptyReqRes {
+    match stored {
+        Some(stored) if !stored.contains('-') => {
+            let actual = encode_crc32c(crc32c::crc32c(data));
+            if actual == stored {
+                return Ok(())
+            }
+            Err(ClientError::ChecksumMismatch { expected: stored.to_string(), actual: Some(actual) })
+        }
+        _ => Ok(())
+    }
+}
+
+/// Confirms the CRC32C if checksums were sent
+pub(crate) fn confirm(expected: Option<&Checksums>, actual: Option<&str>) -> EmptyReqRes {
+    expected.map_or(Ok(()), |t| confirm_crc32c(&t.crc32c, actual))
+}
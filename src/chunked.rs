@@ -0,0 +1,526 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+use bytes::{Bytes, BytesMut};
+use futures::{stream, StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use crate::delegate::delegate;
+use crate::encoding::{resolve_range, slice_range};
+use crate::{ClientError, ClientInterface, ClientObject, Context, CopyOptions, DownloadOptions, EmptyReqRes, ErrorContext, ReqRes, UploadOptions, CONCURRENCY};
+
+/// Chunks of chunked objects are stored below this prefix, one directory per upload
+pub const CHUNK_PREFIX: &str = ".uni-stg/chunks/";
+/// Custom metadata key which marks manifests, its value is the size of the chunked object
+pub const CHUNKED_METADATA: &str = "uni-stg-chunked";
+pub const DEFAULT_CHUNK_SIZE: u64 = 64 * 1024 * 1024;
+/// Marks the data of manifests
+const MANIFEST_MAGIC: &[u8; 8] = b"USTGCHK1";
+
+/// Distinguishes uploads started within the same nanosecond
+static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+fn upload_id() -> String {
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|t| t.as_nanos()).unwrap_or(0);
+    format!("{nanos:x}-{:x}", COUNTER.fetch_add(1, Ordering::Relaxed))
+}
+
+fn chunk_key(upload_id: &str, index: usize) -> String {
+    format!("{CHUNK_PREFIX}{upload_id}/{index:08}")
+}
+
+/// Stored under the name of a chunked object, every chunk but the last has `chunk_size` bytes
+#[derive(Serialize, Deserialize)]
+struct Manifest {
+    size: u64,
+    chunk_size: u64,
+    chunks: Vec<String>,
+}
+
+impl Manifest {
+    fn encode(&self) -> ReqRes<Bytes> {
+        let mut data = MANIFEST_MAGIC.to_vec();
+        data.extend_from_slice(&serde_json::to_vec(self)?);
+        Ok(data.into())
+    }
+
+    /// None if the data is not a manifest
+    fn decode(data: &[u8]) -> Option<ReqRes<Self>> {
+        let json = data.strip_prefix(MANIFEST_MAGIC.as_slice())?;
+        Some(serde_json::from_slice(json).map_err(ClientError::from))
+    }
+
+    /// Chunks overlapping a range, with the (inclusive) range within each chunk
+    fn pieces(&self, starting: Option<u64>, ending: Option<u64>) -> Vec<(String, u64, u64)> {
+        let (from, to) = resolve_range(self.size, starting, ending);
+        self.chunks.iter().enumerate().filter_map(|(i, key)| {
+            let start = i as u64 * self.chunk_size;
+            let end = (start + self.chunk_size).min(self.size);
+            let (s, e) = (from.max(start), to.min(end));
+            (s < e).then(|| (key.clone(), s - start, e - 1 - start))
+        }).collect()
+    }
+}
+
+/// Object of a ChunkedClient, chunked objects report their whole size instead of the size of the manifest
+pub struct ChunkedObject {
+    size: u64,
+    chunked: bool,
+    bucket_name: String,
+    id: String,
+    name: String,
+    content_type: Option<String>,
+    last_modified: Option<SystemTime>,
+    etag: Option<String>,
+    storage_class: Option<String>,
+    custom_metadata: HashMap<String, String>,
+    checksum: Option<String>,
+    version: Option<String>,
+}
+
+impl ChunkedObject {
+    /// `size` overrides the size of manifests whose custom metadata is not known
+    fn of(object: &impl ClientObject, size: Option<u64>) -> Self {
+        let custom_metadata = object.custom_metadata();
+        let marked = custom_metadata.get(CHUNKED_METADATA).and_then(|t| t.parse().ok());
+        let chunked = size.is_some() || marked.is_some();
+        Self {
+            size: size.or(marked).unwrap_or_else(|| object.size()),
+            chunked,
+            bucket_name: object.bucket_name(),
+            id: object.id(),
+            name: object.name(),
+            content_type: object.content_type(),
+            last_modified: object.last_modified(),
+            etag: object.etag(),
+            storage_class: object.storage_class(),
+            custom_metadata,
+            // The checksum of the manifest does not describe the content
+            checksum: if chunked { None } else { object.checksum() },
+            version: object.version(),
+        }
+    }
+
+    /// Whether the object is stored in chunks
+    pub fn is_chunked(&self) -> bool {
+        self.chunked
+    }
+}
+
+impl ClientObject for ChunkedObject {
+    fn size(&self) -> u64 {
+        self.size
+    }
+
+    fn bucket_name(&self) -> String {
+        self.bucket_name.clone()
+    }
+
+    fn id(&self) -> String {
+        self.id.clone()
+    }
+
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn content_type(&self) -> Option<String> {
+        self.content_type.clone()
+    }
+
+    fn last_modified(&self) -> Option<SystemTime> {
+        self.last_modified
+    }
+
+    fn etag(&self) -> Option<String> {
+        self.etag.clone()
+    }
+
+    fn storage_class(&self) -> Option<String> {
+        self.storage_class.clone()
+    }
+
+    fn custom_metadata(&self) -> HashMap<String, String> {
+        self.custom_metadata.clone()
+    }
+
+    fn checksum(&self) -> Option<String> {
+        self.checksum.clone()
+    }
+
+    fn version(&self) -> Option<String> {
+        self.version.clone()
+    }
+}
+
+/// Wraps a client and stores objects larger than a threshold as fixed-size chunk objects (below `CHUNK_PREFIX`) plus a manifest
+/// under the name of the object, to work around object size limits and to read and write the chunks in parallel
+///
+/// Chunked objects are reassembled on read, ranged reads only fetch the chunks they overlap.
+/// The manifest carries the content type and custom metadata of the object (marked with `CHUNKED_METADATA`),
+/// conditions and versions of downloads apply to the manifest.
+/// Writes and removals check whether they replace a chunked object (one stat request) and remove its chunks afterwards,
+/// so old versions of chunked objects can not be restored. Ranged reads stat the object first as well.
+/// Note: Listings show the manifests (with their own size) and the chunks.
+pub struct ChunkedClient<C: ClientInterface> {
+    inner: C,
+    threshold: u64,
+    chunk_size: u64,
+    concurrency: usize,
+}
+
+impl<C: ClientInterface> ChunkedClient<C> {
+    /// Objects larger than `threshold` bytes are chunked (with chunks of `DEFAULT_CHUNK_SIZE`)
+    pub fn new(inner: C, threshold: u64) -> Self {
+        Self { inner, threshold, chunk_size: DEFAULT_CHUNK_SIZE, concurrency: CONCURRENCY }
+    }
+
+    pub fn with_chunk_size(mut self, chunk_size: u64) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// Number of chunks transferred at the same time (default: `CONCURRENCY`)
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    /// The manifest of an object, if it is chunked
+    async fn manifest(&self, bucket: &str, object_id: &str, object: &impl ClientObject) -> ReqRes<Option<Manifest>> {
+        if !object.custom_metadata().contains_key(CHUNKED_METADATA) {
+            return Ok(None)
+        }
+        let data = self.inner.static_download_bytes(bucket.to_string(), object_id.to_string(), None, None).await?;
+        Manifest::decode(&data).transpose()
+    }
+
+    /// Chunks of the object which is about to be replaced (none if it is missing or not chunked)
+    async fn chunks_of(&self, bucket: &str, object_id: &str) -> Vec<String> {
+        let Ok(object) = self.inner.stat_object(bucket.to_string(), object_id.to_string()).await else {
+            return vec![]
+        };
+        match self.manifest(bucket, object_id, &object).await {
+            Ok(Some(manifest)) => manifest.chunks,
+            _ => vec![]
+        }
+    }
+
+    /// Best effort, chunks which are left behind only take up space
+    async fn remove_chunks(&self, bucket: &str, chunks: Vec<String>) {
+        if !chunks.is_empty() {
+            let _ = self.inner.remove_objects(bucket.to_string(), chunks).await;
+        }
+    }
+
+    async fn assemble(&self, bucket: &str, manifest: &Manifest, starting: Option<u64>, ending: Option<u64>) -> ReqRes<Bytes> {
+        let parts: Vec<Bytes> = stream::iter(manifest.pieces(starting, ending)).map(|(key, s, e)| {
+            self.inner.static_download_bytes(bucket.to_string(), key, Some(s), Some(e))
+        }).buffered(self.concurrency).try_collect().await?;
+        let mut data = BytesMut::with_capacity(parts.iter().map(Bytes::len).sum());
+        for part in parts {
+            data.extend_from_slice(&part);
+        }
+        Ok(data.freeze())
+    }
+
+    /// Uploads the chunks of `data`, nothing is left behind if one of them fails
+    async fn upload_chunks(&self, bucket: &str, data: &Bytes, storage_class: Option<String>) -> ReqRes<Vec<String>> {
+        let id = upload_id();
+        let chunks: Vec<String> = (0..data.len().div_ceil(self.chunk_size as usize)).map(|i| chunk_key(&id, i)).collect();
+        let uploaded: Vec<ReqRes<()>> = stream::iter(chunks.clone().into_iter().enumerate()).map(|(i, key)| {
+            let start = i * self.chunk_size as usize;
+            let chunk = data.slice(start..(start + self.chunk_size as usize).min(data.len()));
+            let options = UploadOptions { storage_class: storage_class.clone(), ..Default::default() };
+            async move {
+                self.inner.upload_with_options(bucket.to_string(), key, chunk, options).await?;
+                Ok(())
+            }
+        }).buffer_unordered(self.concurrency).collect().await;
+        if let Some(e) = uploaded.into_iter().find_map(Result::err) {
+            self.remove_chunks(bucket, chunks).await;
+            return Err(e)
+        }
+        Ok(chunks)
+    }
+
+    /// Uploads the manifest of already uploaded chunks, they are removed if this fails
+    async fn finish(&self, bucket: String, object_id: String, manifest: Manifest, mut options: UploadOptions) -> ReqRes<ChunkedObject> {
+        options.custom_metadata.insert(CHUNKED_METADATA.to_string(), manifest.size.to_string());
+        let uploaded = match manifest.encode() {
+            Ok(data) => self.inner.upload_with_options(bucket.clone(), object_id, data, options).await,
+            Err(e) => Err(e)
+        };
+        match uploaded {
+            Ok(object) => Ok(ChunkedObject::of(&object, Some(manifest.size))),
+            Err(e) => {
+                self.remove_chunks(&bucket, manifest.chunks).await;
+                Err(e)
+            }
+        }
+    }
+
+    async fn write(&self, bucket: String, object_id: String, data: Bytes, options: UploadOptions) -> ReqRes<ChunkedObject> {
+        let replaced = self.chunks_of(&bucket, &object_id).await;
+        let object = if data.len() as u64 <= self.threshold {
+            ChunkedObject::of(&self.inner.upload_with_options(bucket.clone(), object_id, data, options).await?, None)
+        } else {
+            let chunks = self.upload_chunks(&bucket, &data, options.storage_class.clone()).await?;
+            let manifest = Manifest { size: data.len() as u64, chunk_size: self.chunk_size, chunks };
+            self.finish(bucket.clone(), object_id, manifest, options).await?
+        };
+        self.remove_chunks(&bucket, replaced).await;
+        Ok(object)
+    }
+
+    async fn copy(&self, src_bucket: String, src_object: String, dest_bucket: String, dest_object: String, options: CopyOptions) -> ReqRes<ChunkedObject> {
+        let source = self.inner.stat_object(src_bucket.clone(), src_object.clone()).await?;
+        let Some(manifest) = self.manifest(&src_bucket, &src_object, &source).await? else {
+            let copy = self.inner.copy_object_with_options(src_bucket, src_object, dest_bucket.clone(), dest_object.clone(), options).await?;
+            return Ok(ChunkedObject::of(&copy, None))
+        };
+        let replaced = self.chunks_of(&dest_bucket, &dest_object).await;
+        // Every chunked object owns its chunks, so they are copied as well
+        let id = upload_id();
+        let chunks: Vec<String> = (0..manifest.chunks.len()).map(|i| chunk_key(&id, i)).collect();
+        let copied: Vec<ReqRes<()>> = stream::iter(manifest.chunks.clone().into_iter().zip(chunks.clone())).map(|(from, to)| {
+            let (src_bucket, dest_bucket, options) = (src_bucket.clone(), dest_bucket.clone(), options.clone());
+            async move {
+                self.inner.copy_object_with_options(src_bucket, from, dest_bucket, to, options).await?;
+                Ok(())
+            }
+        }).buffer_unordered(self.concurrency).collect().await;
+        if let Some(e) = copied.into_iter().find_map(Result::err) {
+            self.remove_chunks(&dest_bucket, chunks).await;
+            return Err(e)
+        }
+        let options = UploadOptions {
+            content_type: source.content_type(),
+            custom_metadata: source.custom_metadata(),
+            storage_class: options.storage_class.or(source.storage_class()),
+            ..Default::default()
+        };
+        let manifest = Manifest { size: manifest.size, chunk_size: manifest.chunk_size, chunks };
+        let copy = self.finish(dest_bucket.clone(), dest_object, manifest, options).await?;
+        self.remove_chunks(&dest_bucket, replaced).await;
+        Ok(copy)
+    }
+}
+
+impl<C: ClientInterface> ClientInterface for ChunkedClient<C> {
+    async fn static_download_bytes(&self, bucket: String, object_id: String, starting: Option<u64>, ending: Option<u64>) -> ReqRes<Bytes> {
+        let object = self.inner.stat_object(bucket.clone(), object_id.clone()).await?;
+        match self.manifest(&bucket, &object_id, &object).await? {
+            Some(manifest) => self.assemble(&bucket, &manifest, starting, ending).await,
+            None => self.inner.static_download_bytes(bucket, object_id, starting, ending).await
+        }
+    }
+
+    /// The conditions and the version apply to the manifest of chunked objects
+    /// Note: The metadata of older versions is not available, so a specific version is recognized as chunked by its content
+    async fn download_with_options(&self, bucket: String, object_id: String, starting: Option<u64>, ending: Option<u64>, options: DownloadOptions) -> ReqRes<Bytes> {
+        if options.version.is_none() {
+            let object = self.inner.stat_object(bucket.clone(), object_id.clone()).await?;
+            if !object.custom_metadata().contains_key(CHUNKED_METADATA) {
+                return self.inner.download_with_options(bucket, object_id, starting, ending, options).await
+            }
+        }
+        let data = self.inner.download_with_options(bucket.clone(), object_id, None, None, options).await?;
+        match Manifest::decode(&data) {
+            Some(manifest) => self.assemble(&bucket, &manifest?, starting, ending).await,
+            None => Ok(slice_range(data, starting, ending))
+        }
+    }
+
+    /// Chunks are fetched in parallel but written in order
+    async fn download_to_writer(&self, bucket: String, object_id: String, starting: Option<u64>, ending: Option<u64>, mut w: impl AsyncWrite + Unpin + Send) -> ReqRes<u64> {
+        let object = self.inner.stat_object(bucket.clone(), object_id.clone()).await?;
+        let Some(manifest) = self.manifest(&bucket, &object_id, &object).await? else {
+            return self.inner.download_to_writer(bucket, object_id, starting, ending, w).await
+        };
+        let mut parts = stream::iter(manifest.pieces(starting, ending)).map(|(key, s, e)| {
+            self.inner.static_download_bytes(bucket.clone(), key, Some(s), Some(e))
+        }).buffered(self.concurrency);
+        let mut written = 0;
+        while let Some(part) = parts.next().await {
+            let part = part?;
+            w.write_all(&part).await.context("download_to_writer", &bucket, Some(&object_id))?;
+            written += part.len() as u64;
+        }
+        w.flush().await.context("download_to_writer", &bucket, Some(&object_id))?;
+        Ok(written)
+    }
+
+    async fn static_upload_bytes(&self, bucket: String, object_id: String, data: Bytes) -> ReqRes<impl ClientObject> {
+        self.write(bucket, object_id, data, UploadOptions::default()).await
+    }
+
+    /// The options apply to the manifest of chunked objects, only the storage class applies to the chunks as well
+    async fn upload_with_options(&self, bucket: String, object_id: String, data: Bytes, options: UploadOptions) -> ReqRes<impl ClientObject> {
+        self.write(bucket, object_id, data, options).await
+    }
+
+    /// Only `threshold` bytes are buffered, chunks of larger uploads are uploaded one after another while reading
+    async fn upload_from_reader(&self, bucket: String, object_id: String, mut r: impl AsyncRead + Unpin + Send + Sync + 'static) -> EmptyReqRes {
+        let mut buffer = vec![];
+        (&mut r).take(self.threshold.saturating_add(1)).read_to_end(&mut buffer).await.context("upload_from_reader", &bucket, Some(&object_id))?;
+        if buffer.len() as u64 <= self.threshold {
+            self.write(bucket, object_id, buffer.into(), UploadOptions::default()).await?;
+            return Ok(())
+        }
+        let replaced = self.chunks_of(&bucket, &object_id).await;
+        let (id, chunk_size) = (upload_id(), self.chunk_size as usize);
+        let (mut chunks, mut size, mut eof) = (vec![], 0, false);
+        loop {
+            if buffer.len() < chunk_size && !eof {
+                let missing = chunk_size - buffer.len();
+                let read = (&mut r).take(missing as u64).read_to_end(&mut buffer).await;
+                match read {
+                    Ok(n) => eof = n < missing,
+                    Err(e) => {
+                        self.remove_chunks(&bucket, chunks).await;
+                        return Err(e).context("upload_from_reader", &bucket, Some(&object_id))
+                    }
+                }
+            }
+            if buffer.is_empty() {
+                break
+            }
+            let rest = buffer.split_off(buffer.len().min(chunk_size));
+            let chunk = std::mem::replace(&mut buffer, rest);
+            let key = chunk_key(&id, chunks.len());
+            size += chunk.len() as u64;
+            let uploaded = self.inner.static_upload_bytes(bucket.clone(), key.clone(), chunk.into()).await;
+            chunks.push(key);
+            if let Err(e) = uploaded {
+                self.remove_chunks(&bucket, chunks).await;
+                return Err(e)
+            }
+        }
+        self.finish(bucket.clone(), object_id, Manifest { size, chunk_size: self.chunk_size, chunks }, UploadOptions::default()).await?;
+        self.remove_chunks(&bucket, replaced).await;
+        Ok(())
+    }
+
+    /// Appending to a chunked object rewrites it as a whole
+    async fn append(&self, bucket: String, object_id: String, data: Bytes) -> EmptyReqRes {
+        let object = match self.inner.stat_object(bucket.clone(), object_id.clone()).await {
+            Ok(object) => object,
+            Err(e) if e.is_not_found() => return self.inner.append(bucket, object_id, data).await,
+            Err(e) => return Err(e)
+        };
+        let Some(manifest) = self.manifest(&bucket, &object_id, &object).await? else {
+            return self.inner.append(bucket, object_id, data).await
+        };
+        let mut combined = self.assemble(&bucket, &manifest, None, None).await?.to_vec();
+        combined.extend_from_slice(&data);
+        let options = UploadOptions { content_type: object.content_type(), custom_metadata: object.custom_metadata(), storage_class: object.storage_class(), ..Default::default() };
+        self.write(bucket, object_id, combined.into(), options).await?;
+        Ok(())
+    }
+
+    /// Chunks are not public, so objects larger than the threshold can not be uploaded publicly
+    async fn upload_public(&self, bucket: String, object_id: String, data: Bytes, content_type: Option<String>, cache_control: String) -> EmptyReqRes {
+        if data.len() as u64 > self.threshold {
+            return Err(ClientError::Unsupported("objects larger than the chunking threshold cannot be public")
+                .with_context(ErrorContext::new("upload_public", Some(&bucket), Some(&object_id))))
+        }
+        let replaced = self.chunks_of(&bucket, &object_id).await;
+        self.inner.upload_public(bucket.clone(), object_id, data, content_type, cache_control).await?;
+        self.remove_chunks(&bucket, replaced).await;
+        Ok(())
+    }
+
+    async fn remove_object(&self, bucket: String, object_id: String) -> EmptyReqRes {
+        let chunks = self.chunks_of(&bucket, &object_id).await;
+        self.inner.remove_object(bucket.clone(), object_id).await?;
+        self.remove_chunks(&bucket, chunks).await;
+        Ok(())
+    }
+
+    async fn remove_objects(&self, bucket_name: String, keys: Vec<String>) -> ReqRes<Vec<(String, EmptyReqRes)>> {
+        Ok(stream::iter(keys).map(|key| {
+            let bucket_name = bucket_name.clone();
+            async move {
+                let removed = self.remove_object(bucket_name, key.clone()).await;
+                (key, removed)
+            }
+        }).buffered(self.concurrency).collect().await)
+    }
+
+    async fn copy_object(&self, src_bucket: String, src_object: String, dest_bucket: String, dest_object: String) -> ReqRes<impl ClientObject> {
+        self.copy(src_bucket, src_object, dest_bucket, dest_object, CopyOptions::default()).await
+    }
+
+    /// The options apply to the chunks of chunked objects as well
+    async fn copy_object_with_options(&self, src_bucket: String, src_object: String, dest_bucket: String, dest_object: String, options: CopyOptions) -> ReqRes<impl ClientObject> {
+        self.copy(src_bucket, src_object, dest_bucket, dest_object, options).await
+    }
+
+    async fn get_object(&self, bucket_name: String, object_name: String) -> ReqRes<impl ClientObject> {
+        Ok(ChunkedObject::of(&self.inner.get_object(bucket_name, object_name).await?, None))
+    }
+
+    async fn stat_object(&self, bucket_name: String, object_name: String) -> ReqRes<impl ClientObject> {
+        Ok(ChunkedObject::of(&self.inner.stat_object(bucket_name, object_name).await?, None))
+    }
+
+    delegate!(inner;
+        update_object_metadata, get_object_tags, set_object_tags, get_object_acl, set_object_acl, public_url, signed_url, presigned_post, remove_bucket, create_bucket, restore_object, restore_status,
+        list_buckets, get_bucket, list_objects, list_prefix, list_projected, list_delimited, list_after, list_versions, restore_object_version,
+        bucket_exists, bucket_name_problems, compliance_records, get_bucket_spec, get_lifecycle_rules, set_lifecycle_rules, get_bucket_policy, set_bucket_policy, get_bucket_labels, set_bucket_labels,
+        get_object_lock, set_object_retention, set_legal_hold
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::in_memory::InMemoryClient;
+    use super::*;
+
+    const DATA: &[u8] = b"abcdefghijklmnopqrstuv";
+
+    fn chunked() -> ChunkedClient<InMemoryClient> {
+        ChunkedClient::new(InMemoryClient::with_bucket("bucket"), 8).with_chunk_size(4).with_concurrency(3)
+    }
+
+    #[tokio::test]
+    async fn assembles_ranges_across_chunks() {
+        let client = chunked();
+        client.static_upload_bytes("bucket".to_string(), "key".to_string(), Bytes::from_static(DATA)).await.unwrap();
+        let chunks = client.inner().list_prefix("bucket".to_string(), CHUNK_PREFIX.to_string(), None).await.unwrap();
+        assert_eq!(chunks.items.len(), 6);
+        let len = DATA.len() as u64;
+        let mut ranges = vec![(None, None), (Some(len), None), (None, Some(0)), (None, Some(len + 5)), (Some(20), Some(100))];
+        for s in 0..len {
+            ranges.extend([(Some(s), None), (None, Some(s))]);
+            ranges.extend((s..len).map(|e| (Some(s), Some(e))));
+        }
+        for (starting, ending) in ranges {
+            let expected = slice_range(Bytes::from_static(DATA), starting, ending);
+            let read = client.static_download_bytes("bucket".to_string(), "key".to_string(), starting, ending).await.unwrap();
+            assert_eq!(read, expected, "{starting:?}..={ending:?}");
+            let mut written = vec![];
+            client.download_to_writer("bucket".to_string(), "key".to_string(), starting, ending, &mut written).await.unwrap();
+            assert_eq!(written, expected, "{starting:?}..={ending:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn replaces_chunks() {
+        let client = chunked();
+        client.upload_from_reader("bucket".to_string(), "key".to_string(), DATA).await.unwrap();
+        client.append("bucket".to_string(), "key".to_string(), Bytes::from_static(b"wxyz")).await.unwrap();
+        let read = client.static_download_bytes("bucket".to_string(), "key".to_string(), Some(18), None).await.unwrap();
+        assert_eq!(read, "stuvwxyz");
+        assert_eq!(client.stat_object("bucket".to_string(), "key".to_string()).await.unwrap().size(), DATA.len() as u64 + 4);
+        // Small enough to be stored as it is, the chunks of the replaced object are removed
+        client.static_upload_bytes("bucket".to_string(), "key".to_string(), Bytes::from_static(b"small")).await.unwrap();
+        assert!(client.inner().list_prefix("bucket".to_string(), CHUNK_PREFIX.to_string(), None).await.unwrap().items.is_empty());
+        assert_eq!(client.static_download_bytes("bucket".to_string(), "key".to_string(), Some(1), Some(3)).await.unwrap(), "mal");
+    }
+}
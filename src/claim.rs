@@ -0,0 +1,134 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use bytes::Bytes;
+use hmac::Mac;
+use crate::{ClientError, ClientInterface, ClientObject, EmptyReqRes, ReqRes, UploadOptions, WritePrecondition};
+use crate::encoding::{hex, mac, unhex};
+
+/// Payloads of claim checks are stored below this prefix, together with a marker while a claim is redeemed
+pub const CLAIM_PREFIX: &str = ".uni-stg/claims/";
+
+/// Time a redemption may take before another one can take over its marker, so a crashed redemption does not block the claim
+/// Note: A redemption which takes longer may hand out the payload together with the one taking over
+pub const REDEMPTION_LEASE: Duration = Duration::from_secs(60);
+/// Custom metadata key of the time (milliseconds since the Unix epoch) the lease of a redemption marker ends at
+const LEASE_KEY: &str = "claim-lease-until";
+
+/// Distinguishes claims created within the same nanosecond
+static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// The claim ID of a token, if its signature is valid
+fn verify(token: &str, key: &[u8]) -> ReqRes<String> {
+    let (id, signature) = token.rsplit_once('.').ok_or(ClientError::InvalidClaim)?;
+    let signature = unhex(signature).ok_or(ClientError::InvalidClaim)?;
+    mac(key, id.as_bytes()).verify_slice(&signature).map_err(|_| ClientError::InvalidClaim)?;
+    Ok(id.to_string())
+}
+
+fn unix_millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).map(|t| t.as_millis() as u64).unwrap_or(0)
+}
+
+/// Writes the marker of a redemption with a new lease
+async fn lease<C: ClientInterface + ?Sized>(client: &C, bucket: &str, marker: &str, precondition: WritePrecondition) -> ReqRes<impl ClientObject> {
+    let until = unix_millis(SystemTime::now() + REDEMPTION_LEASE);
+    let options = UploadOptions {
+        custom_metadata: HashMap::from([(LEASE_KEY.to_string(), until.to_string())]),
+        precondition: Some(precondition),
+        ..Default::default()
+    };
+    client.upload_with_options(bucket.to_string(), marker.to_string(), Bytes::new(), options).await
+}
+
+/// Creates the marker of a redemption, or takes it over once the lease of the redemption holding it ended
+/// Only one of multiple concurrent redemptions succeeds, the others fail with `ClientError::InvalidClaim`
+async fn acquire<C: ClientInterface + ?Sized>(client: &C, bucket: &str, marker: &str) -> EmptyReqRes {
+    loop {
+        match lease(client, bucket, marker, WritePrecondition::DoesNotExist).await {
+            Ok(_) => return Ok(()),
+            Err(e) if e.is_precondition_failed() || e.is_already_exists() => {}
+            Err(e) => return Err(e)
+        }
+        let held = match client.stat_object(bucket.to_string(), marker.to_string()).await {
+            Ok(held) => held,
+            // Released in between by a redemption which failed
+            Err(e) if e.is_not_found() => continue,
+            Err(e) => return Err(e)
+        };
+        let until = held.custom_metadata().get(LEASE_KEY).and_then(|t| t.parse::<u64>().ok()).unwrap_or(0);
+        let Some(version) = held.version().filter(|_| until <= unix_millis(SystemTime::now())) else {
+            return Err(ClientError::InvalidClaim)
+        };
+        return match lease(client, bucket, marker, WritePrecondition::Matches(version)).await {
+            Ok(_) => Ok(()),
+            Err(e) if e.is_precondition_failed() => Err(ClientError::InvalidClaim),
+            Err(e) => Err(e)
+        }
+    }
+}
+
+pub(crate) async fn check<C: ClientInterface + ?Sized>(client: &C, bucket: String, data: Bytes, key: &[u8]) -> ReqRes<String> {
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|t| t.as_nanos()).unwrap_or(0);
+    let id = format!("{nanos:x}-{:x}", COUNTER.fetch_add(1, Ordering::Relaxed));
+    client.upload_if_absent(bucket, format!("{CLAIM_PREFIX}{id}"), data).await?;
+    Ok(format!("{id}.{}", hex(&mac(key, id.as_bytes()).finalize().into_bytes())))
+}
+
+pub(crate) async fn redeem<C: ClientInterface + ?Sized>(client: &C, bucket: String, token: &str, key: &[u8]) -> ReqRes<Bytes> {
+    let id = verify(token, key)?;
+    let (payload, marker) = (format!("{CLAIM_PREFIX}{id}"), format!("{CLAIM_PREFIX}{id}.redeemed"));
+    acquire(client, &bucket, &marker).await?;
+    let redeemed: ReqRes<Bytes> = async {
+        let data = client.static_download_bytes(bucket.clone(), payload.clone(), None, None).await?;
+        client.remove_object(bucket.clone(), payload).await?;
+        Ok(data)
+    }.await;
+    // Removing the marker lets a failed redemption be retried, a redeemed claim has no payload anymore
+    let _ = client.remove_object(bucket, marker).await;
+    match redeemed {
+        Err(e) if e.is_not_found() => Err(ClientError::InvalidClaim),
+        result => result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::in_memory::InMemoryClient;
+    use super::*;
+
+    const KEY: &[u8] = b"secret";
+
+    #[tokio::test]
+    async fn redeems_once() {
+        let client = InMemoryClient::with_bucket("bucket");
+        let payload = Bytes::from_static(b"payload");
+        let token = client.claim_check("bucket".to_string(), payload.clone(), KEY).await.unwrap();
+        assert!(matches!(client.redeem_claim("bucket".to_string(), format!("{token}0"), KEY).await, Err(ClientError::InvalidClaim)));
+        let redemptions = futures::future::join_all((0..8).map(|_| client.redeem_claim("bucket".to_string(), token.clone(), KEY))).await;
+        let redeemed: Vec<_> = redemptions.into_iter().filter_map(|t| match t {
+            Ok(data) => Some(data),
+            Err(e) => {
+                assert!(matches!(e.inner(), ClientError::InvalidClaim), "{e}");
+                None
+            }
+        }).collect();
+        assert_eq!(redeemed, vec![payload]);
+        assert!(matches!(client.redeem_claim("bucket".to_string(), token, KEY).await, Err(ClientError::InvalidClaim)));
+    }
+
+    #[tokio::test]
+    async fn takes_over_expired_leases() {
+        let client = InMemoryClient::with_bucket("bucket");
+        let payload = Bytes::from_static(b"payload");
+        let token = client.claim_check("bucket".to_string(), payload.clone(), KEY).await.unwrap();
+        let marker = format!("{CLAIM_PREFIX}{}.redeemed", token.rsplit_once('.').unwrap().0);
+        let held = |until: u64| UploadOptions { custom_metadata: HashMap::from([(LEASE_KEY.to_string(), until.to_string())]), ..Default::default() };
+        // Left behind by a redemption which is still running
+        client.upload_with_options("bucket".to_string(), marker.clone(), Bytes::new(), held(u64::MAX)).await.unwrap();
+        assert!(matches!(client.redeem_claim("bucket".to_string(), token.clone(), KEY).await, Err(ClientError::InvalidClaim)));
+        // Left behind by a redemption which crashed
+        client.upload_with_options("bucket".to_string(), marker, Bytes::new(), held(0)).await.unwrap();
+        assert_eq!(client.redeem_claim("bucket".to_string(), token, KEY).await.unwrap(), payload);
+    }
+}
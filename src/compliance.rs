@@ -0,0 +1,126 @@
+use std::time::SystemTime;
+use serde::Serialize;
+use crate::ReqRes;
+
+/// Compliance relevant state of a single object
+#[derive(Debug, Clone, Serialize)]
+pub struct ComplianceRecord {
+    pub name: String,
+    pub storage_class: Option<String>,
+    /// Encryption in use (example: 'aws:kms', 'AES256', a KMS key name or 'google-managed'), None if unencrypted
+    pub encryption: Option<String>,
+    /// Whether the object can be read without authentication
+    pub public: bool,
+    /// Whether a legal hold (AWS-S3) or temporary / event based hold (GCS) is set
+    pub legal_hold: bool,
+    /// Retention period of the object, if any
+    pub retain_until: Option<SystemTime>,
+}
+
+/// How strictly a retention period is enforced
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum RetentionMode {
+    /// Users with special permissions can shorten or remove the retention (AWS-S3 governance mode)
+    Governance,
+    /// Nobody can shorten or remove the retention until it expires (AWS-S3 compliance mode, locked GCS retention policies)
+    Compliance,
+}
+
+/// The object can neither be deleted nor overwritten before `retain_until` (WORM)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Retention {
+    pub mode: RetentionMode,
+    pub retain_until: SystemTime,
+}
+
+/// Holds and retention of a single object
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct ObjectLock {
+    /// Whether a legal hold (AWS-S3) or temporary / event based hold (GCS) is set
+    pub legal_hold: bool,
+    pub retention: Option<Retention>,
+}
+
+/// Requirements every object of a bucket is checked against
+#[derive(Debug, Clone, Default)]
+pub struct CompliancePolicy {
+    /// Objects must be encrypted
+    pub require_encryption: bool,
+    /// Objects must be encrypted with a customer managed key (KMS / CSEK)
+    pub require_customer_key: bool,
+    /// Objects may be public
+    pub allow_public: bool,
+    /// Storage classes objects may use, None allows all
+    pub allowed_storage_classes: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum Violation {
+    Unencrypted,
+    NoCustomerKey,
+    Public,
+    StorageClass(Option<String>),
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ComplianceEntry {
+    #[serde(flatten)]
+    pub record: ComplianceRecord,
+    pub violations: Vec<Violation>,
+}
+
+/// Objects of a bucket which are under hold / retention or violate the policy
+#[derive(Debug, Clone, Serialize)]
+pub struct ComplianceReport {
+    pub bucket: String,
+    pub generated_at: SystemTime,
+    /// Number of objects which were checked
+    pub checked: u64,
+    pub entries: Vec<ComplianceEntry>,
+}
+
+/// Encryption values which denote provider managed keys
+pub(crate) const PROVIDER_MANAGED: [&str; 2] = ["AES256", "google-managed"];
+
+impl CompliancePolicy {
+    pub fn check(&self, record: &ComplianceRecord) -> Vec<Violation> {
+        let mut violations = vec![];
+        match &record.encryption {
+            None if self.require_encryption || self.require_customer_key => violations.push(Violation::Unencrypted),
+            Some(encryption) if self.require_customer_key && PROVIDER_MANAGED.contains(&encryption.as_str()) => violations.push(Violation::NoCustomerKey),
+            _ => {}
+        }
+        if record.public && !self.allow_public {
+            violations.push(Violation::Public);
+        }
+        if let Some(allowed) = &self.allowed_storage_classes
+            && !record.storage_class.as_ref().is_some_and(|t| allowed.contains(t)) {
+            violations.push(Violation::StorageClass(record.storage_class.clone()));
+        }
+        violations
+    }
+}
+
+impl ComplianceReport {
+    pub(crate) fn new(bucket: String) -> Self {
+        Self { bucket, generated_at: SystemTime::now(), checked: 0, entries: vec![] }
+    }
+
+    /// Checks a record, it is only kept if it is under hold / retention or violates the policy
+    pub(crate) fn add(&mut self, record: ComplianceRecord, policy: &CompliancePolicy) {
+        self.checked += 1;
+        let violations = policy.check(&record);
+        if !violations.is_empty() || record.legal_hold || record.retain_until.is_some() {
+            self.entries.push(ComplianceEntry { record, violations });
+        }
+    }
+
+    /// Objects which violate the policy
+    pub fn violations(&self) -> impl Iterator<Item = &ComplianceEntry> {
+        self.entries.iter().filter(|t| !t.violations.is_empty())
+    }
+
+    pub fn to_json(&self) -> ReqRes<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
@@ -0,0 +1,97 @@
+use serde::Deserialize;
+#[cfg(feature = "aws_s3")]
+use crate::aws_s3::{AWSClient, AWSConfig};
+#[cfg(feature = "google_cloud")]
+use crate::google_cloud::{GoogleCloud, GoogleCloudConfig};
+#[cfg(feature = "local")]
+use crate::local::LocalClient;
+use crate::{ClientError, Credentials, ReqRes, UnifiedClient};
+
+/// Prefix of the environment variables read by `StorageConfig::from_env` (example: 'UNI_STG_PROVIDER')
+pub const ENV_PREFIX: &str = "UNI_STG_";
+
+/// Serializable description of a client, so the provider can be switched by configuration alone
+/// Example (TOML): `provider = "s3"`, `region = "eu-central-1"`, `credentials = "standard"`
+/// JSON and the environment are always supported, TOML and YAML files need the `toml` and `yaml` features
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct StorageConfig {
+    /// 'gs' (or 'google_cloud') / 's3' (or 'aws_s3') / 'file' (or 'local')
+    pub provider: String,
+    /// Region of the client (AWS-S3), the default provider chain's region is used if None
+    pub region: Option<String>,
+    /// Custom endpoint (example: an emulator or an S3 compatible service), the root directory for local files ('/' if None)
+    pub endpoint: Option<String>,
+    /// Project of the client (Google-Cloud), needed to list and create buckets
+    pub project_id: Option<String>,
+    pub credentials: Credentials,
+}
+
+impl StorageConfig {
+    pub fn from_json(s: &str) -> ReqRes<Self> {
+        serde_json::from_str(s).map_err(|e| ClientError::InvalidConfig(e.to_string()))
+    }
+
+    /// Requires the `toml` feature
+    #[cfg(feature = "toml")]
+    pub fn from_toml(s: &str) -> ReqRes<Self> {
+        toml::from_str(s).map_err(|e| ClientError::InvalidConfig(e.to_string()))
+    }
+
+    /// Requires the `yaml` feature
+    #[cfg(feature = "yaml")]
+    pub fn from_yaml(s: &str) -> ReqRes<Self> {
+        serde_yaml::from_str(s).map_err(|e| ClientError::InvalidConfig(e.to_string()))
+    }
+
+    /// Reads `UNI_STG_PROVIDER`, `UNI_STG_REGION`, `UNI_STG_ENDPOINT`, `UNI_STG_PROJECT_ID` and `UNI_STG_CREDENTIALS`
+    /// ('standard', 'anonymous' or the path of a credentials file)
+    pub fn from_env() -> ReqRes<Self> {
+        let var = |name: &str| std::env::var(format!("{ENV_PREFIX}{name}")).ok().filter(|t| !t.is_empty());
+        let provider = var("PROVIDER").ok_or_else(|| ClientError::InvalidConfig(format!("{ENV_PREFIX}PROVIDER is not set")))?;
+        let credentials = match var("CREDENTIALS").as_deref() {
+            None | Some("standard") => Credentials::Standard,
+            Some("anonymous") => Credentials::Anonymous,
+            Some(path) => Credentials::File(path.to_string())
+        };
+        Ok(Self { provider, region: var("REGION"), endpoint: var("ENDPOINT"), project_id: var("PROJECT_ID"), credentials })
+    }
+
+    /// Builds the client with the process-wide `Defaults`
+    /// Fails with `ClientError::InvalidConfig` if the provider is unknown or its feature is not enabled
+    pub async fn build(&self) -> ReqRes<UnifiedClient> {
+        match self.provider.as_str() {
+            #[cfg(feature = "google_cloud")]
+            "gs" | "google_cloud" => {
+                let project_id = self.project_id.clone().unwrap_or_default();
+                let mut config = match &self.credentials {
+                    Credentials::Standard => GoogleCloudConfig::standard_auth(project_id).await?,
+                    Credentials::Anonymous => GoogleCloudConfig::anonymous(project_id),
+                    Credentials::File(path) => GoogleCloudConfig::from_file(project_id, path.clone()).await?
+                };
+                if let Some(endpoint) = &self.endpoint {
+                    config = config.with_endpoint(endpoint);
+                }
+                Ok(UnifiedClient::GoogleCloud(GoogleCloud::new(config)))
+            }
+            #[cfg(feature = "aws_s3")]
+            "s3" | "aws_s3" => {
+                let mut config = match &self.credentials {
+                    Credentials::Standard => AWSConfig::standard_auth().await,
+                    Credentials::Anonymous => AWSConfig::anonymous_default_region().await,
+                    Credentials::File(path) => AWSConfig::from_credentials_file(path).await
+                };
+                if let Some(region) = &self.region {
+                    config = config.with_region(region);
+                }
+                if let Some(endpoint) = &self.endpoint {
+                    config = config.with_endpoint(endpoint);
+                }
+                Ok(UnifiedClient::AWS(AWSClient::new(config)))
+            }
+            #[cfg(feature = "local")]
+            "file" | "local" => Ok(UnifiedClient::Local(LocalClient::new(self.endpoint.as_deref().unwrap_or("/")))),
+            provider => Err(ClientError::InvalidConfig(format!("the provider '{provider}' is unknown or its feature is not enabled")))
+        }
+    }
+}
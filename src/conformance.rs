@@ -0,0 +1,520 @@
+//! Checks that a client behaves like the built-in backends, runnable against real providers, emulators and new backends
+//! Every check works on objects below a unique key under `CONFORMANCE_PREFIX` in an existing bucket, which is removed afterwards
+//! The bucket's configuration (lifecycle rules, labels) is only read, never changed
+//! Checks of features the client reports as `ClientError::Unsupported` are skipped instead of failed
+
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+use bytes::Bytes;
+use futures::{pin_mut, StreamExt};
+use crate::{AccessControl, ClientBucket, ClientError, ClientInterface, ClientObject, CopyOptions, DownloadOptions, ListOptions, MetadataChanges, ObjectAcl, PostConstraints, ReqRes, SignedUrlOptions, UploadOptions, WritePrecondition};
+
+/// Prefix of the objects written by `run_all`
+pub const CONFORMANCE_PREFIX: &str = ".uni-stg-conformance/";
+
+/// Outcome of a single check
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckResult {
+    pub name: &'static str,
+    /// Why the check failed, None if it passed
+    pub error: Option<String>,
+    /// The client does not support the checked feature
+    pub skipped: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConformanceReport {
+    pub results: Vec<CheckResult>,
+}
+
+impl ConformanceReport {
+    pub fn passed(&self) -> bool {
+        self.results.iter().all(|t| t.error.is_none())
+    }
+
+    pub fn failures(&self) -> impl Iterator<Item = &CheckResult> {
+        self.results.iter().filter(|t| t.error.is_some())
+    }
+
+    pub fn skipped(&self) -> impl Iterator<Item = &CheckResult> {
+        self.results.iter().filter(|t| t.skipped)
+    }
+}
+
+enum Failure {
+    Unsupported,
+    Failed(String),
+}
+
+impl From<String> for Failure {
+    fn from(message: String) -> Self {
+        Failure::Failed(message)
+    }
+}
+
+type Outcome = Result<(), Failure>;
+
+fn fail(e: ClientError) -> Failure {
+    match e.inner() {
+        ClientError::Unsupported(_) => Failure::Unsupported,
+        _ => Failure::Failed(e.to_string())
+    }
+}
+
+fn ensure(condition: bool, message: impl FnOnce() -> String) -> Outcome {
+    if condition { Ok(()) } else { Err(Failure::Failed(message())) }
+}
+
+/// Passes if `result` is an error for which `expected` holds
+fn ensure_error<T>(result: &ReqRes<T>, expected: impl FnOnce(&ClientError) -> bool, message: impl FnOnce() -> String) -> Outcome {
+    match result {
+        Err(e) if expected(e) => Ok(()),
+        Err(e) if matches!(e.inner(), ClientError::Unsupported(_)) => Err(Failure::Unsupported),
+        _ => Err(Failure::Failed(message()))
+    }
+}
+
+/// Runs every check against `bucket` (which has to exist) and removes the written objects
+pub async fn run_all(client: &impl ClientInterface, bucket: &str) -> ConformanceReport {
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|t| t.as_nanos()).unwrap_or_default();
+    let prefix = format!("{CONFORMANCE_PREFIX}{nanos}/");
+    let mut results = vec![];
+    macro_rules! check {
+        ($($name:ident),* $(,)?) => {
+            $(
+                let (error, skipped) = match $name(client, bucket, &prefix).await {
+                    Ok(()) => (None, false),
+                    Err(Failure::Unsupported) => (None, true),
+                    Err(Failure::Failed(message)) => (Some(message), false)
+                };
+                results.push(CheckResult { name: stringify!($name), error, skipped });
+            )*
+        };
+    }
+    check!(round_trip, empty_object, unicode_key, ranges, missing_key, overwrite, metadata, update_metadata, tags, acl,
+        copy, copy_with_options, move_object, append, create_only, compare_and_swap, conditional_download, versions,
+        listing, delimited_listing, streamed_listing, listing_after, stat_many, remove_many, reader_and_writer,
+        signed_urls, presigned_post, get_bucket, bucket_configuration);
+    let _ = client.remove_prefix(bucket.to_string(), prefix).await;
+    ConformanceReport { results }
+}
+
+async fn round_trip(client: &impl ClientInterface, bucket: &str, prefix: &str) -> Outcome {
+    let key = format!("{prefix}round-trip");
+    let data = Bytes::from_static(b"uni-stg conformance");
+    let uploaded = client.static_upload_bytes(bucket.to_string(), key.clone(), data.clone()).await.map_err(fail)?;
+    ensure(uploaded.size() == 0 || uploaded.size() == data.len() as u64, || format!("upload reported {} bytes", uploaded.size()))?;
+    let read = client.static_download_bytes(bucket.to_string(), key.clone(), None, None).await.map_err(fail)?;
+    ensure(read == data, || format!("downloaded {} bytes, expected {}", read.len(), data.len()))?;
+    let stat = client.stat_object(bucket.to_string(), key).await.map_err(fail)?;
+    ensure(stat.size() == data.len() as u64, || format!("stat reported {} bytes, expected {}", stat.size(), data.len()))
+}
+
+async fn empty_object(client: &impl ClientInterface, bucket: &str, prefix: &str) -> Outcome {
+    let key = format!("{prefix}empty");
+    client.static_upload_bytes(bucket.to_string(), key.clone(), Bytes::new()).await.map_err(fail)?;
+    let read = client.static_download_bytes(bucket.to_string(), key.clone(), None, None).await.map_err(fail)?;
+    ensure(read.is_empty(), || format!("downloaded {} bytes of an empty object", read.len()))?;
+    let stat = client.stat_object(bucket.to_string(), key).await.map_err(fail)?;
+    ensure(stat.size() == 0, || format!("stat reported {} bytes for an empty object", stat.size()))
+}
+
+async fn unicode_key(client: &impl ClientInterface, bucket: &str, prefix: &str) -> Outcome {
+    let key = format!("{prefix}ünï/çødé spaces+plus%percent 🚀.txt");
+    let data = Bytes::from_static(b"unicode");
+    client.static_upload_bytes(bucket.to_string(), key.clone(), data.clone()).await.map_err(fail)?;
+    let read = client.static_download_bytes(bucket.to_string(), key.clone(), None, None).await.map_err(fail)?;
+    ensure(read == data, || "downloaded data differs".to_string())?;
+    let page = client.list_prefix(bucket.to_string(), format!("{prefix}ünï/"), None).await.map_err(fail)?;
+    ensure(page.items.iter().any(|t| t.name() == key), || "the key is not listed under its own name".to_string())
+}
+
+async fn ranges(client: &impl ClientInterface, bucket: &str, prefix: &str) -> Outcome {
+    let key = format!("{prefix}ranges");
+    client.static_upload_bytes(bucket.to_string(), key.clone(), Bytes::from_static(b"0123456789")).await.map_err(fail)?;
+    let read = client.static_download_bytes(bucket.to_string(), key.clone(), Some(2), Some(5)).await.map_err(fail)?;
+    ensure(read.as_ref() == b"2345", || format!("range 2-5 returned {:?}", read))?;
+    let read = client.static_download_bytes(bucket.to_string(), key.clone(), Some(0), Some(0)).await.map_err(fail)?;
+    ensure(read.as_ref() == b"0", || format!("range 0-0 returned {:?}", read))?;
+    let read = client.static_download_bytes(bucket.to_string(), key.clone(), Some(7), None).await.map_err(fail)?;
+    ensure(read.as_ref() == b"789", || format!("range 7- returned {:?}", read))?;
+    let read = client.static_download_bytes(bucket.to_string(), key, None, Some(3)).await.map_err(fail)?;
+    ensure(read.as_ref() == b"789", || format!("suffix range -3 returned {:?}", read))
+}
+
+async fn missing_key(client: &impl ClientInterface, bucket: &str, prefix: &str) -> Outcome {
+    let key = format!("{prefix}missing");
+    let download = client.static_download_bytes(bucket.to_string(), key.clone(), None, None).await;
+    ensure_error(&download, ClientError::is_not_found, || "downloading a missing key is not reported as not found".to_string())?;
+    let stat = client.stat_object(bucket.to_string(), key.clone()).await;
+    ensure_error(&stat, ClientError::is_not_found, || "stat of a missing key is not reported as not found".to_string())?;
+    let exists = client.object_exists(bucket.to_string(), key.clone()).await.map_err(fail)?;
+    ensure(!exists, || "a missing key exists".to_string())?;
+    // AWS-S3 accepts deleting missing keys, Google-Cloud reports them as not found
+    match client.remove_object(bucket.to_string(), key).await {
+        Err(e) if !e.is_not_found() => Err(fail(e)),
+        _ => Ok(())
+    }
+}
+
+async fn overwrite(client: &impl ClientInterface, bucket: &str, prefix: &str) -> Outcome {
+    let key = format!("{prefix}overwrite");
+    client.static_upload_bytes(bucket.to_string(), key.clone(), Bytes::from_static(b"first")).await.map_err(fail)?;
+    client.static_upload_bytes(bucket.to_string(), key.clone(), Bytes::from_static(b"second")).await.map_err(fail)?;
+    let read = client.static_download_bytes(bucket.to_string(), key, None, None).await.map_err(fail)?;
+    ensure(read.as_ref() == b"second", || "the second upload did not replace the first".to_string())
+}
+
+async fn metadata(client: &impl ClientInterface, bucket: &str, prefix: &str) -> Outcome {
+    let key = format!("{prefix}metadata");
+    let options = UploadOptions {
+        content_type: Some("text/plain".to_string()),
+        custom_metadata: HashMap::from([("purpose".to_string(), "conformance".to_string())]),
+        ..Default::default()
+    };
+    client.upload_with_options(bucket.to_string(), key.clone(), Bytes::from_static(b"metadata"), options).await.map_err(fail)?;
+    let stat = client.stat_object(bucket.to_string(), key).await.map_err(fail)?;
+    ensure(stat.content_type().as_deref() == Some("text/plain"), || format!("content type is {:?}", stat.content_type()))?;
+    let metadata = stat.custom_metadata();
+    ensure(metadata.get("purpose").map(String::as_str) == Some("conformance"), || format!("custom metadata is {:?}", metadata))
+}
+
+async fn update_metadata(client: &impl ClientInterface, bucket: &str, prefix: &str) -> Outcome {
+    let key = format!("{prefix}update-metadata");
+    let options = UploadOptions {
+        custom_metadata: HashMap::from([("kept".to_string(), "yes".to_string()), ("removed".to_string(), "yes".to_string())]),
+        ..Default::default()
+    };
+    client.upload_with_options(bucket.to_string(), key.clone(), Bytes::from_static(b"update"), options).await.map_err(fail)?;
+    let changes = MetadataChanges {
+        content_type: Some("text/csv".to_string()),
+        custom_metadata: HashMap::from([("added".to_string(), "yes".to_string())]),
+        remove_metadata: vec!["removed".to_string()],
+        ..Default::default()
+    };
+    client.update_object_metadata(bucket.to_string(), key.clone(), changes).await.map_err(fail)?;
+    let stat = client.stat_object(bucket.to_string(), key.clone()).await.map_err(fail)?;
+    ensure(stat.content_type().as_deref() == Some("text/csv"), || format!("content type is {:?}", stat.content_type()))?;
+    let metadata = stat.custom_metadata();
+    let expected = HashMap::from([("kept".to_string(), "yes".to_string()), ("added".to_string(), "yes".to_string())]);
+    ensure(metadata == expected, || format!("custom metadata is {:?}", metadata))?;
+    let read = client.static_download_bytes(bucket.to_string(), key, None, None).await.map_err(fail)?;
+    ensure(read.as_ref() == b"update", || "updating the metadata changed the data".to_string())
+}
+
+async fn tags(client: &impl ClientInterface, bucket: &str, prefix: &str) -> Outcome {
+    let key = format!("{prefix}tags");
+    client.static_upload_bytes(bucket.to_string(), key.clone(), Bytes::from_static(b"tags")).await.map_err(fail)?;
+    let tags = HashMap::from([("team".to_string(), "storage".to_string())]);
+    client.set_object_tags(bucket.to_string(), key.clone(), tags.clone()).await.map_err(fail)?;
+    let read = client.get_object_tags(bucket.to_string(), key).await.map_err(fail)?;
+    ensure(read == tags, || format!("tags are {:?}", read))
+}
+
+/// Only private ACLs are set, buckets which enforce bucket-level access fail this check
+async fn acl(client: &impl ClientInterface, bucket: &str, prefix: &str) -> Outcome {
+    let key = format!("{prefix}acl");
+    client.static_upload_bytes(bucket.to_string(), key.clone(), Bytes::from_static(b"acl")).await.map_err(fail)?;
+    client.set_object_acl(bucket.to_string(), key.clone(), AccessControl::Canned(ObjectAcl::Private)).await.map_err(fail)?;
+    client.get_object_acl(bucket.to_string(), key).await.map_err(fail)?;
+    Ok(())
+}
+
+async fn copy(client: &impl ClientInterface, bucket: &str, prefix: &str) -> Outcome {
+    let src = format!("{prefix}copy-source");
+    let dest = format!("{prefix}copy-destination");
+    client.static_upload_bytes(bucket.to_string(), src.clone(), Bytes::from_static(b"copy")).await.map_err(fail)?;
+    client.copy_object(bucket.to_string(), src.clone(), bucket.to_string(), dest.clone()).await.map_err(fail)?;
+    let read = client.static_download_bytes(bucket.to_string(), dest, None, None).await.map_err(fail)?;
+    ensure(read.as_ref() == b"copy", || "the copy differs from its source".to_string())?;
+    let exists = client.object_exists(bucket.to_string(), src).await.map_err(fail)?;
+    ensure(exists, || "copying removed the source".to_string())
+}
+
+async fn copy_with_options(client: &impl ClientInterface, bucket: &str, prefix: &str) -> Outcome {
+    let src = format!("{prefix}copy-options-source");
+    let dest = format!("{prefix}copy-options-destination");
+    let options = UploadOptions { content_type: Some("text/plain".to_string()), ..Default::default() };
+    client.upload_with_options(bucket.to_string(), src.clone(), Bytes::from_static(b"copy"), options).await.map_err(fail)?;
+    client.copy_object_with_options(bucket.to_string(), src, bucket.to_string(), dest.clone(), CopyOptions::default()).await.map_err(fail)?;
+    let stat = client.stat_object(bucket.to_string(), dest).await.map_err(fail)?;
+    ensure(stat.size() == 4, || format!("the copy has {} bytes, expected 4", stat.size()))?;
+    ensure(stat.content_type().as_deref() == Some("text/plain"), || format!("the copy's content type is {:?}", stat.content_type()))
+}
+
+async fn move_object(client: &impl ClientInterface, bucket: &str, prefix: &str) -> Outcome {
+    let src = format!("{prefix}move-source");
+    let dest = format!("{prefix}move-destination");
+    client.static_upload_bytes(bucket.to_string(), src.clone(), Bytes::from_static(b"move")).await.map_err(fail)?;
+    client.move_object(bucket.to_string(), src.clone(), dest.clone()).await.map_err(fail)?;
+    let exists = client.object_exists(bucket.to_string(), src).await.map_err(fail)?;
+    ensure(!exists, || "the source of a move still exists".to_string())?;
+    let read = client.static_download_bytes(bucket.to_string(), dest, None, None).await.map_err(fail)?;
+    ensure(read.as_ref() == b"move", || "the moved object differs from its source".to_string())
+}
+
+async fn append(client: &impl ClientInterface, bucket: &str, prefix: &str) -> Outcome {
+    let key = format!("{prefix}append");
+    client.append(bucket.to_string(), key.clone(), Bytes::from_static(b"first ")).await.map_err(fail)?;
+    client.append(bucket.to_string(), key.clone(), Bytes::from_static(b"second")).await.map_err(fail)?;
+    let read = client.static_download_bytes(bucket.to_string(), key, None, None).await.map_err(fail)?;
+    ensure(read.as_ref() == b"first second", || format!("appending twice resulted in {:?}", read))
+}
+
+async fn create_only(client: &impl ClientInterface, bucket: &str, prefix: &str) -> Outcome {
+    let key = format!("{prefix}create-only");
+    client.upload_if_absent(bucket.to_string(), key.clone(), Bytes::from_static(b"first")).await.map_err(fail)?;
+    let second = client.upload_if_absent(bucket.to_string(), key.clone(), Bytes::from_static(b"second")).await;
+    ensure_error(&second, |e| e.is_already_exists() || e.is_precondition_failed(), || "a create-only write replaced an object".to_string())?;
+    let read = client.static_download_bytes(bucket.to_string(), key, None, None).await.map_err(fail)?;
+    ensure(read.as_ref() == b"first", || "the object was overwritten".to_string())
+}
+
+async fn compare_and_swap(client: &impl ClientInterface, bucket: &str, prefix: &str) -> Outcome {
+    let key = format!("{prefix}compare-and-swap");
+    client.static_upload_bytes(bucket.to_string(), key.clone(), Bytes::from_static(b"first")).await.map_err(fail)?;
+    let version = client.stat_object(bucket.to_string(), key.clone()).await.map_err(fail)?.version()
+        .ok_or_else(|| "stat reported no version".to_string())?;
+    client.upload_if_match(bucket.to_string(), key.clone(), Bytes::from_static(b"second"), WritePrecondition::Matches(version.clone())).await.map_err(fail)?;
+    let stale = client.upload_if_match(bucket.to_string(), key, Bytes::from_static(b"third"), WritePrecondition::Matches(version)).await;
+    ensure_error(&stale, ClientError::is_precondition_failed, || "a write with a stale version succeeded".to_string())
+}
+
+async fn conditional_download(client: &impl ClientInterface, bucket: &str, prefix: &str) -> Outcome {
+    let key = format!("{prefix}conditional");
+    client.static_upload_bytes(bucket.to_string(), key.clone(), Bytes::from_static(b"conditional")).await.map_err(fail)?;
+    let etag = client.stat_object(bucket.to_string(), key.clone()).await.map_err(fail)?.etag()
+        .ok_or_else(|| "stat reported no ETag".to_string())?;
+    let options = DownloadOptions { if_match: Some(etag.clone()), ..Default::default() };
+    let read = client.download_with_options(bucket.to_string(), key.clone(), None, None, options).await.map_err(fail)?;
+    ensure(read.as_ref() == b"conditional", || "a download with a matching ETag returned other data".to_string())?;
+    let options = DownloadOptions { if_none_match: Some(etag), ..Default::default() };
+    let unchanged = client.download_with_options(bucket.to_string(), key.clone(), None, None, options).await;
+    ensure_error(&unchanged, ClientError::is_not_modified, || "a download with the current ETag as If-None-Match was not reported as not modified".to_string())?;
+    let options = DownloadOptions { if_match: Some("\"stale\"".to_string()), ..Default::default() };
+    let stale = client.download_with_options(bucket.to_string(), key, None, None, options).await;
+    ensure_error(&stale, ClientError::is_precondition_failed, || "a download with a stale ETag succeeded".to_string())
+}
+
+/// Works on buckets without versioning as well, which only list the live version
+async fn versions(client: &impl ClientInterface, bucket: &str, prefix: &str) -> Outcome {
+    let key = format!("{prefix}versions");
+    client.static_upload_bytes(bucket.to_string(), key.clone(), Bytes::from_static(b"first")).await.map_err(fail)?;
+    client.static_upload_bytes(bucket.to_string(), key.clone(), Bytes::from_static(b"second")).await.map_err(fail)?;
+    let versions = client.list_object_versions(bucket.to_string(), key.clone()).await.map_err(fail)?;
+    let newest = versions.first().ok_or_else(|| "no version is listed".to_string())?;
+    ensure(versions.iter().all(|t| t.name == key), || "versions of other objects are listed".to_string())?;
+    let read = client.download_version(bucket.to_string(), key, newest.version.clone()).await.map_err(fail)?;
+    ensure(read.as_ref() == b"second", || "the newest version differs from the live object".to_string())
+}
+
+async fn listing(client: &impl ClientInterface, bucket: &str, prefix: &str) -> Outcome {
+    let dir = format!("{prefix}listing/");
+    for name in ["a", "b", "c"] {
+        client.static_upload_bytes(bucket.to_string(), format!("{dir}{name}"), Bytes::from_static(b"x")).await.map_err(fail)?;
+    }
+    let page = client.list_prefix(bucket.to_string(), dir.clone(), None).await.map_err(fail)?;
+    let mut names: Vec<String> = page.items.iter().map(|t| t.name()).collect();
+    names.sort();
+    ensure(names == [format!("{dir}a"), format!("{dir}b"), format!("{dir}c")], || format!("listed {:?}", names))
+}
+
+async fn delimited_listing(client: &impl ClientInterface, bucket: &str, prefix: &str) -> Outcome {
+    let dir = format!("{prefix}delimited/");
+    for name in ["top", "sub/one", "sub/two"] {
+        client.static_upload_bytes(bucket.to_string(), format!("{dir}{name}"), Bytes::from_static(b"x")).await.map_err(fail)?;
+    }
+    let page = client.list_delimited(bucket.to_string(), dir.clone(), "/".to_string(), None).await.map_err(fail)?;
+    let names: Vec<String> = page.items.iter().map(|t| t.name()).collect();
+    ensure(names == [format!("{dir}top")], || format!("listed objects {:?}", names))?;
+    ensure(page.prefixes == [format!("{dir}sub/")], || format!("listed prefixes {:?}", page.prefixes))
+}
+
+async fn streamed_listing(client: &impl ClientInterface, bucket: &str, prefix: &str) -> Outcome {
+    let dir = format!("{prefix}streamed/");
+    for i in 0..3 {
+        client.static_upload_bytes(bucket.to_string(), format!("{dir}{i}"), Bytes::from_static(b"x")).await.map_err(fail)?;
+    }
+    let options = ListOptions { prefix: Some(dir), limit: Some(2), ..Default::default() };
+    let stream = client.list_objects_stream(bucket.to_string(), options);
+    pin_mut!(stream);
+    let mut count = 0;
+    while let Some(item) = stream.next().await {
+        item.map_err(fail)?;
+        count += 1;
+    }
+    ensure(count == 2, || format!("a listing limited to 2 objects returned {count}"))
+}
+
+async fn listing_after(client: &impl ClientInterface, bucket: &str, prefix: &str) -> Outcome {
+    let dir = format!("{prefix}after/");
+    for name in ["a", "b", "c", "d"] {
+        client.static_upload_bytes(bucket.to_string(), format!("{dir}{name}"), Bytes::from_static(b"x")).await.map_err(fail)?;
+    }
+    let items = client.list_after(bucket.to_string(), dir.clone(), format!("{dir}b"), 1).await.map_err(fail)?;
+    let names: Vec<String> = items.iter().map(|t| t.name()).collect();
+    ensure(names == [format!("{dir}c")], || format!("listed {:?} after {dir}b", names))
+}
+
+async fn stat_many(client: &impl ClientInterface, bucket: &str, prefix: &str) -> Outcome {
+    let present = format!("{prefix}stat-many");
+    let missing = format!("{prefix}stat-many-missing");
+    client.static_upload_bytes(bucket.to_string(), present.clone(), Bytes::from_static(b"stat")).await.map_err(fail)?;
+    let results = client.stat_many(bucket.to_string(), vec![present.clone(), missing.clone()]).await;
+    let keys: Vec<&String> = results.iter().map(|(key, _)| key).collect();
+    ensure(keys == [&present, &missing], || format!("results are for {:?}", keys))?;
+    match &results[0].1 {
+        Ok(stat) => ensure(stat.size() == 4, || format!("stat reported {} bytes, expected 4", stat.size()))?,
+        Err(e) => return Err(Failure::Failed(e.to_string()))
+    }
+    ensure_error(&results[1].1, ClientError::is_not_found, || "the missing key is not reported as not found".to_string())
+}
+
+async fn remove_many(client: &impl ClientInterface, bucket: &str, prefix: &str) -> Outcome {
+    let keys: Vec<String> = (0..3).map(|i| format!("{prefix}remove/{i}")).collect();
+    for key in &keys {
+        client.static_upload_bytes(bucket.to_string(), key.clone(), Bytes::from_static(b"x")).await.map_err(fail)?;
+    }
+    let results = client.remove_objects(bucket.to_string(), keys.clone()).await.map_err(fail)?;
+    if let Some((key, Err(e))) = results.into_iter().find(|(_, t)| t.is_err()) {
+        return Err(format!("removing {key} failed: {e}").into())
+    }
+    for key in keys {
+        let exists = client.object_exists(bucket.to_string(), key.clone()).await.map_err(fail)?;
+        ensure(!exists, || format!("{key} still exists"))?;
+    }
+    Ok(())
+}
+
+async fn reader_and_writer(client: &impl ClientInterface, bucket: &str, prefix: &str) -> Outcome {
+    let key = format!("{prefix}streamed-object");
+    let data = vec![7u8; 64 * 1024];
+    client.upload_from_reader(bucket.to_string(), key.clone(), std::io::Cursor::new(data.clone())).await.map_err(fail)?;
+    let mut written = vec![];
+    let n = client.download_to_writer(bucket.to_string(), key, None, None, &mut written).await.map_err(fail)?;
+    ensure(n == data.len() as u64 && written == data, || format!("wrote {n} bytes, expected {}", data.len()))
+}
+
+/// URLs are only signed (locally for most providers), not requested
+async fn signed_urls(client: &impl ClientInterface, bucket: &str, prefix: &str) -> Outcome {
+    let key = format!("{prefix}signed");
+    for options in [SignedUrlOptions::default(), SignedUrlOptions::upload(), SignedUrlOptions::delete()] {
+        let method = options.method;
+        let url = client.signed_url(bucket.to_string(), key.clone(), options).await.map_err(fail)?;
+        ensure(url.starts_with("https://") || url.starts_with("http://"), || format!("the {method:?} URL is {url}"))?;
+    }
+    Ok(())
+}
+
+async fn presigned_post(client: &impl ClientInterface, bucket: &str, prefix: &str) -> Outcome {
+    let post = client.presigned_post(bucket.to_string(), format!("{prefix}post/"), PostConstraints::default()).await.map_err(fail)?;
+    ensure(post.url.starts_with("https://") || post.url.starts_with("http://"), || format!("the form posts to {}", post.url))?;
+    ensure(!post.fields.is_empty(), || "the form has no fields".to_string())
+}
+
+async fn get_bucket(client: &impl ClientInterface, bucket: &str, _prefix: &str) -> Outcome {
+    let found = client.get_bucket(bucket.to_string()).await.map_err(fail)?;
+    ensure(found.name() == bucket, || format!("getting {bucket} returned {}", found.name()))?;
+    let missing = client.get_bucket(format!("{bucket}-missing-{}", std::process::id())).await;
+    ensure_error(&missing, |e| e.is_not_found() || e.is_permission_denied(), || "getting a missing bucket succeeded".to_string())
+}
+
+async fn bucket_configuration(client: &impl ClientInterface, bucket: &str, _prefix: &str) -> Outcome {
+    client.get_bucket_spec(bucket.to_string()).await.map_err(fail)?;
+    client.get_lifecycle_rules(bucket.to_string()).await.map_err(fail)?;
+    client.get_bucket_labels(bucket.to_string()).await.map_err(fail)?;
+    Ok(())
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+    use futures::executor::block_on;
+    use crate::{MockClient, MockObject};
+    use super::*;
+
+    #[test]
+    fn unsupported_checks_are_skipped() {
+        let report = block_on(run_all(&MockClient::new(), "bucket"));
+        let failures: Vec<_> = report.failures().collect();
+        assert!(failures.is_empty(), "{failures:?}");
+        assert_eq!(report.skipped().count(), report.results.len());
+    }
+
+    #[test]
+    fn runs_every_check() {
+        let data = b"uni-stg conformance";
+        let mock = MockClient::new();
+        mock.expect_put("bucket", "").any_key().succeeds();
+        mock.expect_get("bucket", "").any_key().returns(&data[..]);
+        mock.expect_stat("bucket", "").any_key().returns_object(MockObject::new("bucket", "", data.len() as u64));
+        let report = block_on(run_all(&mock, "bucket"));
+        let names: Vec<&str> = report.results.iter().map(|t| t.name).collect();
+        assert_eq!(names.len(), 29);
+        assert!(names.contains(&"versions") && names.contains(&"signed_urls") && names.contains(&"bucket_configuration"));
+        let round_trip = report.results.iter().find(|t| t.name == "round_trip").unwrap();
+        assert_eq!(round_trip.error, None);
+        assert!(!round_trip.skipped);
+        // The scripted data does not match the other checks
+        let overwrite = report.results.iter().find(|t| t.name == "overwrite").unwrap();
+        assert!(overwrite.error.is_some());
+    }
+}
+
+#[cfg(test)]
+mod wrappers {
+    use std::time::Duration;
+    use crate::in_memory::InMemoryClient;
+    use crate::{CachedClient, ChunkedClient, MemoryCache, PausableClient, ReplicatedClient};
+    use super::*;
+
+    /// Fails unless every check but the `deviating` ones passes (or is skipped)
+    async fn conforms(name: &str, client: &impl ClientInterface, deviating: &[&str]) {
+        let report = run_all(client, "bucket").await;
+        let failures: Vec<_> = report.failures().filter(|t| !deviating.contains(&t.name)).collect();
+        assert!(failures.is_empty(), "{name}: {failures:?}");
+        assert!(report.results.iter().any(|t| t.name == "round_trip" && !t.skipped), "{name}");
+    }
+
+    fn backend() -> InMemoryClient {
+        InMemoryClient::with_bucket("bucket")
+    }
+
+    #[tokio::test]
+    async fn in_memory() {
+        conforms("in-memory", &backend(), &[]).await;
+    }
+
+    #[tokio::test]
+    async fn caches() {
+        let dir = std::env::temp_dir().join(format!("uni-stg-conformance-{}", std::process::id()));
+        let _ = tokio::fs::remove_dir_all(&dir).await;
+        conforms("cache", &CachedClient::open(backend(), &dir, 1024 * 1024).await.unwrap(), &[]).await;
+        let _ = tokio::fs::remove_dir_all(dir).await;
+        conforms("memory", &MemoryCache::new(backend(), 16, Duration::from_secs(60)), &[]).await;
+    }
+
+    #[tokio::test]
+    async fn chunked() {
+        conforms("chunked", &ChunkedClient::new(backend(), 8).with_chunk_size(4), &[]).await;
+    }
+
+    #[tokio::test]
+    async fn replicated() {
+        conforms("replicated", &ReplicatedClient::new(backend(), vec![backend(), backend()]), &[]).await;
+    }
+
+    #[tokio::test]
+    async fn pausable() {
+        conforms("pausable", &PausableClient::new(backend()), &[]).await;
+    }
+
+    #[cfg(feature = "encryption")]
+    #[tokio::test]
+    async fn encrypted() {
+        use crate::{EncryptedClient, EncryptionKey, PrefixKeyResolver};
+        let key = EncryptionKey { id: "k1".to_string(), key: [7; 32] };
+        let client = EncryptedClient::new(backend(), PrefixKeyResolver::new().route("bucket".to_string(), String::new(), key));
+        // Sizes reported by the provider include the encryption overhead
+        conforms("encrypted", &client, &["round_trip", "empty_object", "copy_with_options", "stat_many"]).await;
+    }
+}
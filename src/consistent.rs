@@ -0,0 +1,356 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use bytes::Bytes;
+use tokio::io::{AsyncRead, AsyncWrite};
+use crate::{AccessControl, AclGrant, BucketNameProblem, BucketPolicy, BucketSpec, ComplianceRecord, ClientBucket, ClientError, ClientInterface, ClientObject, CopyOptions, Cursor, DownloadOptions, EmptyReqRes, ErrorContext, LifecycleRule, ListAttributes, ListPage, MetadataChanges, ObjectLock, ObjectVersion, PostConstraints, PresignedPost, ReqRes, RestoreStatus, Retention, RetrievalTier, SignedUrlOptions, UploadOptions};
+
+/// Wraps a client and enforces read-your-writes for objects written through it
+///
+/// The version (`ClientObject::id`, which includes the etag / generation) of every written object is remembered.
+/// Reads of such an object first wait (retrying with a growing delay) until the provider reports the expected version,
+/// and fail with `ClientError::VersionMismatch` if it never does.
+/// A version is forgotten once a read saw it, or once it is older than the window (see `with_window`).
+/// Note: Versions are checked with a metadata request before reading, a concurrent writer can still slip in between
+pub struct ConsistentClient<C: ClientInterface> {
+    inner: C,
+    written: Mutex<Written>,
+    attempts: u32,
+    delay: Duration,
+    window: Duration,
+}
+
+/// Versions of the written objects and when they were written
+struct Written {
+    versions: HashMap<(String, String), (String, Instant)>,
+    /// Expired versions are removed once this many are remembered, which keeps the sweeps amortized O(1)
+    sweep_at: usize,
+}
+
+/// Fewest remembered versions which trigger a sweep
+const SWEEP_MIN: usize = 1024;
+
+impl<C: ClientInterface> ConsistentClient<C> {
+    pub fn new(inner: C) -> Self {
+        let written = Written { versions: HashMap::new(), sweep_at: SWEEP_MIN };
+        Self { inner, written: Mutex::new(written), attempts: 5, delay: Duration::from_millis(100), window: Duration::from_secs(60) }
+    }
+
+    /// Sets how long after a write its version is awaited at most (one minute by default),
+    /// providers are expected to show a write within this time
+    pub fn with_window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Sets how often a read is retried and the initial delay between attempts (doubled on every attempt)
+    pub fn with_retries(mut self, attempts: u32, delay: Duration) -> Self {
+        self.attempts = attempts.max(1);
+        self.delay = delay;
+        self
+    }
+
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    fn written(&self) -> std::sync::MutexGuard<'_, Written> {
+        self.written.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn expected(&self, bucket: &str, object: &str) -> Option<String> {
+        let key = (bucket.to_string(), object.to_string());
+        let mut written = self.written();
+        match written.versions.get(&key) {
+            Some((_, at)) if at.elapsed() >= self.window => {
+                written.versions.remove(&key);
+                None
+            }
+            entry => entry.map(|(version, _)| version.clone())
+        }
+    }
+
+    fn remember(&self, bucket: String, object: String, version: Option<String>) {
+        let mut written = self.written();
+        match version {
+            Some(version) => written.versions.insert((bucket, object), (version, Instant::now())),
+            None => written.versions.remove(&(bucket, object))
+        };
+        if written.versions.len() >= written.sweep_at {
+            written.versions.retain(|_, (_, at)| at.elapsed() < self.window);
+            written.sweep_at = (written.versions.len() * 2).max(SWEEP_MIN);
+        }
+    }
+
+    /// Forgets a version which a read saw, later reads are consistent with it
+    fn confirm(&self, bucket: &str, object: &str, version: &str) {
+        let key = (bucket.to_string(), object.to_string());
+        let mut written = self.written();
+        if written.versions.get(&key).is_some_and(|(t, _)| t == version) {
+            written.versions.remove(&key);
+        }
+    }
+
+    /// Waits until the expected version of an object (if any) is visible
+    async fn await_version(&self, bucket: &str, object: &str) -> EmptyReqRes {
+        let Some(expected) = self.expected(bucket, object) else {
+            return Ok(())
+        };
+        let mut delay = self.delay;
+        let mut actual = String::new();
+        for attempt in 1..=self.attempts {
+            match self.inner.stat_object(bucket.to_string(), object.to_string()).await {
+                Ok(found) if found.id() == expected => {
+                    self.confirm(bucket, object, &expected);
+                    return Ok(())
+                }
+                Ok(found) => actual = found.id(),
+                // Not visible yet
+                Err(e) if e.is_not_found() => {},
+                Err(e) => return Err(e)
+            }
+            if attempt < self.attempts {
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+            }
+        }
+        let mut context = ErrorContext::new("await_version", Some(bucket), Some(object));
+        context.attempt = self.attempts;
+        Err(ClientError::VersionMismatch { expected, actual }.with_context(context))
+    }
+}
+
+impl<C: ClientInterface> ClientInterface for ConsistentClient<C> {
+    async fn static_download_bytes(&self, bucket: String, object_id: String, starting: Option<u64>, ending: Option<u64>) -> ReqRes<Bytes> {
+        self.await_version(&bucket, &object_id).await?;
+        self.inner.static_download_bytes(bucket, object_id, starting, ending).await
+    }
+
+    /// Downloads of a specific version do not wait
+    async fn download_with_options(&self, bucket: String, object_id: String, starting: Option<u64>, ending: Option<u64>, options: DownloadOptions) -> ReqRes<Bytes> {
+        if options.version.is_none() {
+            self.await_version(&bucket, &object_id).await?;
+        }
+        self.inner.download_with_options(bucket, object_id, starting, ending, options).await
+    }
+
+    async fn static_upload_bytes(&self, bucket: String, object_id: String, data: Bytes) -> ReqRes<impl ClientObject> {
+        let object = self.inner.static_upload_bytes(bucket.clone(), object_id.clone(), data).await?;
+        self.remember(bucket, object_id, Some(object.id()));
+        Ok(object)
+    }
+
+    async fn upload_with_options(&self, bucket: String, object_id: String, data: Bytes, options: UploadOptions) -> ReqRes<impl ClientObject> {
+        let object = self.inner.upload_with_options(bucket.clone(), object_id.clone(), data, options).await?;
+        self.remember(bucket, object_id, Some(object.id()));
+        Ok(object)
+    }
+
+    async fn download_to_writer(&self, bucket: String, object_id: String, starting: Option<u64>, ending: Option<u64>, w: impl AsyncWrite + Unpin + Send) -> ReqRes<u64> {
+        self.await_version(&bucket, &object_id).await?;
+        self.inner.download_to_writer(bucket, object_id, starting, ending, w).await
+    }
+
+    /// The version of the uploaded object is unknown, so it is not tracked
+    async fn upload_from_reader(&self, bucket: String, object_id: String, r: impl AsyncRead + Unpin + Send + Sync + 'static) -> EmptyReqRes {
+        self.remember(bucket.clone(), object_id.clone(), None);
+        self.inner.upload_from_reader(bucket, object_id, r).await
+    }
+
+    async fn update_object_metadata(&self, bucket: String, object_id: String, changes: MetadataChanges) -> ReqRes<impl ClientObject> {
+        self.await_version(&bucket, &object_id).await?;
+        let object = self.inner.update_object_metadata(bucket.clone(), object_id.clone(), changes).await?;
+        self.remember(bucket, object_id, Some(object.id()));
+        Ok(object)
+    }
+
+    async fn get_object_tags(&self, bucket: String, object_id: String) -> ReqRes<HashMap<String, String>> {
+        self.await_version(&bucket, &object_id).await?;
+        self.inner.get_object_tags(bucket, object_id).await
+    }
+
+    async fn set_object_tags(&self, bucket: String, object_id: String, tags: HashMap<String, String>) -> EmptyReqRes {
+        self.await_version(&bucket, &object_id).await?;
+        self.inner.set_object_tags(bucket, object_id, tags).await
+    }
+
+    async fn get_object_lock(&self, bucket_name: String, object_name: String) -> ReqRes<ObjectLock> {
+        self.await_version(&bucket_name, &object_name).await?;
+        self.inner.get_object_lock(bucket_name, object_name).await
+    }
+
+    async fn set_object_retention(&self, bucket_name: String, object_name: String, retention: Option<Retention>) -> EmptyReqRes {
+        self.await_version(&bucket_name, &object_name).await?;
+        self.inner.set_object_retention(bucket_name, object_name, retention).await
+    }
+
+    async fn set_legal_hold(&self, bucket_name: String, object_name: String, on: bool) -> EmptyReqRes {
+        self.await_version(&bucket_name, &object_name).await?;
+        self.inner.set_legal_hold(bucket_name, object_name, on).await
+    }
+
+    async fn get_object_acl(&self, bucket: String, object_id: String) -> ReqRes<Vec<AclGrant>> {
+        self.await_version(&bucket, &object_id).await?;
+        self.inner.get_object_acl(bucket, object_id).await
+    }
+
+    async fn set_object_acl(&self, bucket: String, object_id: String, acl: AccessControl) -> EmptyReqRes {
+        self.await_version(&bucket, &object_id).await?;
+        self.inner.set_object_acl(bucket, object_id, acl).await
+    }
+
+    async fn append(&self, bucket: String, object_id: String, data: Bytes) -> EmptyReqRes {
+        self.remember(bucket.clone(), object_id.clone(), None);
+        self.inner.append(bucket, object_id, data).await
+    }
+
+    async fn upload_public(&self, bucket: String, object_id: String, data: Bytes, content_type: Option<String>, cache_control: String) -> EmptyReqRes {
+        self.remember(bucket.clone(), object_id.clone(), None);
+        self.inner.upload_public(bucket, object_id, data, content_type, cache_control).await
+    }
+
+    fn public_url(&self, bucket: &str, object_id: &str) -> String {
+        self.inner.public_url(bucket, object_id)
+    }
+
+    async fn signed_url(&self, bucket: String, object_id: String, options: SignedUrlOptions) -> ReqRes<String> {
+        self.inner.signed_url(bucket, object_id, options).await
+    }
+
+    async fn presigned_post(&self, bucket: String, key_prefix: String, constraints: PostConstraints) -> ReqRes<PresignedPost> {
+        self.inner.presigned_post(bucket, key_prefix, constraints).await
+    }
+
+    async fn remove_bucket(&self, bucket: String) -> EmptyReqRes {
+        self.written().versions.retain(|(t, _), _| *t != bucket);
+        self.inner.remove_bucket(bucket).await
+    }
+
+    async fn remove_object(&self, bucket: String, object_id: String) -> EmptyReqRes {
+        self.remember(bucket.clone(), object_id.clone(), None);
+        self.inner.remove_object(bucket, object_id).await
+    }
+
+    async fn create_bucket(&self, bucket: String) -> ReqRes<impl ClientBucket> {
+        self.inner.create_bucket(bucket).await
+    }
+
+    async fn copy_object(&self, src_bucket: String, src_object: String, dest_bucket: String, dest_object: String) -> ReqRes<impl ClientObject> {
+        self.await_version(&src_bucket, &src_object).await?;
+        let object = self.inner.copy_object(src_bucket, src_object, dest_bucket.clone(), dest_object.clone()).await?;
+        self.remember(dest_bucket, dest_object, Some(object.id()));
+        Ok(object)
+    }
+
+    async fn copy_object_with_options(&self, src_bucket: String, src_object: String, dest_bucket: String, dest_object: String, options: CopyOptions) -> ReqRes<impl ClientObject> {
+        self.await_version(&src_bucket, &src_object).await?;
+        let object = self.inner.copy_object_with_options(src_bucket, src_object, dest_bucket.clone(), dest_object.clone(), options).await?;
+        self.remember(dest_bucket, dest_object, Some(object.id()));
+        Ok(object)
+    }
+
+    async fn restore_object(&self, bucket_name: String, object_name: String, days: u32, tier: RetrievalTier) -> EmptyReqRes {
+        self.await_version(&bucket_name, &object_name).await?;
+        self.inner.restore_object(bucket_name, object_name, days, tier).await
+    }
+
+    async fn restore_status(&self, bucket_name: String, object_name: String) -> ReqRes<RestoreStatus> {
+        self.await_version(&bucket_name, &object_name).await?;
+        self.inner.restore_status(bucket_name, object_name).await
+    }
+
+    async fn list_buckets(&self, max_results: Option<u32>, page_token: Option<Cursor>) -> ReqRes<ListPage<impl ClientBucket>> {
+        self.inner.list_buckets(max_results, page_token).await
+    }
+
+    async fn get_bucket(&self, bucket_name: String) -> ReqRes<impl ClientBucket> {
+        self.inner.get_bucket(bucket_name).await
+    }
+
+    async fn get_object(&self, bucket_name: String, object_name: String) -> ReqRes<impl ClientObject> {
+        self.await_version(&bucket_name, &object_name).await?;
+        self.inner.get_object(bucket_name, object_name).await
+    }
+
+    async fn stat_object(&self, bucket_name: String, object_name: String) -> ReqRes<impl ClientObject> {
+        self.await_version(&bucket_name, &object_name).await?;
+        self.inner.stat_object(bucket_name, object_name).await
+    }
+
+    async fn list_objects(&self, bucket_name: String, max_results: Option<u32>, page_token: Option<Cursor>) -> ReqRes<ListPage<impl ClientObject>> {
+        self.inner.list_objects(bucket_name, max_results, page_token).await
+    }
+
+    async fn list_prefix(&self, bucket_name: String, prefix: String, page_token: Option<Cursor>) -> ReqRes<ListPage<impl ClientObject>> {
+        self.inner.list_prefix(bucket_name, prefix, page_token).await
+    }
+
+    async fn list_projected(&self, bucket_name: String, prefix: String, attributes: ListAttributes, page_token: Option<Cursor>) -> ReqRes<ListPage<impl ClientObject>> {
+        self.inner.list_projected(bucket_name, prefix, attributes, page_token).await
+    }
+
+    async fn list_delimited(&self, bucket_name: String, prefix: String, delimiter: String, page_token: Option<Cursor>) -> ReqRes<ListPage<impl ClientObject>> {
+        self.inner.list_delimited(bucket_name, prefix, delimiter, page_token).await
+    }
+
+    async fn list_after(&self, bucket_name: String, prefix: String, start_after: String, max_results: u32) -> ReqRes<Vec<impl ClientObject>> {
+        self.inner.list_after(bucket_name, prefix, start_after, max_results).await
+    }
+
+    async fn list_versions(&self, bucket_name: String, prefix: String, page_token: Option<Cursor>) -> ReqRes<ListPage<ObjectVersion>> {
+        self.inner.list_versions(bucket_name, prefix, page_token).await
+    }
+
+    async fn restore_object_version(&self, bucket_name: String, object_name: String, version: String) -> ReqRes<impl ClientObject> {
+        let object = self.inner.restore_object_version(bucket_name.clone(), object_name.clone(), version).await?;
+        self.remember(bucket_name, object_name, Some(object.id()));
+        Ok(object)
+    }
+
+    async fn remove_objects(&self, bucket_name: String, keys: Vec<String>) -> ReqRes<Vec<(String, EmptyReqRes)>> {
+        for key in &keys {
+            self.remember(bucket_name.clone(), key.clone(), None);
+        }
+        self.inner.remove_objects(bucket_name, keys).await
+    }
+
+    async fn compliance_records(&self, bucket_name: String, page_token: Option<Cursor>) -> ReqRes<ListPage<ComplianceRecord>> {
+        self.inner.compliance_records(bucket_name, page_token).await
+    }
+
+    async fn bucket_exists(&self, bucket_name: String) -> ReqRes<bool> {
+        self.inner.bucket_exists(bucket_name).await
+    }
+
+    async fn get_bucket_spec(&self, bucket_name: String) -> ReqRes<BucketSpec> {
+        self.inner.get_bucket_spec(bucket_name).await
+    }
+
+    async fn get_lifecycle_rules(&self, bucket_name: String) -> ReqRes<Vec<LifecycleRule>> {
+        self.inner.get_lifecycle_rules(bucket_name).await
+    }
+
+    async fn set_lifecycle_rules(&self, bucket_name: String, rules: Vec<LifecycleRule>) -> EmptyReqRes {
+        self.inner.set_lifecycle_rules(bucket_name, rules).await
+    }
+
+    async fn get_bucket_labels(&self, bucket_name: String) -> ReqRes<HashMap<String, String>> {
+        self.inner.get_bucket_labels(bucket_name).await
+    }
+
+    async fn set_bucket_labels(&self, bucket_name: String, labels: HashMap<String, String>) -> EmptyReqRes {
+        self.inner.set_bucket_labels(bucket_name, labels).await
+    }
+
+    async fn get_bucket_policy(&self, bucket_name: String) -> ReqRes<Option<BucketPolicy>> {
+        self.inner.get_bucket_policy(bucket_name).await
+    }
+
+    async fn set_bucket_policy(&self, bucket_name: String, policy: BucketPolicy) -> EmptyReqRes {
+        self.inner.set_bucket_policy(bucket_name, policy).await
+    }
+
+    fn bucket_name_problems(&self, bucket_name: &str) -> Vec<BucketNameProblem> {
+        self.inner.bucket_name_problems(bucket_name)
+    }
+}
@@ -0,0 +1,75 @@
+use std::fmt;
+use std::str::FromStr;
+use hmac::Mac;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use crate::ClientError;
+use crate::encoding::{hex, mac, unhex};
+
+/// Opaque position in a listing, returned by `ListPage::next_token` and passed back to get the next page
+///
+/// Its text form (`Display`, `FromStr` and serde) is URL-safe and does not expose the provider's token as it is.
+/// Cursors handed to untrusted clients should be signed (see `Cursor::sign`), so they cannot be forged.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct Cursor(String);
+
+impl Cursor {
+    #[cfg(any(feature = "google_cloud", feature = "aws_s3"))]
+    pub(crate) fn new(token: impl Into<String>) -> Self {
+        Self(token.into())
+    }
+
+    /// The provider's continuation token
+    #[cfg(any(feature = "google_cloud", feature = "aws_s3"))]
+    pub(crate) fn into_token(self) -> String {
+        self.0
+    }
+
+    /// Text form followed by a HMAC-SHA256 of it, separated by '.'
+    pub fn sign(&self, key: &[u8]) -> String {
+        let encoded = self.to_string();
+        format!("{encoded}.{}", hex(&mac(key, encoded.as_bytes()).finalize().into_bytes()))
+    }
+
+    /// Parses a cursor created by `Cursor::sign` with the same key
+    pub fn verify(signed: &str, key: &[u8]) -> Result<Self, ClientError> {
+        let (encoded, signature) = signed.rsplit_once('.').ok_or(ClientError::InvalidCursor)?;
+        let signature = unhex(signature).ok_or(ClientError::InvalidCursor)?;
+        mac(key, encoded.as_bytes()).verify_slice(&signature).map_err(|_| ClientError::InvalidCursor)?;
+        encoded.parse()
+    }
+}
+
+/// Hex of the token, so it only contains URL-safe characters
+impl fmt::Display for Cursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&hex(self.0.as_bytes()))
+    }
+}
+
+/// The token is not printed
+impl fmt::Debug for Cursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Cursor(..)")
+    }
+}
+
+impl FromStr for Cursor {
+    type Err = ClientError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let token = unhex(s).and_then(|t| String::from_utf8(t).ok()).ok_or(ClientError::InvalidCursor)?;
+        Ok(Self(token))
+    }
+}
+
+impl Serialize for Cursor {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Cursor {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(|_| de::Error::custom("invalid cursor"))
+    }
+}
@@ -0,0 +1,97 @@
+use std::sync::RwLock;
+use std::time::Duration;
+use crate::TRANSFER_BUFFER;
+
+/// Retries of failed (transient) requests, with an exponentially growing delay
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Attempts including the first one, 1 disables retries
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self { max_attempts: 3, initial_backoff: Duration::from_secs(1), max_backoff: Duration::from_secs(20) }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay before the retry which follows `attempt` (starting at 1)
+    /// A Retry-After hint of the provider is waited for, up to `max_backoff`
+    pub fn delay(&self, attempt: u32, retry_after: Option<Duration>) -> Duration {
+        let backoff = self.initial_backoff.saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1))).min(self.max_backoff);
+        retry_after.map_or(backoff, |t| t.min(self.max_backoff).max(backoff))
+    }
+}
+
+/// Value of a Retry-After header in seconds (the HTTP-date form is not used by the providers)
+#[cfg(any(feature = "google_cloud", feature = "aws_s3"))]
+pub(crate) fn parse_retry_after(value: &str) -> Option<Duration> {
+    value.trim().parse().ok().map(Duration::from_secs)
+}
+
+/// Settings which newly built clients inherit (see `Defaults::set`), fields which are None use the provider's defaults
+#[derive(Debug, Clone, PartialEq)]
+pub struct Defaults {
+    pub retry: RetryPolicy,
+    pub connect_timeout: Option<Duration>,
+    /// Limit of a whole request (including retries on AWS-S3), also applies to large streamed uploads and downloads
+    pub operation_timeout: Option<Duration>,
+    /// Application identifier appended to the user agent of every request (example: 'my-service'),
+    /// clients configured with an own identifier (`with_app_id`) use that one instead
+    pub user_agent_suffix: Option<String>,
+    /// Size of the in-memory pipe of `transfer`
+    pub transfer_buffer: usize,
+    /// Size of the parts of multipart uploads from a reader (AWS-S3 requires at least 5 MiB)
+    pub part_size: usize,
+    /// Every upload carries a Content-MD5 and CRC32C, the call fails with `ClientError::ChecksumMismatch`
+    /// unless the provider's response confirms the CRC32C
+    /// Complete downloads of objects with a stored CRC32C are verified as well
+    pub strict_checksums: bool,
+    /// Uploads of bytes without a Content-Type get one guessed from the key or data (see `guess_content_type`),
+    /// instead of the provider's default ('binary/octet-stream' on AWS-S3, 'application/octet-stream' on Google-Cloud)
+    pub detect_content_type: bool,
+}
+
+impl Default for Defaults {
+    fn default() -> Self {
+        Self {
+            retry: RetryPolicy::default(),
+            connect_timeout: None,
+            operation_timeout: None,
+            user_agent_suffix: None,
+            transfer_buffer: TRANSFER_BUFFER,
+            part_size: 8 * 1024 * 1024,
+            strict_checksums: false,
+            detect_content_type: false,
+        }
+    }
+}
+
+/// None until `Defaults::set` is called
+static GLOBAL: RwLock<Option<Defaults>> = RwLock::new(None);
+
+impl Defaults {
+    /// The process-wide defaults (`Defaults::default` unless set)
+    pub fn get() -> Self {
+        GLOBAL.read().unwrap_or_else(|e| e.into_inner()).clone().unwrap_or_default()
+    }
+
+    /// Replaces the process-wide defaults, clients which were already built keep their settings
+    pub fn set(defaults: Defaults) {
+        *GLOBAL.write().unwrap_or_else(|e| e.into_inner()) = Some(defaults);
+    }
+
+    /// Changes the process-wide defaults in place (atomically, concurrent updates are not lost)
+    pub fn update(f: impl FnOnce(&mut Defaults)) {
+        let mut global = GLOBAL.write().unwrap_or_else(|e| e.into_inner());
+        f(global.get_or_insert_with(Defaults::default));
+    }
+
+    /// Restores `Defaults::default`
+    pub fn reset() {
+        *GLOBAL.write().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+}
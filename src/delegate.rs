@@ -0,0 +1,256 @@
+/// Implements `ClientInterface` methods of a wrapper by forwarding them to one of its fields
+/// Usage (inside an `impl ClientInterface for ...` block): `delegate!(inner; signed_url, remove_bucket);`
+macro_rules! delegate {
+    ($field:ident; $($method:ident),* $(,)?) => {
+        $(
+            delegate!(@ $field $method);
+        )*
+    };
+    (@ $field:ident static_download_bytes) => {
+        async fn static_download_bytes(&self, bucket: String, object_id: String, starting: Option<u64>, ending: Option<u64>) -> $crate::ReqRes<::bytes::Bytes> {
+            self.$field.static_download_bytes(bucket, object_id, starting, ending).await
+        }
+    };
+    (@ $field:ident download_with_options) => {
+        async fn download_with_options(&self, bucket: String, object_id: String, starting: Option<u64>, ending: Option<u64>, options: $crate::DownloadOptions) -> $crate::ReqRes<::bytes::Bytes> {
+            self.$field.download_with_options(bucket, object_id, starting, ending, options).await
+        }
+    };
+    (@ $field:ident static_upload_bytes) => {
+        async fn static_upload_bytes(&self, bucket: String, object_id: String, data: ::bytes::Bytes) -> $crate::ReqRes<impl $crate::ClientObject> {
+            self.$field.static_upload_bytes(bucket, object_id, data).await
+        }
+    };
+    (@ $field:ident upload_with_options) => {
+        async fn upload_with_options(&self, bucket: String, object_id: String, data: ::bytes::Bytes, options: $crate::UploadOptions) -> $crate::ReqRes<impl $crate::ClientObject> {
+            self.$field.upload_with_options(bucket, object_id, data, options).await
+        }
+    };
+    (@ $field:ident download_to_writer) => {
+        async fn download_to_writer(&self, bucket: String, object_id: String, starting: Option<u64>, ending: Option<u64>, w: impl ::tokio::io::AsyncWrite + Unpin + Send) -> $crate::ReqRes<u64> {
+            self.$field.download_to_writer(bucket, object_id, starting, ending, w).await
+        }
+    };
+    (@ $field:ident upload_from_reader) => {
+        async fn upload_from_reader(&self, bucket: String, object_id: String, r: impl ::tokio::io::AsyncRead + Unpin + Send + Sync + 'static) -> $crate::EmptyReqRes {
+            self.$field.upload_from_reader(bucket, object_id, r).await
+        }
+    };
+    (@ $field:ident update_object_metadata) => {
+        async fn update_object_metadata(&self, bucket: String, object_id: String, changes: $crate::MetadataChanges) -> $crate::ReqRes<impl $crate::ClientObject> {
+            self.$field.update_object_metadata(bucket, object_id, changes).await
+        }
+    };
+    (@ $field:ident get_object_tags) => {
+        async fn get_object_tags(&self, bucket: String, object_id: String) -> $crate::ReqRes<::std::collections::HashMap<String, String>> {
+            self.$field.get_object_tags(bucket, object_id).await
+        }
+    };
+    (@ $field:ident set_object_tags) => {
+        async fn set_object_tags(&self, bucket: String, object_id: String, tags: ::std::collections::HashMap<String, String>) -> $crate::EmptyReqRes {
+            self.$field.set_object_tags(bucket, object_id, tags).await
+        }
+    };
+    (@ $field:ident get_object_acl) => {
+        async fn get_object_acl(&self, bucket: String, object_id: String) -> $crate::ReqRes<Vec<$crate::AclGrant>> {
+            self.$field.get_object_acl(bucket, object_id).await
+        }
+    };
+    (@ $field:ident set_object_acl) => {
+        async fn set_object_acl(&self, bucket: String, object_id: String, acl: $crate::AccessControl) -> $crate::EmptyReqRes {
+            self.$field.set_object_acl(bucket, object_id, acl).await
+        }
+    };
+    (@ $field:ident copy_object_progress) => {
+        fn copy_object_progress(&self, src_bucket: String, src_object: String, dest_bucket: String, dest_object: String) -> impl ::futures::Stream<Item = $crate::ReqRes<$crate::CopyProgress>> + Send {
+            self.$field.copy_object_progress(src_bucket, src_object, dest_bucket, dest_object)
+        }
+    };
+    (@ $field:ident bucket_name_problems) => {
+        fn bucket_name_problems(&self, bucket_name: &str) -> Vec<$crate::BucketNameProblem> {
+            self.$field.bucket_name_problems(bucket_name)
+        }
+    };
+    (@ $field:ident append) => {
+        async fn append(&self, bucket: String, object_id: String, data: ::bytes::Bytes) -> $crate::EmptyReqRes {
+            self.$field.append(bucket, object_id, data).await
+        }
+    };
+    (@ $field:ident upload_public) => {
+        async fn upload_public(&self, bucket: String, object_id: String, data: ::bytes::Bytes, content_type: Option<String>, cache_control: String) -> $crate::EmptyReqRes {
+            self.$field.upload_public(bucket, object_id, data, content_type, cache_control).await
+        }
+    };
+    (@ $field:ident public_url) => {
+        fn public_url(&self, bucket: &str, object_id: &str) -> String {
+            self.$field.public_url(bucket, object_id)
+        }
+    };
+    (@ $field:ident signed_url) => {
+        async fn signed_url(&self, bucket: String, object_id: String, options: $crate::SignedUrlOptions) -> $crate::ReqRes<String> {
+            self.$field.signed_url(bucket, object_id, options).await
+        }
+    };
+    (@ $field:ident presigned_post) => {
+        async fn presigned_post(&self, bucket: String, key_prefix: String, constraints: $crate::PostConstraints) -> $crate::ReqRes<$crate::PresignedPost> {
+            self.$field.presigned_post(bucket, key_prefix, constraints).await
+        }
+    };
+    (@ $field:ident remove_bucket) => {
+        async fn remove_bucket(&self, bucket: String) -> $crate::EmptyReqRes {
+            self.$field.remove_bucket(bucket).await
+        }
+    };
+    (@ $field:ident remove_object) => {
+        async fn remove_object(&self, bucket: String, object_id: String) -> $crate::EmptyReqRes {
+            self.$field.remove_object(bucket, object_id).await
+        }
+    };
+    (@ $field:ident create_bucket) => {
+        async fn create_bucket(&self, bucket: String) -> $crate::ReqRes<impl $crate::ClientBucket> {
+            self.$field.create_bucket(bucket).await
+        }
+    };
+    (@ $field:ident copy_object) => {
+        async fn copy_object(&self, src_bucket: String, src_object: String, dest_bucket: String, dest_object: String) -> $crate::ReqRes<impl $crate::ClientObject> {
+            self.$field.copy_object(src_bucket, src_object, dest_bucket, dest_object).await
+        }
+    };
+    (@ $field:ident list_buckets) => {
+        async fn list_buckets(&self, max_results: Option<u32>, page_token: Option<$crate::Cursor>) -> $crate::ReqRes<$crate::ListPage<impl $crate::ClientBucket>> {
+            self.$field.list_buckets(max_results, page_token).await
+        }
+    };
+    (@ $field:ident get_bucket) => {
+        async fn get_bucket(&self, bucket_name: String) -> $crate::ReqRes<impl $crate::ClientBucket> {
+            self.$field.get_bucket(bucket_name).await
+        }
+    };
+    (@ $field:ident get_object) => {
+        async fn get_object(&self, bucket_name: String, object_name: String) -> $crate::ReqRes<impl $crate::ClientObject> {
+            self.$field.get_object(bucket_name, object_name).await
+        }
+    };
+    (@ $field:ident stat_object) => {
+        async fn stat_object(&self, bucket_name: String, object_name: String) -> $crate::ReqRes<impl $crate::ClientObject> {
+            self.$field.stat_object(bucket_name, object_name).await
+        }
+    };
+    (@ $field:ident list_objects) => {
+        async fn list_objects(&self, bucket_name: String, max_results: Option<u32>, page_token: Option<$crate::Cursor>) -> $crate::ReqRes<$crate::ListPage<impl $crate::ClientObject>> {
+            self.$field.list_objects(bucket_name, max_results, page_token).await
+        }
+    };
+    (@ $field:ident list_projected) => {
+        async fn list_projected(&self, bucket_name: String, prefix: String, attributes: $crate::ListAttributes, page_token: Option<$crate::Cursor>) -> $crate::ReqRes<$crate::ListPage<impl $crate::ClientObject>> {
+            self.$field.list_projected(bucket_name, prefix, attributes, page_token).await
+        }
+    };
+    (@ $field:ident list_prefix) => {
+        async fn list_prefix(&self, bucket_name: String, prefix: String, page_token: Option<$crate::Cursor>) -> $crate::ReqRes<$crate::ListPage<impl $crate::ClientObject>> {
+            self.$field.list_prefix(bucket_name, prefix, page_token).await
+        }
+    };
+    (@ $field:ident list_delimited) => {
+        async fn list_delimited(&self, bucket_name: String, prefix: String, delimiter: String, page_token: Option<$crate::Cursor>) -> $crate::ReqRes<$crate::ListPage<impl $crate::ClientObject>> {
+            self.$field.list_delimited(bucket_name, prefix, delimiter, page_token).await
+        }
+    };
+    (@ $field:ident list_after) => {
+        async fn list_after(&self, bucket_name: String, prefix: String, start_after: String, max_results: u32) -> $crate::ReqRes<Vec<impl $crate::ClientObject>> {
+            self.$field.list_after(bucket_name, prefix, start_after, max_results).await
+        }
+    };
+    (@ $field:ident list_versions) => {
+        async fn list_versions(&self, bucket_name: String, prefix: String, page_token: Option<$crate::Cursor>) -> $crate::ReqRes<$crate::ListPage<$crate::ObjectVersion>> {
+            self.$field.list_versions(bucket_name, prefix, page_token).await
+        }
+    };
+    (@ $field:ident copy_object_with_options) => {
+        async fn copy_object_with_options(&self, src_bucket: String, src_object: String, dest_bucket: String, dest_object: String, options: $crate::CopyOptions) -> $crate::ReqRes<impl $crate::ClientObject> {
+            self.$field.copy_object_with_options(src_bucket, src_object, dest_bucket, dest_object, options).await
+        }
+    };
+    (@ $field:ident restore_object) => {
+        async fn restore_object(&self, bucket_name: String, object_name: String, days: u32, tier: $crate::RetrievalTier) -> $crate::EmptyReqRes {
+            self.$field.restore_object(bucket_name, object_name, days, tier).await
+        }
+    };
+    (@ $field:ident restore_status) => {
+        async fn restore_status(&self, bucket_name: String, object_name: String) -> $crate::ReqRes<$crate::RestoreStatus> {
+            self.$field.restore_status(bucket_name, object_name).await
+        }
+    };
+    (@ $field:ident restore_object_version) => {
+        async fn restore_object_version(&self, bucket_name: String, object_name: String, version: String) -> $crate::ReqRes<impl $crate::ClientObject> {
+            self.$field.restore_object_version(bucket_name, object_name, version).await
+        }
+    };
+    (@ $field:ident remove_objects) => {
+        async fn remove_objects(&self, bucket_name: String, keys: Vec<String>) -> $crate::ReqRes<Vec<(String, $crate::EmptyReqRes)>> {
+            self.$field.remove_objects(bucket_name, keys).await
+        }
+    };
+    (@ $field:ident bucket_exists) => {
+        async fn bucket_exists(&self, bucket_name: String) -> $crate::ReqRes<bool> {
+            self.$field.bucket_exists(bucket_name).await
+        }
+    };
+    (@ $field:ident compliance_records) => {
+        async fn compliance_records(&self, bucket_name: String, page_token: Option<$crate::Cursor>) -> $crate::ReqRes<$crate::ListPage<$crate::ComplianceRecord>> {
+            self.$field.compliance_records(bucket_name, page_token).await
+        }
+    };
+    (@ $field:ident get_lifecycle_rules) => {
+        async fn get_lifecycle_rules(&self, bucket_name: String) -> $crate::ReqRes<Vec<$crate::LifecycleRule>> {
+            self.$field.get_lifecycle_rules(bucket_name).await
+        }
+    };
+    (@ $field:ident set_lifecycle_rules) => {
+        async fn set_lifecycle_rules(&self, bucket_name: String, rules: Vec<$crate::LifecycleRule>) -> $crate::EmptyReqRes {
+            self.$field.set_lifecycle_rules(bucket_name, rules).await
+        }
+    };
+    (@ $field:ident get_bucket_labels) => {
+        async fn get_bucket_labels(&self, bucket_name: String) -> $crate::ReqRes<::std::collections::HashMap<String, String>> {
+            self.$field.get_bucket_labels(bucket_name).await
+        }
+    };
+    (@ $field:ident set_bucket_labels) => {
+        async fn set_bucket_labels(&self, bucket_name: String, labels: ::std::collections::HashMap<String, String>) -> $crate::EmptyReqRes {
+            self.$field.set_bucket_labels(bucket_name, labels).await
+        }
+    };
+    (@ $field:ident get_bucket_policy) => {
+        async fn get_bucket_policy(&self, bucket_name: String) -> $crate::ReqRes<Option<$crate::BucketPolicy>> {
+            self.$field.get_bucket_policy(bucket_name).await
+        }
+    };
+    (@ $field:ident set_bucket_policy) => {
+        async fn set_bucket_policy(&self, bucket_name: String, policy: $crate::BucketPolicy) -> $crate::EmptyReqRes {
+            self.$field.set_bucket_policy(bucket_name, policy).await
+        }
+    };
+    (@ $field:ident get_object_lock) => {
+        async fn get_object_lock(&self, bucket_name: String, object_name: String) -> $crate::ReqRes<$crate::ObjectLock> {
+            self.$field.get_object_lock(bucket_name, object_name).await
+        }
+    };
+    (@ $field:ident set_object_retention) => {
+        async fn set_object_retention(&self, bucket_name: String, object_name: String, retention: Option<$crate::Retention>) -> $crate::EmptyReqRes {
+            self.$field.set_object_retention(bucket_name, object_name, retention).await
+        }
+    };
+    (@ $field:ident set_legal_hold) => {
+        async fn set_legal_hold(&self, bucket_name: String, object_name: String, on: bool) -> $crate::EmptyReqRes {
+            self.$field.set_legal_hold(bucket_name, object_name, on).await
+        }
+    };
+    (@ $field:ident get_bucket_spec) => {
+        async fn get_bucket_spec(&self, bucket_name: String) -> $crate::ReqRes<$crate::BucketSpec> {
+            self.$field.get_bucket_spec(bucket_name).await
+        }
+    };
+}
+
+pub(crate) use delegate;
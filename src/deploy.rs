@@ -0,0 +1,160 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use futures::{stream, StreamExt};
+use sha2::{Digest, Sha256};
+use tokio::io::AsyncReadExt;
+use crate::encoding::hex;
+use crate::mime::by_extension;
+use crate::publish::content_hash;
+use crate::{ClientInterface, Context, ReqRes, CONCURRENCY, IMMUTABLE_CACHE_CONTROL};
+
+/// Name (below the deploy prefix) of the object which records the hashes of the deployed files
+pub const DEPLOY_MANIFEST: &str = ".uni-stg/deploy.json";
+
+/// Options of `ClientInterface::deploy_site`
+#[derive(Debug, Clone)]
+pub struct DeployOptions {
+    /// Prefix the site is deployed below (example: 'docs/'), empty for the bucket root
+    pub prefix: String,
+    /// Files (relative to the site directory) which are uploaded last, once everything they reference is in place
+    pub entry_points: Vec<String>,
+    /// Removes objects of files which are no longer part of the site (after the entry points were updated)
+    pub prune: bool,
+    /// Cache-Control of HTML files
+    pub html_cache_control: String,
+    /// Cache-Control of files without a content hash in their name
+    pub default_cache_control: String,
+}
+
+impl Default for DeployOptions {
+    fn default() -> Self {
+        Self {
+            prefix: String::new(),
+            entry_points: vec!["index.html".to_string()],
+            prune: false,
+            html_cache_control: "no-cache".to_string(),
+            default_cache_control: "public, max-age=3600".to_string(),
+        }
+    }
+}
+
+/// Result of a deploy
+#[derive(Debug, Clone, Default)]
+pub struct DeployReport {
+    /// Files which were uploaded (new or changed)
+    pub uploaded: Vec<String>,
+    /// Number of files which were already deployed
+    pub unchanged: u64,
+    /// Files which were removed (see `DeployOptions::prune`)
+    pub removed: Vec<String>,
+}
+
+fn is_html(path: &str) -> bool {
+    path.ends_with(".html") || path.ends_with(".htm")
+}
+
+/// Whether a file name contains a content hash (example: 'app.3f2a9c1b.js', 'index-4b1e09fc.css'), so it can be cached forever
+/// Only hex segments of at least 8 characters count, all digits are a date or counter (example: 'report-20240101.pdf')
+fn is_hashed(path: &str) -> bool {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    let stem = name.rsplit_once('.').map(|(t, _)| t).unwrap_or(name);
+    stem.split(['.', '-']).skip(1).any(|t| {
+        t.len() >= 8 && t.chars().all(|c| c.is_ascii_hexdigit()) && !t.chars().all(|c| c.is_ascii_digit())
+    })
+}
+
+/// Hex encoded SHA-256 hash of a file (see `content_hash`), read chunk by chunk
+async fn file_hash(path: &Path) -> std::io::Result<String> {
+    let mut file = tokio::fs::File::open(path).await?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0; 64 * 1024];
+    loop {
+        let n = file.read(&mut buf).await?;
+        if n == 0 {
+            return Ok(hex(&hasher.finalize()))
+        }
+        hasher.update(&buf[..n]);
+    }
+}
+
+/// All files below `dir`, as paths relative to `dir` separated by '/'
+async fn walk(dir: &Path) -> std::io::Result<Vec<(String, PathBuf)>> {
+    let mut files = vec![];
+    let mut pending = vec![(String::new(), dir.to_path_buf())];
+    while let Some((relative, path)) = pending.pop() {
+        let mut entries = tokio::fs::read_dir(&path).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let name = format!("{relative}{}", entry.file_name().to_string_lossy());
+            if entry.file_type().await?.is_dir() {
+                pending.push((format!("{name}/"), entry.path()));
+            } else {
+                files.push((name, entry.path()));
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// See `ClientInterface::deploy_site`
+pub(crate) async fn deploy<C: ClientInterface + ?Sized>(client: &C, local_dir: &Path, bucket: String, options: &DeployOptions) -> ReqRes<DeployReport> {
+    let manifest_key = format!("{}{DEPLOY_MANIFEST}", options.prefix);
+    let deployed: BTreeMap<String, String> = match client.static_download_bytes(bucket.clone(), manifest_key.clone(), None, None).await {
+        Ok(data) => serde_json::from_slice(&data).context("deploy_site", &bucket, Some(&manifest_key))?,
+        Err(e) if e.is_not_found() => BTreeMap::new(),
+        Err(e) => return Err(e)
+    };
+    let mut report = DeployReport::default();
+    let mut manifest = BTreeMap::new();
+    // Assets, other HTML files and entry points are uploaded in this order, so nothing is referenced before it exists
+    // Files are only hashed here and read again for their upload, so at most `CONCURRENCY` of them are held in memory
+    let mut phases: [Vec<(String, PathBuf)>; 3] = Default::default();
+    for (name, path) in walk(local_dir).await.context("deploy_site", &bucket, None)? {
+        let hash = file_hash(&path).await.context("deploy_site", &bucket, Some(&name))?;
+        if deployed.get(&name) == Some(&hash) {
+            report.unchanged += 1;
+            manifest.insert(name, hash);
+        } else {
+            let phase = if options.entry_points.contains(&name) { 2 } else if is_html(&name) { 1 } else { 0 };
+            phases[phase].push((name, path));
+        }
+    }
+    let bucket = &bucket;
+    for phase in phases {
+        let results: Vec<_> = stream::iter(phase).map(|(name, path)| async move {
+            let cache_control = if is_html(&name) {
+                options.html_cache_control.as_str()
+            } else if is_hashed(&name) {
+                IMMUTABLE_CACHE_CONTROL
+            } else {
+                options.default_cache_control.as_str()
+            };
+            let result: ReqRes<String> = async {
+                let data = tokio::fs::read(&path).await.context("deploy_site", bucket, Some(&name))?;
+                // Hashed again, the file may have changed since it was compared with the manifest
+                let hash = content_hash(&data);
+                client.upload_public(bucket.clone(), format!("{}{name}", options.prefix), data.into(),
+                                     by_extension(&name).map(|t| t.to_string()), cache_control.to_string()).await?;
+                Ok(hash)
+            }.await;
+            (name, result)
+        }).buffer_unordered(CONCURRENCY).collect().await;
+        // A failed phase stops the deploy, the entry points keep referencing the previous version
+        for (name, result) in results {
+            manifest.insert(name.clone(), result?);
+            report.uploaded.push(name);
+        }
+    }
+    if options.prune {
+        let stale = deployed.into_keys().filter(|t| !manifest.contains_key(t)).map(|t| format!("{}{t}", options.prefix)).collect();
+        for (key, result) in client.remove_objects(bucket.clone(), stale).await? {
+            match result {
+                Err(e) if !e.is_not_found() => return Err(e),
+                _ => report.removed.push(key.strip_prefix(options.prefix.as_str()).unwrap_or(&key).to_string())
+            }
+        }
+    }
+    let manifest = serde_json::to_vec(&manifest).context("deploy_site", bucket, Some(&manifest_key))?;
+    client.static_upload_bytes(bucket.clone(), manifest_key, manifest.into()).await?;
+    Ok(report)
+}
@@ -0,0 +1,74 @@
+//! Pure helpers for building request parameters
+//! None of these may panic on any input, this is checked by the targets in `fuzz/`
+
+use bytes::Bytes;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+/// Builds the value of a HTTP Range header
+pub fn range_header(starting: Option<u64>, ending: Option<u64>) -> Option<String> {
+    match (starting, ending) {
+        (Some(s), Some(e)) => Some(format!("bytes={}-{}", s, e)),
+        (Some(s), None)    => Some(format!("bytes={}-", s)),
+        (None, Some(e))    => Some(format!("bytes=-{}", e)),
+        (None, None)       => None,
+    }
+}
+
+/// Start (inclusive) and end (exclusive) of a range (same semantics as the providers' Range headers) within `len` bytes
+pub(crate) fn resolve_range(len: u64, starting: Option<u64>, ending: Option<u64>) -> (u64, u64) {
+    let (from, to) = match (starting, ending) {
+        (Some(s), Some(e)) => (s, e.saturating_add(1)),
+        (Some(s), None) => (s, len),
+        (None, Some(e)) => (len.saturating_sub(e), len),
+        (None, None) => (0, len),
+    };
+    let to = to.min(len);
+    (from.min(to), to)
+}
+
+/// Applies a range (same semantics as the providers' Range headers) to already downloaded data
+pub(crate) fn slice_range(data: Bytes, starting: Option<u64>, ending: Option<u64>) -> Bytes {
+    let (from, to) = resolve_range(data.len() as u64, starting, ending);
+    data.slice(from as usize..to as usize)
+}
+
+/// Whether an application identifier can be sent to every provider (the rules of AWS app names)
+/// Up to 50 characters, letters, digits and !#$%&'*+-.^_`|~
+pub fn valid_app_id(app_id: &str) -> bool {
+    !app_id.is_empty() && app_id.len() <= 50 && app_id.bytes().all(|t| t.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&t))
+}
+
+/// Percent-encodes an object key for use in a URL path
+/// Unreserved characters and '/' are kept as they are
+pub fn encode_key(key: &str) -> String {
+    let mut encoded = String::with_capacity(key.len());
+    for byte in key.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => encoded.push(byte as char),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// Lowercase hex of the data
+pub(crate) fn hex(data: &[u8]) -> String {
+    data.iter().map(|t| format!("{t:02x}")).collect()
+}
+
+/// Parses hex (either case), None if it is malformed
+pub(crate) fn unhex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) || !text.is_ascii() {
+        return None
+    }
+    (0..text.len()).step_by(2).map(|i| u8::from_str_radix(&text[i..i + 2], 16).ok()).collect()
+}
+
+/// HMAC-SHA256 of `data`, to be finalized or verified by the caller
+pub(crate) fn mac(key: &[u8], data: &[u8]) -> Hmac<Sha256> {
+    // HMAC accepts keys of any length
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC key of any length");
+    mac.update(data);
+    mac
+}
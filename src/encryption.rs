@@ -0,0 +1,602 @@
+use std::future::Future;
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use bytes::Bytes;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use crate::delegate::delegate;
+use crate::encoding::slice_range;
+use crate::{ClientError, ClientInterface, ClientObject, Context, CopyOptions, DownloadOptions, EmptyReqRes, ErrorContext, PostConstraints, PresignedPost, ReqRes, UploadOptions, WritePrecondition};
+
+/// Marks objects encrypted by an EncryptedClient
+pub const MAGIC: &[u8; 8] = b"USTGENC1";
+const NONCE_SIZE: usize = 12;
+
+/// A 256-bit AES key with an ID under which it is stored next to the encrypted data
+#[derive(Clone)]
+pub struct EncryptionKey {
+    pub id: String,
+    pub key: [u8; 32],
+}
+
+/// Decides which key an object is encrypted with
+pub trait KeyResolver: Send + Sync {
+    /// Key a new object is encrypted with, None if the object may not be written
+    fn resolve(&self, bucket: &str, object: &str) -> Option<EncryptionKey>;
+    /// Key with the given ID, used for decryption
+    fn by_id(&self, id: &str) -> Option<EncryptionKey>;
+}
+
+/// Routes keys by bucket and object prefix (the longest matching prefix wins)
+/// Multi-tenant setups usually use one prefix (and key) per tenant
+#[derive(Clone, Default)]
+pub struct PrefixKeyResolver {
+    routes: Vec<(String, String, EncryptionKey)>,
+}
+
+impl PrefixKeyResolver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Encrypts objects in `bucket` starting with `prefix` with `key`
+    pub fn route(mut self, bucket: String, prefix: String, key: EncryptionKey) -> Self {
+        self.routes.push((bucket, prefix, key));
+        self
+    }
+}
+
+impl KeyResolver for PrefixKeyResolver {
+    fn resolve(&self, bucket: &str, object: &str) -> Option<EncryptionKey> {
+        self.routes.iter()
+            .filter(|(b, prefix, _)| b == bucket && object.starts_with(prefix.as_str()))
+            .max_by_key(|(_, prefix, _)| prefix.len())
+            .map(|(_, _, key)| key.clone())
+    }
+
+    fn by_id(&self, id: &str) -> Option<EncryptionKey> {
+        self.routes.iter().find(|(_, _, key)| key.id == id).map(|(_, _, key)| key.clone())
+    }
+}
+
+#[derive(Debug)]
+pub enum EncryptionError {
+    /// The resolver returned no key for an object which is written
+    MissingKey,
+    /// The key an object was encrypted with is unknown to the resolver
+    UnknownKeyId(String),
+    /// The encrypted data is truncated or its header is invalid
+    Malformed,
+    /// Encryption failed or the data could not be authenticated (wrong key, tampered data or data moved to another object)
+    Crypto,
+    /// The data has no encryption header (see `EncryptedClient::with_plaintext_passthrough`)
+    NotEncrypted,
+    /// The key management service failed to wrap or unwrap a data key
+    Kms(String),
+}
+
+impl std::fmt::Display for EncryptionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EncryptionError::MissingKey => f.write_str("no key to encrypt with"),
+            EncryptionError::UnknownKeyId(id) => write!(f, "unknown key '{id}'"),
+            EncryptionError::Malformed => f.write_str("malformed encrypted data"),
+            EncryptionError::Crypto => f.write_str("encryption or authentication failed"),
+            EncryptionError::NotEncrypted => f.write_str("data is not encrypted"),
+            EncryptionError::Kms(e) => write!(f, "key management: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for EncryptionError {}
+
+impl From<EncryptionError> for ClientError {
+    fn from(value: EncryptionError) -> Self {
+        ClientError::Encryption(value)
+    }
+}
+
+/// Authenticated with the ciphertext, so data cannot be moved to another object or key (each part is length prefixed)
+fn associated_data(parts: &[&str]) -> Vec<u8> {
+    let mut aad = vec![];
+    for part in parts {
+        aad.extend_from_slice(&(part.len() as u32).to_be_bytes());
+        aad.extend_from_slice(part.as_bytes());
+    }
+    aad
+}
+
+/// Nonce followed by the ciphertext (including tag)
+fn seal(key: &[u8; 32], plaintext: &[u8], aad: &[u8]) -> Result<Vec<u8>, EncryptionError> {
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key)).encrypt(&nonce, Payload { msg: plaintext, aad })
+        .map_err(|_| EncryptionError::Crypto)?;
+    let mut out = Vec::with_capacity(NONCE_SIZE + ciphertext.len());
+    out.extend_from_slice(&nonce);
+    out.extend_from_slice(&ciphertext);
+    Ok(out)
+}
+
+/// Reverses `seal`
+fn open(key: &[u8; 32], sealed: &[u8], aad: &[u8]) -> Result<Bytes, EncryptionError> {
+    if sealed.len() < NONCE_SIZE {
+        return Err(EncryptionError::Malformed)
+    }
+    let (nonce, ciphertext) = sealed.split_at(NONCE_SIZE);
+    Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key)).decrypt(Nonce::from_slice(nonce), Payload { msg: ciphertext, aad })
+        .map(Bytes::from).map_err(|_| EncryptionError::Crypto)
+}
+
+/// Encrypts the data of an object with AES-256-GCM, bucket, object and key ID are authenticated as associated data
+/// Layout: MAGIC, key ID length (1 byte), key ID, nonce, ciphertext (including tag)
+pub fn encrypt(key: &EncryptionKey, bucket: &str, object: &str, plaintext: &[u8]) -> Result<Vec<u8>, EncryptionError> {
+    let id = key.id.as_bytes();
+    let id_len = u8::try_from(id.len()).map_err(|_| EncryptionError::Malformed)?;
+    let sealed = seal(&key.key, plaintext, &associated_data(&[bucket, object, &key.id]))?;
+    let mut out = Vec::with_capacity(MAGIC.len() + 1 + id.len() + sealed.len());
+    out.extend_from_slice(MAGIC);
+    out.push(id_len);
+    out.extend_from_slice(id);
+    out.extend_from_slice(&sealed);
+    Ok(out)
+}
+
+/// Reads the key ID of encrypted data, None if the data was not encrypted by an EncryptedClient
+pub fn key_id(data: &[u8]) -> Result<Option<&str>, EncryptionError> {
+    let Some(rest) = data.strip_prefix(MAGIC.as_slice()) else {
+        return Ok(None)
+    };
+    let (&id_len, rest) = rest.split_first().ok_or(EncryptionError::Malformed)?;
+    let id = rest.get(..id_len as usize).ok_or(EncryptionError::Malformed)?;
+    std::str::from_utf8(id).map(Some).map_err(|_| EncryptionError::Malformed)
+}
+
+/// Decrypts data `encrypt` produced for the same bucket and object, fails with `EncryptionError::NotEncrypted` for data without header
+pub fn decrypt(resolver: &dyn KeyResolver, bucket: &str, object: &str, data: Bytes) -> Result<Bytes, EncryptionError> {
+    let id = key_id(&data)?.ok_or(EncryptionError::NotEncrypted)?;
+    let key = resolver.by_id(id).ok_or_else(|| EncryptionError::UnknownKeyId(id.to_string()))?;
+    open(&key.key, &data[MAGIC.len() + 1 + id.len()..], &associated_data(&[bucket, object, id]))
+}
+
+/// Whole object encryption of a wrapper client, the data moving methods of `EncryptedClient` and `EnvelopeClient` are built on it
+trait Cipher: Send + Sync {
+    type Inner: ClientInterface;
+
+    fn client(&self) -> &Self::Inner;
+    fn encrypt_for(&self, bucket: &str, object: &str, data: &[u8]) -> impl Future<Output = ReqRes<Bytes>> + Send;
+    /// Fails with `EncryptionError::NotEncrypted` for data without header, unless plaintext passes through
+    fn decrypt_for(&self, bucket: &str, object: &str, data: Bytes) -> impl Future<Output = ReqRes<Bytes>> + Send;
+}
+
+/// Objects are decrypted as a whole, so the range is cut out afterwards
+async fn download(cipher: &impl Cipher, bucket: String, object_id: String, starting: Option<u64>, ending: Option<u64>) -> ReqRes<Bytes> {
+    let data = cipher.client().static_download_bytes(bucket.clone(), object_id.clone(), None, None).await?;
+    Ok(slice_range(cipher.decrypt_for(&bucket, &object_id, data).await?, starting, ending))
+}
+
+async fn download_with_options(cipher: &impl Cipher, bucket: String, object_id: String, starting: Option<u64>, ending: Option<u64>, options: DownloadOptions) -> ReqRes<Bytes> {
+    let data = cipher.client().download_with_options(bucket.clone(), object_id.clone(), None, None, options).await?;
+    Ok(slice_range(cipher.decrypt_for(&bucket, &object_id, data).await?, starting, ending))
+}
+
+async fn upload_bytes(cipher: &impl Cipher, bucket: String, object_id: String, data: Bytes) -> ReqRes<impl ClientObject> {
+    let data = cipher.encrypt_for(&bucket, &object_id, &data).await?;
+    cipher.client().static_upload_bytes(bucket, object_id, data).await
+}
+
+async fn upload_with_options(cipher: &impl Cipher, bucket: String, object_id: String, data: Bytes, options: UploadOptions) -> ReqRes<impl ClientObject> {
+    let data = cipher.encrypt_for(&bucket, &object_id, &data).await?;
+    cipher.client().upload_with_options(bucket, object_id, data, options).await
+}
+
+/// Decrypts the source and encrypts it for the destination, content type, custom metadata and storage class are kept
+async fn copy(cipher: &impl Cipher, src_bucket: String, src_object: String, dest_bucket: String, dest_object: String, options: CopyOptions) -> ReqRes<impl ClientObject> {
+    let source = cipher.client().stat_object(src_bucket.clone(), src_object.clone()).await?;
+    let data = download(cipher, src_bucket, src_object, None, None).await?;
+    let options = UploadOptions {
+        content_type: source.content_type(),
+        custom_metadata: source.custom_metadata(),
+        storage_class: options.storage_class.or(source.storage_class()),
+        ..Default::default()
+    };
+    upload_with_options(cipher, dest_bucket, dest_object, data, options).await
+}
+
+async fn download_to_writer(cipher: &impl Cipher, bucket: String, object_id: String, starting: Option<u64>, ending: Option<u64>, mut w: impl AsyncWrite + Unpin + Send) -> ReqRes<u64> {
+    let data = download(cipher, bucket.clone(), object_id.clone(), starting, ending).await?;
+    w.write_all(&data).await.context("download_to_writer", &bucket, Some(&object_id))?;
+    w.flush().await.context("download_to_writer", &bucket, Some(&object_id))?;
+    Ok(data.len() as u64)
+}
+
+async fn upload_from_reader(cipher: &impl Cipher, bucket: String, object_id: String, mut r: impl AsyncRead + Unpin + Send) -> EmptyReqRes {
+    let mut data = vec![];
+    r.read_to_end(&mut data).await.context("upload_from_reader", &bucket, Some(&object_id))?;
+    upload_bytes(cipher, bucket, object_id, data.into()).await?;
+    Ok(())
+}
+
+/// Appending to an encrypted object requires decrypting and encrypting it as a whole
+async fn append(cipher: &impl Cipher, bucket: String, object_id: String, data: Bytes) -> EmptyReqRes {
+    let mut combined = match download(cipher, bucket.clone(), object_id.clone(), None, None).await {
+        Ok(existing) => existing.to_vec(),
+        Err(e) if e.is_not_found() => vec![],
+        Err(e) => return Err(e)
+    };
+    combined.extend_from_slice(&data);
+    upload_bytes(cipher, bucket, object_id, combined.into()).await?;
+    Ok(())
+}
+
+/// Public objects cannot be encrypted (nobody could read them), so making objects public always fails
+fn public_unsupported(operation: &'static str, bucket: &str, object_id: &str) -> EmptyReqRes {
+    Err(ClientError::Unsupported("public objects cannot be encrypted on the client side")
+        .with_context(ErrorContext::new(operation, Some(bucket), Some(object_id))))
+}
+
+/// Browsers would upload unencrypted objects with a presigned POST policy, so creating one always fails
+fn post_unsupported(bucket: &str, key_prefix: &str) -> ReqRes<PresignedPost> {
+    Err(ClientError::Unsupported("presigned POST uploads bypass the client-side encryption")
+        .with_context(ErrorContext::new("presigned_post", Some(bucket), Some(key_prefix))))
+}
+
+/// Encrypts objects on the client side before they are uploaded, with a key chosen per object by a KeyResolver
+///
+/// Note: Objects are encrypted as a whole, so uploads from readers are buffered and ranged downloads fetch the whole object.
+/// Sizes reported by the provider (e.g. `stat_object`) include the encryption overhead.
+/// The encrypted data is bound to its object, so copies are downloaded and encrypted again. Signed URLs bypass the encryption, public objects and presigned POST policies are not supported.
+pub struct EncryptedClient<C: ClientInterface, R: KeyResolver> {
+    inner: C,
+    resolver: R,
+    passthrough: bool,
+}
+
+impl<C: ClientInterface, R: KeyResolver> EncryptedClient<C, R> {
+    pub fn new(inner: C, resolver: R) -> Self {
+        Self { inner, resolver, passthrough: false }
+    }
+
+    /// Returns objects without encryption header unchanged instead of failing with `EncryptionError::NotEncrypted`
+    /// (for migrating buckets with unencrypted objects, anyone who can write to the bucket can then plant plaintext)
+    pub fn with_plaintext_passthrough(mut self, passthrough: bool) -> Self {
+        self.passthrough = passthrough;
+        self
+    }
+
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+}
+
+impl<C: ClientInterface, R: KeyResolver> Cipher for EncryptedClient<C, R> {
+    type Inner = C;
+
+    fn client(&self) -> &C {
+        &self.inner
+    }
+
+    async fn encrypt_for(&self, bucket: &str, object: &str, data: &[u8]) -> ReqRes<Bytes> {
+        let key = self.resolver.resolve(bucket, object).ok_or(EncryptionError::MissingKey).context("encrypt", bucket, Some(object))?;
+        Ok(encrypt(&key, bucket, object, data).context("encrypt", bucket, Some(object))?.into())
+    }
+
+    async fn decrypt_for(&self, bucket: &str, object: &str, data: Bytes) -> ReqRes<Bytes> {
+        if self.passthrough && key_id(&data).context("decrypt", bucket, Some(object))?.is_none() {
+            return Ok(data)
+        }
+        decrypt(&self.resolver, bucket, object, data).context("decrypt", bucket, Some(object))
+    }
+}
+
+impl<C: ClientInterface, R: KeyResolver> ClientInterface for EncryptedClient<C, R> {
+    async fn static_download_bytes(&self, bucket: String, object_id: String, starting: Option<u64>, ending: Option<u64>) -> ReqRes<Bytes> {
+        download(self, bucket, object_id, starting, ending).await
+    }
+
+    /// The conditions apply to the encrypted object
+    async fn download_with_options(&self, bucket: String, object_id: String, starting: Option<u64>, ending: Option<u64>, options: DownloadOptions) -> ReqRes<Bytes> {
+        download_with_options(self, bucket, object_id, starting, ending, options).await
+    }
+
+    async fn copy_object(&self, src_bucket: String, src_object: String, dest_bucket: String, dest_object: String) -> ReqRes<impl ClientObject> {
+        copy(self, src_bucket, src_object, dest_bucket, dest_object, CopyOptions::default()).await
+    }
+
+    async fn copy_object_with_options(&self, src_bucket: String, src_object: String, dest_bucket: String, dest_object: String, options: CopyOptions) -> ReqRes<impl ClientObject> {
+        copy(self, src_bucket, src_object, dest_bucket, dest_object, options).await
+    }
+
+    async fn static_upload_bytes(&self, bucket: String, object_id: String, data: Bytes) -> ReqRes<impl ClientObject> {
+        upload_bytes(self, bucket, object_id, data).await
+    }
+
+    /// The options apply to the encrypted object, a content encoding does not describe the stored data
+    async fn upload_with_options(&self, bucket: String, object_id: String, data: Bytes, options: UploadOptions) -> ReqRes<impl ClientObject> {
+        upload_with_options(self, bucket, object_id, data, options).await
+    }
+
+    async fn download_to_writer(&self, bucket: String, object_id: String, starting: Option<u64>, ending: Option<u64>, w: impl AsyncWrite + Unpin + Send) -> ReqRes<u64> {
+        download_to_writer(self, bucket, object_id, starting, ending, w).await
+    }
+
+    async fn upload_from_reader(&self, bucket: String, object_id: String, r: impl AsyncRead + Unpin + Send + Sync + 'static) -> EmptyReqRes {
+        upload_from_reader(self, bucket, object_id, r).await
+    }
+
+    async fn append(&self, bucket: String, object_id: String, data: Bytes) -> EmptyReqRes {
+        append(self, bucket, object_id, data).await
+    }
+
+    async fn upload_public(&self, bucket: String, object_id: String, _data: Bytes, _content_type: Option<String>, _cache_control: String) -> EmptyReqRes {
+        public_unsupported("upload_public", &bucket, &object_id)
+    }
+
+    async fn make_object_public(&self, bucket: String, object_id: String) -> EmptyReqRes {
+        public_unsupported("make_object_public", &bucket, &object_id)
+    }
+
+    async fn presigned_post(&self, bucket: String, key_prefix: String, _constraints: PostConstraints) -> ReqRes<PresignedPost> {
+        post_unsupported(&bucket, &key_prefix)
+    }
+
+    delegate!(inner;
+        update_object_metadata, get_object_tags, set_object_tags, get_object_acl, set_object_acl, public_url, signed_url, remove_bucket, remove_object, create_bucket, restore_object, restore_status,
+        list_buckets, get_bucket, get_object, stat_object, list_objects, list_prefix, list_projected, list_delimited, list_after, list_versions, restore_object_version,
+        remove_objects, bucket_exists, bucket_name_problems, compliance_records, get_bucket_spec, get_lifecycle_rules, set_lifecycle_rules, get_bucket_policy, set_bucket_policy, get_bucket_labels, set_bucket_labels,
+        get_object_lock, set_object_retention, set_legal_hold
+    );
+}
+
+/// Marks objects encrypted by an EnvelopeClient
+pub const ENVELOPE_MAGIC: &[u8; 8] = b"USTGENV1";
+
+/// Wraps (encrypts) the data keys of envelope encrypted objects with a key encryption key, which usually never leaves a KMS
+/// Implementations: `AwsKms` (feature 'aws_kms'), `GoogleKms` (feature 'google_kms')
+pub trait KeyWrapper: Send + Sync {
+    /// ID of the key encryption key new data keys are wrapped with, stored with every object (example: the ARN of an AWS KMS key)
+    fn key_id(&self) -> &str;
+    fn wrap(&self, data_key: &[u8; 32]) -> impl Future<Output = ReqRes<Vec<u8>>> + Send;
+    /// `key_id` is the ID the data key was wrapped with, which differs from the current one after a rotation
+    fn unwrap(&self, key_id: &str, wrapped_key: &[u8]) -> impl Future<Output = ReqRes<[u8; 32]>> + Send;
+}
+
+/// Header of an envelope encrypted object (the per-object key metadata)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Envelope<'a> {
+    /// ID of the key encryption key the data key is wrapped with
+    pub key_id: &'a str,
+    pub wrapped_key: &'a [u8],
+    /// Nonce followed by the ciphertext
+    sealed: &'a [u8],
+}
+
+/// Reads the header of envelope encrypted data, None if the data was not encrypted by an EnvelopeClient
+/// Layout: ENVELOPE_MAGIC, key ID length (1 byte), key ID, wrapped key length (2 bytes, big endian), wrapped key, nonce, ciphertext (including tag)
+pub fn envelope(data: &[u8]) -> Result<Option<Envelope<'_>>, EncryptionError> {
+    let Some(rest) = data.strip_prefix(ENVELOPE_MAGIC.as_slice()) else {
+        return Ok(None)
+    };
+    let (&id_len, rest) = rest.split_first().ok_or(EncryptionError::Malformed)?;
+    let (id, rest) = rest.split_at_checked(id_len as usize).ok_or(EncryptionError::Malformed)?;
+    let key_id = std::str::from_utf8(id).map_err(|_| EncryptionError::Malformed)?;
+    let (wrapped_len, rest) = rest.split_first_chunk::<2>().ok_or(EncryptionError::Malformed)?;
+    let (wrapped_key, sealed) = rest.split_at_checked(u16::from_be_bytes(*wrapped_len) as usize).ok_or(EncryptionError::Malformed)?;
+    Ok(Some(Envelope { key_id, wrapped_key, sealed }))
+}
+
+fn seal_envelope(key_id: &str, wrapped_key: &[u8], sealed: &[u8]) -> Result<Vec<u8>, EncryptionError> {
+    let id_len = u8::try_from(key_id.len()).map_err(|_| EncryptionError::Malformed)?;
+    let wrapped_len = u16::try_from(wrapped_key.len()).map_err(|_| EncryptionError::Malformed)?;
+    let mut out = Vec::with_capacity(ENVELOPE_MAGIC.len() + 3 + key_id.len() + wrapped_key.len() + sealed.len());
+    out.extend_from_slice(ENVELOPE_MAGIC);
+    out.push(id_len);
+    out.extend_from_slice(key_id.as_bytes());
+    out.extend_from_slice(&wrapped_len.to_be_bytes());
+    out.extend_from_slice(wrapped_key);
+    out.extend_from_slice(sealed);
+    Ok(out)
+}
+
+/// Encrypts every object with its own random data key (AES-256-GCM), which is wrapped by a KeyWrapper
+/// and stored in the header of the object, so customer managed keys (BYOK) never leave their KMS
+/// Bucket and object are authenticated as associated data, the data key is bound to its key encryption key by the KMS
+///
+/// Rotating the key encryption key inside the KMS needs no changes, old data keys still unwrap.
+/// After switching to another key, `rewrap` moves objects to it without decrypting their content.
+/// Note: Every upload wraps and every download unwraps a data key, which is one KMS request each.
+/// Otherwise this behaves like an EncryptedClient (whole object encryption, sizes include the overhead, copies are encrypted again, no public objects or presigned POST policies).
+pub struct EnvelopeClient<C: ClientInterface, W: KeyWrapper> {
+    inner: C,
+    wrapper: W,
+    passthrough: bool,
+}
+
+impl<C: ClientInterface, W: KeyWrapper> EnvelopeClient<C, W> {
+    pub fn new(inner: C, wrapper: W) -> Self {
+        Self { inner, wrapper, passthrough: false }
+    }
+
+    /// Returns objects without envelope header unchanged instead of failing with `EncryptionError::NotEncrypted`
+    /// (see `EncryptedClient::with_plaintext_passthrough`)
+    pub fn with_plaintext_passthrough(mut self, passthrough: bool) -> Self {
+        self.passthrough = passthrough;
+        self
+    }
+
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    pub fn wrapper(&self) -> &W {
+        &self.wrapper
+    }
+
+    /// Wraps the data key of an object with the current key encryption key (see `KeyWrapper::key_id`), the content is not re-encrypted
+    /// Returns false if the object uses the current key already or is not envelope encrypted.
+    /// Content type, custom metadata and storage class are kept. Fails with `ClientError::PreconditionFailed` if the object changes meanwhile
+    pub async fn rewrap(&self, bucket: String, object_id: String) -> ReqRes<bool> {
+        let object = self.inner.stat_object(bucket.clone(), object_id.clone()).await?;
+        let options = DownloadOptions { if_match: object.etag(), ..Default::default() };
+        let data = self.inner.download_with_options(bucket.clone(), object_id.clone(), None, None, options).await?;
+        let Some(header) = envelope(&data).context("rewrap", &bucket, Some(&object_id))? else {
+            return Ok(false)
+        };
+        if header.key_id == self.wrapper.key_id() {
+            return Ok(false)
+        }
+        let data_key = self.wrapper.unwrap(header.key_id, header.wrapped_key).await.context("unwrap_key", &bucket, Some(&object_id))?;
+        let wrapped_key = self.wrapper.wrap(&data_key).await.context("wrap_key", &bucket, Some(&object_id))?;
+        let data = seal_envelope(self.wrapper.key_id(), &wrapped_key, header.sealed).context("rewrap", &bucket, Some(&object_id))?;
+        let options = UploadOptions {
+            content_type: object.content_type(),
+            custom_metadata: object.custom_metadata(),
+            storage_class: object.storage_class(),
+            precondition: object.version().map(WritePrecondition::Matches),
+            ..Default::default()
+        };
+        self.inner.upload_with_options(bucket, object_id, data.into(), options).await?;
+        Ok(true)
+    }
+}
+
+impl<C: ClientInterface, W: KeyWrapper> Cipher for EnvelopeClient<C, W> {
+    type Inner = C;
+
+    fn client(&self) -> &C {
+        &self.inner
+    }
+
+    async fn encrypt_for(&self, bucket: &str, object: &str, data: &[u8]) -> ReqRes<Bytes> {
+        let data_key: [u8; 32] = Aes256Gcm::generate_key(&mut OsRng).into();
+        let wrapped_key = self.wrapper.wrap(&data_key).await.context("wrap_key", bucket, Some(object))?;
+        let sealed = seal(&data_key, data, &associated_data(&[bucket, object])).context("encrypt", bucket, Some(object))?;
+        Ok(seal_envelope(self.wrapper.key_id(), &wrapped_key, &sealed).context("encrypt", bucket, Some(object))?.into())
+    }
+
+    async fn decrypt_for(&self, bucket: &str, object: &str, data: Bytes) -> ReqRes<Bytes> {
+        let header = match envelope(&data).context("decrypt", bucket, Some(object))? {
+            Some(header) => header,
+            None if self.passthrough => return Ok(data),
+            None => return Err(EncryptionError::NotEncrypted).context("decrypt", bucket, Some(object))
+        };
+        let data_key = self.wrapper.unwrap(header.key_id, header.wrapped_key).await.context("unwrap_key", bucket, Some(object))?;
+        open(&data_key, header.sealed, &associated_data(&[bucket, object])).context("decrypt", bucket, Some(object))
+    }
+}
+
+impl<C: ClientInterface, W: KeyWrapper> ClientInterface for EnvelopeClient<C, W> {
+    async fn static_download_bytes(&self, bucket: String, object_id: String, starting: Option<u64>, ending: Option<u64>) -> ReqRes<Bytes> {
+        download(self, bucket, object_id, starting, ending).await
+    }
+
+    /// The conditions apply to the encrypted object
+    async fn download_with_options(&self, bucket: String, object_id: String, starting: Option<u64>, ending: Option<u64>, options: DownloadOptions) -> ReqRes<Bytes> {
+        download_with_options(self, bucket, object_id, starting, ending, options).await
+    }
+
+    async fn copy_object(&self, src_bucket: String, src_object: String, dest_bucket: String, dest_object: String) -> ReqRes<impl ClientObject> {
+        copy(self, src_bucket, src_object, dest_bucket, dest_object, CopyOptions::default()).await
+    }
+
+    async fn copy_object_with_options(&self, src_bucket: String, src_object: String, dest_bucket: String, dest_object: String, options: CopyOptions) -> ReqRes<impl ClientObject> {
+        copy(self, src_bucket, src_object, dest_bucket, dest_object, options).await
+    }
+
+    async fn static_upload_bytes(&self, bucket: String, object_id: String, data: Bytes) -> ReqRes<impl ClientObject> {
+        upload_bytes(self, bucket, object_id, data).await
+    }
+
+    /// The options apply to the encrypted object, a content encoding does not describe the stored data
+    async fn upload_with_options(&self, bucket: String, object_id: String, data: Bytes, options: UploadOptions) -> ReqRes<impl ClientObject> {
+        upload_with_options(self, bucket, object_id, data, options).await
+    }
+
+    async fn download_to_writer(&self, bucket: String, object_id: String, starting: Option<u64>, ending: Option<u64>, w: impl AsyncWrite + Unpin + Send) -> ReqRes<u64> {
+        download_to_writer(self, bucket, object_id, starting, ending, w).await
+    }
+
+    async fn upload_from_reader(&self, bucket: String, object_id: String, r: impl AsyncRead + Unpin + Send + Sync + 'static) -> EmptyReqRes {
+        upload_from_reader(self, bucket, object_id, r).await
+    }
+
+    async fn append(&self, bucket: String, object_id: String, data: Bytes) -> EmptyReqRes {
+        append(self, bucket, object_id, data).await
+    }
+
+    async fn upload_public(&self, bucket: String, object_id: String, _data: Bytes, _content_type: Option<String>, _cache_control: String) -> EmptyReqRes {
+        public_unsupported("upload_public", &bucket, &object_id)
+    }
+
+    async fn make_object_public(&self, bucket: String, object_id: String) -> EmptyReqRes {
+        public_unsupported("make_object_public", &bucket, &object_id)
+    }
+
+    async fn presigned_post(&self, bucket: String, key_prefix: String, _constraints: PostConstraints) -> ReqRes<PresignedPost> {
+        post_unsupported(&bucket, &key_prefix)
+    }
+
+    delegate!(inner;
+        update_object_metadata, get_object_tags, set_object_tags, get_object_acl, set_object_acl, public_url, signed_url, remove_bucket, remove_object, create_bucket, restore_object, restore_status,
+        list_buckets, get_bucket, get_object, stat_object, list_objects, list_prefix, list_projected, list_delimited, list_after, list_versions, restore_object_version,
+        remove_objects, bucket_exists, bucket_name_problems, compliance_records, get_bucket_spec, get_lifecycle_rules, set_lifecycle_rules, get_bucket_policy, set_bucket_policy, get_bucket_labels, set_bucket_labels,
+        get_object_lock, set_object_retention, set_legal_hold
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::in_memory::InMemoryClient;
+    use super::*;
+
+    /// Wraps data keys with a local key encryption key instead of a KMS
+    struct LocalWrapper(&'static str, [u8; 32]);
+
+    impl KeyWrapper for LocalWrapper {
+        fn key_id(&self) -> &str {
+            self.0
+        }
+
+        async fn wrap(&self, data_key: &[u8; 32]) -> ReqRes<Vec<u8>> {
+            Ok(seal(&self.1, data_key, self.0.as_bytes())?)
+        }
+
+        async fn unwrap(&self, key_id: &str, wrapped_key: &[u8]) -> ReqRes<[u8; 32]> {
+            let key = open(&self.1, wrapped_key, key_id.as_bytes())?;
+            Ok(key.as_ref().try_into().map_err(|_| EncryptionError::Malformed)?)
+        }
+    }
+
+    /// Round-trips an object and then flips one byte of the stored ciphertext, which must fail to decrypt
+    async fn round_trip<C: ClientInterface>(client: &C, inner: &InMemoryClient) {
+        let plaintext = Bytes::from_static(b"secret payload");
+        client.static_upload_bytes("bucket".to_string(), "key".to_string(), plaintext.clone()).await.unwrap();
+        let stored = inner.static_download_bytes("bucket".to_string(), "key".to_string(), None, None).await.unwrap();
+        assert!(!stored.windows(plaintext.len()).any(|w| w == plaintext));
+        assert_eq!(client.static_download_bytes("bucket".to_string(), "key".to_string(), None, None).await.unwrap(), plaintext);
+        assert_eq!(client.static_download_bytes("bucket".to_string(), "key".to_string(), Some(7), Some(13)).await.unwrap(), "payload");
+
+        let mut tampered = stored.to_vec();
+        *tampered.last_mut().unwrap() ^= 1;
+        inner.static_upload_bytes("bucket".to_string(), "key".to_string(), tampered.into()).await.unwrap();
+        let err = client.static_download_bytes("bucket".to_string(), "key".to_string(), None, None).await.unwrap_err();
+        assert!(matches!(err.inner(), ClientError::Encryption(EncryptionError::Crypto)), "{err}");
+        // Moved to another object, the associated data no longer matches
+        inner.static_upload_bytes("bucket".to_string(), "other".to_string(), stored).await.unwrap();
+        assert!(client.static_download_bytes("bucket".to_string(), "other".to_string(), None, None).await.is_err());
+
+        let err = client.presigned_post("bucket".to_string(), "uploads/".to_string(), PostConstraints::default()).await.unwrap_err();
+        assert!(matches!(err.inner(), ClientError::Unsupported(_)), "{err}");
+    }
+
+    #[tokio::test]
+    async fn encrypted_round_trip() {
+        let key = EncryptionKey { id: "k1".to_string(), key: [7; 32] };
+        let client = EncryptedClient::new(InMemoryClient::with_bucket("bucket"), PrefixKeyResolver::new().route("bucket".to_string(), String::new(), key));
+        round_trip(&client, client.inner()).await;
+    }
+
+    #[tokio::test]
+    async fn envelope_round_trip() {
+        let client = EnvelopeClient::new(InMemoryClient::with_bucket("bucket"), LocalWrapper("kek-1", [1; 32]));
+        round_trip(&client, client.inner()).await;
+    }
+}
@@ -0,0 +1,424 @@
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use bytes::Bytes;
+use tokio::io::{AsyncRead, AsyncWrite};
+use crate::{AccessControl, AclGrant, BucketNameProblem, BucketPolicy, BucketSpec, ClientBucket, ClientError, ClientInterface, ClientObject, ComplianceRecord, CopyOptions, Cursor, DownloadOptions, EmptyReqRes, LifecycleRule, ListAttributes, ListPage, MetadataChanges, ObjectLock, ObjectVersion, PostConstraints, PresignedPost, ReqRes, RestoreStatus, Retention, RetrievalTier, SignedUrlOptions, UploadOptions};
+
+/// Faults injected into an operation of a `FaultInjecting` client
+#[derive(Clone)]
+pub struct Fault {
+    /// Share of the calls (0.0 - 1.0) which fail before reaching the provider
+    pub error_rate: f64,
+    /// Waited before every call
+    pub latency: Duration,
+    /// Share of the downloads (0.0 - 1.0) whose body is cut off at a random length
+    pub truncate_rate: f64,
+    error: Arc<dyn Fn() -> ClientError + Send + Sync>,
+}
+
+impl Default for Fault {
+    fn default() -> Self {
+        Self { error_rate: 0.0, latency: Duration::ZERO, truncate_rate: 0.0, error: Arc::new(connection_reset) }
+    }
+}
+
+/// Default injected error, a dropped connection (retryable, see `ClientError::is_network`)
+fn connection_reset() -> ClientError {
+    ClientError::Io(std::io::Error::new(std::io::ErrorKind::ConnectionReset, "injected fault"))
+}
+
+impl Fault {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn error_rate(mut self, rate: f64) -> Self {
+        self.error_rate = rate;
+        self
+    }
+
+    pub fn latency(mut self, latency: Duration) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    pub fn truncate_rate(mut self, rate: f64) -> Self {
+        self.truncate_rate = rate;
+        self
+    }
+
+    /// Fails with the error `error` creates instead of a dropped connection
+    pub fn error_with(mut self, error: impl Fn() -> ClientError + Send + Sync + 'static) -> Self {
+        self.error = Arc::new(error);
+        self
+    }
+}
+
+/// Writer which drops the connection after `remaining` bytes
+struct Truncating<W> {
+    inner: W,
+    remaining: u64,
+}
+
+impl<W: AsyncWrite + Unpin> AsyncWrite for Truncating<W> {
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<std::io::Result<usize>> {
+        if self.remaining == 0 {
+            return Poll::Ready(Err(std::io::Error::new(std::io::ErrorKind::ConnectionReset, "injected truncation")))
+        }
+        let len = buf.len().min(self.remaining as usize);
+        let written = std::task::ready!(Pin::new(&mut self.inner).poll_write(cx, &buf[..len]))?;
+        self.remaining -= written as u64;
+        Poll::Ready(Ok(written))
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+/// Wraps a client and injects errors, latency and truncated downloads, for chaos testing of retry and resume logic
+/// Example: `FaultInjecting::new(client).on("static_download_bytes", Fault::new().truncate_rate(0.5))`
+///
+/// Faults are configured per operation (the name of the `ClientInterface` method) or for all of them,
+/// injected errors never reach the provider. Truncated `static_download_bytes` / `download_with_options` bodies are
+/// returned silently (like a response missing its tail), `download_to_writer` fails with a dropped connection after part of the body.
+/// The random numbers are seeded, so a run can be repeated with `with_seed`.
+pub struct FaultInjecting<C: ClientInterface> {
+    inner: C,
+    faults: HashMap<&'static str, Fault>,
+    fallback: Fault,
+    state: AtomicU64,
+    errors: AtomicU64,
+    truncations: AtomicU64,
+}
+
+impl<C: ClientInterface> FaultInjecting<C> {
+    pub fn new(inner: C) -> Self {
+        let seed = SystemTime::now().duration_since(UNIX_EPOCH).map(|t| t.as_nanos() as u64).unwrap_or_default();
+        Self::with_seed(inner, seed)
+    }
+
+    pub fn with_seed(inner: C, seed: u64) -> Self {
+        Self { inner, faults: HashMap::new(), fallback: Fault::default(), state: AtomicU64::new(seed), errors: AtomicU64::new(0), truncations: AtomicU64::new(0) }
+    }
+
+    /// Injects `fault` into `operation` (example: 'static_download_bytes')
+    pub fn on(mut self, operation: &'static str, fault: Fault) -> Self {
+        self.faults.insert(operation, fault);
+        self
+    }
+
+    /// Injects `fault` into every operation without a fault of its own
+    pub fn on_all(mut self, fault: Fault) -> Self {
+        self.fallback = fault;
+        self
+    }
+
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    /// Number of errors injected so far
+    pub fn injected_errors(&self) -> u64 {
+        self.errors.load(Ordering::Relaxed)
+    }
+
+    /// Number of downloads truncated so far
+    pub fn truncated(&self) -> u64 {
+        self.truncations.load(Ordering::Relaxed)
+    }
+
+    fn fault(&self, operation: &str) -> &Fault {
+        self.faults.get(operation).unwrap_or(&self.fallback)
+    }
+
+    /// Uniform random number in [0, 1) (splitmix64)
+    fn roll(&self) -> f64 {
+        let mut z = self.state.fetch_add(0x9E37_79B9_7F4A_7C15, Ordering::Relaxed).wrapping_add(0x9E37_79B9_7F4A_7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        (z ^ (z >> 31)) as f64 / (u64::MAX as f64 + 1.0)
+    }
+
+    /// Waits for the latency of the operation and fails with its error at its error rate
+    async fn inject(&self, operation: &'static str) -> EmptyReqRes {
+        let fault = self.fault(operation);
+        if !fault.latency.is_zero() {
+            tokio::time::sleep(fault.latency).await;
+        }
+        if fault.error_rate > 0.0 && self.roll() < fault.error_rate {
+            self.errors.fetch_add(1, Ordering::Relaxed);
+            return Err((fault.error)())
+        }
+        Ok(())
+    }
+
+    /// Whether the next download of the operation is truncated
+    fn truncates(&self, operation: &'static str) -> bool {
+        let rate = self.fault(operation).truncate_rate;
+        if rate <= 0.0 || self.roll() >= rate {
+            return false
+        }
+        self.truncations.fetch_add(1, Ordering::Relaxed);
+        true
+    }
+
+    fn truncate(&self, operation: &'static str, data: Bytes) -> Bytes {
+        if data.is_empty() || !self.truncates(operation) {
+            return data
+        }
+        data.slice(..(self.roll() * data.len() as f64) as usize)
+    }
+}
+
+impl<C: ClientInterface> ClientInterface for FaultInjecting<C> {
+    async fn static_download_bytes(&self, bucket: String, object_id: String, starting: Option<u64>, ending: Option<u64>) -> ReqRes<Bytes> {
+        self.inject("static_download_bytes").await?;
+        let data = self.inner.static_download_bytes(bucket, object_id, starting, ending).await?;
+        Ok(self.truncate("static_download_bytes", data))
+    }
+
+    async fn static_upload_bytes(&self, bucket: String, object_id: String, data: Bytes) -> ReqRes<impl ClientObject> {
+        self.inject("static_upload_bytes").await?;
+        self.inner.static_upload_bytes(bucket, object_id, data).await
+    }
+
+    async fn upload_with_options(&self, bucket: String, object_id: String, data: Bytes, options: UploadOptions) -> ReqRes<impl ClientObject> {
+        self.inject("upload_with_options").await?;
+        self.inner.upload_with_options(bucket, object_id, data, options).await
+    }
+
+    async fn download_with_options(&self, bucket: String, object_id: String, starting: Option<u64>, ending: Option<u64>, options: DownloadOptions) -> ReqRes<Bytes> {
+        self.inject("download_with_options").await?;
+        let data = self.inner.download_with_options(bucket, object_id, starting, ending, options).await?;
+        Ok(self.truncate("download_with_options", data))
+    }
+
+    async fn download_to_writer(&self, bucket: String, object_id: String, starting: Option<u64>, ending: Option<u64>, w: impl AsyncWrite + Unpin + Send) -> ReqRes<u64> {
+        self.inject("download_to_writer").await?;
+        if !self.truncates("download_to_writer") {
+            return self.inner.download_to_writer(bucket, object_id, starting, ending, w).await
+        }
+        // The length of the body is not known in advance, so the cut is placed within the object
+        let size = self.inner.stat_object(bucket.clone(), object_id.clone()).await?.size();
+        let w = Truncating { inner: w, remaining: (self.roll() * size as f64) as u64 };
+        self.inner.download_to_writer(bucket, object_id, starting, ending, w).await
+    }
+
+    async fn upload_from_reader(&self, bucket: String, object_id: String, r: impl AsyncRead + Unpin + Send + Sync + 'static) -> EmptyReqRes {
+        self.inject("upload_from_reader").await?;
+        self.inner.upload_from_reader(bucket, object_id, r).await
+    }
+
+    async fn signed_url(&self, bucket: String, object_id: String, options: SignedUrlOptions) -> ReqRes<String> {
+        self.inject("signed_url").await?;
+        self.inner.signed_url(bucket, object_id, options).await
+    }
+
+    async fn presigned_post(&self, bucket: String, key_prefix: String, constraints: PostConstraints) -> ReqRes<PresignedPost> {
+        self.inject("presigned_post").await?;
+        self.inner.presigned_post(bucket, key_prefix, constraints).await
+    }
+
+    async fn remove_bucket(&self, bucket: String) -> EmptyReqRes {
+        self.inject("remove_bucket").await?;
+        self.inner.remove_bucket(bucket).await
+    }
+
+    async fn remove_object(&self, bucket: String, object_id: String) -> EmptyReqRes {
+        self.inject("remove_object").await?;
+        self.inner.remove_object(bucket, object_id).await
+    }
+
+    async fn create_bucket(&self, bucket: String) -> ReqRes<impl ClientBucket> {
+        self.inject("create_bucket").await?;
+        self.inner.create_bucket(bucket).await
+    }
+
+    async fn copy_object(&self, src_bucket: String, src_object: String, dest_bucket: String, dest_object: String) -> ReqRes<impl ClientObject> {
+        self.inject("copy_object").await?;
+        self.inner.copy_object(src_bucket, src_object, dest_bucket, dest_object).await
+    }
+
+    async fn copy_object_with_options(&self, src_bucket: String, src_object: String, dest_bucket: String, dest_object: String, options: CopyOptions) -> ReqRes<impl ClientObject> {
+        self.inject("copy_object_with_options").await?;
+        self.inner.copy_object_with_options(src_bucket, src_object, dest_bucket, dest_object, options).await
+    }
+
+    async fn restore_object(&self, bucket_name: String, object_name: String, days: u32, tier: RetrievalTier) -> EmptyReqRes {
+        self.inject("restore_object").await?;
+        self.inner.restore_object(bucket_name, object_name, days, tier).await
+    }
+
+    async fn restore_status(&self, bucket_name: String, object_name: String) -> ReqRes<RestoreStatus> {
+        self.inject("restore_status").await?;
+        self.inner.restore_status(bucket_name, object_name).await
+    }
+
+    async fn update_object_metadata(&self, bucket: String, object_id: String, changes: MetadataChanges) -> ReqRes<impl ClientObject> {
+        self.inject("update_object_metadata").await?;
+        self.inner.update_object_metadata(bucket, object_id, changes).await
+    }
+
+    async fn get_object_tags(&self, bucket: String, object_id: String) -> ReqRes<HashMap<String, String>> {
+        self.inject("get_object_tags").await?;
+        self.inner.get_object_tags(bucket, object_id).await
+    }
+
+    async fn set_object_tags(&self, bucket: String, object_id: String, tags: HashMap<String, String>) -> EmptyReqRes {
+        self.inject("set_object_tags").await?;
+        self.inner.set_object_tags(bucket, object_id, tags).await
+    }
+
+    async fn get_object_acl(&self, bucket: String, object_id: String) -> ReqRes<Vec<AclGrant>> {
+        self.inject("get_object_acl").await?;
+        self.inner.get_object_acl(bucket, object_id).await
+    }
+
+    async fn set_object_acl(&self, bucket: String, object_id: String, acl: AccessControl) -> EmptyReqRes {
+        self.inject("set_object_acl").await?;
+        self.inner.set_object_acl(bucket, object_id, acl).await
+    }
+
+    async fn append(&self, bucket: String, object_id: String, data: Bytes) -> EmptyReqRes {
+        self.inject("append").await?;
+        self.inner.append(bucket, object_id, data).await
+    }
+
+    async fn upload_public(&self, bucket: String, object_id: String, data: Bytes, content_type: Option<String>, cache_control: String) -> EmptyReqRes {
+        self.inject("upload_public").await?;
+        self.inner.upload_public(bucket, object_id, data, content_type, cache_control).await
+    }
+
+    /// Does not send a request, so no faults are injected
+    fn public_url(&self, bucket: &str, object_id: &str) -> String {
+        self.inner.public_url(bucket, object_id)
+    }
+
+    async fn list_buckets(&self, max_results: Option<u32>, page_token: Option<Cursor>) -> ReqRes<ListPage<impl ClientBucket>> {
+        self.inject("list_buckets").await?;
+        self.inner.list_buckets(max_results, page_token).await
+    }
+
+    async fn get_bucket(&self, bucket_name: String) -> ReqRes<impl ClientBucket> {
+        self.inject("get_bucket").await?;
+        self.inner.get_bucket(bucket_name).await
+    }
+
+    async fn get_object(&self, bucket_name: String, object_name: String) -> ReqRes<impl ClientObject> {
+        self.inject("get_object").await?;
+        self.inner.get_object(bucket_name, object_name).await
+    }
+
+    async fn stat_object(&self, bucket_name: String, object_name: String) -> ReqRes<impl ClientObject> {
+        self.inject("stat_object").await?;
+        self.inner.stat_object(bucket_name, object_name).await
+    }
+
+    async fn list_objects(&self, bucket_name: String, max_results: Option<u32>, page_token: Option<Cursor>) -> ReqRes<ListPage<impl ClientObject>> {
+        self.inject("list_objects").await?;
+        self.inner.list_objects(bucket_name, max_results, page_token).await
+    }
+
+    async fn list_prefix(&self, bucket_name: String, prefix: String, page_token: Option<Cursor>) -> ReqRes<ListPage<impl ClientObject>> {
+        self.inject("list_prefix").await?;
+        self.inner.list_prefix(bucket_name, prefix, page_token).await
+    }
+
+    async fn list_projected(&self, bucket_name: String, prefix: String, attributes: ListAttributes, page_token: Option<Cursor>) -> ReqRes<ListPage<impl ClientObject>> {
+        self.inject("list_projected").await?;
+        self.inner.list_projected(bucket_name, prefix, attributes, page_token).await
+    }
+
+    async fn list_delimited(&self, bucket_name: String, prefix: String, delimiter: String, page_token: Option<Cursor>) -> ReqRes<ListPage<impl ClientObject>> {
+        self.inject("list_delimited").await?;
+        self.inner.list_delimited(bucket_name, prefix, delimiter, page_token).await
+    }
+
+    async fn list_after(&self, bucket_name: String, prefix: String, start_after: String, max_results: u32) -> ReqRes<Vec<impl ClientObject>> {
+        self.inject("list_after").await?;
+        self.inner.list_after(bucket_name, prefix, start_after, max_results).await
+    }
+
+    async fn list_versions(&self, bucket_name: String, prefix: String, page_token: Option<Cursor>) -> ReqRes<ListPage<ObjectVersion>> {
+        self.inject("list_versions").await?;
+        self.inner.list_versions(bucket_name, prefix, page_token).await
+    }
+
+    async fn restore_object_version(&self, bucket_name: String, object_name: String, version: String) -> ReqRes<impl ClientObject> {
+        self.inject("restore_object_version").await?;
+        self.inner.restore_object_version(bucket_name, object_name, version).await
+    }
+
+    async fn remove_objects(&self, bucket_name: String, keys: Vec<String>) -> ReqRes<Vec<(String, EmptyReqRes)>> {
+        self.inject("remove_objects").await?;
+        self.inner.remove_objects(bucket_name, keys).await
+    }
+
+    async fn compliance_records(&self, bucket_name: String, page_token: Option<Cursor>) -> ReqRes<ListPage<ComplianceRecord>> {
+        self.inject("compliance_records").await?;
+        self.inner.compliance_records(bucket_name, page_token).await
+    }
+
+    async fn get_bucket_spec(&self, bucket_name: String) -> ReqRes<BucketSpec> {
+        self.inject("get_bucket_spec").await?;
+        self.inner.get_bucket_spec(bucket_name).await
+    }
+
+    async fn get_lifecycle_rules(&self, bucket_name: String) -> ReqRes<Vec<LifecycleRule>> {
+        self.inject("get_lifecycle_rules").await?;
+        self.inner.get_lifecycle_rules(bucket_name).await
+    }
+
+    async fn set_lifecycle_rules(&self, bucket_name: String, rules: Vec<LifecycleRule>) -> EmptyReqRes {
+        self.inject("set_lifecycle_rules").await?;
+        self.inner.set_lifecycle_rules(bucket_name, rules).await
+    }
+
+    async fn get_object_lock(&self, bucket_name: String, object_name: String) -> ReqRes<ObjectLock> {
+        self.inject("get_object_lock").await?;
+        self.inner.get_object_lock(bucket_name, object_name).await
+    }
+
+    async fn set_object_retention(&self, bucket_name: String, object_name: String, retention: Option<Retention>) -> EmptyReqRes {
+        self.inject("set_object_retention").await?;
+        self.inner.set_object_retention(bucket_name, object_name, retention).await
+    }
+
+    async fn set_legal_hold(&self, bucket_name: String, object_name: String, on: bool) -> EmptyReqRes {
+        self.inject("set_legal_hold").await?;
+        self.inner.set_legal_hold(bucket_name, object_name, on).await
+    }
+
+    async fn get_bucket_labels(&self, bucket_name: String) -> ReqRes<HashMap<String, String>> {
+        self.inject("get_bucket_labels").await?;
+        self.inner.get_bucket_labels(bucket_name).await
+    }
+
+    async fn set_bucket_labels(&self, bucket_name: String, labels: HashMap<String, String>) -> EmptyReqRes {
+        self.inject("set_bucket_labels").await?;
+        self.inner.set_bucket_labels(bucket_name, labels).await
+    }
+
+    async fn get_bucket_policy(&self, bucket_name: String) -> ReqRes<Option<BucketPolicy>> {
+        self.inject("get_bucket_policy").await?;
+        self.inner.get_bucket_policy(bucket_name).await
+    }
+
+    async fn set_bucket_policy(&self, bucket_name: String, policy: BucketPolicy) -> EmptyReqRes {
+        self.inject("set_bucket_policy").await?;
+        self.inner.set_bucket_policy(bucket_name, policy).await
+    }
+
+    fn bucket_name_problems(&self, bucket_name: &str) -> Vec<BucketNameProblem> {
+        self.inner.bucket_name_problems(bucket_name)
+    }
+}
@@ -0,0 +1,59 @@
+use std::time::SystemTime;
+use regex::Regex;
+use crate::{ClientInterface, ClientObject, ReqRes};
+
+/// Conditions objects found by `ClientInterface::find` have to meet (unset conditions match everything)
+#[derive(Debug, Clone, Default)]
+pub struct Filter {
+    /// Only objects below this prefix are listed
+    pub prefix: Option<String>,
+    /// Minimum size in bytes (inclusive)
+    pub min_size: Option<u64>,
+    /// Maximum size in bytes (inclusive)
+    pub max_size: Option<u64>,
+    /// Objects must have been modified at or after this time
+    pub modified_after: Option<SystemTime>,
+    /// Objects must have been modified before this time
+    pub modified_before: Option<SystemTime>,
+    /// Content types must start with this (example: 'image/' or 'application/json')
+    pub content_type: Option<String>,
+    /// Object names must match this regex
+    pub name: Option<Regex>,
+}
+
+impl Filter {
+    pub fn matches(&self, object: &impl ClientObject) -> bool {
+        self.matches_listed(object) && self.matches_content_type(object.content_type())
+    }
+
+    /// Checks every condition except the content type
+    fn matches_listed(&self, object: &impl ClientObject) -> bool {
+        let size = object.size();
+        let modified = object.last_modified();
+        self.min_size.is_none_or(|t| size >= t)
+            && self.max_size.is_none_or(|t| size <= t)
+            && self.modified_after.is_none_or(|t| modified.is_some_and(|m| m >= t))
+            && self.modified_before.is_none_or(|t| modified.is_some_and(|m| m < t))
+            && self.name.as_ref().is_none_or(|t| t.is_match(&object.name()))
+    }
+
+    fn matches_content_type(&self, content_type: Option<String>) -> bool {
+        self.content_type.as_ref().is_none_or(|t| content_type.is_some_and(|c| c.starts_with(t.as_str())))
+    }
+}
+
+/// Checks a listed object, its metadata is fetched if the listing lacks the content type (AWS-S3)
+/// None if the object does not match
+pub(crate) async fn check<C: ClientInterface + ?Sized, T: ClientObject>(client: &C, bucket: String, filter: &Filter, object: T) -> Option<ReqRes<T>> {
+    if !filter.matches_listed(&object) {
+        return None
+    }
+    let content_type = match object.content_type() {
+        None if filter.content_type.is_some() => match client.stat_object(bucket, object.name()).await {
+            Ok(stat) => stat.content_type(),
+            Err(e) => return Some(Err(e))
+        },
+        content_type => content_type
+    };
+    filter.matches_content_type(content_type).then_some(Ok(object))
+}
@@ -0,0 +1,143 @@
+use std::future::Future;
+use std::time::{Duration, SystemTime};
+use futures::{stream, StreamExt};
+use crate::aws_s3::AWSClient;
+use crate::{ClientError, ClientInterface, EmptyReqRes, RestoreStatus, RetrievalTier, CONCURRENCY};
+
+/// An archived object to retrieve
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArchivedObject {
+    pub key: String,
+    pub size: u64,
+    pub tier: RetrievalTier,
+}
+
+/// Restores which are submitted together, all of the same tier
+#[derive(Debug, Clone, PartialEq)]
+pub struct RestoreWave {
+    pub tier: RetrievalTier,
+    pub objects: Vec<ArchivedObject>,
+}
+
+impl RestoreWave {
+    pub fn bytes(&self) -> u64 {
+        self.objects.iter().map(|t| t.size).sum()
+    }
+}
+
+/// Result of `RestorePlanner::run`
+#[derive(Default)]
+pub struct RestoreReport {
+    /// Objects which were restored and downloaded
+    pub downloaded: Vec<String>,
+    /// Objects whose restore or download failed
+    pub failed: Vec<(String, ClientError)>,
+    /// Objects whose restored copy expired (or vanished) before they were downloaded
+    pub expired: Vec<String>,
+}
+
+/// Retrieves many archived objects: restores are grouped by tier and submitted in waves within a budget,
+/// objects are downloaded as soon as they are restored (those expiring first, first)
+///
+/// The next wave is only submitted once every object of the previous one was handled,
+/// so no more than one wave of restored copies is waiting (and billed) at a time.
+pub struct RestorePlanner {
+    bucket: String,
+    /// Days a restored copy stays readable
+    days: u32,
+    /// Budget of a single wave
+    max_wave_bytes: u64,
+    max_wave_objects: usize,
+    /// Delay between checks of the restore status
+    poll_interval: Duration,
+}
+
+impl RestorePlanner {
+    /// Waves are limited to 1 TiB and 1000 objects by default, the status is checked every 15 minutes
+    pub fn new(bucket: String, days: u32) -> Self {
+        Self { bucket, days, max_wave_bytes: 1 << 40, max_wave_objects: 1000, poll_interval: Duration::from_secs(15 * 60) }
+    }
+
+    pub fn with_wave_budget(mut self, max_bytes: u64, max_objects: usize) -> Self {
+        self.max_wave_bytes = max_bytes;
+        self.max_wave_objects = max_objects.max(1);
+        self
+    }
+
+    pub fn with_poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Groups the objects by tier (fastest first) and splits every group into waves within the budget
+    /// Objects larger than the byte budget get a wave of their own
+    pub fn plan(&self, mut objects: Vec<ArchivedObject>) -> Vec<RestoreWave> {
+        objects.sort_by(|a, b| a.tier.cmp(&b.tier).then_with(|| a.key.cmp(&b.key)));
+        let mut waves: Vec<RestoreWave> = vec![];
+        for object in objects {
+            match waves.last_mut() {
+                Some(wave) if wave.tier == object.tier && wave.objects.len() < self.max_wave_objects
+                    && wave.bytes() + object.size <= self.max_wave_bytes => wave.objects.push(object),
+                _ => waves.push(RestoreWave { tier: object.tier, objects: vec![object] })
+            }
+        }
+        waves
+    }
+
+    /// Submits the waves one after another and passes every restored object (with the expiry of its copy) to `download`
+    pub async fn run<F, Fut>(&self, client: &AWSClient, waves: Vec<RestoreWave>, download: F) -> RestoreReport
+    where F: Fn(String, Option<SystemTime>) -> Fut, Fut: Future<Output = EmptyReqRes> {
+        let mut report = RestoreReport::default();
+        let (bucket, download) = (&self.bucket, &download);
+        for wave in waves {
+            let tier = wave.tier;
+            let submitted: Vec<_> = stream::iter(wave.objects).map(|object| async move {
+                let result = client.restore_object(bucket.clone(), object.key.clone(), self.days, tier).await;
+                (object.key, result)
+            }).buffer_unordered(CONCURRENCY).collect().await;
+            let mut pending = vec![];
+            for (key, result) in submitted {
+                match result {
+                    Ok(()) => pending.push(key),
+                    Err(e) => report.failed.push((key, e))
+                }
+            }
+            while !pending.is_empty() {
+                let statuses: Vec<_> = stream::iter(pending).map(|key| async move {
+                    let status = client.restore_status(bucket.clone(), key.clone()).await;
+                    (key, status)
+                }).buffer_unordered(CONCURRENCY).collect().await;
+                pending = vec![];
+                let mut restored = vec![];
+                for (key, status) in statuses {
+                    match status {
+                        Ok(RestoreStatus::InProgress) => pending.push(key),
+                        Ok(RestoreStatus::Restored { expires }) => restored.push((key, expires)),
+                        Ok(RestoreStatus::NotRequested) => report.expired.push(key),
+                        Err(e) => report.failed.push((key, e))
+                    }
+                }
+                // Copies expiring first are downloaded first, copies without a known expiry last
+                restored.sort_by_key(|(_, expires)| (expires.is_none(), *expires));
+                let downloaded: Vec<_> = stream::iter(restored).map(|(key, expires)| async move {
+                    if expires.is_some_and(|t| t <= SystemTime::now()) {
+                        return (key, None)
+                    }
+                    let result = download(key.clone(), expires).await;
+                    (key, Some(result))
+                }).buffered(CONCURRENCY).collect().await;
+                for (key, result) in downloaded {
+                    match result {
+                        Some(Ok(())) => report.downloaded.push(key),
+                        Some(Err(e)) => report.failed.push((key, e)),
+                        None => report.expired.push(key)
+                    }
+                }
+                if !pending.is_empty() {
+                    tokio::time::sleep(self.poll_interval).await;
+                }
+            }
+        }
+        report
+    }
+}
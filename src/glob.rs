@@ -0,0 +1,173 @@
+/// Part of a glob pattern before its first wildcard, which can be listed as a prefix server-side
+pub fn glob_prefix(pattern: &str) -> String {
+    let mut prefix = String::new();
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' | '?' | '[' => break,
+            '\\' => match chars.next() {
+                Some(c) => prefix.push(c),
+                None => break
+            },
+            c => prefix.push(c)
+        }
+    }
+    prefix
+}
+
+/// Matches an object name against a glob pattern
+/// `*` matches any characters except '/', `**` also matches '/' (and `**/` zero or more directories),
+/// `?` matches one character except '/', `[abc]`, `[a-z]` and `[!abc]` match character classes and `\` escapes
+pub fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    matches(&tokens(&pattern), &name)
+}
+
+enum Token<'a> {
+    Literal(char),
+    /// `?`
+    Any,
+    /// `[...]`, without the brackets
+    Class(&'a [char]),
+    /// `*`
+    Star,
+    /// `**`
+    DoubleStar,
+    /// `**/`
+    Directories,
+}
+
+fn tokens(p: &[char]) -> Vec<Token<'_>> {
+    let mut tokens = vec![];
+    let mut i = 0;
+    while i < p.len() {
+        let (token, len) = match p[i] {
+            '*' if p.get(i + 1) == Some(&'*') && p.get(i + 2) == Some(&'/') => (Token::Directories, 3),
+            '*' if p.get(i + 1) == Some(&'*') => (Token::DoubleStar, 2),
+            '*' => (Token::Star, 1),
+            '?' => (Token::Any, 1),
+            '[' => match class_end(&p[i + 1..]) {
+                Some(end) => (Token::Class(&p[i + 1..i + end + 1]), end + 2),
+                // No closing bracket, so it is a literal '['
+                None => (Token::Literal('['), 1)
+            },
+            '\\' if i + 1 < p.len() => (Token::Literal(p[i + 1]), 2),
+            c => (Token::Literal(c), 1)
+        };
+        tokens.push(token);
+        i += len;
+    }
+    tokens
+}
+
+/// Dynamic programming over the tokens from the back, `next[j]` is whether the following tokens match `n[j..]`,
+/// so matching takes O(tokens * name) time however many wildcards the pattern has
+fn matches(tokens: &[Token], n: &[char]) -> bool {
+    let mut next = vec![false; n.len() + 1];
+    next[n.len()] = true;
+    for token in tokens.iter().rev() {
+        let mut current = vec![false; n.len() + 1];
+        // Whether `**/` matches a prefix of `n[j..]` which ends with a '/'
+        let mut directories = false;
+        for j in (0..=n.len()).rev() {
+            let c = n.get(j).copied();
+            current[j] = match token {
+                Token::Literal(l) => c == Some(*l) && next[j + 1],
+                Token::Any => c.is_some_and(|c| c != '/') && next[j + 1],
+                Token::Class(class) => c.is_some_and(|c| c != '/' && class_contains(class, c)) && next[j + 1],
+                Token::Star => next[j] || c.is_some_and(|c| c != '/') && current[j + 1],
+                Token::DoubleStar => next[j] || c.is_some() && current[j + 1],
+                Token::Directories => {
+                    directories |= c == Some('/') && next[j + 1];
+                    next[j] || directories
+                }
+            };
+        }
+        next = current;
+    }
+    next[0]
+}
+
+/// Index of the ']' closing a character class (`p` starts after the '[')
+fn class_end(p: &[char]) -> Option<usize> {
+    let start = if matches!(p.first(), Some('!' | '^')) { 1 } else { 0 };
+    // A ']' directly after the opening bracket is part of the class
+    p.iter().enumerate().skip(start + 1).find(|(_, c)| **c == ']').map(|(i, _)| i)
+}
+
+fn class_contains(class: &[char], c: char) -> bool {
+    let (negated, class) = match class.first() {
+        Some('!' | '^') => (true, &class[1..]),
+        _ => (false, class)
+    };
+    let mut found = false;
+    let mut i = 0;
+    while i < class.len() {
+        if class.get(i + 1) == Some(&'-') && i + 2 < class.len() {
+            found |= (class[i]..=class[i + 2]).contains(&c);
+            i += 3;
+        } else {
+            found |= class[i] == c;
+            i += 1;
+        }
+    }
+    found != negated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prefix() {
+        assert_eq!(glob_prefix("logs/2024-*.txt"), "logs/2024-");
+        assert_eq!(glob_prefix("a\\*b/*"), "a*b/");
+        assert_eq!(glob_prefix("plain"), "plain");
+    }
+
+    #[test]
+    fn wildcards() {
+        assert!(glob_match("*.txt", "a.txt"));
+        assert!(!glob_match("*.txt", "dir/a.txt"));
+        assert!(glob_match("**.txt", "dir/a.txt"));
+        assert!(glob_match("a?c", "abc"));
+        assert!(!glob_match("a?c", "a/c"));
+        assert!(glob_match("", ""));
+        assert!(!glob_match("", "a"));
+    }
+
+    #[test]
+    fn directories() {
+        assert!(glob_match("**/a.txt", "a.txt"));
+        assert!(glob_match("**/a.txt", "x/y/a.txt"));
+        assert!(glob_match("logs/**/*.gz", "logs/a.gz"));
+        assert!(glob_match("logs/**/*.gz", "logs/2024/01/a.gz"));
+        assert!(!glob_match("logs/**/*.gz", "logs/2024/a.txt"));
+        assert!(glob_match("logs/**", "logs/2024/01/a.gz"));
+    }
+
+    #[test]
+    fn classes() {
+        assert!(glob_match("[abc].txt", "b.txt"));
+        assert!(glob_match("[a-c].txt", "c.txt"));
+        assert!(!glob_match("[!a-c].txt", "c.txt"));
+        assert!(glob_match("[]]", "]"));
+        assert!(glob_match("[a", "[a"));
+        assert!(!glob_match("[/]", "/"));
+    }
+
+    #[test]
+    fn escapes() {
+        assert!(glob_match("a\\*", "a*"));
+        assert!(!glob_match("a\\*", "ab"));
+        assert!(glob_match("a\\", "a\\"));
+    }
+
+    #[test]
+    fn many_wildcards() {
+        let name = "a".repeat(64);
+        assert!(!glob_match(&format!("{}b", "*a".repeat(32)), &name));
+        assert!(!glob_match(&format!("{}b", "**a".repeat(32)), &name));
+    }
+}
@@ -1,43 +1,177 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use bytes::Bytes;
+use futures::{stream, Stream, StreamExt};
 use google_cloud_storage::client::{Client, ClientConfig};
 use google_cloud_storage::client::google_cloud_auth::credentials::CredentialsFile;
-use google_cloud_storage::http::buckets::Bucket;
+use google_cloud_storage::client::google_cloud_auth::error::Error as AuthError;
+use google_cloud_storage::http::bucket_access_controls::PredefinedBucketAcl;
+use google_cloud_storage::http::buckets::{Binding, Bucket, Cors, Lifecycle, Policy};
+use google_cloud_storage::http::buckets::get_iam_policy::GetIamPolicyRequest;
+use google_cloud_storage::http::buckets::set_iam_policy::SetIamPolicyRequest;
+use google_cloud_storage::http::buckets::lifecycle::Rule;
+use google_cloud_storage::http::buckets::lifecycle::rule::{Action, ActionType, Condition};
+use google_cloud_storage::http::buckets::patch::{BucketPatchConfig, PatchBucketRequest};
 use google_cloud_storage::http::buckets::delete::{DeleteBucketParam, DeleteBucketRequest};
 use google_cloud_storage::http::buckets::get::GetBucketRequest;
 use google_cloud_storage::http::buckets::insert::{BucketCreationConfig, InsertBucketRequest};
 use google_cloud_storage::http::buckets::list::ListBucketsRequest;
 use google_cloud_storage::http::Error;
-use google_cloud_storage::http::error::ErrorResponseItem;
-use google_cloud_storage::http::objects::copy::CopyObjectRequest;
+use google_cloud_storage::http::error::{ErrorResponse, ErrorResponseItem};
+use google_cloud_storage::http::object_access_controls::{ObjectACLRole, ObjectAccessControl, PredefinedObjectAcl, Projection};
+use google_cloud_storage::http::objects::compose::{ComposeObjectRequest, ComposingTargets};
 use google_cloud_storage::http::objects::delete::DeleteObjectRequest;
 use google_cloud_storage::http::objects::download::Range;
 use google_cloud_storage::http::objects::get::GetObjectRequest;
 use google_cloud_storage::http::objects::list::ListObjectsRequest;
-use google_cloud_storage::http::objects::Object;
+use google_cloud_storage::http::objects::patch::PatchObjectRequest;
+use google_cloud_storage::http::objects::rewrite::RewriteObjectRequest;
+use google_cloud_storage::http::objects::{Object, SourceObjects};
 use google_cloud_storage::http::objects::upload::{Media, UploadObjectRequest, UploadType};
 use google_cloud_storage::sign::{SignedURLError, SignedURLMethod, SignedURLOptions};
-use crate::{ClientBucket, ClientError, ClientInterface, ClientObject, EmptyReqRes, ReqRes};
+use http::Extensions;
+use reqwest::header::{HeaderMap, HeaderValue, RETRY_AFTER, USER_AGENT};
+use reqwest_middleware::{Middleware, Next};
+use reqwest_retry::Retryable;
+use rsa::RsaPrivateKey;
+use rsa::pkcs1v15::SigningKey;
+use rsa::pkcs8::DecodePrivateKey;
+use rsa::signature::{SignatureEncoding, Signer};
+use rsa::sha2::Sha256;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio_util::io::ReaderStream;
+use crate::checksum::{confirm, confirm_crc32c, encode_crc32c, verify_crc32c, Checksums};
+use crate::defaults::parse_retry_after;
+use crate::guess_content_type;
+use crate::mime::SNIFF_LEN;
+#[cfg(feature = "cassette")]
+use crate::{Cassette, CassetteMode, Interaction};
+use crate::{encoding, post};
+use crate::{encode_key, valid_app_id, AccessControl, AclGrant, AclPermission, BucketNameProblem, BucketPolicy, BucketSpec, ClientBucket, ClientError, ClientInterface, ClientObject, Context, CopyOptions, CorsRule, Cursor, DownloadOptions, EmptyReqRes, ErrorContext, Grantee, IamBinding, IamPolicy, ObjectLock, Retention, RetentionMode, LifecycleAction, LifecycleRule, ListAttributes, ListPage, MetadataChanges, NamingRules, ObjectAcl, ObjectVersion, PostConstraints, PresignedPost, ReqRes, ComplianceRecord, CopyProgress, Defaults, RetryPolicy, RestoreStatus, RetrievalTier, SignedUrlMethod, SignedUrlOptions, UploadOptions, WritePrecondition};
 
+#[derive(Debug)]
 pub enum GoogleCloudError {
     HttpError(Error),
     GoogleCloudStorageError(Vec<ErrorResponseItem>),
-    SignedURLError(SignedURLError)
+    SignedURLError(SignedURLError),
+    /// Credentials could not be loaded
+    AuthError(AuthError)
+}
+
+impl GoogleCloudError {
+    /// Whether the object or bucket does not exist
+    pub fn is_not_found(&self) -> bool {
+        match self {
+            GoogleCloudError::GoogleCloudStorageError(items) => items.iter().any(|t| t.reason == "notFound"),
+            _ => false
+        }
+    }
+
+    /// Whether the credentials lack the permission for the request
+    pub fn is_permission_denied(&self) -> bool {
+        match self {
+            GoogleCloudError::GoogleCloudStorageError(items) => items.iter().any(|t| matches!(t.reason.as_str(), "forbidden" | "accessDenied" | "insufficientPermissions")),
+            _ => false
+        }
+    }
+
+    pub fn is_throttled(&self) -> bool {
+        match self {
+            GoogleCloudError::GoogleCloudStorageError(items) => items.iter().any(|t| matches!(t.reason.as_str(), "rateLimitExceeded" | "userRateLimitExceeded")),
+            _ => false
+        }
+    }
+
+    /// Error of the HTTP client, if the request got no response
+    fn transport(&self) -> Option<&reqwest::Error> {
+        match self {
+            GoogleCloudError::HttpError(Error::HttpClient(e) | Error::RawResponse(e, _)) => Some(e),
+            // Errors of the middleware (retries) are type-erased
+            GoogleCloudError::HttpError(Error::HttpMiddleware(e)) => e.downcast_ref(),
+            _ => None
+        }
+    }
+
+    /// Whether the request did not complete in time
+    pub fn is_timeout(&self) -> bool {
+        self.transport().is_some_and(|e| e.is_timeout())
+    }
+
+    /// Whether the request could not be sent (connection or transport failure)
+    pub fn is_network(&self) -> bool {
+        self.transport().is_some_and(|e| !e.is_timeout() && (e.is_connect() || e.is_request()))
+    }
+
+    /// Whether Google-Cloud failed to handle the request
+    pub fn is_server_error(&self) -> bool {
+        match self {
+            GoogleCloudError::GoogleCloudStorageError(items) => items.iter().any(|t| matches!(t.reason.as_str(), "backendError" | "internalError")),
+            _ => false
+        }
+    }
+
+    /// Whether the bucket exists already (owned by anyone)
+    pub fn is_already_exists(&self) -> bool {
+        match self {
+            GoogleCloudError::GoogleCloudStorageError(items) => items.iter().any(|t| t.reason == "conflict"),
+            _ => false
+        }
+    }
+
+    /// Whether a condition (example: the generation to match) of the request does not hold
+    pub fn is_precondition_failed(&self) -> bool {
+        match self {
+            GoogleCloudError::GoogleCloudStorageError(items) => items.iter().any(|t| t.reason == "conditionNotMet"),
+            _ => false
+        }
+    }
+}
+
+impl std::fmt::Display for GoogleCloudError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GoogleCloudError::HttpError(e) => std::fmt::Display::fmt(e, f),
+            GoogleCloudError::GoogleCloudStorageError(items) => {
+                let items: Vec<String> = items.iter().map(|t| format!("{} ({})", t.message, t.reason)).collect();
+                f.write_str(&items.join("; "))
+            }
+            GoogleCloudError::SignedURLError(e) => std::fmt::Display::fmt(e, f),
+            GoogleCloudError::AuthError(e) => std::fmt::Display::fmt(e, f)
+        }
+    }
+}
+
+impl std::error::Error for GoogleCloudError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            GoogleCloudError::HttpError(e) => Some(e),
+            GoogleCloudError::SignedURLError(e) => Some(e),
+            GoogleCloudError::AuthError(e) => Some(e),
+            GoogleCloudError::GoogleCloudStorageError(_) => None
+        }
+    }
 }
 
 impl From<Error> for GoogleCloudError {
     fn from(value: Error) -> Self {
-        match value {
+        match retried(value).0 {
             Error::Response(e) => GoogleCloudError::GoogleCloudStorageError(e.errors),
-            _ => GoogleCloudError::HttpError(value)
+            value => GoogleCloudError::HttpError(value)
         }
     }
 }
 
 impl From<Error> for ClientError {
     fn from(value: Error) -> Self {
-        ClientError::GoogleCloudClient(match value {
-            Error::Response(e) => GoogleCloudError::GoogleCloudStorageError(e.errors),
-            _ => GoogleCloudError::HttpError(value)
-        })
+        let (value, attempts) = retried(value);
+        ClientError::GoogleCloudClient(value.into()).attempted(attempts)
+    }
+}
+
+impl From<AuthError> for ClientError {
+    fn from(value: AuthError) -> Self {
+        ClientError::GoogleCloudClient(GoogleCloudError::AuthError(value))
     }
 }
 
@@ -47,33 +181,106 @@ impl From<SignedURLError> for ClientError {
     }
 }
 
+/// Service account key of the credentials, used to sign POST policies locally
+struct ServiceAccountKey {
+    client_email: String,
+    private_key: String
+}
+
+impl ServiceAccountKey {
+    fn of(file: &CredentialsFile) -> Option<Self> {
+        Some(Self { client_email: file.client_email.clone()?, private_key: file.private_key.clone()? })
+    }
+}
+
 pub struct GoogleCloudConfig {
-    config: ClientConfig
+    config: ClientConfig,
+    app_id: Option<String>,
+    /// Project billed for requests to requester-pays buckets
+    user_project: Option<String>,
+    service_account: Option<ServiceAccountKey>,
+    #[cfg(feature = "cassette")]
+    cassette: Option<Cassette>
 }
 
 impl GoogleCloudConfig {
     pub fn anonymous(project_id: String) -> Self {
         Self {
-            config: (ClientConfig { project_id: Some(project_id), ..Default::default()}).anonymous()
+            config: (ClientConfig { project_id: Some(project_id), ..Default::default()}).anonymous(),
+            app_id: None,
+            user_project: None,
+            service_account: None,
+            #[cfg(feature = "cassette")]
+            cassette: None
         }
     }
 
-    pub async fn standard_auth(project_id: String) -> Self {
-        Self {
-            config: (ClientConfig { project_id: Some(project_id), ..Default::default()}).with_auth().await.unwrap()
-        }
+    /// Fails if no application default credentials are found
+    pub async fn standard_auth(project_id: String) -> ReqRes<Self> {
+        Ok(Self {
+            config: (ClientConfig { project_id: Some(project_id), ..Default::default()}).with_auth().await?,
+            app_id: None,
+            user_project: None,
+            // Only key files carry a private key (not the metadata server or user credentials)
+            service_account: CredentialsFile::new().await.ok().as_ref().and_then(ServiceAccountKey::of),
+            #[cfg(feature = "cassette")]
+            cassette: None
+        })
     }
 
-    pub async fn from_file(project_id: String, path: String) -> Self {
-        Self {
-            config: (ClientConfig { project_id: Some(project_id), ..Default::default()}).with_credentials(CredentialsFile::new_from_file(path).await.unwrap()).await.unwrap()
-        }
+    /// Fails if the credentials file can not be read or is invalid
+    pub async fn from_file(project_id: String, path: String) -> ReqRes<Self> {
+        let file = CredentialsFile::new_from_file(path).await?;
+        Self::from_credentials(project_id, file).await
     }
 
-    pub async fn from_str(project_id: String, s: &str) -> Self {
-        Self {
-            config: (ClientConfig { project_id: Some(project_id), ..Default::default()}).with_credentials(CredentialsFile::new_from_str(s).await.unwrap()).await.unwrap()
+    /// Fails if the credentials are invalid
+    pub async fn from_str(project_id: String, s: &str) -> ReqRes<Self> {
+        let file = CredentialsFile::new_from_str(s).await?;
+        Self::from_credentials(project_id, file).await
+    }
+
+    async fn from_credentials(project_id: String, file: CredentialsFile) -> ReqRes<Self> {
+        let service_account = ServiceAccountKey::of(&file);
+        Ok(Self {
+            config: (ClientConfig { project_id: Some(project_id), ..Default::default()}).with_credentials(file).await?,
+            app_id: None,
+            user_project: None,
+            service_account,
+            #[cfg(feature = "cassette")]
+            cassette: None
+        })
+    }
+
+    /// Identifies the application in the user agent and the `x-goog-api-client` header of every request (example: 'my-service')
+    /// Overrides `Defaults::user_agent_suffix`
+    pub fn with_app_id(mut self, app_id: &str) -> ReqRes<Self> {
+        if !valid_app_id(app_id) {
+            return Err(ClientError::Unsupported("application identifiers are limited to 50 letters, digits and !#$%&'*+-.^_`|~")
+                .with_context(ErrorContext::new("with_app_id", None, None)))
         }
+        self.app_id = Some(app_id.to_string());
+        Ok(self)
+    }
+
+    /// Bills requests to `project` (sent as `userProject` with every request), so requester-pays buckets are accessible
+    /// Note: Signed URLs are created locally and do not carry the project
+    pub fn with_requester_pays(mut self, project: &str) -> Self {
+        self.user_project = Some(project.to_string());
+        self
+    }
+
+    /// Sends requests to another endpoint (example: an emulator at 'http://localhost:4443')
+    pub fn with_endpoint(mut self, endpoint: &str) -> Self {
+        self.config.storage_endpoint = endpoint.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Records the storage requests to `cassette` or replays them from it (see `Cassette`)
+    #[cfg(feature = "cassette")]
+    pub fn with_cassette(mut self, cassette: Cassette) -> Self {
+        self.cassette = Some(cassette);
+        self
     }
 }
 
@@ -101,6 +308,35 @@ impl ClientObject for GoogleCloudObject {
     fn content_type(&self) -> Option<String> {
         self.object.content_type.clone()
     }
+
+    fn last_modified(&self) -> Option<SystemTime> {
+        self.object.updated.map(SystemTime::from)
+    }
+
+    fn etag(&self) -> Option<String> {
+        Some(self.object.etag.clone())
+    }
+
+    fn storage_class(&self) -> Option<String> {
+        self.object.storage_class.clone()
+    }
+
+    fn custom_metadata(&self) -> HashMap<String, String> {
+        self.object.metadata.clone().unwrap_or_default()
+    }
+
+    /// CRC32C, MD5 for objects without one
+    fn checksum(&self) -> Option<String> {
+        match (&self.object.crc32c, &self.object.md5_hash) {
+            (Some(crc32c), _) => Some(format!("crc32c:{crc32c}")),
+            (None, Some(md5)) => Some(format!("md5:{md5}")),
+            (None, None) => None
+        }
+    }
+
+    fn version(&self) -> Option<String> {
+        Some(self.object.generation.to_string())
+    }
 }
 
 impl From<Object> for GoogleCloudObject {
@@ -133,44 +369,658 @@ impl From<Bucket> for GoogleCloudBucket {
     }
 }
 
+impl From<Cors> for CorsRule {
+    fn from(value: Cors) -> Self {
+        CorsRule {
+            origins: value.origin,
+            methods: value.method,
+            response_headers: value.response_header,
+            max_age_seconds: Some(value.max_age_seconds)
+        }
+    }
+}
+
+/// Converts a GCS lifecycle rule, rules without an action are skipped
+/// Prefix conditions are not exposed by google-cloud-storage, so the converted rules have none
+fn lifecycle_rule_from(rule: Rule) -> Option<LifecycleRule> {
+    let action = rule.action?;
+    let condition = rule.condition.unwrap_or_default();
+    Some(LifecycleRule {
+        prefix: None,
+        age_days: condition.age.map(|t| t as u32),
+        action: match action.r#type {
+            ActionType::Delete => LifecycleAction::Delete,
+            ActionType::SetStorageClass => LifecycleAction::SetStorageClass(action.storage_class.unwrap_or_default()),
+            ActionType::AbortIncompleteMultipartUpload => LifecycleAction::AbortIncompleteMultipart,
+        }
+    })
+}
+
+/// Fails for rules with a prefix, google-cloud-storage cannot send prefix conditions
+fn lifecycle_rule_to(rule: LifecycleRule) -> ReqRes<Rule> {
+    if rule.prefix.is_some() {
+        return Err(ClientError::Unsupported("Google-Cloud lifecycle rules with a prefix"))
+    }
+    let (r#type, storage_class) = match rule.action {
+        LifecycleAction::Delete => (ActionType::Delete, None),
+        LifecycleAction::SetStorageClass(storage_class) => (ActionType::SetStorageClass, Some(storage_class)),
+        LifecycleAction::AbortIncompleteMultipart => (ActionType::AbortIncompleteMultipartUpload, None),
+    };
+    Ok(Rule {
+        action: Some(Action { r#type, storage_class }),
+        condition: Some(Condition {
+            age: rule.age_days.map(|t| t as i32),
+            ..Default::default()
+        }),
+    })
+}
+
+/// Checks the ETag and time conditions of a download against the object's metadata (GCS only checks generations server-side)
+fn check_conditions(options: &DownloadOptions, object: &Object) -> EmptyReqRes {
+    let matches = |t: &String| t == "*" || t.trim_matches('"') == object.etag;
+    if options.if_match.as_ref().is_some_and(|t| !matches(t)) {
+        return Err(ClientError::PreconditionFailed)
+    }
+    if options.if_none_match.as_ref().is_some_and(matches) {
+        return Err(ClientError::NotModified)
+    }
+    if let (Some(since), Some(updated)) = (options.if_modified_since, object.updated) {
+        // HTTP dates have a resolution of one second
+        if !SystemTime::from(updated).duration_since(since).is_ok_and(|t| t.as_secs() > 0) {
+            return Err(ClientError::NotModified)
+        }
+    }
+    Ok(())
+}
+
+impl From<ObjectAcl> for PredefinedObjectAcl {
+    fn from(value: ObjectAcl) -> Self {
+        match value {
+            ObjectAcl::Private => PredefinedObjectAcl::Private,
+            ObjectAcl::PublicRead => PredefinedObjectAcl::PublicRead,
+            ObjectAcl::AuthenticatedRead => PredefinedObjectAcl::AuthenticatedRead,
+            ObjectAcl::BucketOwnerRead => PredefinedObjectAcl::BucketOwnerRead,
+            ObjectAcl::BucketOwnerFullControl => PredefinedObjectAcl::BucketOwnerFullControl,
+        }
+    }
+}
+
+/// Canned ACL of an object patch, google-cloud-storage types it as a bucket ACL although GCS reads it as an object ACL
+/// Only the ACLs which are sent with the same name are supported
+fn patch_acl(acl: ObjectAcl) -> ReqRes<PredefinedBucketAcl> {
+    match acl {
+        ObjectAcl::Private => Ok(PredefinedBucketAcl::Private),
+        ObjectAcl::PublicRead => Ok(PredefinedBucketAcl::PublicRead),
+        ObjectAcl::AuthenticatedRead => Ok(PredefinedBucketAcl::AuthenticatedRead),
+        ObjectAcl::BucketOwnerRead | ObjectAcl::BucketOwnerFullControl => Err(ClientError::Unsupported("bucket owner ACLs cannot be set on existing Google-Cloud objects")),
+    }
+}
+
+/// E-mail addresses of users are reported as such, other entities (groups, domains, project teams) by their name
+fn grant_from(acl: ObjectAccessControl) -> AclGrant {
+    let grantee = match acl.entity.as_str() {
+        "allUsers" => Grantee::AllUsers,
+        "allAuthenticatedUsers" => Grantee::AuthenticatedUsers,
+        entity => match entity.strip_prefix("user-") {
+            Some(email) if email.contains('@') => Grantee::Email(email.to_string()),
+            _ => Grantee::Id(acl.entity)
+        }
+    };
+    let permission = match acl.role {
+        ObjectACLRole::OWNER => AclPermission::FullControl,
+        ObjectACLRole::READER => AclPermission::Read,
+    };
+    AclGrant { grantee, permission }
+}
+
+fn grant_to(grant: AclGrant) -> ObjectAccessControl {
+    let entity = match grant.grantee {
+        Grantee::AllUsers => "allUsers".to_string(),
+        Grantee::AuthenticatedUsers => "allAuthenticatedUsers".to_string(),
+        Grantee::Email(email) => format!("user-{email}"),
+        Grantee::Id(entity) => entity,
+    };
+    let role = match grant.permission {
+        AclPermission::Read => ObjectACLRole::READER,
+        AclPermission::FullControl => ObjectACLRole::OWNER,
+    };
+    ObjectAccessControl { entity, role, ..Default::default() }
+}
+
+impl From<&Bucket> for BucketSpec {
+    fn from(value: &Bucket) -> Self {
+        BucketSpec {
+            versioning: Some(value.versioning.as_ref().map(|t| t.enabled).unwrap_or(false)),
+            lifecycle_rules: Some(value.lifecycle.clone().map(|t| t.rule.into_iter().filter_map(lifecycle_rule_from).collect()).unwrap_or_default()),
+            cors: Some(value.cors.clone().unwrap_or_default().into_iter().map(|t| t.into()).collect()),
+            encryption: Some(value.encryption.as_ref().map(|t| t.default_kms_key_name.clone()).unwrap_or_else(|| "google-managed".to_string())),
+        }
+    }
+}
+
+/// Identifies this library in the user agent
+const CLIENT_ID: &str = concat!("uni-stg/", env!("CARGO_PKG_VERSION"));
+
+/// Prefix of the custom metadata keys which hold object tags
+const TAG_PREFIX: &str = "tag-";
+
+/// Retries transient failures with the delays of `RetryPolicy::delay`, which honor Retry-After headers
+struct Retry(RetryPolicy);
+
+#[async_trait::async_trait]
+impl Middleware for Retry {
+    async fn handle(&self, req: reqwest::Request, extensions: &mut Extensions, next: Next<'_>) -> reqwest_middleware::Result<reqwest::Response> {
+        let mut attempt = 1;
+        loop {
+            // Requests with a streamed body can not be repeated
+            let Some(request) = req.try_clone() else {
+                return next.run(req, extensions).await
+            };
+            let result = next.clone().run(request, extensions).await;
+            let transient = matches!(Retryable::from_reqwest_response(&result), Some(Retryable::Transient));
+            if transient && attempt > 1 && attempt >= self.0.max_attempts {
+                return Err(reqwest_middleware::Error::middleware(Exhausted::of(attempt, result).await))
+            }
+            if attempt >= self.0.max_attempts || !transient {
+                return result
+            }
+            let retry_after = result.as_ref().ok().and_then(|t| t.headers().get(RETRY_AFTER)).and_then(|t| t.to_str().ok()).and_then(parse_retry_after);
+            tokio::time::sleep(self.0.delay(attempt, retry_after)).await;
+            attempt += 1;
+        }
+    }
+}
+
+/// Failure of the last attempt of a retried request, returned by `Retry` so the attempts reach `ErrorContext::attempt`
+/// `retried` turns it back into the error the request would have failed with otherwise
+#[derive(Debug)]
+struct Exhausted {
+    attempts: u32,
+    failure: Failure,
+}
+
+#[derive(Debug)]
+enum Failure {
+    Response(ErrorResponse),
+    /// Error responses which are not JSON, e.g. of a proxy
+    Status(reqwest::StatusCode, String),
+    Transport(reqwest_middleware::Error),
+}
+
+/// Body of JSON error responses
+#[derive(serde::Deserialize)]
+struct ErrorBody {
+    error: ErrorResponse,
+}
+
+impl Exhausted {
+    async fn of(attempts: u32, result: reqwest_middleware::Result<reqwest::Response>) -> Self {
+        let failure = match result {
+            Ok(response) => {
+                let status = response.status();
+                let body = response.bytes().await.unwrap_or_default();
+                match serde_json::from_slice::<ErrorBody>(&body) {
+                    Ok(body) => Failure::Response(body.error),
+                    Err(_) => Failure::Status(status, String::from_utf8_lossy(&body).into_owned())
+                }
+            }
+            Err(e) => Failure::Transport(e)
+        };
+        Self { attempts, failure }
+    }
+}
+
+impl std::fmt::Display for Exhausted {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.failure {
+            Failure::Response(e) => std::fmt::Display::fmt(e, f),
+            Failure::Status(status, body) => write!(f, "{status}: {body}"),
+            Failure::Transport(e) => std::fmt::Display::fmt(e, f)
+        }
+    }
+}
+
+impl std::error::Error for Exhausted {}
+
+/// Unwraps an `Exhausted` error, returns the error with the number of attempts
+fn retried(value: Error) -> (Error, u32) {
+    let e = match value {
+        Error::HttpMiddleware(e) => e,
+        value => return (value, 1)
+    };
+    match e.downcast::<Exhausted>() {
+        Ok(Exhausted { attempts, failure: Failure::Response(e) }) => (Error::Response(e), attempts),
+        Ok(Exhausted { attempts, failure: Failure::Transport(e) }) => (e.into(), attempts),
+        Ok(exhausted) => {
+            let attempts = exhausted.attempts;
+            (reqwest_middleware::Error::middleware(exhausted).into(), attempts)
+        }
+        Err(e) => (Error::HttpMiddleware(e), 1)
+    }
+}
+
+/// Partial responses of projected listings, google-cloud-storage cannot parse objects without these fields
+const NAME_FIELDS: &str = "nextPageToken,items(name,bucket,id,generation,metageneration,selfLink,mediaLink)";
+const SIZE_FIELDS: &str = "nextPageToken,items(name,bucket,id,generation,metageneration,selfLink,mediaLink,size)";
+
+tokio::task_local! {
+    /// Fields the requests of the current task are restricted to (see `list_projected`)
+    static FIELDS: &'static str;
+}
+
+/// Adds the `fields` parameter to requests sent while `FIELDS` is set
+struct Fields;
+
+#[async_trait::async_trait]
+impl Middleware for Fields {
+    async fn handle(&self, mut req: reqwest::Request, extensions: &mut Extensions, next: Next<'_>) -> reqwest_middleware::Result<reqwest::Response> {
+        if let Ok(fields) = FIELDS.try_with(|t| *t) {
+            req.url_mut().query_pairs_mut().append_pair("fields", fields);
+        }
+        next.run(req, extensions).await
+    }
+}
+
+/// Records responses to a cassette or answers requests from it, outside of the retries so only final responses are kept
+#[cfg(feature = "cassette")]
+struct Recorder(Cassette);
+
+#[cfg(feature = "cassette")]
+#[async_trait::async_trait]
+impl Middleware for Recorder {
+    async fn handle(&self, req: reqwest::Request, extensions: &mut Extensions, next: Next<'_>) -> reqwest_middleware::Result<reqwest::Response> {
+        let method = req.method().to_string();
+        let url = req.url().to_string();
+        let (status, headers, body) = match self.0.mode() {
+            CassetteMode::Replay => {
+                let interaction = self.0.take(&method, &url).ok_or_else(|| reqwest_middleware::Error::middleware(
+                    std::io::Error::new(std::io::ErrorKind::NotFound, format!("no recorded response for {method} {url}"))))?;
+                let body = interaction.body();
+                (interaction.status, interaction.headers, body)
+            }
+            CassetteMode::Record => {
+                let response = next.run(req, extensions).await?;
+                let status = response.status().as_u16();
+                let headers: Vec<(String, String)> = response.headers().iter()
+                    .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string()))).collect();
+                let body = response.bytes().await?;
+                self.0.push(Interaction::new(method, &url, status, headers.clone(), &body));
+                (status, headers, body)
+            }
+        };
+        let mut response = http::Response::builder().status(status);
+        for (name, value) in headers {
+            response = response.header(name, value);
+        }
+        let response = response.body(body).map_err(reqwest_middleware::Error::middleware)?;
+        Ok(reqwest::Response::from(response))
+    }
+}
+
+/// Client for Google-Cloud-Storage, it is `Send + Sync` and cheap to clone
+/// Clones share the connection pool, credentials and settings, so one configured client can serve all request handlers.
+#[derive(Clone)]
 pub struct GoogleCloud {
     client: Client,
-    project_id: String
+    project_id: String,
+    /// See `Defaults::strict_checksums`
+    strict_checksums: bool,
+    /// See `Defaults::detect_content_type`
+    detect_content_type: bool,
+    service_account: Option<Arc<ServiceAccountKey>>
 }
 
 impl GoogleCloud {
+    /// Inherits the process-wide `Defaults`
     pub fn new(config: GoogleCloudConfig) -> Self {
-        let project_id = config.config.project_id.clone().unwrap();
+        Self::with_defaults(config, Defaults::get())
+    }
+
+    /// Uses `defaults` instead of the process-wide ones
+    pub fn with_defaults(mut config: GoogleCloudConfig, defaults: Defaults) -> Self {
+        let project_id = config.config.project_id.clone().unwrap_or_default();
+        let ident = match config.app_id.take().or(defaults.user_agent_suffix) {
+            Some(app_id) => format!("{CLIENT_ID} {app_id}"),
+            None => CLIENT_ID.to_string()
+        };
+        let mut headers = HeaderMap::new();
+        if let Ok(ident) = HeaderValue::from_str(&ident) {
+            headers.insert(USER_AGENT, ident.clone());
+            headers.insert("x-goog-api-client", ident);
+        }
+        let mut http = reqwest::Client::builder().default_headers(headers);
+        if let Some(timeout) = defaults.connect_timeout {
+            http = http.connect_timeout(timeout);
+        }
+        if let Some(timeout) = defaults.operation_timeout {
+            http = http.timeout(timeout);
+        }
+        // Building only fails if the TLS backend cannot be initialized, the library's own client is kept then
+        if let Ok(http) = http.build() {
+            // Outermost, so cassettes record the complete URL
+            let http = reqwest_middleware::ClientBuilder::new(http).with(Fields);
+            #[cfg(feature = "cassette")]
+            let http = match config.cassette.take() {
+                Some(cassette) => http.with(Recorder(cassette)),
+                None => http
+            };
+            let mut http = http.with(Retry(defaults.retry));
+            if let Some(project) = config.user_project.take() {
+                http = http.with_init(move |req: reqwest_middleware::RequestBuilder| req.query(&[("userProject", project.as_str())]));
+            }
+            config.config.http = Some(http.build());
+        }
         let client = Client::new(config.config);
-        Self { client, project_id }
+        Self { client, project_id, strict_checksums: defaults.strict_checksums, detect_content_type: defaults.detect_content_type, service_account: config.service_account.map(Arc::new) }
+    }
+
+    /// Adds the checksums of `data` to the metadata of an upload in strict mode, Google-Cloud rejects data which does not match them
+    fn checksummed(&self, mut metadata: Object, data: &[u8]) -> (Object, Option<Checksums>) {
+        if !self.strict_checksums {
+            return (metadata, None)
+        }
+        let checksums = Checksums::of(data);
+        metadata.md5_hash = Some(checksums.md5.clone());
+        metadata.crc32c = Some(checksums.crc32c.clone());
+        (metadata, Some(checksums))
+    }
+
+    /// Content-Type guessed for an upload without one (see `Defaults::detect_content_type`)
+    fn guessed_content_type(&self, key: &str, data: &[u8]) -> Option<String> {
+        self.detect_content_type.then(|| guess_content_type(key, data)).flatten()
+    }
+
+    /// Sends rewrite requests until the rewrite is done
+    async fn rewrite(&self, mut req: RewriteObjectRequest, operation: &'static str) -> ReqRes<GoogleCloudObject> {
+        loop {
+            let res = self.client.rewrite_object(&req).await.context(operation, &req.source_bucket, Some(&req.source_object))?;
+            if res.done {
+                if let Some(object) = res.resource {
+                    return Ok(object.into())
+                }
+                return self.get_object(req.destination_bucket, req.destination_object).await
+            }
+            req.rewrite_token = res.rewrite_token;
+        }
+    }
+
+    /// Reads the IAM policy of a bucket (see `get_bucket_policy`)
+    pub async fn get_iam_policy(&self, bucket_name: String) -> ReqRes<IamPolicy> {
+        let req = GetIamPolicyRequest {
+            resource: bucket_name,
+            ..Default::default()
+        };
+        let policy = self.client.get_iam_policy(&req).await.context("get_iam_policy", &req.resource, None)?;
+        Ok(IamPolicy {
+            bindings: policy.bindings.into_iter().map(|t| IamBinding { role: t.role, members: t.members }).collect(),
+            etag: policy.etag,
+        })
+    }
+
+    /// Replaces the IAM policy of a bucket, fails with `ClientError::PreconditionFailed`
+    /// if the policy changed since `policy` was read (unless its ETag is empty)
+    /// Note: Conditional bindings are not supported and are removed
+    pub async fn set_iam_policy(&self, bucket_name: String, policy: IamPolicy) -> EmptyReqRes {
+        let req = SetIamPolicyRequest {
+            resource: bucket_name,
+            policy: Policy {
+                bindings: policy.bindings.into_iter().map(|t| Binding { role: t.role, members: t.members, condition: None }).collect(),
+                etag: policy.etag,
+                ..Default::default()
+            },
+        };
+        self.client.set_iam_policy(&req).await.context("set_iam_policy", &req.resource, None)?;
+        Ok(())
     }
 }
 
+#[allow(refining_impl_trait)]
 impl ClientInterface for GoogleCloud {
-    async fn static_download_object(&self, bucket: String, object: String, starting: Option<u64>, ending: Option<u64>) -> ReqRes<Vec<u8>> {
+    /// Strict mode verifies complete downloads (see `download_with_options`)
+    async fn static_download_bytes(&self, bucket: String, object: String, starting: Option<u64>, ending: Option<u64>) -> ReqRes<Bytes> {
+        if self.strict_checksums {
+            return self.download_with_options(bucket, object, starting, ending, DownloadOptions::default()).await
+        }
         let req = GetObjectRequest {
             bucket,
             object,
             ..Default::default()
         };
-        Ok(self.client.download_object(&req, &Range(starting, ending)).await?)
+        Ok(self.client.download_object(&req, &Range(starting, ending)).await.context("static_download_object", &req.bucket, Some(&req.object))?.into())
+    }
+
+    /// The conditions are checked against the object's metadata first, the download is then pinned to the checked generation
+    /// Strict mode verifies complete downloads against the object's CRC32C (except for gzip-encoded objects, which are decompressed)
+    async fn download_with_options(&self, bucket: String, object: String, starting: Option<u64>, ending: Option<u64>, options: DownloadOptions) -> ReqRes<Bytes> {
+        let generation = match options.version.as_deref().map(str::parse) {
+            Some(Ok(generation)) => Some(generation),
+            Some(Err(_)) => return Err(ClientError::Unsupported("Google-Cloud versions are generation numbers")
+                .with_context(ErrorContext::new("download_with_options", Some(&bucket), Some(&object)))),
+            None => None
+        };
+        let mut req = GetObjectRequest {
+            bucket,
+            object,
+            generation,
+            ..Default::default()
+        };
+        let verify = self.strict_checksums && starting.is_none() && ending.is_none();
+        let mut stored = None;
+        if options.is_conditional() || verify {
+            let metadata = self.client.get_object(&req).await.context("download_with_options", &req.bucket, Some(&req.object))?;
+            check_conditions(&options, &metadata).context("download_with_options", &req.bucket, Some(&req.object))?;
+            req.if_generation_match = Some(metadata.generation);
+            if verify && metadata.content_encoding.as_deref() != Some("gzip") {
+                stored = metadata.crc32c;
+            }
+        }
+        let data = self.client.download_object(&req, &Range(starting, ending)).await.context("download_with_options", &req.bucket, Some(&req.object))?;
+        verify_crc32c(&data, stored.as_deref()).context("download_with_options", &req.bucket, Some(&req.object))?;
+        Ok(data.into())
     }
 
-    async fn static_upload_object(&self, bucket: String, object: String, data: Vec<u8>) -> ReqRes<GoogleCloudObject> {
-        let upload_type = UploadType::Simple(Media::new(object));
+    /// Strict mode uses a multipart upload to send the checksums with the data
+    async fn static_upload_bytes(&self, bucket: String, object: String, data: Bytes) -> ReqRes<GoogleCloudObject> {
+        let content_type = self.guessed_content_type(&object, &data);
+        let (metadata, checksums) = self.checksummed(Object { name: object.clone(), content_type: content_type.clone(), ..Default::default() }, &data);
+        let upload_type = match (checksums.as_ref(), content_type) {
+            (Some(_), _) => UploadType::Multipart(Box::new(metadata)),
+            (None, Some(content_type)) => UploadType::Simple(Media { content_type: content_type.into(), ..Media::new(object.clone()) }),
+            (None, None) => UploadType::Simple(Media::new(object.clone()))
+        };
         let req = UploadObjectRequest {
             bucket,
             ..Default::default()
         };
-        Ok(self.client.upload_object(&req, data, &upload_type).await?.into())
+        let uploaded = self.client.upload_object(&req, data, &upload_type).await.context("static_upload_object", &req.bucket, Some(&object))?;
+        confirm(checksums.as_ref(), uploaded.crc32c.as_deref()).context("static_upload_object", &req.bucket, Some(&object))?;
+        Ok(uploaded.into())
     }
 
-    async fn url_upload_object(&self, bucket: String, object: String) -> ReqRes<String> {
-        Ok(self.client.signed_url(bucket.as_str(), object.as_str(), None, None, SignedURLOptions { method: SignedURLMethod::PUT, ..Default::default() }).await?)
+    async fn upload_with_options(&self, bucket: String, object: String, data: Bytes, options: UploadOptions) -> ReqRes<GoogleCloudObject> {
+        let content_type = options.content_type.or_else(|| self.guessed_content_type(&object, &data));
+        let (metadata, checksums) = self.checksummed(Object {
+            name: object.clone(),
+            content_type,
+            cache_control: options.cache_control,
+            content_encoding: options.content_encoding,
+            storage_class: options.storage_class,
+            metadata: (!options.custom_metadata.is_empty()).then_some(options.custom_metadata),
+            ..Default::default()
+        }, &data);
+        let upload_type = UploadType::Multipart(Box::new(metadata));
+        let if_generation_match = match options.precondition {
+            Some(WritePrecondition::DoesNotExist) => Some(0),
+            // Anything but a generation can never match
+            Some(WritePrecondition::Matches(generation)) => Some(generation.parse().map_err(|_| ClientError::PreconditionFailed)
+                .context("upload_with_options", &bucket, Some(&object))?),
+            None => None
+        };
+        let req = UploadObjectRequest {
+            bucket,
+            predefined_acl: options.acl.map(PredefinedObjectAcl::from),
+            if_generation_match,
+            ..Default::default()
+        };
+        let uploaded = self.client.upload_object(&req, data, &upload_type).await.context("upload_with_options", &req.bucket, Some(&object))?;
+        confirm(checksums.as_ref(), uploaded.crc32c.as_deref()).context("upload_with_options", &req.bucket, Some(&object))?;
+        Ok(uploaded.into())
     }
 
-    async fn url_download_object(&self, bucket: String, object: String) -> ReqRes<String> {
-        Ok(self.client.signed_url(bucket.as_str(), object.as_str(), None, None, SignedURLOptions::default()).await?)
+    async fn download_to_writer(&self, bucket: String, object: String, starting: Option<u64>, ending: Option<u64>, mut w: impl AsyncWrite + Unpin + Send) -> ReqRes<u64> {
+        let req = GetObjectRequest {
+            bucket,
+            object,
+            ..Default::default()
+        };
+        let context = |e: ClientError| e.with_context(ErrorContext::new("download_to_writer", Some(&req.bucket), Some(&req.object)));
+        let mut stream = self.client.download_streamed_object(&req, &Range(starting, ending)).await.map_err(|e| context(e.into()))?;
+        let mut written = 0;
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.map_err(|e| context(e.into()))?;
+            w.write_all(&chunk).await.map_err(|e| context(e.into()))?;
+            written += chunk.len() as u64;
+        }
+        w.flush().await.map_err(|e| context(e.into()))?;
+        Ok(written)
+    }
+
+    /// In strict mode the CRC32C is computed while streaming and compared with the uploaded object afterwards,
+    /// an object which does not match is removed again
+    async fn upload_from_reader(&self, bucket: String, object: String, mut r: impl AsyncRead + Unpin + Send + Sync + 'static) -> EmptyReqRes {
+        // The first bytes are read ahead to guess the content type from
+        let mut first = vec![];
+        if self.detect_content_type {
+            (&mut r).take(SNIFF_LEN as u64).read_to_end(&mut first).await.context("upload_from_reader", &bucket, Some(&object))?;
+        }
+        let upload_type = match self.guessed_content_type(&object, &first) {
+            Some(content_type) => UploadType::Simple(Media { content_type: content_type.into(), ..Media::new(object.clone()) }),
+            None => UploadType::Simple(Media::new(object.clone()))
+        };
+        let req = UploadObjectRequest {
+            bucket,
+            ..Default::default()
+        };
+        let r = std::io::Cursor::new(first).chain(r);
+        let crc = Arc::new(Mutex::new(0u32));
+        let strict = self.strict_checksums;
+        let stream = ReaderStream::new(r).inspect({
+            let crc = crc.clone();
+            move |chunk| if let (true, Ok(chunk)) = (strict, chunk) {
+                let mut crc = crc.lock().unwrap_or_else(|e| e.into_inner());
+                *crc = crc32c::crc32c_append(*crc, chunk);
+            }
+        });
+        let uploaded = self.client.upload_streamed_object(&req, stream, &upload_type).await.context("upload_from_reader", &req.bucket, Some(&object))?;
+        if strict {
+            let expected = encode_crc32c(*crc.lock().unwrap_or_else(|e| e.into_inner()));
+            if let Err(e) = confirm_crc32c(&expected, uploaded.crc32c.as_deref()) {
+                // Best effort, the mismatch is more useful to the caller
+                let _ = self.remove_object(req.bucket.clone(), object.clone()).await;
+                return Err(e.with_context(ErrorContext::new("upload_from_reader", Some(&req.bucket), Some(&object))))
+            }
+        }
+        Ok(())
+    }
+
+    /// Uploads the data to a temporary object and composes it with the existing one
+    /// Note: GCS limits composed objects to 32 components per compose call, but not the number of appends
+    async fn append(&self, bucket: String, object: String, data: Bytes) -> EmptyReqRes {
+        if !self.object_exists(bucket.clone(), object.clone()).await? {
+            self.static_upload_bytes(bucket, object, data).await?;
+            return Ok(())
+        }
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|t| t.as_nanos()).unwrap_or(0);
+        let temporary = format!("{object}.append-{nanos}");
+        self.static_upload_bytes(bucket.clone(), temporary.clone(), data).await?;
+        let req = ComposeObjectRequest {
+            bucket: bucket.clone(),
+            destination_object: object.clone(),
+            composing_targets: ComposingTargets {
+                source_objects: vec![
+                    SourceObjects { name: object.clone(), ..Default::default() },
+                    SourceObjects { name: temporary.clone(), ..Default::default() },
+                ],
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let composed = self.client.compose_object(&req).await.context("append", &bucket, Some(&object));
+        // Best effort, a leftover temporary object must not turn a completed append into a failure (a retry would append twice)
+        let _ = self.remove_object(bucket, temporary).await;
+        composed.map(|_| ())
+    }
+
+    /// Downloads are served with the Content-Type and Content-Disposition through the response-* query parameters
+    async fn signed_url(&self, bucket: String, object: String, options: SignedUrlOptions) -> ReqRes<String> {
+        let mut query_parameters = HashMap::new();
+        let mut content_type = options.content_type;
+        if options.method != SignedUrlMethod::Put && let Some(content_type) = content_type.take() {
+            query_parameters.insert("response-content-type".to_string(), vec![content_type]);
+        }
+        if let Some(disposition) = options.response_content_disposition {
+            query_parameters.insert("response-content-disposition".to_string(), vec![disposition]);
+        }
+        let opts = SignedURLOptions {
+            method: match options.method {
+                SignedUrlMethod::Get => SignedURLMethod::GET,
+                SignedUrlMethod::Put => SignedURLMethod::PUT,
+                SignedUrlMethod::Head => SignedURLMethod::HEAD,
+                SignedUrlMethod::Delete => SignedURLMethod::DELETE,
+            },
+            expires: options.expires_in,
+            content_type,
+            headers: options.extra_headers.iter().map(|(k, v)| format!("{k}:{v}")).collect(),
+            query_parameters,
+            ..Default::default()
+        };
+        self.client.signed_url(bucket.as_str(), object.as_str(), None, None, opts).await
+            .context("signed_url", &bucket, Some(&object))
+    }
+
+    /// Uses the publicRead predefined ACL, which is rejected by buckets with uniform bucket-level access
+    /// (those have to grant allUsers read access on the bucket instead)
+    async fn upload_public(&self, bucket: String, object: String, data: Bytes, content_type: Option<String>, cache_control: String) -> EmptyReqRes {
+        let (metadata, checksums) = self.checksummed(Object {
+            name: object.clone(),
+            content_type,
+            cache_control: Some(cache_control),
+            ..Default::default()
+        }, &data);
+        let upload_type = UploadType::Multipart(Box::new(metadata));
+        let req = UploadObjectRequest {
+            bucket,
+            predefined_acl: Some(PredefinedObjectAcl::PublicRead),
+            ..Default::default()
+        };
+        let uploaded = self.client.upload_object(&req, data, &upload_type).await.context("upload_public", &req.bucket, Some(&object))?;
+        confirm(checksums.as_ref(), uploaded.crc32c.as_deref()).context("upload_public", &req.bucket, Some(&object))
+    }
+
+    /// V4 POST policy, signed with the service account key of the credentials (credentials without a key are not supported)
+    async fn presigned_post(&self, bucket: String, key_prefix: String, constraints: PostConstraints) -> ReqRes<PresignedPost> {
+        let Some(account) = &self.service_account else {
+            return Err(ClientError::Unsupported("presigned POST policies require service account key credentials"))
+        };
+        let now = SystemTime::now();
+        let (timestamp, date) = post::signing_time(now);
+        let mut fields = HashMap::from([
+            ("x-goog-algorithm".to_string(), "GOOG4-RSA-SHA256".to_string()),
+            ("x-goog-credential".to_string(), format!("{}/{date}/auto/storage/goog4_request", account.client_email)),
+            ("x-goog-date".to_string(), timestamp),
+        ]);
+        let policy = post::policy(now + constraints.expires_in, post::conditions(&bucket, &key_prefix, &constraints, &fields));
+        let signature = RsaPrivateKey::from_pkcs8_pem(&account.private_key).ok()
+            .and_then(|key| SigningKey::<Sha256>::new(key).try_sign(policy.as_bytes()).ok())
+            .ok_or(ClientError::Unsupported("the service account key is not a valid RSA key"))?;
+        fields.insert("x-goog-signature".to_string(), encoding::hex(&signature.to_bytes()));
+        fields.insert("policy".to_string(), policy);
+        fields.insert("key".to_string(), format!("{key_prefix}${{filename}}"));
+        Ok(PresignedPost { url: format!("https://storage.googleapis.com/{bucket}"), fields })
+    }
+
+    fn public_url(&self, bucket: &str, object: &str) -> String {
+        format!("https://storage.googleapis.com/{bucket}/{}", encode_key(object))
     }
 
     async fn remove_bucket(&self, bucket: String) -> EmptyReqRes {
@@ -178,7 +1028,7 @@ impl ClientInterface for GoogleCloud {
             bucket,
             param: DeleteBucketParam::default()
         };
-        Ok(self.client.delete_bucket(&req).await?)
+        self.client.delete_bucket(&req).await.context("remove_bucket", &req.bucket, None)
     }
 
     async fn remove_object(&self, bucket: String, object: String) -> EmptyReqRes {
@@ -187,7 +1037,7 @@ impl ClientInterface for GoogleCloud {
             object,
             ..Default::default()
         };
-        Ok(self.client.delete_object(&req).await?)
+        self.client.delete_object(&req).await.context("remove_object", &req.bucket, Some(&req.object))
     }
 
     async fn create_bucket(&self, bucket: String) -> ReqRes<GoogleCloudBucket> {
@@ -196,27 +1046,186 @@ impl ClientInterface for GoogleCloud {
             param: Default::default(),
             bucket: BucketCreationConfig::default()
         };
-        Ok(self.client.insert_bucket(&req).await?.into())
+        Ok(self.client.insert_bucket(&req).await.context("create_bucket", &req.name, None)?.into())
     }
 
+    /// Uses rewrite requests, which (unlike copy requests) work for objects of any size, location and storage class
+    /// Large objects are copied in several requests, each continuing with the token of the previous one
     async fn copy_object(&self, src_bucket: String, src_object: String, dest_bucket: String, dest_object: String) -> ReqRes<GoogleCloudObject> {
-        let req = CopyObjectRequest {
+        let req = RewriteObjectRequest {
+            destination_bucket: dest_bucket,
+            destination_object: dest_object,
+            source_object: src_object,
+            source_bucket: src_bucket,
+            ..Default::default()
+        };
+        self.rewrite(req, "copy_object").await
+    }
+
+    /// The metadata of the source is kept, only the storage class is replaced
+    async fn copy_object_with_options(&self, src_bucket: String, src_object: String, dest_bucket: String, dest_object: String, options: CopyOptions) -> ReqRes<GoogleCloudObject> {
+        let destination_metadata = match options.storage_class {
+            Some(storage_class) => {
+                let req = GetObjectRequest {
+                    bucket: src_bucket.clone(),
+                    object: src_object.clone(),
+                    ..Default::default()
+                };
+                let mut metadata = self.client.get_object(&req).await.context("copy_object_with_options", &req.bucket, Some(&req.object))?;
+                metadata.storage_class = Some(storage_class);
+                Some(metadata)
+            }
+            None => None
+        };
+        let req = RewriteObjectRequest {
+            destination_bucket: dest_bucket,
+            destination_object: dest_object,
+            source_object: src_object,
+            source_bucket: src_bucket,
+            destination_metadata,
+            ..Default::default()
+        };
+        self.rewrite(req, "copy_object_with_options").await
+    }
+
+    /// Objects of all storage classes (including ARCHIVE) can be read without a restore, so this does nothing
+    async fn restore_object(&self, _bucket_name: String, _object_name: String, _days: u32, _tier: RetrievalTier) -> EmptyReqRes {
+        Ok(())
+    }
+
+    /// Existing objects are always readable, so they are reported as restored without expiry
+    async fn restore_status(&self, bucket_name: String, object_name: String) -> ReqRes<RestoreStatus> {
+        self.get_object(bucket_name, object_name).await?;
+        Ok(RestoreStatus::Restored { expires: None })
+    }
+
+    /// Yields the progress reported by every rewrite request
+    fn copy_object_progress(&self, src_bucket: String, src_object: String, dest_bucket: String, dest_object: String) -> impl Stream<Item = ReqRes<CopyProgress>> + Send {
+        let req = RewriteObjectRequest {
             destination_bucket: dest_bucket,
             destination_object: dest_object,
             source_object: src_object,
             source_bucket: src_bucket,
             ..Default::default()
         };
-        Ok(GoogleCloudObject::from(self.client.copy_object(&req).await?))
+        // None once the copy is finished or failed
+        stream::unfold(Some(req), move |req| async move {
+            let mut req = req?;
+            match self.client.rewrite_object(&req).await.context("copy_object_progress", &req.source_bucket, Some(&req.source_object)) {
+                Ok(res) => {
+                    let progress = CopyProgress {
+                        total_bytes: res.object_size as u64,
+                        bytes_copied: res.total_bytes_rewritten as u64,
+                        rewrite_token: res.rewrite_token.clone(),
+                        done: res.done,
+                    };
+                    req.rewrite_token = res.rewrite_token;
+                    Some((Ok(progress), (!res.done).then_some(req)))
+                }
+                Err(e) => Some((Err(e), None))
+            }
+        })
+    }
+
+    /// Patches the object with its current metadata and the changes applied, fails if the metadata changed in between
+    /// Note: Google-Cloud cannot remove custom metadata keys with a patch, removals fail with `ClientError::Unsupported`
+    async fn update_object_metadata(&self, bucket: String, object: String, changes: MetadataChanges) -> ReqRes<GoogleCloudObject> {
+        if !changes.remove_metadata.is_empty() {
+            return Err(ClientError::Unsupported("Google-Cloud cannot remove custom metadata keys with a patch")
+                .with_context(ErrorContext::new("update_object_metadata", Some(&bucket), Some(&object))))
+        }
+        let req = GetObjectRequest {
+            bucket,
+            object,
+            ..Default::default()
+        };
+        let mut metadata = self.client.get_object(&req).await.context("update_object_metadata", &req.bucket, Some(&req.object))?;
+        metadata.metadata = Some(changes.merge_custom(metadata.metadata.unwrap_or_default()));
+        metadata.content_type = changes.content_type.or(metadata.content_type);
+        metadata.cache_control = changes.cache_control.or(metadata.cache_control);
+        metadata.content_encoding = changes.content_encoding.or(metadata.content_encoding);
+        metadata.content_disposition = changes.content_disposition.or(metadata.content_disposition);
+        let req = PatchObjectRequest {
+            bucket: req.bucket,
+            object: req.object,
+            if_metageneration_match: Some(metadata.metageneration),
+            metadata: Some(metadata),
+            ..Default::default()
+        };
+        Ok(self.client.patch_object(&req).await.context("update_object_metadata", &req.bucket, Some(&req.object))?.into())
+    }
+
+    /// Note: Buckets with uniform bucket-level access have no object ACLs, the request fails
+    async fn get_object_acl(&self, bucket: String, object: String) -> ReqRes<Vec<AclGrant>> {
+        let req = GetObjectRequest {
+            bucket,
+            object,
+            projection: Some(Projection::Full),
+            ..Default::default()
+        };
+        let object = self.client.get_object(&req).await.context("get_object_acl", &req.bucket, Some(&req.object))?;
+        Ok(object.acl.unwrap_or_default().into_iter().map(grant_from).collect())
+    }
+
+    /// Patches the object's ACL, fails if the metadata changed in between
+    /// Note: Buckets with uniform bucket-level access reject object ACLs, the canned bucket owner ACLs are not supported
+    async fn set_object_acl(&self, bucket: String, object: String, acl: AccessControl) -> EmptyReqRes {
+        let req = GetObjectRequest {
+            bucket,
+            object,
+            ..Default::default()
+        };
+        let mut metadata = self.client.get_object(&req).await.context("set_object_acl", &req.bucket, Some(&req.object))?;
+        let predefined_acl = match acl {
+            AccessControl::Canned(acl) => Some(patch_acl(acl).context("set_object_acl", &req.bucket, Some(&req.object))?),
+            AccessControl::Grants(grants) => {
+                metadata.acl = Some(grants.into_iter().map(grant_to).collect());
+                None
+            }
+        };
+        let req = PatchObjectRequest {
+            bucket: req.bucket,
+            object: req.object,
+            if_metageneration_match: Some(metadata.metageneration),
+            predefined_acl,
+            metadata: Some(metadata),
+            ..Default::default()
+        };
+        self.client.patch_object(&req).await.context("set_object_acl", &req.bucket, Some(&req.object))?;
+        Ok(())
+    }
+
+    /// Tags are custom metadata whose keys start with `TAG_PREFIX`
+    async fn get_object_tags(&self, bucket: String, object: String) -> ReqRes<HashMap<String, String>> {
+        let metadata = self.get_object(bucket, object).await?.object.metadata.unwrap_or_default();
+        Ok(metadata.into_iter().filter(|(_, v)| !v.is_empty())
+            .filter_map(|(k, v)| k.strip_prefix(TAG_PREFIX).map(|k| (k.to_string(), v))).collect())
+    }
+
+    /// Note: Patches cannot delete custom metadata, removed tags are kept with an empty value (and ignored by `get_object_tags`),
+    /// so tags with an empty value cannot be stored
+    async fn set_object_tags(&self, bucket: String, object: String, tags: HashMap<String, String>) -> EmptyReqRes {
+        let current = self.get_object(bucket.clone(), object.clone()).await?.object.metadata.unwrap_or_default();
+        let mut custom_metadata: HashMap<_, _> = current.into_keys().filter(|t| t.starts_with(TAG_PREFIX)).map(|t| (t, String::new())).collect();
+        custom_metadata.extend(tags.into_iter().map(|(k, v)| (format!("{TAG_PREFIX}{k}"), v)));
+        let changes = MetadataChanges { custom_metadata, ..Default::default() };
+        self.update_object_metadata(bucket, object, changes).await?;
+        Ok(())
     }
 
-    async fn list_buckets(&self, max_results: Option<u32>) -> ReqRes<Vec<GoogleCloudBucket>> {
+    async fn list_buckets(&self, max_results: Option<u32>, page_token: Option<Cursor>) -> ReqRes<ListPage<GoogleCloudBucket>> {
         let req = ListBucketsRequest {
             project: self.project_id.clone(),
             max_results: max_results.map(|t| t as i32),
+            page_token: page_token.map(Cursor::into_token),
             ..Default::default()
         };
-        Ok(self.client.list_buckets(&req).await?.items.into_iter().map(|x| {x.into()}).collect())
+        let res = self.client.list_buckets(&req).await.map_err(|e| ClientError::from(e).with_context(ErrorContext::new("list_buckets", None, None)))?;
+        Ok(ListPage {
+            items: res.items.into_iter().map(|x| {x.into()}).collect(),
+            prefixes: vec![],
+            next_token: res.next_page_token.map(Cursor::new)
+        })
     }
 
     async fn get_bucket(&self, bucket_name: String) -> ReqRes<GoogleCloudBucket> {
@@ -224,7 +1233,7 @@ impl ClientInterface for GoogleCloud {
             bucket: bucket_name,
             ..Default::default()
         };
-        Ok(self.client.get_bucket(&req).await?.into())
+        Ok(self.client.get_bucket(&req).await.context("get_bucket", &req.bucket, None)?.into())
     }
 
     async fn get_object(&self, bucket_name: String, object_name: String) -> ReqRes<GoogleCloudObject> {
@@ -233,15 +1242,270 @@ impl ClientInterface for GoogleCloud {
             object: object_name,
             ..Default::default()
         };
-        Ok(self.client.get_object(&req).await?.into())
+        Ok(self.client.get_object(&req).await.context("get_object", &req.bucket, Some(&req.object))?.into())
+    }
+
+    /// Same as `get_object`, objects.get only returns metadata on GCS
+    async fn stat_object(&self, bucket_name: String, object_name: String) -> ReqRes<GoogleCloudObject> {
+        let req = GetObjectRequest {
+            bucket: bucket_name,
+            object: object_name,
+            ..Default::default()
+        };
+        Ok(self.client.get_object(&req).await.context("stat_object", &req.bucket, Some(&req.object))?.into())
     }
 
-    async fn list_objects(&self, bucket: String, max_results: Option<u32>) -> ReqRes<Vec<GoogleCloudObject>> {
+    async fn list_objects(&self, bucket: String, max_results: Option<u32>, page_token: Option<Cursor>) -> ReqRes<ListPage<GoogleCloudObject>> {
         let req = ListObjectsRequest {
             bucket,
             max_results: max_results.map(|t| t as i32),
+            page_token: page_token.map(Cursor::into_token),
             ..Default::default()
         };
-        Ok(self.client.list_objects(&req).await?.items.unwrap().into_iter().map(|x| {x.into()}).collect())
+        let res = self.client.list_objects(&req).await.context("list_objects", &req.bucket, None)?;
+        Ok(ListPage {
+            items: res.items.unwrap_or_default().into_iter().map(|x| {x.into()}).collect(),
+            prefixes: vec![],
+            next_token: res.next_page_token.map(Cursor::new)
+        })
+    }
+
+    async fn list_prefix(&self, bucket: String, prefix: String, page_token: Option<Cursor>) -> ReqRes<ListPage<GoogleCloudObject>> {
+        let req = ListObjectsRequest {
+            bucket,
+            prefix: Some(prefix),
+            page_token: page_token.map(Cursor::into_token),
+            ..Default::default()
+        };
+        let res = self.client.list_objects(&req).await.context("list_prefix", &req.bucket, req.prefix.as_deref())?;
+        Ok(ListPage {
+            items: res.items.unwrap_or_default().into_iter().map(|x| {x.into()}).collect(),
+            prefixes: vec![],
+            next_token: res.next_page_token.map(Cursor::new)
+        })
+    }
+
+    /// Requests a partial response with the attributes (and the fields google-cloud-storage needs) unless all attributes are needed
+    async fn list_projected(&self, bucket: String, prefix: String, attributes: ListAttributes, page_token: Option<Cursor>) -> ReqRes<ListPage<GoogleCloudObject>> {
+        let req = ListObjectsRequest {
+            bucket,
+            prefix: Some(prefix),
+            page_token: page_token.map(Cursor::into_token),
+            projection: (attributes != ListAttributes::Full).then_some(Projection::NoAcl),
+            ..Default::default()
+        };
+        let res = match attributes {
+            ListAttributes::Names => FIELDS.scope(NAME_FIELDS, self.client.list_objects(&req)).await,
+            ListAttributes::NamesAndSizes => FIELDS.scope(SIZE_FIELDS, self.client.list_objects(&req)).await,
+            ListAttributes::Full => self.client.list_objects(&req).await
+        }.context("list_projected", &req.bucket, req.prefix.as_deref())?;
+        Ok(ListPage {
+            items: res.items.unwrap_or_default().into_iter().map(|x| {x.into()}).collect(),
+            prefixes: vec![],
+            next_token: res.next_page_token.map(Cursor::new)
+        })
+    }
+
+    async fn list_delimited(&self, bucket: String, prefix: String, delimiter: String, page_token: Option<Cursor>) -> ReqRes<ListPage<GoogleCloudObject>> {
+        let req = ListObjectsRequest {
+            bucket,
+            prefix: Some(prefix),
+            delimiter: Some(delimiter),
+            page_token: page_token.map(Cursor::into_token),
+            ..Default::default()
+        };
+        let res = self.client.list_objects(&req).await.context("list_delimited", &req.bucket, req.prefix.as_deref())?;
+        Ok(ListPage {
+            items: res.items.unwrap_or_default().into_iter().map(|x| {x.into()}).collect(),
+            prefixes: res.prefixes.unwrap_or_default(),
+            next_token: res.next_page_token.map(Cursor::new)
+        })
+    }
+
+    /// GCS starts listings at (not after) an offset, so an object named `start_after` is skipped here
+    async fn list_after(&self, bucket: String, prefix: String, start_after: String, max_results: u32) -> ReqRes<Vec<GoogleCloudObject>> {
+        let req = ListObjectsRequest {
+            bucket,
+            prefix: Some(prefix),
+            start_offset: Some(start_after.clone()),
+            max_results: Some(max_results.saturating_add(1) as i32),
+            ..Default::default()
+        };
+        let res = self.client.list_objects(&req).await.context("list_after", &req.bucket, req.prefix.as_deref())?;
+        Ok(res.items.unwrap_or_default().into_iter().filter(|t| t.name != start_after).take(max_results as usize).map(|x| {x.into()}).collect())
+    }
+
+    async fn list_versions(&self, bucket: String, prefix: String, page_token: Option<Cursor>) -> ReqRes<ListPage<ObjectVersion>> {
+        let req = ListObjectsRequest {
+            bucket,
+            prefix: Some(prefix),
+            page_token: page_token.map(Cursor::into_token),
+            versions: Some(true),
+            ..Default::default()
+        };
+        let res = self.client.list_objects(&req).await.context("list_versions", &req.bucket, req.prefix.as_deref())?;
+        Ok(ListPage {
+            items: res.items.unwrap_or_default().into_iter().map(|t| ObjectVersion {
+                bucket: t.bucket,
+                name: t.name,
+                version: t.generation.to_string(),
+                size: t.size as u64,
+                created: t.time_created.map(SystemTime::from).unwrap_or(UNIX_EPOCH),
+                superseded: t.time_deleted.map(SystemTime::from),
+                delete_marker: false,
+            }).collect(),
+            prefixes: vec![],
+            next_token: res.next_page_token.map(Cursor::new)
+        })
+    }
+
+    async fn restore_object_version(&self, bucket: String, object: String, version: String) -> ReqRes<GoogleCloudObject> {
+        let Ok(generation) = version.parse() else {
+            return Err(ClientError::Unsupported("Google-Cloud versions are generation numbers")
+                .with_context(ErrorContext::new("restore_object_version", Some(&bucket), Some(&object))))
+        };
+        let req = RewriteObjectRequest {
+            destination_bucket: bucket.clone(),
+            destination_object: object.clone(),
+            source_bucket: bucket,
+            source_object: object,
+            source_generation: Some(generation),
+            ..Default::default()
+        };
+        self.rewrite(req, "restore_object_version").await
+    }
+
+    /// Retentions come from the bucket's retention policy, which nobody can bypass for an object, so they are reported as compliance retentions
+    async fn get_object_lock(&self, bucket: String, object: String) -> ReqRes<ObjectLock> {
+        let req = GetObjectRequest {
+            bucket,
+            object,
+            ..Default::default()
+        };
+        let object = self.client.get_object(&req).await.context("get_object_lock", &req.bucket, Some(&req.object))?;
+        Ok(ObjectLock {
+            legal_hold: object.temporary_hold.unwrap_or(false) || object.event_based_hold.unwrap_or(false),
+            retention: object.retention_expiration_time.map(|t| Retention { mode: RetentionMode::Compliance, retain_until: SystemTime::from(t) }),
+        })
+    }
+
+    /// Google-Cloud retains objects with bucket retention policies, retentions of single objects are not supported
+    async fn set_object_retention(&self, bucket: String, object: String, _retention: Option<Retention>) -> EmptyReqRes {
+        Err(ClientError::Unsupported("Google-Cloud retention is configured with bucket retention policies")
+            .with_context(ErrorContext::new("set_object_retention", Some(&bucket), Some(&object))))
+    }
+
+    /// Releasing only clears the temporary hold, an event based hold is kept
+    async fn set_legal_hold(&self, bucket: String, object: String, on: bool) -> EmptyReqRes {
+        let req = GetObjectRequest {
+            bucket,
+            object,
+            ..Default::default()
+        };
+        let mut metadata = self.client.get_object(&req).await.context("set_legal_hold", &req.bucket, Some(&req.object))?;
+        metadata.temporary_hold = Some(on);
+        let req = PatchObjectRequest {
+            bucket: req.bucket,
+            object: req.object,
+            if_metageneration_match: Some(metadata.metageneration),
+            metadata: Some(metadata),
+            ..Default::default()
+        };
+        self.client.patch_object(&req).await.context("set_legal_hold", &req.bucket, Some(&req.object))?;
+        Ok(())
+    }
+
+    /// Public access is derived from object ACLs, buckets with uniform bucket-level access always report private objects
+    async fn compliance_records(&self, bucket: String, page_token: Option<Cursor>) -> ReqRes<ListPage<ComplianceRecord>> {
+        let req = ListObjectsRequest {
+            bucket,
+            page_token: page_token.map(Cursor::into_token),
+            projection: Some(Projection::Full),
+            ..Default::default()
+        };
+        let res = self.client.list_objects(&req).await.context("compliance_records", &req.bucket, None)?;
+        Ok(ListPage {
+            items: res.items.unwrap_or_default().into_iter().map(|t| ComplianceRecord {
+                encryption: Some(match (t.kms_key_name, t.customer_encryption) {
+                    (Some(key), _) => key,
+                    (None, Some(_)) => "customer-supplied".to_string(),
+                    (None, None) => "google-managed".to_string(),
+                }),
+                public: t.acl.unwrap_or_default().iter().any(|t| t.entity == "allUsers" || t.entity == "allAuthenticatedUsers"),
+                legal_hold: t.temporary_hold.unwrap_or(false) || t.event_based_hold.unwrap_or(false),
+                retain_until: t.retention_expiration_time.map(SystemTime::from),
+                storage_class: t.storage_class,
+                name: t.name,
+            }).collect(),
+            prefixes: vec![],
+            next_token: res.next_page_token.map(Cursor::new)
+        })
+    }
+
+    async fn get_bucket_spec(&self, bucket_name: String) -> ReqRes<BucketSpec> {
+        let req = GetBucketRequest {
+            bucket: bucket_name,
+            ..Default::default()
+        };
+        Ok(BucketSpec::from(&self.client.get_bucket(&req).await.context("get_bucket_spec", &req.bucket, None)?))
+    }
+
+    /// Fails with `ClientError::Unsupported` for rules with a prefix
+    async fn set_lifecycle_rules(&self, bucket_name: String, rules: Vec<LifecycleRule>) -> EmptyReqRes {
+        let rule = rules.into_iter().map(lifecycle_rule_to).collect::<ReqRes<_>>().context("set_lifecycle_rules", &bucket_name, None)?;
+        let req = PatchBucketRequest {
+            bucket: bucket_name,
+            metadata: Some(BucketPatchConfig {
+                lifecycle: Some(Lifecycle { rule }),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        self.client.patch_bucket(&req).await.context("set_lifecycle_rules", &req.bucket, None)?;
+        Ok(())
+    }
+
+    async fn get_bucket_labels(&self, bucket_name: String) -> ReqRes<HashMap<String, String>> {
+        let req = GetBucketRequest {
+            bucket: bucket_name,
+            ..Default::default()
+        };
+        Ok(self.client.get_bucket(&req).await.context("get_bucket_labels", &req.bucket, None)?.labels.unwrap_or_default())
+    }
+
+    /// Patches merge labels, so removing a label which is set on the bucket fails with `ClientError::Unsupported`
+    async fn set_bucket_labels(&self, bucket_name: String, labels: HashMap<String, String>) -> EmptyReqRes {
+        let current = self.get_bucket_labels(bucket_name.clone()).await?;
+        if current.keys().any(|t| !labels.contains_key(t)) {
+            return Err(ClientError::Unsupported("Google-Cloud cannot remove bucket labels with a patch")
+                .with_context(ErrorContext::new("set_bucket_labels", Some(&bucket_name), None)))
+        }
+        let req = PatchBucketRequest {
+            bucket: bucket_name,
+            metadata: Some(BucketPatchConfig {
+                labels: Some(labels),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        self.client.patch_bucket(&req).await.context("set_bucket_labels", &req.bucket, None)?;
+        Ok(())
+    }
+
+    /// Buckets always have an IAM policy
+    async fn get_bucket_policy(&self, bucket_name: String) -> ReqRes<Option<BucketPolicy>> {
+        Ok(Some(BucketPolicy::Iam(self.get_iam_policy(bucket_name).await?)))
+    }
+
+    async fn set_bucket_policy(&self, bucket_name: String, policy: BucketPolicy) -> EmptyReqRes {
+        match policy {
+            BucketPolicy::Iam(policy) => self.set_iam_policy(bucket_name, policy).await,
+            _ => Err(ClientError::Unsupported("Google-Cloud bucket policies are IAM policies")
+                .with_context(ErrorContext::new("set_bucket_policy", Some(&bucket_name), None)))
+        }
+    }
+
+    fn bucket_name_problems(&self, bucket_name: &str) -> Vec<BucketNameProblem> {
+        NamingRules::GoogleCloud.problems(bucket_name)
     }
 }
\ No newline at end of file
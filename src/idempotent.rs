@@ -0,0 +1,80 @@
+use std::collections::HashMap;
+use bytes::Bytes;
+use crate::{BucketOptions, ClientBucket, ClientError, ClientInterface, ClientObject, ErrorContext, ReqRes, UploadOptions, WritePrecondition};
+
+/// Custom metadata key of the token an idempotent upload stores with the object
+pub const IDEMPOTENCY_KEY: &str = "idempotency-token";
+
+/// Outcome of an idempotent operation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Idempotent {
+    /// This call performed the operation
+    Applied,
+    /// An earlier attempt had already performed it (example: a retry whose first attempt succeeded but whose response was lost)
+    AlreadyApplied,
+}
+
+fn carries(object: &impl ClientObject, token: &str) -> bool {
+    object.custom_metadata().get(IDEMPOTENCY_KEY).is_some_and(|t| t == token)
+}
+
+pub(crate) async fn upload<C: ClientInterface + ?Sized>(client: &C, bucket: String, object_id: String, data: Bytes, token: String) -> ReqRes<Idempotent> {
+    let precondition = match client.stat_object(bucket.clone(), object_id.clone()).await {
+        Ok(object) if carries(&object, &token) => return Ok(Idempotent::AlreadyApplied),
+        Ok(object) => WritePrecondition::Matches(object.version().ok_or(ClientError::Unsupported("idempotent uploads require object versions"))?),
+        Err(e) if e.is_not_found() => WritePrecondition::DoesNotExist,
+        Err(e) => return Err(e)
+    };
+    let options = UploadOptions {
+        custom_metadata: HashMap::from([(IDEMPOTENCY_KEY.to_string(), token.clone())]),
+        precondition: Some(precondition),
+        ..Default::default()
+    };
+    match client.upload_with_options(bucket.clone(), object_id.clone(), data, options).await {
+        Ok(_) => Ok(Idempotent::Applied),
+        // Either an earlier attempt of this upload or another writer changed the object in the meantime
+        Err(e) if e.is_precondition_failed() => match client.stat_object(bucket, object_id).await {
+            Ok(object) if carries(&object, &token) => Ok(Idempotent::AlreadyApplied),
+            _ => Err(e)
+        },
+        Err(e) => Err(e)
+    }
+}
+
+pub(crate) async fn create_bucket<C: ClientInterface + ?Sized>(client: &C, bucket: String) -> ReqRes<Idempotent> {
+    match client.create_bucket(bucket.clone()).await {
+        Ok(_) => Ok(Idempotent::Applied),
+        // Buckets of other accounts are not accessible, so an existing bucket is one of ours
+        Err(e) => match client.bucket_exists(bucket).await {
+            Ok(true) => Ok(Idempotent::AlreadyApplied),
+            _ => Err(e)
+        }
+    }
+}
+
+pub(crate) async fn ensure_bucket<C: ClientInterface + ?Sized>(client: &C, bucket: String, options: BucketOptions) -> ReqRes<impl ClientBucket> {
+    match client.get_bucket(bucket.clone()).await {
+        Ok(existing) => return Ok(existing),
+        // Buckets of other accounts are usually reported as inaccessible
+        Err(e) if e.is_not_found() || e.is_permission_denied() => {}
+        Err(e) => return Err(e)
+    }
+    let created = match client.create_bucket(bucket.clone()).await {
+        Ok(_) => true,
+        // Either created concurrently by us or taken by another account, which only the next lookup tells apart
+        Err(e) if e.is_already_exists() => false,
+        Err(e) => return Err(e)
+    };
+    if created {
+        if !options.labels.is_empty() {
+            client.set_bucket_labels(bucket.clone(), options.labels).await?;
+        }
+        if let Some(rules) = options.lifecycle_rules {
+            client.set_lifecycle_rules(bucket.clone(), rules).await?;
+        }
+    }
+    match client.get_bucket(bucket.clone()).await {
+        Err(e) if !created && e.is_permission_denied() => Err(ClientError::BucketOwnedByOther.with_context(ErrorContext::new("ensure_bucket", Some(&bucket), None))),
+        result => result
+    }
+}
@@ -0,0 +1,452 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use bytes::{Bytes, BytesMut};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use crate::encoding::slice_range;
+use crate::{AccessControl, AclGrant, BucketNameProblem, BucketPolicy, BucketSpec, ClientBucket, ClientError, ClientInterface, ClientObject, ComplianceRecord, CopyOptions, Cursor, DownloadOptions, EmptyReqRes, LifecycleRule, ListAttributes, ListPage, MetadataChanges, ObjectLock, ObjectVersion, PostConstraints, PresignedPost, ReqRes, RestoreStatus, Retention, RetrievalTier, SignedUrlOptions, UploadOptions, WritePrecondition};
+
+/// Object returned by an `InMemoryClient`
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct InMemoryObject {
+    pub bucket_name: String,
+    pub name: String,
+    pub size: u64,
+    pub content_type: Option<String>,
+    pub last_modified: Option<SystemTime>,
+    pub etag: Option<String>,
+    pub custom_metadata: HashMap<String, String>,
+}
+
+impl ClientObject for InMemoryObject {
+    fn size(&self) -> u64 {
+        self.size
+    }
+
+    fn bucket_name(&self) -> String {
+        self.bucket_name.clone()
+    }
+
+    fn id(&self) -> String {
+        self.name.clone()
+    }
+
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn content_type(&self) -> Option<String> {
+        self.content_type.clone()
+    }
+
+    fn last_modified(&self) -> Option<SystemTime> {
+        self.last_modified
+    }
+
+    fn etag(&self) -> Option<String> {
+        self.etag.clone()
+    }
+
+    fn storage_class(&self) -> Option<String> {
+        None
+    }
+
+    fn custom_metadata(&self) -> HashMap<String, String> {
+        self.custom_metadata.clone()
+    }
+
+    fn checksum(&self) -> Option<String> {
+        None
+    }
+
+    fn version(&self) -> Option<String> {
+        self.etag.clone()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct InMemoryBucket(pub String);
+
+impl ClientBucket for InMemoryBucket {
+    fn id(&self) -> String {
+        self.0.clone()
+    }
+
+    fn name(&self) -> String {
+        self.0.clone()
+    }
+
+    fn location(&self) -> Option<String> {
+        None
+    }
+}
+
+#[derive(Clone)]
+struct Stored {
+    data: Bytes,
+    content_type: Option<String>,
+    custom_metadata: HashMap<String, String>,
+    tags: HashMap<String, String>,
+    generation: u64,
+    modified: SystemTime,
+}
+
+#[derive(Default)]
+struct State {
+    buckets: BTreeSet<String>,
+    objects: BTreeMap<(String, String), Stored>,
+    generation: u64,
+    /// Drives how often calls yield
+    calls: u64,
+}
+
+/// Stateful backend for unit tests, objects live in memory with a generation which every write increases
+/// Supports conditional reads and writes, so wrappers which rely on them (caches, CAS stores, claims) can be tested
+/// Every call yields to the runtime one to four times (varying from call to call, but reproducible),
+/// so concurrently polled calls interleave like requests would
+#[derive(Clone, Default)]
+pub(crate) struct InMemoryClient {
+    state: Arc<Mutex<State>>,
+}
+
+impl InMemoryClient {
+    /// A client with one (empty) bucket
+    pub fn with_bucket(bucket: &str) -> Self {
+        let client = Self::default();
+        client.state().buckets.insert(bucket.to_string());
+        client
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    async fn pause(&self) {
+        let yields = {
+            let mut state = self.state();
+            state.calls += 1;
+            // Multiplicative hash, so the pattern does not repeat with a short period
+            (state.calls.wrapping_mul(0x9e37_79b9_7f4a_7c15) >> 62) + 1
+        };
+        for _ in 0..yields {
+            tokio::task::yield_now().await;
+        }
+    }
+
+    fn object(bucket: &str, key: &str, stored: &Stored) -> InMemoryObject {
+        InMemoryObject {
+            bucket_name: bucket.to_string(),
+            name: key.to_string(),
+            size: stored.data.len() as u64,
+            content_type: stored.content_type.clone(),
+            last_modified: Some(stored.modified),
+            etag: Some(stored.generation.to_string()),
+            custom_metadata: stored.custom_metadata.clone(),
+        }
+    }
+
+    async fn get(&self, bucket: &str, key: &str) -> ReqRes<Stored> {
+        self.pause().await;
+        let state = self.state();
+        if !state.buckets.contains(bucket) {
+            return Err(ClientError::NotFound)
+        }
+        state.objects.get(&(bucket.to_string(), key.to_string())).cloned().ok_or(ClientError::NotFound)
+    }
+
+    async fn put(&self, bucket: &str, key: &str, data: Bytes, options: UploadOptions) -> ReqRes<InMemoryObject> {
+        self.pause().await;
+        let mut state = self.state();
+        if !state.buckets.contains(bucket) {
+            return Err(ClientError::NotFound)
+        }
+        let id = (bucket.to_string(), key.to_string());
+        let current = state.objects.get(&id).map(|t| t.generation.to_string());
+        match (&options.precondition, current) {
+            (Some(WritePrecondition::DoesNotExist), Some(_)) => return Err(ClientError::PreconditionFailed),
+            (Some(WritePrecondition::Matches(version)), current) if current.as_ref() != Some(version) => return Err(ClientError::PreconditionFailed),
+            _ => {}
+        }
+        state.generation += 1;
+        let stored = Stored {
+            data,
+            content_type: options.content_type,
+            custom_metadata: options.custom_metadata,
+            tags: HashMap::new(),
+            generation: state.generation,
+            modified: SystemTime::now(),
+        };
+        let object = Self::object(bucket, key, &stored);
+        state.objects.insert(id, stored);
+        Ok(object)
+    }
+
+    async fn update(&self, bucket: &str, key: &str, change: impl FnOnce(&mut Stored)) -> ReqRes<InMemoryObject> {
+        self.pause().await;
+        let mut state = self.state();
+        state.generation += 1;
+        let generation = state.generation;
+        let stored = state.objects.get_mut(&(bucket.to_string(), key.to_string())).ok_or(ClientError::NotFound)?;
+        change(stored);
+        stored.generation = generation;
+        stored.modified = SystemTime::now();
+        Ok(Self::object(bucket, key, stored))
+    }
+
+    async fn list(&self, bucket: &str, prefix: &str) -> ReqRes<Vec<InMemoryObject>> {
+        self.pause().await;
+        let state = self.state();
+        if !state.buckets.contains(bucket) {
+            return Err(ClientError::NotFound)
+        }
+        Ok(state.objects.iter()
+            .filter(|((b, k), _)| b == bucket && k.starts_with(prefix))
+            .map(|((b, k), stored)| Self::object(b, k, stored))
+            .collect())
+    }
+
+    async fn copy(&self, src_bucket: &str, src_object: &str, dest_bucket: &str, dest_object: &str) -> ReqRes<InMemoryObject> {
+        let source = self.get(src_bucket, src_object).await?;
+        let options = UploadOptions { content_type: source.content_type, custom_metadata: source.custom_metadata, ..Default::default() };
+        self.put(dest_bucket, dest_object, source.data, options).await
+    }
+}
+
+fn page<T>(items: Vec<T>) -> ListPage<T> {
+    ListPage { items, prefixes: vec![], next_token: None }
+}
+
+#[allow(refining_impl_trait)]
+impl ClientInterface for InMemoryClient {
+    async fn static_download_bytes(&self, bucket: String, object_id: String, starting: Option<u64>, ending: Option<u64>) -> ReqRes<Bytes> {
+        Ok(slice_range(self.get(&bucket, &object_id).await?.data, starting, ending))
+    }
+
+    async fn static_upload_bytes(&self, bucket: String, object_id: String, data: Bytes) -> ReqRes<InMemoryObject> {
+        self.put(&bucket, &object_id, data, UploadOptions::default()).await
+    }
+
+    async fn upload_with_options(&self, bucket: String, object_id: String, data: Bytes, options: UploadOptions) -> ReqRes<InMemoryObject> {
+        self.put(&bucket, &object_id, data, options).await
+    }
+
+    async fn download_with_options(&self, bucket: String, object_id: String, starting: Option<u64>, ending: Option<u64>, options: DownloadOptions) -> ReqRes<Bytes> {
+        let stored = self.get(&bucket, &object_id).await?;
+        let etag = stored.generation.to_string();
+        if options.version.as_ref().is_some_and(|t| *t != etag) {
+            return Err(ClientError::Unsupported("only the live version is kept in memory"))
+        }
+        if options.if_match.as_ref().is_some_and(|t| t != "*" && *t != etag) {
+            return Err(ClientError::PreconditionFailed)
+        }
+        let unmodified = options.if_modified_since.is_some_and(|t| stored.modified <= t);
+        if options.if_none_match.as_ref().is_some_and(|t| t == "*" || *t == etag) || unmodified {
+            return Err(ClientError::NotModified)
+        }
+        Ok(slice_range(stored.data, starting, ending))
+    }
+
+    async fn download_to_writer(&self, bucket: String, object_id: String, starting: Option<u64>, ending: Option<u64>, mut w: impl AsyncWrite + Unpin + Send) -> ReqRes<u64> {
+        let data = self.static_download_bytes(bucket, object_id, starting, ending).await?;
+        w.write_all(&data).await?;
+        w.flush().await?;
+        Ok(data.len() as u64)
+    }
+
+    async fn upload_from_reader(&self, bucket: String, object_id: String, mut r: impl AsyncRead + Unpin + Send + Sync + 'static) -> EmptyReqRes {
+        let mut data = vec![];
+        r.read_to_end(&mut data).await?;
+        self.put(&bucket, &object_id, data.into(), UploadOptions::default()).await?;
+        Ok(())
+    }
+
+    async fn signed_url(&self, _bucket: String, _object_id: String, _options: SignedUrlOptions) -> ReqRes<String> {
+        Err(ClientError::Unsupported("objects in memory have no URLs"))
+    }
+
+    async fn presigned_post(&self, _bucket: String, _key_prefix: String, _constraints: PostConstraints) -> ReqRes<PresignedPost> {
+        Err(ClientError::Unsupported("objects in memory have no URLs"))
+    }
+
+    async fn remove_bucket(&self, bucket: String) -> EmptyReqRes {
+        let mut state = self.state();
+        if state.objects.keys().any(|(b, _)| *b == bucket) {
+            return Err(ClientError::PreconditionFailed)
+        }
+        state.buckets.remove(&bucket).then_some(()).ok_or(ClientError::NotFound)
+    }
+
+    async fn remove_object(&self, bucket: String, object_id: String) -> EmptyReqRes {
+        self.pause().await;
+        self.state().objects.remove(&(bucket, object_id)).map(|_| ()).ok_or(ClientError::NotFound)
+    }
+
+    async fn create_bucket(&self, bucket: String) -> ReqRes<InMemoryBucket> {
+        match self.state().buckets.insert(bucket.clone()) {
+            true => Ok(InMemoryBucket(bucket)),
+            false => Err(ClientError::AlreadyExists)
+        }
+    }
+
+    async fn copy_object(&self, src_bucket: String, src_object: String, dest_bucket: String, dest_object: String) -> ReqRes<InMemoryObject> {
+        self.copy(&src_bucket, &src_object, &dest_bucket, &dest_object).await
+    }
+
+    async fn copy_object_with_options(&self, src_bucket: String, src_object: String, dest_bucket: String, dest_object: String, _options: CopyOptions) -> ReqRes<InMemoryObject> {
+        self.copy(&src_bucket, &src_object, &dest_bucket, &dest_object).await
+    }
+
+    async fn restore_object(&self, _bucket_name: String, _object_name: String, _days: u32, _tier: RetrievalTier) -> EmptyReqRes {
+        Err(ClientError::Unsupported("objects in memory are never archived"))
+    }
+
+    async fn restore_status(&self, bucket_name: String, object_name: String) -> ReqRes<RestoreStatus> {
+        self.get(&bucket_name, &object_name).await?;
+        Ok(RestoreStatus::NotRequested)
+    }
+
+    async fn update_object_metadata(&self, bucket: String, object_id: String, changes: MetadataChanges) -> ReqRes<InMemoryObject> {
+        self.update(&bucket, &object_id, |stored| {
+            if changes.content_type.is_some() {
+                stored.content_type = changes.content_type.clone();
+            }
+            stored.custom_metadata.retain(|k, _| !changes.remove_metadata.contains(k));
+            stored.custom_metadata.extend(changes.custom_metadata.clone());
+        }).await
+    }
+
+    async fn get_object_tags(&self, bucket: String, object_id: String) -> ReqRes<HashMap<String, String>> {
+        Ok(self.get(&bucket, &object_id).await?.tags)
+    }
+
+    async fn set_object_tags(&self, bucket: String, object_id: String, tags: HashMap<String, String>) -> EmptyReqRes {
+        self.update(&bucket, &object_id, |stored| stored.tags = tags).await?;
+        Ok(())
+    }
+
+    async fn get_object_acl(&self, _bucket: String, _object_id: String) -> ReqRes<Vec<AclGrant>> {
+        Err(ClientError::Unsupported("objects in memory have no ACLs"))
+    }
+
+    async fn set_object_acl(&self, _bucket: String, _object_id: String, _acl: AccessControl) -> EmptyReqRes {
+        Err(ClientError::Unsupported("objects in memory have no ACLs"))
+    }
+
+    async fn append(&self, bucket: String, object_id: String, data: Bytes) -> EmptyReqRes {
+        let current = match self.get(&bucket, &object_id).await {
+            Ok(stored) => stored.data,
+            Err(e) if e.is_not_found() => Bytes::new(),
+            Err(e) => return Err(e)
+        };
+        let mut appended = BytesMut::from(current);
+        appended.extend_from_slice(&data);
+        self.put(&bucket, &object_id, appended.freeze(), UploadOptions::default()).await?;
+        Ok(())
+    }
+
+    async fn upload_public(&self, bucket: String, object_id: String, data: Bytes, content_type: Option<String>, _cache_control: String) -> EmptyReqRes {
+        self.put(&bucket, &object_id, data, UploadOptions { content_type, ..Default::default() }).await?;
+        Ok(())
+    }
+
+    fn public_url(&self, bucket: &str, object_id: &str) -> String {
+        format!("memory://{bucket}/{object_id}")
+    }
+
+    async fn list_buckets(&self, _max_results: Option<u32>, _page_token: Option<Cursor>) -> ReqRes<ListPage<InMemoryBucket>> {
+        Ok(page(self.state().buckets.iter().cloned().map(InMemoryBucket).collect()))
+    }
+
+    async fn get_bucket(&self, bucket_name: String) -> ReqRes<InMemoryBucket> {
+        self.state().buckets.contains(&bucket_name).then_some(InMemoryBucket(bucket_name)).ok_or(ClientError::NotFound)
+    }
+
+    async fn get_object(&self, bucket_name: String, object_name: String) -> ReqRes<InMemoryObject> {
+        self.stat_object(bucket_name, object_name).await
+    }
+
+    async fn stat_object(&self, bucket_name: String, object_name: String) -> ReqRes<InMemoryObject> {
+        Ok(Self::object(&bucket_name, &object_name, &self.get(&bucket_name, &object_name).await?))
+    }
+
+    async fn list_objects(&self, bucket_name: String, _max_results: Option<u32>, _page_token: Option<Cursor>) -> ReqRes<ListPage<InMemoryObject>> {
+        Ok(page(self.list(&bucket_name, "").await?))
+    }
+
+    async fn list_prefix(&self, bucket_name: String, prefix: String, _page_token: Option<Cursor>) -> ReqRes<ListPage<InMemoryObject>> {
+        Ok(page(self.list(&bucket_name, &prefix).await?))
+    }
+
+    async fn list_projected(&self, bucket_name: String, prefix: String, _attributes: ListAttributes, _page_token: Option<Cursor>) -> ReqRes<ListPage<InMemoryObject>> {
+        Ok(page(self.list(&bucket_name, &prefix).await?))
+    }
+
+    async fn list_delimited(&self, bucket_name: String, prefix: String, delimiter: String, _page_token: Option<Cursor>) -> ReqRes<ListPage<InMemoryObject>> {
+        let (mut items, mut prefixes) = (vec![], BTreeSet::new());
+        for object in self.list(&bucket_name, &prefix).await? {
+            match object.name[prefix.len()..].find(&delimiter).filter(|_| !delimiter.is_empty()) {
+                Some(at) => {
+                    prefixes.insert(object.name[..prefix.len() + at + delimiter.len()].to_string());
+                }
+                None => items.push(object)
+            }
+        }
+        Ok(ListPage { items, prefixes: prefixes.into_iter().collect(), next_token: None })
+    }
+
+    async fn list_after(&self, bucket_name: String, prefix: String, start_after: String, max_results: u32) -> ReqRes<Vec<InMemoryObject>> {
+        Ok(self.list(&bucket_name, &prefix).await?.into_iter().filter(|t| t.name > start_after).take(max_results as usize).collect())
+    }
+
+    async fn list_versions(&self, _bucket_name: String, _prefix: String, _page_token: Option<Cursor>) -> ReqRes<ListPage<ObjectVersion>> {
+        Err(ClientError::Unsupported("only the live version is kept in memory"))
+    }
+
+    async fn restore_object_version(&self, _bucket_name: String, _object_name: String, _version: String) -> ReqRes<InMemoryObject> {
+        Err(ClientError::Unsupported("only the live version is kept in memory"))
+    }
+
+    async fn compliance_records(&self, _bucket_name: String, _page_token: Option<Cursor>) -> ReqRes<ListPage<ComplianceRecord>> {
+        Err(ClientError::Unsupported("objects in memory have no retention"))
+    }
+
+    async fn get_bucket_spec(&self, _bucket_name: String) -> ReqRes<BucketSpec> {
+        Err(ClientError::Unsupported("buckets in memory have no configuration"))
+    }
+
+    async fn set_lifecycle_rules(&self, _bucket_name: String, _rules: Vec<LifecycleRule>) -> EmptyReqRes {
+        Err(ClientError::Unsupported("buckets in memory have no configuration"))
+    }
+
+    async fn get_object_lock(&self, _bucket_name: String, _object_name: String) -> ReqRes<ObjectLock> {
+        Err(ClientError::Unsupported("objects in memory have no retention"))
+    }
+
+    async fn set_object_retention(&self, _bucket_name: String, _object_name: String, _retention: Option<Retention>) -> EmptyReqRes {
+        Err(ClientError::Unsupported("objects in memory have no retention"))
+    }
+
+    async fn set_legal_hold(&self, _bucket_name: String, _object_name: String, _on: bool) -> EmptyReqRes {
+        Err(ClientError::Unsupported("objects in memory have no retention"))
+    }
+
+    async fn get_bucket_labels(&self, _bucket_name: String) -> ReqRes<HashMap<String, String>> {
+        Err(ClientError::Unsupported("buckets in memory have no configuration"))
+    }
+
+    async fn set_bucket_labels(&self, _bucket_name: String, _labels: HashMap<String, String>) -> EmptyReqRes {
+        Err(ClientError::Unsupported("buckets in memory have no configuration"))
+    }
+
+    async fn get_bucket_policy(&self, _bucket_name: String) -> ReqRes<Option<BucketPolicy>> {
+        Err(ClientError::Unsupported("buckets in memory have no configuration"))
+    }
+
+    async fn set_bucket_policy(&self, _bucket_name: String, _policy: BucketPolicy) -> EmptyReqRes {
+        Err(ClientError::Unsupported("buckets in memory have no configuration"))
+    }
+
+    fn bucket_name_problems(&self, _bucket_name: &str) -> Vec<BucketNameProblem> {
+        vec![]
+    }
+}
@@ -0,0 +1,120 @@
+use serde::Serialize;
+use crate::ClientError;
+
+/// Provider independent category of a `ClientError`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum ErrorKind {
+    NotFound,
+    AlreadyExists,
+    PermissionDenied,
+    /// The provider rejected the request because of its rate limits
+    Throttled,
+    /// A condition of the request does not hold
+    Precondition,
+    /// The request did not complete in time
+    Timeout,
+    /// The provider could not be reached (connection or transport failure)
+    Network,
+    /// A conditional download was skipped because the object did not change
+    NotModified,
+    /// The request (example: a cursor or claim token) is malformed or forged
+    InvalidInput,
+    /// Stored data does not match what was expected (checksums, sizes, versions, replicas)
+    Integrity,
+    Unsupported,
+    Other,
+}
+
+impl ErrorKind {
+    /// HTTP status a web service built on this crate can answer with
+    pub fn http_status(self) -> u16 {
+        match self {
+            ErrorKind::NotFound => 404,
+            ErrorKind::AlreadyExists => 409,
+            ErrorKind::PermissionDenied => 403,
+            ErrorKind::Throttled => 429,
+            ErrorKind::Precondition => 412,
+            ErrorKind::Timeout => 504,
+            ErrorKind::Network => 503,
+            ErrorKind::NotModified => 304,
+            ErrorKind::InvalidInput => 400,
+            ErrorKind::Integrity => 502,
+            ErrorKind::Unsupported => 501,
+            ErrorKind::Other => 500,
+        }
+    }
+
+    /// Message which is safe to show to clients
+    pub fn message(self) -> &'static str {
+        match self {
+            ErrorKind::NotFound => "the object or bucket does not exist",
+            ErrorKind::AlreadyExists => "the object or bucket exists already",
+            ErrorKind::PermissionDenied => "access to the object or bucket is denied",
+            ErrorKind::Throttled => "too many requests, try again later",
+            ErrorKind::Precondition => "a condition of the request does not hold",
+            ErrorKind::Timeout => "the storage request timed out",
+            ErrorKind::Network => "the storage provider could not be reached",
+            ErrorKind::NotModified => "the object did not change",
+            ErrorKind::InvalidInput => "the request is invalid",
+            ErrorKind::Integrity => "the stored data failed an integrity check",
+            ErrorKind::Unsupported => "the operation is not supported",
+            ErrorKind::Other => "the storage request failed",
+        }
+    }
+}
+
+/// Serializable body of an error response, without provider messages, bucket names or keys
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ErrorBody {
+    pub kind: ErrorKind,
+    pub status: u16,
+    pub message: &'static str,
+    /// Name of the failed operation (example: 'get_object'), if known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub operation: Option<&'static str>,
+}
+
+impl ClientError {
+    /// The category of the error (see `ErrorKind`)
+    pub fn kind(&self) -> ErrorKind {
+        match self.inner() {
+            ClientError::NotModified => return ErrorKind::NotModified,
+            ClientError::InvalidCursor | ClientError::InvalidClaim | ClientError::InvalidUri(_) | ClientError::InvalidConfig(_) => return ErrorKind::InvalidInput,
+            ClientError::Unsupported(_) => return ErrorKind::Unsupported,
+            ClientError::QuarantineExpired => return ErrorKind::NotFound,
+            ClientError::VersionMismatch { .. } | ClientError::SizeMismatch { .. } | ClientError::ChecksumMismatch { .. }
+            | ClientError::ReplicaDivergence(_) => return ErrorKind::Integrity,
+            _ => {}
+        }
+        if self.is_not_found() {
+            ErrorKind::NotFound
+        } else if self.is_already_exists() {
+            ErrorKind::AlreadyExists
+        } else if self.is_permission_denied() {
+            ErrorKind::PermissionDenied
+        } else if self.is_throttled() {
+            ErrorKind::Throttled
+        } else if self.is_precondition_failed() {
+            ErrorKind::Precondition
+        } else if self.is_timeout() {
+            ErrorKind::Timeout
+        } else if self.is_network() {
+            ErrorKind::Network
+        } else {
+            ErrorKind::Other
+        }
+    }
+
+    /// Response body with the status of `ErrorKind::http_status`
+    pub fn to_body(&self) -> ErrorBody {
+        self.to_body_with(ErrorKind::http_status)
+    }
+
+    /// Response body with a custom mapping of kinds to HTTP statuses
+    pub fn to_body_with(&self, status: impl Fn(ErrorKind) -> u16) -> ErrorBody {
+        let kind = self.kind();
+        ErrorBody { kind, status: status(kind), message: kind.message(), operation: self.context().map(|t| t.operation) }
+    }
+}
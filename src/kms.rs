@@ -0,0 +1,144 @@
+#[cfg(feature = "google_kms")]
+use std::sync::Arc;
+#[cfg(feature = "google_kms")]
+use base64::Engine;
+#[cfg(feature = "google_kms")]
+use base64::engine::general_purpose::STANDARD;
+#[cfg(feature = "google_kms")]
+use serde::{Deserialize, Serialize};
+use crate::{EncryptionError, KeyWrapper, ReqRes};
+
+fn kms_error(e: impl std::fmt::Display) -> EncryptionError {
+    EncryptionError::Kms(e.to_string())
+}
+
+/// Data keys are 32 bytes, anything else was not wrapped by an EnvelopeClient
+fn data_key(plaintext: &[u8]) -> Result<[u8; 32], EncryptionError> {
+    plaintext.try_into().map_err(|_| EncryptionError::Malformed)
+}
+
+/// Wraps data keys with a symmetric AWS KMS key
+#[cfg(feature = "aws_kms")]
+#[derive(Clone)]
+pub struct AwsKms {
+    client: aws_sdk_kms::Client,
+    key_id: String,
+}
+
+#[cfg(feature = "aws_kms")]
+impl AwsKms {
+    /// `key_id` is the ARN (preferred, it is stored with every object), ID or alias of the key
+    pub fn new(client: aws_sdk_kms::Client, key_id: String) -> Self {
+        Self { client, key_id }
+    }
+
+    /// Loads region and credentials from the default provider chain (environment, profile, IMDS, ...)
+    pub async fn standard_auth(key_id: String) -> Self {
+        let config = aws_config::load_defaults(aws_config::BehaviorVersion::latest()).await;
+        Self::new(aws_sdk_kms::Client::new(&config), key_id)
+    }
+}
+
+#[cfg(feature = "aws_kms")]
+impl KeyWrapper for AwsKms {
+    fn key_id(&self) -> &str {
+        &self.key_id
+    }
+
+    async fn wrap(&self, data_key: &[u8; 32]) -> ReqRes<Vec<u8>> {
+        let res = self.client.encrypt()
+            .key_id(&self.key_id)
+            .plaintext(aws_sdk_kms::primitives::Blob::new(data_key.as_slice()))
+            .send().await.map_err(kms_error)?;
+        Ok(res.ciphertext_blob.ok_or_else(|| EncryptionError::Kms("no ciphertext returned".to_string()))?.into_inner())
+    }
+
+    async fn unwrap(&self, key_id: &str, wrapped_key: &[u8]) -> ReqRes<[u8; 32]> {
+        let res = self.client.decrypt()
+            .key_id(key_id)
+            .ciphertext_blob(aws_sdk_kms::primitives::Blob::new(wrapped_key))
+            .send().await.map_err(kms_error)?;
+        Ok(data_key(res.plaintext.ok_or_else(|| EncryptionError::Kms("no plaintext returned".to_string()))?.as_ref())?)
+    }
+}
+
+#[cfg(feature = "google_kms")]
+const KMS_SCOPES: [&str; 1] = ["https://www.googleapis.com/auth/cloudkms"];
+
+#[cfg(feature = "google_kms")]
+#[derive(Serialize)]
+struct EncryptRequest {
+    plaintext: String,
+}
+
+#[cfg(feature = "google_kms")]
+#[derive(Deserialize)]
+struct EncryptResponse {
+    ciphertext: String,
+}
+
+#[cfg(feature = "google_kms")]
+#[derive(Serialize)]
+struct DecryptRequest {
+    ciphertext: String,
+}
+
+#[cfg(feature = "google_kms")]
+#[derive(Deserialize)]
+struct DecryptResponse {
+    plaintext: String,
+}
+
+/// Wraps data keys with a symmetric Google Cloud KMS key
+/// New data keys are wrapped with the primary version of the key, data keys wrapped with older versions still unwrap
+#[cfg(feature = "google_kms")]
+#[derive(Clone)]
+pub struct GoogleKms {
+    http: reqwest::Client,
+    tokens: Arc<dyn google_cloud_token::TokenSource>,
+    key_name: String,
+}
+
+#[cfg(feature = "google_kms")]
+impl GoogleKms {
+    /// `key_name` is the resource name of the key (example: 'projects/p/locations/global/keyRings/r/cryptoKeys/k')
+    /// Fails if no application default credentials are found
+    pub async fn standard_auth(key_name: String) -> ReqRes<Self> {
+        use google_cloud_storage::client::google_cloud_auth::project::Config;
+        use google_cloud_storage::client::google_cloud_auth::token::DefaultTokenSourceProvider;
+        use google_cloud_token::TokenSourceProvider;
+        let provider = DefaultTokenSourceProvider::new(Config::default().with_scopes(&KMS_SCOPES)).await?;
+        Ok(Self { http: reqwest::Client::new(), tokens: provider.token_source(), key_name })
+    }
+
+    async fn call<Req: Serialize, Res: for<'de> Deserialize<'de>>(&self, key_name: &str, method: &str, body: &Req) -> ReqRes<Res> {
+        let token = self.tokens.token().await.map_err(kms_error)?;
+        let res = self.http.post(format!("https://cloudkms.googleapis.com/v1/{key_name}:{method}"))
+            .header(reqwest::header::AUTHORIZATION, token)
+            .json(body)
+            .send().await.map_err(kms_error)?;
+        if !res.status().is_success() {
+            let status = res.status();
+            let text = res.text().await.unwrap_or_default();
+            return Err(EncryptionError::Kms(format!("{status}: {text}")).into())
+        }
+        Ok(res.json().await.map_err(kms_error)?)
+    }
+}
+
+#[cfg(feature = "google_kms")]
+impl KeyWrapper for GoogleKms {
+    fn key_id(&self) -> &str {
+        &self.key_name
+    }
+
+    async fn wrap(&self, data_key: &[u8; 32]) -> ReqRes<Vec<u8>> {
+        let res: EncryptResponse = self.call(&self.key_name, "encrypt", &EncryptRequest { plaintext: STANDARD.encode(data_key) }).await?;
+        Ok(STANDARD.decode(res.ciphertext).map_err(kms_error)?)
+    }
+
+    async fn unwrap(&self, key_id: &str, wrapped_key: &[u8]) -> ReqRes<[u8; 32]> {
+        let res: DecryptResponse = self.call(key_id, "decrypt", &DecryptRequest { ciphertext: STANDARD.encode(wrapped_key) }).await?;
+        Ok(data_key(&STANDARD.decode(res.plaintext).map_err(kms_error)?)?)
+    }
+}
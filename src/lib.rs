@@ -1,48 +1,769 @@
-#[cfg(feature = "aws_s3")]
-use crate::aws_s3::AWSError;
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+use bytes::Bytes;
+use futures::{stream, Stream, StreamExt};
+use tokio::io::{AsyncRead, AsyncWrite};
 #[cfg(feature = "aws_s3")]
 mod aws_s3;
+#[cfg(feature = "aws_s3")]
+mod glacier;
 
-#[cfg(feature = "google_cloud")]
-use crate::google_cloud::GoogleCloudError;
 #[cfg(feature = "google_cloud")]
 mod google_cloud;
+#[cfg(feature = "local")]
+mod local;
+
+#[cfg(any(feature = "google_cloud", feature = "aws_s3"))]
+mod checksum;
+#[cfg(any(feature = "google_cloud", feature = "aws_s3"))]
+mod post;
+#[cfg(any(feature = "google_cloud", feature = "aws_s3", feature = "local"))]
+mod unified;
+#[cfg(any(feature = "google_cloud", feature = "aws_s3", feature = "local"))]
+mod config;
+
+#[cfg(feature = "encryption")]
+mod encryption;
+#[cfg(any(feature = "aws_kms", feature = "google_kms"))]
+mod kms;
+
+#[cfg(feature = "boxed")]
+mod boxed;
+
+#[cfg(feature = "conformance")]
+pub mod conformance;
+#[cfg(all(feature = "testing", any(feature = "google_cloud", feature = "aws_s3")))]
+pub mod testing;
+
+#[cfg(feature = "mock")]
+mod mock;
 
+#[cfg(feature = "cassette")]
+mod cassette;
+#[cfg(test)]
+mod in_memory;
 
-// TODO: Find a better way for async traits
+mod delegate;
+mod spec;
+mod checkpoint;
+mod encoding;
+mod consistent;
+mod bulk;
+mod versions;
+mod compliance;
+mod verifying;
+mod transfer;
+mod permissions;
+mod onboarding;
+mod shadow;
+mod split;
+mod publish;
+mod deploy;
+mod glob;
+mod watchdog;
+mod sample;
+mod find;
+mod cursor;
+mod options;
+mod changes;
+mod pause;
+mod fault;
+mod defaults;
+mod progress;
+mod naming;
+mod idempotent;
+mod claim;
+mod quarantine;
+mod orphans;
+mod kind;
+mod policy;
+mod archive;
+mod mime;
+mod uri;
+mod cache;
+mod memory;
+mod chunked;
+mod cas;
+mod replicated;
 
-#[allow(async_fn_in_trait)]
-pub trait ClientInterface {
+pub use spec::{BucketSpec, CorsRule, LifecycleAction, LifecycleRule, SpecDrift};
+pub use encoding::{encode_key, range_header, valid_app_id};
+pub use checkpoint::{CheckpointPart, UploadCheckpoint, CHECKPOINT_PREFIX, DEFAULT_LEASE};
+pub use consistent::ConsistentClient;
+pub use bulk::{BulkReport, PrefixStats};
+pub use versions::ObjectVersion;
+pub use verifying::{AuditLog, RepairRecord, Replica, ReplicaStatus, VerifyingReadClient, SAMPLE_SIZE};
+pub use transfer::{transfer, TRANSFER_BUFFER};
+pub use permissions::{Capability, PermissionReport, ProbeOutcome, PROBE_PREFIX};
+pub use onboarding::{OnboardingProblem, OnboardingReport, OnboardingRequirements};
+pub use shadow::{ShadowClient, ShadowLog, ShadowOutcome, ShadowRecord};
+pub use split::{Routed, SplitClient, SplitConfig};
+pub use publish::{PublicUrl, IMMUTABLE_CACHE_CONTROL};
+pub use deploy::{DeployOptions, DeployReport, DEPLOY_MANIFEST};
+pub use glob::{glob_match, glob_prefix};
+pub use watchdog::{WatchAlert, WatchRule, WatchViolation, Watchdog};
+pub use find::Filter;
+pub use cursor::Cursor;
+pub use options::{AccessControl, AclGrant, AclPermission, BucketOptions, CacheValidator, CopyOptions, DownloadOptions, Grantee, MetadataChanges, ObjectAcl, PostConstraints, PresignedPost, SignedUrlMethod, SignedUrlOptions, UploadOptions, WritePrecondition};
+pub use changes::{Change, ChangeFeed, ChangeKind, CHANGES_PREFIX};
+pub use pause::{PausableClient, PauseHandle};
+pub use fault::{Fault, FaultInjecting};
+pub use defaults::{Defaults, RetryPolicy};
+pub use progress::CopyProgress;
+pub use naming::{BucketNameCheck, BucketNameProblem, NamingRules};
+pub use idempotent::{Idempotent, IDEMPOTENCY_KEY};
+pub use claim::{CLAIM_PREFIX, REDEMPTION_LEASE};
+pub use quarantine::QUARANTINE_PREFIX;
+pub use orphans::Orphans;
+pub use kind::{ErrorBody, ErrorKind};
+pub use policy::{BucketPolicy, IamBinding, IamPolicy};
+pub use archive::{RestoreStatus, RetrievalTier};
+pub use mime::guess_content_type;
+pub use uri::{Credentials, StorageUri};
+pub use cache::CachedClient;
+pub use memory::{MemoryCache, DEFAULT_MAX_OBJECT_SIZE};
+pub use chunked::{ChunkedClient, ChunkedObject, CHUNKED_METADATA, CHUNK_PREFIX, DEFAULT_CHUNK_SIZE};
+pub use cas::{content_digest, CasManifest, CasStore, CAS_PREFIX, SWEEP_LEASE};
+pub use replicated::{FailureLog, ReplicaFailure, ReplicatedClient, ReplicationPolicy};
+#[cfg(feature = "google_cloud")]
+pub use google_cloud::{GoogleCloud, GoogleCloudBucket, GoogleCloudConfig, GoogleCloudError, GoogleCloudObject};
+#[cfg(feature = "aws_s3")]
+pub use aws_s3::{AWSBucket, AWSClient, AWSConfig, AWSError, AWSListedObject, AWSObject, AWSObjectPut, TransportError};
+#[cfg(feature = "local")]
+pub use local::{LocalBucket, LocalClient, LocalObject};
+#[cfg(any(feature = "google_cloud", feature = "aws_s3", feature = "local"))]
+pub use unified::{UnifiedBucket, UnifiedClient, UnifiedObject};
+#[cfg(any(feature = "google_cloud", feature = "aws_s3", feature = "local"))]
+pub use config::{StorageConfig, ENV_PREFIX};
+#[cfg(feature = "aws_s3")]
+pub use glacier::{ArchivedObject, RestorePlanner, RestoreReport, RestoreWave};
+#[cfg(feature = "cassette")]
+pub use cassette::{scrub_url, Cassette, CassetteMode, Interaction, RECORD_VAR};
+#[cfg(feature = "mock")]
+pub use mock::{MockBucket, MockCall, MockClient, MockExpectation, MockObject};
+#[cfg(feature = "boxed")]
+pub use boxed::{BoxedBucket, BoxedClient, BoxedObject, DynClient};
+#[cfg(feature = "encryption")]
+pub use encryption::{decrypt, encrypt, envelope, key_id, EncryptedClient, EncryptionError, EncryptionKey, Envelope, EnvelopeClient, KeyResolver, KeyWrapper, PrefixKeyResolver, ENVELOPE_MAGIC, MAGIC};
+#[cfg(feature = "aws_kms")]
+pub use kms::AwsKms;
+#[cfg(feature = "google_kms")]
+pub use kms::GoogleKms;
+pub use compliance::{ComplianceEntry, CompliancePolicy, ComplianceRecord, ComplianceReport, ObjectLock, Retention, RetentionMode, Violation};
+
+/// Maximum number of concurrent requests issued by bulk operations
+pub const CONCURRENCY: usize = 32;
+
+/// The futures and streams of every client are `Send`, so they can be used with `tokio::spawn`
+/// Implementations write the methods as `async fn`
+pub trait ClientInterface: Send + Sync {
     /// Statically (at once) downloads an object from remote
-    async fn static_download_object(&self, bucket: String, object_id: String, starting: Option<u64>, ending: Option<u64>) -> ReqRes<Vec<u8>>;
+    fn static_download_bytes(&self, bucket: String, object_id: String, starting: Option<u64>, ending: Option<u64>) -> impl Future<Output = ReqRes<Bytes>> + Send;
     /// Statically (at once) uploads an object to remote
-    async fn static_upload_object(&self, bucket: String, object_id: String, data: Vec<u8>) -> ReqRes<impl ClientObject>;
-    /// Gets a URL which can be used to upload data
-    /// Not supported: AWS-S3
-    async fn url_upload_object(&self, bucket: String, object_id: String) -> ReqRes<String>;
+    fn static_upload_bytes(&self, bucket: String, object_id: String, data: Bytes) -> impl Future<Output = ReqRes<impl ClientObject>> + Send;
+    /// Same as `static_upload_bytes`, but sets content type, metadata, storage class etc. (see `UploadOptions`)
+    fn upload_with_options(&self, bucket: String, object_id: String, data: Bytes, options: UploadOptions) -> impl Future<Output = ReqRes<impl ClientObject>> + Send;
+    /// Uploads an object at most once per `token` (stored as `IDEMPOTENCY_KEY` metadata), so retrying after an ambiguous failure
+    /// cannot overwrite twice, and reports whether an earlier attempt had already succeeded
+    /// The write is conditional on the version seen before, a concurrent writer makes it fail with `ClientError::PreconditionFailed`
+    fn upload_idempotent(&self, bucket: String, object_id: String, data: Bytes, token: String) -> impl Future<Output = ReqRes<Idempotent>> + Send {
+        async move {
+            idempotent::upload(self, bucket, object_id, data, token).await
+        }
+    }
+    /// Compare-and-swap write for optimistic concurrency: read the object, remember its `ClientObject::version`,
+    /// then write with `WritePrecondition::Matches`; fails with `ClientError::PreconditionFailed` if another writer came first
+    fn upload_if_match(&self, bucket: String, object_id: String, data: Bytes, precondition: WritePrecondition) -> impl Future<Output = ReqRes<impl ClientObject>> + Send {
+        async move {
+            let options = UploadOptions { precondition: Some(precondition), ..Default::default() };
+            self.upload_with_options(bucket, object_id, data, options).await
+        }
+    }
+    /// Stores a payload below `CLAIM_PREFIX` and returns a compact token signed with `key` (claim check pattern),
+    /// which can be passed around (example: in a queue message) instead of the payload
+    fn claim_check(&self, bucket: String, data: Bytes, key: &[u8]) -> impl Future<Output = ReqRes<String>> + Send {
+        async move {
+            claim::check(self, bucket, data, key).await
+        }
+    }
+    /// Downloads the payload of a claim check token once and removes it,
+    /// fails with `ClientError::InvalidClaim` if the token is forged or redeemed already (also by a concurrent call)
+    /// A redemption which failed without releasing the claim holds it until its lease ends (see `REDEMPTION_LEASE`)
+    fn redeem_claim(&self, bucket: String, token: String, key: &[u8]) -> impl Future<Output = ReqRes<Bytes>> + Send {
+        async move {
+            claim::redeem(self, bucket, &token, key).await
+        }
+    }
+    /// Uploads an object only if none of this name exists (claim by create: one of multiple concurrent writers succeeds),
+    /// fails with `ClientError::AlreadyExists` otherwise
+    fn upload_if_absent(&self, bucket: String, object_id: String, data: Bytes) -> impl Future<Output = ReqRes<impl ClientObject>> + Send {
+        async move {
+            let options = UploadOptions { precondition: Some(WritePrecondition::DoesNotExist), ..Default::default() };
+            self.upload_with_options(bucket.clone(), object_id.clone(), data, options).await.map_err(|e| match e.is_precondition_failed() {
+                true => ClientError::AlreadyExists.with_context(ErrorContext::new("upload_if_absent", Some(&bucket), Some(&object_id))),
+                false => e
+            })
+        }
+    }
+    /// Same as `static_download_bytes`, but only downloads if the conditions are met and can pin a version (see `DownloadOptions`)
+    fn download_with_options(&self, bucket: String, object_id: String, starting: Option<u64>, ending: Option<u64>, options: DownloadOptions) -> impl Future<Output = ReqRes<Bytes>> + Send;
+    /// Downloads an object unless the cached copy described by `validator` is still current (None then)
+    fn download_if_modified(&self, bucket: String, object_id: String, validator: CacheValidator) -> impl Future<Output = ReqRes<Option<Vec<u8>>>> + Send {
+        async move {
+            match self.download_with_options(bucket, object_id, None, None, validator.into()).await {
+                Ok(data) => Ok(Some(data.into())),
+                Err(e) if e.is_not_modified() => Ok(None),
+                Err(e) => Err(e)
+            }
+        }
+    }
+    /// Same as `static_download_bytes`, but returns a Vec
+    fn static_download_object(&self, bucket: String, object_id: String, starting: Option<u64>, ending: Option<u64>) -> impl Future<Output = ReqRes<Vec<u8>>> + Send {
+        async move {
+            Ok(self.static_download_bytes(bucket, object_id, starting, ending).await?.into())
+        }
+    }
+    /// Same as `static_upload_bytes`, but accepts a Vec
+    fn static_upload_object(&self, bucket: String, object_id: String, data: Vec<u8>) -> impl Future<Output = ReqRes<impl ClientObject>> + Send {
+        async move {
+            self.static_upload_bytes(bucket, object_id, data.into()).await
+        }
+    }
+    /// Downloads an object into a writer chunk by chunk (without buffering the whole object)
+    /// Returns the number of bytes written
+    fn download_to_writer(&self, bucket: String, object_id: String, starting: Option<u64>, ending: Option<u64>, w: impl AsyncWrite + Unpin + Send) -> impl Future<Output = ReqRes<u64>> + Send;
+    /// Uploads everything read from a reader to remote chunk by chunk
+    fn upload_from_reader(&self, bucket: String, object_id: String, r: impl AsyncRead + Unpin + Send + Sync + 'static) -> impl Future<Output = EmptyReqRes> + Send;
+    /// Gets a URL which grants access to an object without credentials (see `SignedUrlOptions`)
+    fn signed_url(&self, bucket: String, object_id: String, options: SignedUrlOptions) -> impl Future<Output = ReqRes<String>> + Send;
+    /// Gets a URL which can be used to upload data (with a PUT request)
+    fn url_upload_object(&self, bucket: String, object_id: String) -> impl Future<Output = ReqRes<String>> + Send {
+        async move {
+            self.signed_url(bucket, object_id, SignedUrlOptions::upload()).await
+        }
+    }
     /// Gets a URL which can be used to download data
-    async fn url_download_object(&self, bucket: String, object_id: String) -> ReqRes<String>;
+    fn url_download_object(&self, bucket: String, object_id: String) -> impl Future<Output = ReqRes<String>> + Send {
+        async move {
+            self.signed_url(bucket, object_id, SignedUrlOptions::default()).await
+        }
+    }
+    /// Gets a URL which can be used to delete an object (with a DELETE request)
+    fn url_delete_object(&self, bucket: String, object_id: String) -> impl Future<Output = ReqRes<String>> + Send {
+        async move {
+            self.signed_url(bucket, object_id, SignedUrlOptions::delete()).await
+        }
+    }
+    /// Gets an HTML form which lets browsers upload objects whose keys start with `key_prefix` directly (see `PresignedPost`)
+    /// Varies (see implementation): Google-Cloud, AWS-S3
+    fn presigned_post(&self, bucket: String, key_prefix: String, constraints: PostConstraints) -> impl Future<Output = ReqRes<PresignedPost>> + Send;
     /// Deletes a bucket
-    async fn remove_bucket(&self, bucket: String) -> EmptyReqRes;
+    fn remove_bucket(&self, bucket: String) -> impl Future<Output = EmptyReqRes> + Send;
     /// Deletes an object from a bucket
-    async fn remove_object(&self, bucket: String, object_id: String) -> EmptyReqRes;
+    fn remove_object(&self, bucket: String, object_id: String) -> impl Future<Output = EmptyReqRes> + Send;
     /// Creates a new bucket
-    async fn create_bucket(&self, bucket: String) -> ReqRes<impl ClientBucket>;
+    fn create_bucket(&self, bucket: String) -> impl Future<Output = ReqRes<impl ClientBucket>> + Send;
+    /// Creates a bucket, a bucket of this name which is accessible already counts as created by an earlier attempt
+    /// (AWS-S3 in us-east-1 accepts creating an own bucket again, which is reported as `Idempotent::Applied`)
+    fn create_bucket_idempotent(&self, bucket: String) -> impl Future<Output = ReqRes<Idempotent>> + Send {
+        async move {
+            idempotent::create_bucket(self, bucket).await
+        }
+    }
+    /// Returns the bucket if it is accessible, creates it otherwise (applying `options` only to a created bucket)
+    /// Fails with `ClientError::BucketOwnedByOther` if the name is taken by another account
+    fn ensure_bucket(&self, bucket: String, options: BucketOptions) -> impl Future<Output = ReqRes<impl ClientBucket>> + Send {
+        async move {
+            idempotent::ensure_bucket(self, bucket, options).await
+        }
+    }
     /// Copies an object from one position to another
     /// Varies (see implementation): AWS-S3
-    async fn copy_object(&self, src_bucket: String, src_object: String, dest_bucket: String, dest_object: String) -> ReqRes<impl ClientObject>;
-    /// List available buckets
-    async fn list_buckets(&self, max_results: Option<u32>) -> ReqRes<Vec<impl ClientBucket>>;
+    fn copy_object(&self, src_bucket: String, src_object: String, dest_bucket: String, dest_object: String) -> impl Future<Output = ReqRes<impl ClientObject>> + Send;
+    /// Same as `copy_object`, with `options` applied to the copy
+    /// Copying an object onto itself with another storage class changes the class in place (see `set_storage_class`)
+    fn copy_object_with_options(&self, src_bucket: String, src_object: String, dest_bucket: String, dest_object: String, options: CopyOptions) -> impl Future<Output = ReqRes<impl ClientObject>> + Send;
+    /// Moves an object to another storage class (example: 'GLACIER' on AWS-S3, 'ARCHIVE' on Google-Cloud) by copying it onto itself
+    fn set_storage_class(&self, bucket: String, object_id: String, storage_class: String) -> impl Future<Output = ReqRes<impl ClientObject>> + Send {
+        async move {
+            let options = CopyOptions { storage_class: Some(storage_class) };
+            self.copy_object_with_options(bucket.clone(), object_id.clone(), bucket, object_id, options).await
+        }
+    }
+    /// Requests a temporary copy of an archived object (AWS-S3 Glacier / Deep Archive), readable for `days` once restored
+    /// Requesting a restore which is in progress already succeeds
+    /// Varies (see implementation): Google-Cloud
+    fn restore_object(&self, bucket_name: String, object_name: String, days: u32, tier: RetrievalTier) -> impl Future<Output = EmptyReqRes> + Send;
+    /// Whether a restore of an archived object was requested and finished
+    fn restore_status(&self, bucket_name: String, object_name: String) -> impl Future<Output = ReqRes<RestoreStatus>> + Send;
+    /// Copies an object like `copy_object`, yielding the progress of long-running server-side copies
+    /// The last item reports the finished copy (`CopyProgress::done`), the stream ends after the first error
+    /// Varies (see implementation): AWS-S3, Google-Cloud (other clients only report the finished copy)
+    fn copy_object_progress(&self, src_bucket: String, src_object: String, dest_bucket: String, dest_object: String) -> impl Stream<Item = ReqRes<CopyProgress>> + Send {
+        stream::once(async move {
+            let copy = self.copy_object(src_bucket, src_object, dest_bucket, dest_object).await?;
+            Ok(CopyProgress::finished(copy.size()))
+        })
+    }
+    /// Moves (renames) an object inside a bucket by copying and then deleting it
+    /// The source is only deleted once the copy was verified to have the same size
+    fn move_object(&self, bucket: String, src_object: String, dest_object: String) -> impl Future<Output = ReqRes<impl ClientObject>> + Send {
+        async move {
+            let source_size = self.stat_object(bucket.clone(), src_object.clone()).await?.size();
+            self.copy_object(bucket.clone(), src_object.clone(), bucket.clone(), dest_object.clone()).await?;
+            let copy = self.stat_object(bucket.clone(), dest_object.clone()).await?;
+            if copy.size() != source_size {
+                return Err(ClientError::SizeMismatch { expected: source_size, actual: copy.size() }
+                    .with_context(ErrorContext::new("move_object", Some(&bucket), Some(&dest_object))))
+            }
+            self.remove_object(bucket, src_object).await?;
+            Ok(copy)
+        }
+    }
+    /// Uploads an object for review below `QUARANTINE_PREFIX`, it has to be promoted (or rejected) within `ttl`
+    fn upload_quarantined(&self, bucket: String, key: String, data: Bytes, ttl: Duration) -> impl Future<Output = ReqRes<impl ClientObject>> + Send {
+        async move {
+            quarantine::upload(self, bucket, key, data, ttl).await
+        }
+    }
+    /// Moves a quarantined upload to `key`, fails with `ClientError::QuarantineExpired` (and removes it) once its TTL passed
+    fn promote(&self, bucket: String, key: String) -> impl Future<Output = ReqRes<impl ClientObject>> + Send {
+        async move {
+            quarantine::promote(self, bucket, key).await
+        }
+    }
+    /// Removes a quarantined upload
+    fn reject(&self, bucket: String, key: String) -> impl Future<Output = EmptyReqRes> + Send {
+        async move {
+            self.remove_object(bucket, quarantine::quarantined(&key)).await
+        }
+    }
+    /// Removes all quarantined uploads whose TTL passed (the report only covers those)
+    /// A lifecycle rule on `QUARANTINE_PREFIX` (see `BucketSpec`) is a cheaper alternative for short TTLs measured in days
+    fn purge_quarantine(&self, bucket: String) -> impl Future<Output = ReqRes<BulkReport>> + Send {
+        async move {
+            quarantine::purge(self, bucket).await
+        }
+    }
+    /// Changes content type, cache control or custom metadata of an object without uploading its data again
+    /// Varies (see implementation): AWS-S3, Google-Cloud
+    fn update_object_metadata(&self, bucket: String, object_id: String, changes: MetadataChanges) -> impl Future<Output = ReqRes<impl ClientObject>> + Send;
+    /// Tags of an object (used by lifecycle rules and cost allocation)
+    /// Varies (see implementation): Google-Cloud
+    fn get_object_tags(&self, bucket: String, object_id: String) -> impl Future<Output = ReqRes<HashMap<String, String>>> + Send;
+    /// Replaces the tags of an object
+    /// Varies (see implementation): Google-Cloud
+    fn set_object_tags(&self, bucket: String, object_id: String, tags: HashMap<String, String>) -> impl Future<Output = EmptyReqRes> + Send;
+    /// Grants of an object, permissions without a unified equivalent are left out
+    /// Varies (see implementation): AWS-S3, Google-Cloud
+    fn get_object_acl(&self, bucket: String, object_id: String) -> impl Future<Output = ReqRes<Vec<AclGrant>>> + Send;
+    /// Replaces the access control of an object
+    /// Varies (see implementation): AWS-S3, Google-Cloud
+    fn set_object_acl(&self, bucket: String, object_id: String, acl: AccessControl) -> impl Future<Output = EmptyReqRes> + Send;
+    /// Appends data to an object (the object is created if it does not exist)
+    /// Emulated, see implementations: the object is replaced by a new one, concurrent appends or writes may get lost
+    fn append(&self, bucket: String, object_id: String, data: Bytes) -> impl Future<Output = EmptyReqRes> + Send;
+    /// Uploads an object which can be read by anyone (see `public_url`), with the given Cache-Control header
+    fn upload_public(&self, bucket: String, object_id: String, data: Bytes, content_type: Option<String>, cache_control: String) -> impl Future<Output = EmptyReqRes> + Send;
+    /// Unsigned URL of an object, only usable if the object can be read by anyone
+    fn public_url(&self, bucket: &str, object_id: &str) -> String;
+    /// Makes an existing object readable by anyone (see `public_url`)
+    /// Varies (see `set_object_acl`): AWS-S3, Google-Cloud
+    fn make_object_public(&self, bucket: String, object_id: String) -> impl Future<Output = EmptyReqRes> + Send {
+        async move {
+            self.set_object_acl(bucket, object_id, AccessControl::Canned(ObjectAcl::PublicRead)).await
+        }
+    }
+    /// Publishes immutable content: the object is named after the SHA-256 hash of `data` (below `prefix`),
+    /// cached for a year and publicly readable, so the returned URL never changes and never goes stale
+    /// Content which was published before is not uploaded again
+    fn publish(&self, bucket: String, prefix: String, data: Bytes, content_type: Option<String>) -> impl Future<Output = ReqRes<PublicUrl>> + Send {
+        async move {
+            let key = format!("{prefix}{}", publish::content_hash(&data));
+            let url = self.public_url(&bucket, &key);
+            if self.object_exists(bucket.clone(), key.clone()).await? {
+                return Ok(PublicUrl { url, key, uploaded: false })
+            }
+            self.upload_public(bucket, key.clone(), data, content_type, IMMUTABLE_CACHE_CONTROL.to_string()).await?;
+            Ok(PublicUrl { url, key, uploaded: true })
+        }
+    }
+    /// Deploys a static website from `local_dir`: only new and changed files are uploaded (publicly readable,
+    /// with content types and cache headers), assets before HTML and the entry points last,
+    /// so visitors see either the old or the new version of the site as long as the upload does not fail midway
+    /// The hashes of the deployed files are stored in `DEPLOY_MANIFEST` below the prefix
+    fn deploy_site(&self, local_dir: impl AsRef<Path> + Send + Sync, bucket_name: String, options: &DeployOptions) -> impl Future<Output = ReqRes<DeployReport>> + Send {
+        async move {
+            deploy::deploy(self, local_dir.as_ref(), bucket_name, options).await
+        }
+    }
+    /// Lists one page of the available buckets
+    /// `page_token` is the `next_token` of the previous page (None for the first page)
+    fn list_buckets(&self, max_results: Option<u32>, page_token: Option<Cursor>) -> impl Future<Output = ReqRes<ListPage<impl ClientBucket>>> + Send;
     /// Get a specific bucket
-    async fn get_bucket(&self, bucket_name: String) -> ReqRes<impl ClientBucket>;
+    fn get_bucket(&self, bucket_name: String) -> impl Future<Output = ReqRes<impl ClientBucket>> + Send;
     /// Get a specific object from a bucket
-    async fn get_object(&self, bucket_name: String, object_name: String) -> ReqRes<impl ClientBucket>;
-    /// List objects in a bucket
-    async fn list_objects(&self, bucket_name: String, max_results: Option<u32>) -> ReqRes<Vec<impl ClientObject>>;
+    fn get_object(&self, bucket_name: String, object_name: String) -> impl Future<Output = ReqRes<impl ClientObject>> + Send;
+    /// Fetches only the metadata of an object (no body is downloaded)
+    fn stat_object(&self, bucket_name: String, object_name: String) -> impl Future<Output = ReqRes<impl ClientObject>> + Send;
+    /// Fetches the metadata of multiple objects (example: to fill a file browser), returns the result for every key (in input order)
+    /// Runs up to `CONCURRENCY` requests at a time (google-cloud-storage does not expose GCS batch requests)
+    fn stat_many(&self, bucket_name: String, keys: Vec<String>) -> impl Future<Output = Vec<(String, ReqRes<impl ClientObject>)>> + Send {
+        async move {
+            let bucket_name = &bucket_name;
+            stream::iter(keys).map(|key| async move {
+                let result = self.stat_object(bucket_name.clone(), key.clone()).await;
+                (key, result)
+            }).buffered(CONCURRENCY).collect().await
+        }
+    }
+    /// Lists one page of the objects in a bucket
+    /// `page_token` is the `next_token` of the previous page (None for the first page)
+    fn list_objects(&self, bucket_name: String, max_results: Option<u32>, page_token: Option<Cursor>) -> impl Future<Output = ReqRes<ListPage<impl ClientObject>>> + Send;
+    /// Lists one page of the objects whose names start with `prefix`
+    /// `page_token` is the `next_token` of the previous page (None for the first page)
+    fn list_prefix(&self, bucket_name: String, prefix: String, page_token: Option<Cursor>) -> impl Future<Output = ReqRes<ListPage<impl ClientObject>>> + Send;
+    /// Same as `list_prefix`, but the provider only has to return `attributes`, which cuts the payload of listings of huge buckets
+    /// AWS-S3 listings (ListObjectsV2 without owners) are minimal already and ignore `attributes`
+    /// Varies (see implementation): Google-Cloud
+    fn list_projected(&self, bucket_name: String, prefix: String, _attributes: ListAttributes, page_token: Option<Cursor>) -> impl Future<Output = ReqRes<ListPage<impl ClientObject>>> + Send {
+        async move {
+            self.list_prefix(bucket_name, prefix, page_token).await
+        }
+    }
+    /// Lists one page of the objects directly below `prefix`: objects whose names contain `delimiter` after the prefix
+    /// are grouped into common prefixes (example: prefix 'a/' and delimiter '/' list 'a/b.txt' and the prefix 'a/c/')
+    fn list_delimited(&self, bucket_name: String, prefix: String, delimiter: String, page_token: Option<Cursor>) -> impl Future<Output = ReqRes<ListPage<impl ClientObject>>> + Send;
+    /// Lists up to `max_results` objects whose names start with `prefix` and sort after `start_after`
+    fn list_after(&self, bucket_name: String, prefix: String, start_after: String, max_results: u32) -> impl Future<Output = ReqRes<Vec<impl ClientObject>>> + Send;
+    /// Lists one page of all versions (including deleted ones) of the objects whose names start with `prefix`
+    /// Only useful for buckets with versioning enabled
+    fn list_versions(&self, bucket_name: String, prefix: String, page_token: Option<Cursor>) -> impl Future<Output = ReqRes<ListPage<ObjectVersion>>> + Send;
+    /// Lists all versions (including delete markers) of a single object, newest first
+    fn list_object_versions(&self, bucket_name: String, object_name: String) -> impl Future<Output = ReqRes<Vec<ObjectVersion>>> + Send {
+        async move {
+            let mut versions = vec![];
+            let mut page_token = None;
+            loop {
+                // The object's name is listed as a prefix, which also matches longer names
+                let page = self.list_versions(bucket_name.clone(), object_name.clone(), page_token).await?;
+                versions.extend(page.items.into_iter().filter(|t| t.name == object_name));
+                page_token = page.next_token;
+                if page_token.is_none() {
+                    versions.sort_by_key(|t| std::cmp::Reverse(t.created));
+                    return Ok(versions)
+                }
+            }
+        }
+    }
+    /// Downloads a historical version of an object (`ObjectVersion::version`)
+    fn download_version(&self, bucket_name: String, object_name: String, version: String) -> impl Future<Output = ReqRes<Bytes>> + Send {
+        async move {
+            let options = DownloadOptions { version: Some(version), ..Default::default() };
+            self.download_with_options(bucket_name, object_name, None, None, options).await
+        }
+    }
+    /// Copies a historical version (see `list_object_versions`) back as the live object, undoing later overwrites
+    /// The restored copy is a new version, the versions in between are kept
+    fn restore_object_version(&self, bucket_name: String, object_name: String, version: String) -> impl Future<Output = ReqRes<impl ClientObject>> + Send;
+    /// Reconstructs which object versions were live at `timestamp` ("browse the bucket as it was")
+    fn list_objects_as_of(&self, bucket_name: String, prefix: String, timestamp: SystemTime) -> impl Future<Output = ReqRes<Vec<ObjectVersion>>> + Send {
+        async move {
+            let mut all = vec![];
+            let mut page_token = None;
+            loop {
+                let page = self.list_versions(bucket_name.clone(), prefix.clone(), page_token).await?;
+                all.extend(page.items);
+                page_token = page.next_token;
+                if page_token.is_none() {
+                    return Ok(versions::live_at(all, timestamp))
+                }
+            }
+        }
+    }
+    /// Deletes multiple objects from a bucket, returns the result for every key (in input order)
+    /// Varies (see implementation): AWS-S3
+    fn remove_objects(&self, bucket_name: String, keys: Vec<String>) -> impl Future<Output = ReqRes<Vec<(String, EmptyReqRes)>>> + Send {
+        async move {
+            let bucket_name = &bucket_name;
+            Ok(stream::iter(keys).map(|key| async move {
+                let result = self.remove_object(bucket_name.clone(), key.clone()).await;
+                (key, result)
+            }).buffered(CONCURRENCY).collect().await)
+        }
+    }
+    /// Deletes every object whose name starts with `prefix` ("rm -r")
+    /// Failures of single objects are collected in the report instead of aborting
+    fn remove_prefix(&self, bucket_name: String, prefix: String) -> impl Future<Output = ReqRes<BulkReport>> + Send {
+        async move {
+            let mut report = BulkReport::default();
+            let mut page_token = None;
+            loop {
+                let page = self.list_prefix(bucket_name.clone(), prefix.clone(), page_token).await?;
+                let keys = page.items.iter().map(|t| t.name()).collect();
+                report.extend(self.remove_objects(bucket_name.clone(), keys).await?);
+                page_token = page.next_token;
+                if page_token.is_none() {
+                    return Ok(report)
+                }
+            }
+        }
+    }
+    /// Streams all objects matching `options`, requesting further pages as the stream is consumed
+    /// The stream ends after the first error
+    fn list_objects_stream(&self, bucket_name: String, options: ListOptions) -> impl Stream<Item = ReqRes<impl ClientObject>> + Send {
+        let (prefix, attributes) = (options.prefix.unwrap_or_default(), options.attributes);
+        // None once the last page was fetched, Some(token) otherwise
+        stream::unfold(Some(None), move |page_token: Option<Option<Cursor>>| {
+            let (bucket_name, prefix) = (bucket_name.clone(), prefix.clone());
+            async move {
+                match self.list_projected(bucket_name, prefix, attributes, page_token?).await {
+                    Ok(page) => Some((Ok(page.items), page.next_token.map(Some))),
+                    Err(e) => Some((Err(e), None))
+                }
+            }
+        }).flat_map(|page| match page {
+            Ok(items) => stream::iter(items.into_iter().map(Ok)).left_stream(),
+            Err(e) => stream::once(async { Err(e) }).right_stream()
+        }).take(options.limit.map(|t| t as usize).unwrap_or(usize::MAX))
+    }
+    /// Streams all objects whose names match a glob pattern (example: 'logs/2024-*/*.json.gz', see `glob_match`)
+    /// The part before the first wildcard is listed as a prefix, the rest is matched while streaming
+    fn list_glob(&self, bucket_name: String, pattern: String) -> impl Stream<Item = ReqRes<impl ClientObject>> + Send {
+        let options = ListOptions { prefix: Some(glob_prefix(&pattern)), ..Default::default() };
+        self.list_objects_stream(bucket_name, options)
+            .filter(move |t| std::future::ready(match t {
+                Ok(t) => glob_match(&pattern, &t.name()),
+                // Errors are passed on
+                Err(_) => true
+            }))
+    }
+    /// Streams all objects matching `filter`, conditions are evaluated while the listing is streamed
+    /// Objects whose listing lacks the content type (AWS-S3) are fetched with `stat_object` if the filter requires one
+    fn find(&self, bucket_name: String, filter: Filter) -> impl Stream<Item = ReqRes<impl ClientObject>> + Send {
+        let options = ListOptions { prefix: filter.prefix.clone(), ..Default::default() };
+        self.list_objects_stream(bucket_name.clone(), options).filter_map(move |t| {
+            let (bucket_name, filter) = (bucket_name.clone(), filter.clone());
+            async move {
+                match t {
+                    Ok(t) => find::check(self, bucket_name, &filter, t).await,
+                    Err(e) => Some(Err(e))
+                }
+            }
+        })
+    }
+    /// Cheaply samples about `n` object names spread across the keyspace below `prefix`, without listing everything:
+    /// folders are found with delimited listings, then each is probed with a few `list_after` jumps
+    /// The sample is biased towards sparse regions of the keyspace, it characterizes a bucket but is not uniform
+    fn sample_objects(&self, bucket_name: String, prefix: String, n: usize) -> impl Future<Output = ReqRes<Vec<String>>> + Send {
+        async move {
+            sample::sample(self, bucket_name, prefix, n).await
+        }
+    }
+    /// Lists the names of all objects whose names start with `prefix` (following all pages)
+    fn list_prefix_names(&self, bucket_name: String, prefix: String) -> impl Future<Output = ReqRes<Vec<String>>> + Send {
+        async move {
+            let mut names = vec![];
+            let mut page_token = None;
+            loop {
+                let page = self.list_projected(bucket_name.clone(), prefix.clone(), ListAttributes::Names, page_token).await?;
+                names.extend(page.items.iter().map(|t| t.name()));
+                page_token = page.next_token;
+                if page_token.is_none() {
+                    return Ok(names)
+                }
+            }
+        }
+    }
+    /// Counts the objects below `prefix` and sums up their sizes (following all pages)
+    fn prefix_size(&self, bucket_name: String, prefix: String) -> impl Future<Output = ReqRes<PrefixStats>> + Send {
+        async move {
+            let mut stats = PrefixStats::default();
+            let mut page_token = None;
+            loop {
+                let page = self.list_prefix(bucket_name.clone(), prefix.clone(), page_token).await?;
+                for object in &page.items {
+                    stats.add(object.size());
+                }
+                page_token = page.next_token;
+                if page_token.is_none() {
+                    return Ok(stats)
+                }
+            }
+        }
+    }
+    /// Like `prefix_size`, but the folders ('/' delimited) directly below `prefix` are listed concurrently
+    /// Only faster for prefixes whose objects are spread over several folders
+    fn prefix_size_concurrent(&self, bucket_name: String, prefix: String, concurrency: usize) -> impl Future<Output = ReqRes<PrefixStats>> + Send {
+        async move {
+            let mut stats = PrefixStats::default();
+            let mut folders = vec![];
+            let mut page_token = None;
+            loop {
+                let page = self.list_delimited(bucket_name.clone(), prefix.clone(), "/".to_string(), page_token).await?;
+                for object in &page.items {
+                    stats.add(object.size());
+                }
+                folders.extend(page.prefixes);
+                page_token = page.next_token;
+                if page_token.is_none() {
+                    break
+                }
+            }
+            let bucket_name = &bucket_name;
+            let results: Vec<ReqRes<PrefixStats>> = stream::iter(folders)
+                .map(|folder| self.prefix_size(bucket_name.clone(), folder))
+                .buffer_unordered(concurrency.max(1)).collect().await;
+            for result in results {
+                stats.merge(result?);
+            }
+            Ok(stats)
+        }
+    }
+    /// Server-side copies every object under `src_prefix` to `dest_prefix` in another (or the same) bucket
+    /// All keys are listed before copying, so the destination may lie inside the source
+    fn copy_prefix(&self, src_bucket: String, src_prefix: String, dest_bucket: String, dest_prefix: String) -> impl Future<Output = ReqRes<BulkReport>> + Send {
+        async move {
+            let keys = self.list_prefix_names(src_bucket.clone(), src_prefix.clone()).await?;
+            let (src_bucket, src_prefix, dest_bucket, dest_prefix) = (&src_bucket, &src_prefix, &dest_bucket, &dest_prefix);
+            let results = stream::iter(keys).map(|key| async move {
+                let dest = format!("{dest_prefix}{}", key.strip_prefix(src_prefix.as_str()).unwrap_or(&key));
+                let result = self.copy_object(src_bucket.clone(), key.clone(), dest_bucket.clone(), dest).await.map(|_| ());
+                (key, result)
+            }).buffer_unordered(CONCURRENCY).collect().await;
+            let mut report = BulkReport::default();
+            report.extend(results);
+            Ok(report)
+        }
+    }
+    /// Moves every object under `old_prefix` to `new_prefix` ("folder rename") using `move_object`
+    /// All keys are listed before moving, so `new_prefix` may lie inside `old_prefix`
+    fn rename_prefix(&self, bucket_name: String, old_prefix: String, new_prefix: String) -> impl Future<Output = ReqRes<BulkReport>> + Send {
+        async move {
+            let keys = self.list_prefix_names(bucket_name.clone(), old_prefix.clone()).await?;
+            let (bucket_name, old_prefix, new_prefix) = (&bucket_name, &old_prefix, &new_prefix);
+            let results = stream::iter(keys).map(|key| async move {
+                let dest = format!("{new_prefix}{}", key.strip_prefix(old_prefix.as_str()).unwrap_or(&key));
+                let result = self.move_object(bucket_name.clone(), key.clone(), dest).await.map(|_| ());
+                (key, result)
+            }).buffer_unordered(CONCURRENCY).collect().await;
+            let mut report = BulkReport::default();
+            report.extend(results);
+            Ok(report)
+        }
+    }
+    /// Compares the objects below `primary_prefix` with the ones derived from them below `derived_prefix`
+    /// (example: originals and their thumbnails), `key_mapping` maps a source's key to the key of its derived object
+    /// Both prefixes are listed completely, which takes a while for huge prefixes
+    fn find_orphans(&self, bucket_name: String, primary_prefix: String, derived_prefix: String, key_mapping: impl Fn(&str) -> String + Send + Sync) -> impl Future<Output = ReqRes<Orphans>> + Send {
+        async move {
+            orphans::find(self, bucket_name, primary_prefix, derived_prefix, key_mapping).await
+        }
+    }
+    /// Checks whether an object exists, a missing bucket is reported as an error
+    fn object_exists(&self, bucket_name: String, object_name: String) -> impl Future<Output = ReqRes<bool>> + Send {
+        async move {
+            match self.stat_object(bucket_name, object_name).await {
+                Ok(_) => Ok(true),
+                Err(e) if e.is_not_found() => Ok(false),
+                Err(e) => Err(e)
+            }
+        }
+    }
+    /// Checks whether a bucket exists
+    fn bucket_exists(&self, bucket_name: String) -> impl Future<Output = ReqRes<bool>> + Send {
+        async move {
+            match self.get_bucket(bucket_name).await {
+                Ok(_) => Ok(true),
+                Err(e) if e.is_not_found() => Ok(false),
+                Err(e) => Err(e)
+            }
+        }
+    }
+    /// Reads the legal hold and retention of an object
+    fn get_object_lock(&self, bucket_name: String, object_name: String) -> impl Future<Output = ReqRes<ObjectLock>> + Send;
+    /// Sets (or with None removes) the retention of an object, only longer retentions can replace a compliance retention
+    /// AWS-S3 requires Object Lock to be enabled on the bucket, removing a governance retention bypasses it (which requires permission to)
+    /// Varies (see implementation): Google-Cloud
+    fn set_object_retention(&self, bucket_name: String, object_name: String, retention: Option<Retention>) -> impl Future<Output = EmptyReqRes> + Send;
+    /// Sets or releases the legal hold of an object (GCS: temporary hold), which blocks deletion regardless of the retention
+    fn set_legal_hold(&self, bucket_name: String, object_name: String, on: bool) -> impl Future<Output = EmptyReqRes> + Send;
+    /// Lists one page of the compliance relevant state (encryption, holds, retention, ...) of the objects in a bucket
+    fn compliance_records(&self, bucket_name: String, page_token: Option<Cursor>) -> impl Future<Output = ReqRes<ListPage<ComplianceRecord>>> + Send;
+    /// Rules of the provider a new bucket's name violates
+    fn bucket_name_problems(&self, bucket_name: &str) -> Vec<BucketNameProblem>;
+    /// Checks a name for a new bucket against the provider's naming rules and probes whether it is taken (bucket names are global),
+    /// up to `suggestions` available variants are looked for if the name cannot be used
+    fn check_bucket_name(&self, bucket_name: String, suggestions: usize) -> impl Future<Output = ReqRes<BucketNameCheck>> + Send {
+        async move {
+            naming::check(self, bucket_name, suggestions).await
+        }
+    }
+    /// Checks every object of a bucket against `policy`
+    /// The report contains all objects which are under hold / retention or violate the policy
+    fn compliance_report(&self, bucket_name: String, policy: &CompliancePolicy) -> impl Future<Output = ReqRes<ComplianceReport>> + Send {
+        async move {
+            let mut report = ComplianceReport::new(bucket_name.clone());
+            let mut page_token = None;
+            loop {
+                let page = self.compliance_records(bucket_name.clone(), page_token).await?;
+                for record in page.items {
+                    report.add(record, policy);
+                }
+                page_token = page.next_token;
+                if page_token.is_none() {
+                    return Ok(report)
+                }
+            }
+        }
+    }
+    /// Reads the current configuration (versioning, lifecycle, CORS, default encryption) of a bucket
+    fn get_bucket_spec(&self, bucket_name: String) -> impl Future<Output = ReqRes<BucketSpec>> + Send;
+    /// Reads the lifecycle rules of a bucket (bundled S3 rules are split into one rule per action)
+    fn get_lifecycle_rules(&self, bucket_name: String) -> impl Future<Output = ReqRes<Vec<LifecycleRule>>> + Send {
+        async move {
+            Ok(self.get_bucket_spec(bucket_name).await?.lifecycle_rules.unwrap_or_default())
+        }
+    }
+    /// Replaces all lifecycle rules of a bucket, an empty list removes the lifecycle configuration
+    fn set_lifecycle_rules(&self, bucket_name: String, rules: Vec<LifecycleRule>) -> impl Future<Output = EmptyReqRes> + Send;
+    /// Labels of a bucket (AWS-S3 bucket tags, Google-Cloud labels), used for cost allocation
+    fn get_bucket_labels(&self, bucket_name: String) -> impl Future<Output = ReqRes<HashMap<String, String>>> + Send;
+    /// Replaces the labels of a bucket
+    /// Varies (see implementation): Google-Cloud
+    fn set_bucket_labels(&self, bucket_name: String, labels: HashMap<String, String>) -> impl Future<Output = EmptyReqRes> + Send;
+    /// Reads the access policy of a bucket (None if it has none)
+    /// AWS-S3 returns `BucketPolicy::S3`, Google-Cloud `BucketPolicy::Iam`
+    fn get_bucket_policy(&self, bucket_name: String) -> impl Future<Output = ReqRes<Option<BucketPolicy>>> + Send;
+    /// Replaces the access policy of a bucket, policies of another provider fail with `ClientError::Unsupported`
+    fn set_bucket_policy(&self, bucket_name: String, policy: BucketPolicy) -> impl Future<Output = EmptyReqRes> + Send;
+    /// Reports how a bucket's configuration drifts from `spec` without changing anything
+    fn diff_spec(&self, bucket_name: String, spec: &BucketSpec) -> impl Future<Output = ReqRes<Vec<SpecDrift>>> + Send {
+        async move {
+            Ok(spec.diff(&self.get_bucket_spec(bucket_name).await?))
+        }
+    }
+    /// Attempts every operation of `Capability` on a bucket (writing only objects under `PROBE_PREFIX`, which are removed again)
+    /// and reports which are allowed and which are denied
+    fn probe_permissions(&self, bucket_name: String) -> impl Future<Output = PermissionReport> + Send {
+        async move {
+            permissions::probe(self, bucket_name).await
+        }
+    }
+    /// Validates a (customer provided) bucket: probes list / put / get / delete / sign (see `probe_permissions`)
+    /// and checks its location and default encryption against `requirements`
+    /// Failed probes are reported as problems instead of errors
+    /// Note: Signing URLs happens locally for most providers, so a successful sign does not prove the URL is usable
+    fn onboard_bucket(&self, bucket_name: String, requirements: &OnboardingRequirements) -> impl Future<Output = OnboardingReport> + Send {
+        async move {
+            onboarding::onboard(self, bucket_name, requirements).await
+        }
+    }
 }
 
-#[allow(async_fn_in_trait)]
-pub trait ClientObject {
+pub trait ClientObject: Send + Sync {
     /// Returns the total byte-size of the object
     fn size(&self) -> u64;
     /// Name of the bucket the object is in
@@ -53,10 +774,21 @@ pub trait ClientObject {
     fn name(&self) -> String;
     /// Object's content type (if available)
     fn content_type(&self) -> Option<String>;
+    /// When the object was last written (if available)
+    fn last_modified(&self) -> Option<SystemTime>;
+    /// Entity tag, changes whenever the content changes (if available)
+    fn etag(&self) -> Option<String>;
+    /// Provider specific name of the storage class (example: 'STANDARD', 'NEARLINE'; if available)
+    fn storage_class(&self) -> Option<String>;
+    /// User defined key-value pairs stored with the object (empty if not available, e.g. in listings)
+    fn custom_metadata(&self) -> HashMap<String, String>;
+    /// Integrity checksum reported by the provider, prefixed with its algorithm (example: 'crc32c:yZRlqg==', if available)
+    fn checksum(&self) -> Option<String>;
+    /// Version a conditional write compares against (ETag on AWS-S3, generation on Google-Cloud; if available)
+    fn version(&self) -> Option<String>;
 }
 
-#[allow(async_fn_in_trait)]
-pub trait ClientBucket {
+pub trait ClientBucket: Send + Sync {
     /// ID of the bucket (often etag; often same as name)
     fn id(&self) -> String;
     /// Name of the bucket (often etag; often same as name)
@@ -66,12 +798,359 @@ pub trait ClientBucket {
 }
 
 /// A wrapper around errors from different clients
-/// TODO: Create a unified Access point
+/// The original error of the provider is kept, `ClientError::kind` gives a provider independent category
+#[derive(Debug)]
 pub enum ClientError {
     #[cfg(feature = "google_cloud")]
     GoogleCloudClient(GoogleCloudError),
+    /// Boxed, the errors of the AWS SDK would make every `ClientError` (and `ReqRes`) several times larger
     #[cfg(feature = "aws_s3")]
-    AWSClient(AWSError)
+    AWSClient(Box<AWSError>),
+    #[cfg(feature = "encryption")]
+    Encryption(EncryptionError),
+    /// Reading from or writing to a local stream failed
+    Io(std::io::Error),
+    /// Data stored by this crate (e.g. checkpoints) could not be (de)serialized
+    Serialization(serde_json::Error),
+    /// The expected version of an object never became visible
+    VersionMismatch { expected: String, actual: String },
+    /// A written object does not have the expected size
+    SizeMismatch { expected: u64, actual: u64 },
+    /// The provider did not confirm the checksum of an upload, or downloaded data does not match the stored one
+    /// (base64 CRC32C, see `Defaults::strict_checksums`)
+    ChecksumMismatch { expected: String, actual: Option<String> },
+    /// Two replicas of an object differ
+    ReplicaDivergence(ReplicaStatus),
+    /// The provider does not support the requested operation (the reason is included)
+    Unsupported(&'static str),
+    /// A cursor could not be decoded or its signature is invalid
+    InvalidCursor,
+    /// A storage URI is malformed (see `StorageUri::parse`)
+    InvalidUri(String),
+    /// A `StorageConfig` could not be parsed or names an unknown provider (the reason is included)
+    InvalidConfig(String),
+    /// A claim check token is forged or was redeemed already
+    InvalidClaim,
+    /// The object was not downloaded because it did not change (`DownloadOptions::if_none_match` / `if_modified_since`)
+    NotModified,
+    /// A condition of a download (`DownloadOptions::if_match`) or write (`UploadOptions::precondition`) does not hold
+    PreconditionFailed,
+    /// The object or bucket does not exist (reported by backends without an error type of their own, e.g. `LocalClient`)
+    NotFound,
+    /// A create-only write found an existing object (see `ClientInterface::upload_if_absent`)
+    AlreadyExists,
+    /// The bucket name is taken by another account (see `ClientInterface::ensure_bucket`)
+    BucketOwnedByOther,
+    /// A quarantined upload outlived its TTL before it was promoted (it is removed)
+    QuarantineExpired,
+    /// An error with information about the call which caused it
+    Context(Box<ClientError>, ErrorContext)
+}
+
+/// Describes the call an error originated from
+#[derive(Debug, Clone, PartialEq)]
+pub struct ErrorContext {
+    /// Name of the operation (example: 'get_object')
+    pub operation: &'static str,
+    pub bucket: Option<String>,
+    pub key: Option<String>,
+    /// Number of the attempt which failed, starting at 1
+    pub attempt: u32,
+}
+
+impl std::fmt::Display for ErrorContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.operation)?;
+        if let Some(bucket) = &self.bucket {
+            write!(f, " on {bucket}")?;
+            if let Some(key) = &self.key {
+                write!(f, "/{key}")?;
+            }
+        }
+        if self.attempt > 1 {
+            write!(f, " (attempt {})", self.attempt)?;
+        }
+        Ok(())
+    }
+}
+
+impl ErrorContext {
+    pub fn new(operation: &'static str, bucket: Option<&str>, key: Option<&str>) -> Self {
+        Self { operation, bucket: bucket.map(|t| t.to_string()), key: key.map(|t| t.to_string()), attempt: 1 }
+    }
+}
+
+impl ClientError {
+    /// Whether the error was caused by a missing object or bucket
+    pub fn is_not_found(&self) -> bool {
+        match self.inner() {
+            #[cfg(feature = "google_cloud")]
+            ClientError::GoogleCloudClient(e) => e.is_not_found(),
+            #[cfg(feature = "aws_s3")]
+            ClientError::AWSClient(e) => e.is_not_found(),
+            ClientError::NotFound => true,
+            _ => false
+        }
+    }
+
+    /// Whether the request was rejected because of missing permissions
+    pub fn is_permission_denied(&self) -> bool {
+        match self.inner() {
+            #[cfg(feature = "google_cloud")]
+            ClientError::GoogleCloudClient(e) => e.is_permission_denied(),
+            #[cfg(feature = "aws_s3")]
+            ClientError::AWSClient(e) => e.is_permission_denied(),
+            _ => false
+        }
+    }
+
+    /// Attaches context to the error, replacing any existing context
+    pub fn with_context(self, context: ErrorContext) -> Self {
+        ClientError::Context(Box::new(self.into_inner()), context)
+    }
+
+    /// Records the attempts a retried request took, the operation is filled in by `Context::context`
+    #[cfg(any(feature = "google_cloud", feature = "aws_s3"))]
+    pub(crate) fn attempted(self, attempt: u32) -> Self {
+        if attempt <= 1 {
+            return self
+        }
+        let context = self.context().cloned().unwrap_or_else(|| ErrorContext::new("request", None, None));
+        self.with_context(ErrorContext { attempt, ..context })
+    }
+
+    /// Whether a conditional download was skipped because the object did not change
+    pub fn is_not_modified(&self) -> bool {
+        matches!(self.inner(), ClientError::NotModified)
+    }
+
+    /// Whether a condition of the request does not hold
+    pub fn is_precondition_failed(&self) -> bool {
+        match self.inner() {
+            #[cfg(feature = "google_cloud")]
+            ClientError::GoogleCloudClient(e) => e.is_precondition_failed(),
+            #[cfg(feature = "aws_s3")]
+            ClientError::AWSClient(e) => e.is_precondition_failed(),
+            ClientError::PreconditionFailed => true,
+            _ => false
+        }
+    }
+
+    /// Whether the provider rejected the request because of its rate limits
+    pub fn is_throttled(&self) -> bool {
+        match self.inner() {
+            #[cfg(feature = "google_cloud")]
+            ClientError::GoogleCloudClient(e) => e.is_throttled(),
+            #[cfg(feature = "aws_s3")]
+            ClientError::AWSClient(e) => e.is_throttled(),
+            _ => false
+        }
+    }
+
+    /// Whether the request did not complete in time
+    pub fn is_timeout(&self) -> bool {
+        match self.inner() {
+            #[cfg(feature = "google_cloud")]
+            ClientError::GoogleCloudClient(e) => e.is_timeout(),
+            #[cfg(feature = "aws_s3")]
+            ClientError::AWSClient(e) => e.is_timeout(),
+            ClientError::Io(e) => e.kind() == std::io::ErrorKind::TimedOut,
+            _ => false
+        }
+    }
+
+    /// Whether the provider could not be reached (the request may not have been sent)
+    pub fn is_network(&self) -> bool {
+        match self.inner() {
+            #[cfg(feature = "google_cloud")]
+            ClientError::GoogleCloudClient(e) => e.is_network(),
+            #[cfg(feature = "aws_s3")]
+            ClientError::AWSClient(e) => e.is_network(),
+            ClientError::Io(e) => matches!(e.kind(), std::io::ErrorKind::ConnectionReset | std::io::ErrorKind::ConnectionAborted | std::io::ErrorKind::BrokenPipe),
+            _ => false
+        }
+    }
+
+    /// Whether the provider failed to handle the request (example: an internal error or an unavailable backend)
+    pub fn is_server_error(&self) -> bool {
+        match self.inner() {
+            #[cfg(feature = "google_cloud")]
+            ClientError::GoogleCloudClient(e) => e.is_server_error(),
+            #[cfg(feature = "aws_s3")]
+            ClientError::AWSClient(e) => e.is_server_error(),
+            _ => false
+        }
+    }
+
+    /// Whether repeating the request may succeed (throttling, timeouts, network failures and errors of the provider)
+    /// Requests are retried according to `Defaults::retry` already, this tells whether retrying later makes sense
+    pub fn is_retryable(&self) -> bool {
+        self.is_throttled() || self.is_timeout() || self.is_network() || self.is_server_error()
+    }
+
+    /// Whether an object or bucket of this name exists already
+    pub fn is_already_exists(&self) -> bool {
+        match self.inner() {
+            #[cfg(feature = "google_cloud")]
+            ClientError::GoogleCloudClient(e) => e.is_already_exists(),
+            #[cfg(feature = "aws_s3")]
+            ClientError::AWSClient(e) => e.is_already_exists(),
+            ClientError::AlreadyExists | ClientError::BucketOwnedByOther => true,
+            _ => false
+        }
+    }
+
+    /// The error without its context
+    pub fn inner(&self) -> &ClientError {
+        match self {
+            ClientError::Context(e, _) => e.inner(),
+            _ => self
+        }
+    }
+
+    /// Takes the error out of its context
+    pub fn into_inner(self) -> ClientError {
+        match self {
+            ClientError::Context(e, _) => e.into_inner(),
+            _ => self
+        }
+    }
+
+    pub fn context(&self) -> Option<&ErrorContext> {
+        match self {
+            ClientError::Context(_, context) => Some(context),
+            _ => None
+        }
+    }
+
+    /// Operation which failed
+    pub fn operation(&self) -> Option<&'static str> {
+        self.context().map(|t| t.operation)
+    }
+
+    /// Bucket the failed operation was working on
+    pub fn bucket(&self) -> Option<&str> {
+        self.context().and_then(|t| t.bucket.as_deref())
+    }
+
+    /// Key of the object the failed operation was working on
+    pub fn key(&self) -> Option<&str> {
+        self.context().and_then(|t| t.key.as_deref())
+    }
+
+    /// Attempt which failed (1 if the operation was not retried)
+    pub fn attempt(&self) -> Option<u32> {
+        self.context().map(|t| t.attempt)
+    }
+}
+
+impl std::fmt::Display for ClientError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            #[cfg(feature = "google_cloud")]
+            ClientError::GoogleCloudClient(e) => write!(f, "google cloud storage: {e}"),
+            #[cfg(feature = "aws_s3")]
+            ClientError::AWSClient(e) => write!(f, "aws s3: {e}"),
+            #[cfg(feature = "encryption")]
+            ClientError::Encryption(e) => write!(f, "encryption: {e}"),
+            ClientError::Io(e) => write!(f, "io: {e}"),
+            ClientError::Serialization(e) => write!(f, "serialization: {e}"),
+            ClientError::VersionMismatch { expected, actual } => write!(f, "expected version {expected}, found {actual}"),
+            ClientError::SizeMismatch { expected, actual } => write!(f, "expected {expected} bytes, found {actual}"),
+            ClientError::ChecksumMismatch { expected, actual } => match actual {
+                Some(actual) => write!(f, "expected checksum {expected}, found {actual}"),
+                None => write!(f, "expected checksum {expected}, the provider reported none")
+            },
+            ClientError::ReplicaDivergence(status) => write!(f, "replicas differ: {status:?}"),
+            ClientError::Unsupported(reason) => write!(f, "unsupported: {reason}"),
+            ClientError::InvalidCursor => f.write_str("invalid cursor"),
+            ClientError::InvalidUri(uri) => write!(f, "invalid storage URI '{uri}'"),
+            ClientError::InvalidConfig(reason) => write!(f, "invalid storage configuration: {reason}"),
+            ClientError::InvalidClaim => f.write_str("invalid or redeemed claim"),
+            ClientError::NotModified => f.write_str("not modified"),
+            ClientError::PreconditionFailed => f.write_str("precondition failed"),
+            ClientError::NotFound => f.write_str("not found"),
+            ClientError::AlreadyExists => f.write_str("already exists"),
+            ClientError::BucketOwnedByOther => f.write_str("the bucket is owned by another account"),
+            ClientError::QuarantineExpired => f.write_str("the quarantined upload expired"),
+            ClientError::Context(e, context) => write!(f, "{context}: {e}")
+        }
+    }
+}
+
+impl std::error::Error for ClientError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            #[cfg(feature = "google_cloud")]
+            ClientError::GoogleCloudClient(e) => Some(e),
+            #[cfg(feature = "aws_s3")]
+            ClientError::AWSClient(e) => Some(e.as_ref()),
+            #[cfg(feature = "encryption")]
+            ClientError::Encryption(e) => Some(e),
+            ClientError::Io(e) => Some(e),
+            ClientError::Serialization(e) => Some(e),
+            ClientError::Context(e, _) => e.source(),
+            _ => None
+        }
+    }
+}
+
+/// Attaches an ErrorContext to the error of a result
+pub(crate) trait Context<T> {
+    fn context(self, operation: &'static str, bucket: &str, key: Option<&str>) -> ReqRes<T>;
+}
+
+impl<T, E: Into<ClientError>> Context<T> for Result<T, E> {
+    fn context(self, operation: &'static str, bucket: &str, key: Option<&str>) -> ReqRes<T> {
+        self.map_err(|e| {
+            let e = e.into();
+            // Keeps the attempts recorded by the retrying backend (see `ClientError::attempted`)
+            let attempt = e.attempt().unwrap_or(1);
+            e.with_context(ErrorContext { attempt, ..ErrorContext::new(operation, Some(bucket), key) })
+        })
+    }
+}
+
+impl From<std::io::Error> for ClientError {
+    fn from(value: std::io::Error) -> Self {
+        ClientError::Io(value)
+    }
+}
+
+impl From<serde_json::Error> for ClientError {
+    fn from(value: serde_json::Error) -> Self {
+        ClientError::Serialization(value)
+    }
+}
+
+/// Object attributes a listing has to return (see `ClientInterface::list_projected`), attributes which are not requested
+/// may be left out by the provider, so `ClientObject` methods returning them are unreliable
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ListAttributes {
+    Names,
+    NamesAndSizes,
+    /// Everything a regular listing returns
+    #[default]
+    Full,
+}
+
+/// Options of `ClientInterface::list_objects_stream`
+#[derive(Debug, Clone, Default)]
+pub struct ListOptions {
+    /// Only objects whose names start with this prefix are listed
+    pub prefix: Option<String>,
+    /// Maximum number of objects, no further pages are requested once it is reached
+    pub limit: Option<u64>,
+    pub attributes: ListAttributes,
+}
+
+/// A single page of a listing
+pub struct ListPage<T> {
+    pub items: Vec<T>,
+    /// Common prefixes ("folders") of a delimited listing, always empty otherwise
+    pub prefixes: Vec<String>,
+    /// Cursor to request the next page with, None on the last page
+    pub next_token: Option<Cursor>,
 }
 
 pub type ReqRes<T> = Result<T, ClientError>;
@@ -0,0 +1,560 @@
+use std::collections::{BTreeSet, HashMap};
+use std::io::{ErrorKind, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use bytes::Bytes;
+use tokio::fs::OpenOptions;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeekExt, AsyncWrite, AsyncWriteExt};
+use crate::encoding::resolve_range;
+use crate::{guess_content_type, AccessControl, AclGrant, BucketNameProblem, BucketPolicy, BucketSpec, ClientBucket, ClientError, ClientInterface, ClientObject, ComplianceRecord, Context, CopyOptions, Cursor, DownloadOptions, EmptyReqRes, LifecycleRule, ListAttributes, ListPage, MetadataChanges, ObjectLock, ObjectVersion, PostConstraints, PresignedPost, ReqRes, RestoreStatus, Retention, RetrievalTier, SignedUrlOptions, UploadOptions, WritePrecondition};
+
+/// Files which are being written contain this in their name, listings skip them
+const PARTIAL_MARKER: &str = ".uni-stg-partial-";
+
+/// Distinguishes partial files created within the same nanosecond
+static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Object returned by a `LocalClient`, a snapshot of the file's metadata
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocalObject {
+    pub bucket_name: String,
+    pub name: String,
+    pub size: u64,
+    pub last_modified: Option<SystemTime>,
+}
+
+impl LocalObject {
+    fn of(bucket: &str, key: &str, metadata: &std::fs::Metadata) -> Self {
+        Self { bucket_name: bucket.to_string(), name: key.to_string(), size: metadata.len(), last_modified: metadata.modified().ok() }
+    }
+}
+
+impl ClientObject for LocalObject {
+    fn size(&self) -> u64 {
+        self.size
+    }
+
+    fn bucket_name(&self) -> String {
+        self.bucket_name.clone()
+    }
+
+    fn id(&self) -> String {
+        self.name.clone()
+    }
+
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    /// Guessed from the extension, files have no stored content type
+    fn content_type(&self) -> Option<String> {
+        guess_content_type(&self.name, &[])
+    }
+
+    fn last_modified(&self) -> Option<SystemTime> {
+        self.last_modified
+    }
+
+    fn etag(&self) -> Option<String> {
+        self.version()
+    }
+
+    fn storage_class(&self) -> Option<String> {
+        None
+    }
+
+    fn custom_metadata(&self) -> HashMap<String, String> {
+        HashMap::new()
+    }
+
+    fn checksum(&self) -> Option<String> {
+        None
+    }
+
+    /// Modification time (in nanoseconds) and size of the file, every write replaces the file so both change together
+    fn version(&self) -> Option<String> {
+        let nanos = self.last_modified?.duration_since(UNIX_EPOCH).ok()?.as_nanos();
+        Some(format!("{nanos:x}-{:x}", self.size))
+    }
+}
+
+/// Bucket of a `LocalClient`, a directory below its root
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocalBucket(pub String);
+
+impl ClientBucket for LocalBucket {
+    fn id(&self) -> String {
+        self.0.clone()
+    }
+
+    fn name(&self) -> String {
+        self.0.clone()
+    }
+
+    fn location(&self) -> Option<String> {
+        None
+    }
+}
+
+/// Client storing objects as files, for 'file://' URIs and local development
+/// Buckets are directories below the root (the empty bucket is the root itself), keys are paths below them
+///
+/// Files have no metadata: content types are guessed from the extension, uploads with other content types, headers
+/// or custom metadata, metadata updates, tags, ACLs, versions, signed URLs and bucket configuration fail with
+/// `ClientError::Unsupported`.
+/// Conditional writes are atomic against other writes of the same `LocalClient` (and its clones) only.
+/// Listings are returned in one page.
+#[derive(Debug, Clone)]
+pub struct LocalClient {
+    root: PathBuf,
+    /// Held while a conditional write compares the current version and replaces the file
+    writes: Arc<Mutex<()>>,
+}
+
+/// Maps missing files to `ClientError::NotFound` and existing ones to `ClientError::AlreadyExists`
+fn error(e: std::io::Error) -> ClientError {
+    match e.kind() {
+        ErrorKind::NotFound => ClientError::NotFound,
+        ErrorKind::AlreadyExists => ClientError::AlreadyExists,
+        _ => ClientError::Io(e)
+    }
+}
+
+impl LocalClient {
+    /// The root directory has to exist, buckets are created below it
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into(), writes: Arc::new(Mutex::new(())) }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Path of an object (or of the bucket for an empty key), rejects names which would leave the root
+    fn path(&self, bucket: &str, key: &str) -> ReqRes<PathBuf> {
+        let escapes = |name: &str| name.starts_with('/') || name.split('/').any(|t| t == "..");
+        if bucket.contains('/') || escapes(bucket) || escapes(key) {
+            return Err(ClientError::Io(std::io::Error::new(ErrorKind::InvalidInput, "bucket or key leaves the root directory")))
+        }
+        Ok(self.root.join(bucket).join(key))
+    }
+
+    async fn stat(&self, bucket: &str, key: &str) -> ReqRes<LocalObject> {
+        let metadata = tokio::fs::metadata(self.path(bucket, key)?).await.map_err(error)?;
+        if !metadata.is_file() {
+            return Err(ClientError::NotFound)
+        }
+        Ok(LocalObject::of(bucket, key, &metadata))
+    }
+
+    /// New partial file next to `path`, its parent directories are created
+    async fn partial(&self, path: &Path) -> ReqRes<(PathBuf, tokio::fs::File)> {
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|t| t.as_nanos()).unwrap_or(0);
+        let name = path.file_name().map(|t| t.to_string_lossy().into_owned()).unwrap_or_default();
+        let partial = path.with_file_name(format!(".{name}{PARTIAL_MARKER}{nanos:x}-{:x}", COUNTER.fetch_add(1, Ordering::Relaxed)));
+        let file = OpenOptions::new().write(true).create_new(true).open(&partial).await?;
+        Ok((partial, file))
+    }
+
+    /// Moves a completely written partial file into place if the precondition holds
+    fn commit(&self, partial: &Path, path: &Path, precondition: Option<&WritePrecondition>) -> EmptyReqRes {
+        let _guard = self.writes.lock().unwrap_or_else(|e| e.into_inner());
+        let result = match precondition {
+            None => std::fs::rename(partial, path).map_err(error),
+            // A hard link is not created over an existing file, which also holds against other processes
+            Some(WritePrecondition::DoesNotExist) => match std::fs::hard_link(partial, path) {
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => Err(ClientError::PreconditionFailed),
+                result => result.map_err(error)
+            },
+            Some(WritePrecondition::Matches(version)) => {
+                let current = std::fs::metadata(path).ok().filter(|t| t.is_file()).and_then(|t| LocalObject::of("", "", &t).version());
+                match current.as_ref() == Some(version) {
+                    true => std::fs::rename(partial, path).map_err(error),
+                    false => Err(ClientError::PreconditionFailed)
+                }
+            }
+        };
+        let _ = std::fs::remove_file(partial);
+        result
+    }
+
+    async fn write(&self, bucket: &str, key: &str, data: &[u8], precondition: Option<&WritePrecondition>) -> ReqRes<LocalObject> {
+        let path = self.path(bucket, key)?;
+        let (partial, mut file) = self.partial(&path).await?;
+        let written: EmptyReqRes = async {
+            file.write_all(data).await?;
+            file.sync_all().await?;
+            Ok(())
+        }.await;
+        match written {
+            Ok(()) => self.commit(&partial, &path, precondition)?,
+            Err(e) => {
+                let _ = tokio::fs::remove_file(&partial).await;
+                return Err(e)
+            }
+        }
+        self.stat(bucket, key).await
+    }
+
+    /// Removes empty directories between a removed file and its bucket
+    async fn prune(&self, bucket: &str, path: &Path) {
+        let Ok(bucket) = self.path(bucket, "") else { return };
+        let mut dir = path.parent();
+        while let Some(current) = dir.filter(|t| t.starts_with(&bucket) && *t != bucket) {
+            if tokio::fs::remove_dir(current).await.is_err() {
+                break
+            }
+            dir = current.parent();
+        }
+    }
+
+    /// Objects of a bucket whose key starts with `prefix`, sorted by key
+    async fn walk(&self, bucket: &str, prefix: &str) -> ReqRes<Vec<LocalObject>> {
+        let mut objects = vec![];
+        let mut dirs = vec![(self.path(bucket, "")?, String::new())];
+        while let Some((dir, dir_key)) = dirs.pop() {
+            let mut entries = match tokio::fs::read_dir(&dir).await {
+                Ok(entries) => entries,
+                Err(e) => return Err(error(e))
+            };
+            while let Some(entry) = entries.next_entry().await? {
+                let Ok(name) = entry.file_name().into_string() else { continue };
+                if name.contains(PARTIAL_MARKER) {
+                    continue
+                }
+                let key = format!("{dir_key}{name}");
+                let metadata = entry.metadata().await?;
+                if metadata.is_dir() {
+                    let key = format!("{key}/");
+                    if key.starts_with(prefix) || prefix.starts_with(&key) {
+                        dirs.push((entry.path(), key));
+                    }
+                } else if metadata.is_file() && key.starts_with(prefix) {
+                    objects.push(LocalObject::of(bucket, &key, &metadata));
+                }
+            }
+        }
+        objects.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(objects)
+    }
+
+    async fn copy(&self, src_bucket: &str, src_object: &str, dest_bucket: &str, dest_object: &str) -> ReqRes<LocalObject> {
+        let (src, dest) = (self.path(src_bucket, src_object)?, self.path(dest_bucket, dest_object)?);
+        self.stat(src_bucket, src_object).await?;
+        let (partial, file) = self.partial(&dest).await?;
+        drop(file);
+        if let Err(e) = tokio::fs::copy(&src, &partial).await {
+            let _ = tokio::fs::remove_file(&partial).await;
+            return Err(error(e))
+        }
+        self.commit(&partial, &dest, None)?;
+        self.stat(dest_bucket, dest_object).await
+    }
+}
+
+// Returns the concrete `LocalObject` / `LocalBucket` instead of opaque types
+#[allow(refining_impl_trait)]
+impl ClientInterface for LocalClient {
+    async fn static_download_bytes(&self, bucket: String, object_id: String, starting: Option<u64>, ending: Option<u64>) -> ReqRes<Bytes> {
+        self.download_with_options(bucket, object_id, starting, ending, DownloadOptions::default()).await
+    }
+
+    async fn static_upload_bytes(&self, bucket: String, object_id: String, data: Bytes) -> ReqRes<LocalObject> {
+        self.write(&bucket, &object_id, &data, None).await.context("static_upload_bytes", &bucket, Some(&object_id))
+    }
+
+    async fn upload_with_options(&self, bucket: String, object_id: String, data: Bytes, options: UploadOptions) -> ReqRes<LocalObject> {
+        let guessed = options.content_type.is_none() || options.content_type == guess_content_type(&object_id, &[]);
+        if !guessed || options.cache_control.is_some() || options.content_encoding.is_some() {
+            return Err(ClientError::Unsupported("local files have no stored content type or headers"))
+        }
+        if !options.custom_metadata.is_empty() || options.storage_class.is_some() || options.acl.is_some() {
+            return Err(ClientError::Unsupported("local files have no custom metadata, storage classes or ACLs"))
+        }
+        self.write(&bucket, &object_id, &data, options.precondition.as_ref()).await.context("upload_with_options", &bucket, Some(&object_id))
+    }
+
+    async fn download_with_options(&self, bucket: String, object_id: String, starting: Option<u64>, ending: Option<u64>, options: DownloadOptions) -> ReqRes<Bytes> {
+        let read: ReqRes<Bytes> = async {
+            if options.version.is_some() {
+                return Err(ClientError::Unsupported("local files have no versions"))
+            }
+            let object = self.stat(&bucket, &object_id).await?;
+            let etag = object.etag().unwrap_or_default();
+            if options.if_match.as_ref().is_some_and(|t| t != "*" && *t != etag) {
+                return Err(ClientError::PreconditionFailed)
+            }
+            let unmodified = options.if_modified_since.is_some_and(|since| object.last_modified.is_some_and(|t| t <= since));
+            if options.if_none_match.as_ref().is_some_and(|t| t == "*" || *t == etag) || unmodified {
+                return Err(ClientError::NotModified)
+            }
+            let mut data = vec![];
+            self.download_to_writer(bucket.clone(), object_id.clone(), starting, ending, &mut data).await?;
+            Ok(data.into())
+        }.await;
+        read.context("download_with_options", &bucket, Some(&object_id))
+    }
+
+    async fn download_to_writer(&self, bucket: String, object_id: String, starting: Option<u64>, ending: Option<u64>, mut w: impl AsyncWrite + Unpin + Send) -> ReqRes<u64> {
+        let read: ReqRes<u64> = async {
+            let mut file = tokio::fs::File::open(self.path(&bucket, &object_id)?).await.map_err(error)?;
+            let metadata = file.metadata().await?;
+            if !metadata.is_file() {
+                return Err(ClientError::NotFound)
+            }
+            let (start, end) = resolve_range(metadata.len(), starting, ending);
+            file.seek(SeekFrom::Start(start)).await?;
+            let copied = tokio::io::copy(&mut file.take(end - start), &mut w).await?;
+            w.flush().await?;
+            Ok(copied)
+        }.await;
+        read.context("download_to_writer", &bucket, Some(&object_id))
+    }
+
+    async fn upload_from_reader(&self, bucket: String, object_id: String, mut r: impl AsyncRead + Unpin + Send + Sync + 'static) -> EmptyReqRes {
+        let written: EmptyReqRes = async {
+            let path = self.path(&bucket, &object_id)?;
+            let (partial, mut file) = self.partial(&path).await?;
+            let copied = async {
+                tokio::io::copy(&mut r, &mut file).await?;
+                file.sync_all().await
+            }.await;
+            match copied {
+                Ok(()) => self.commit(&partial, &path, None),
+                Err(e) => {
+                    let _ = tokio::fs::remove_file(&partial).await;
+                    Err(e.into())
+                }
+            }
+        }.await;
+        written.context("upload_from_reader", &bucket, Some(&object_id))
+    }
+
+    async fn signed_url(&self, _bucket: String, _object_id: String, _options: SignedUrlOptions) -> ReqRes<String> {
+        Err(ClientError::Unsupported("local files have no signed URLs"))
+    }
+
+    async fn presigned_post(&self, _bucket: String, _key_prefix: String, _constraints: PostConstraints) -> ReqRes<PresignedPost> {
+        Err(ClientError::Unsupported("local files have no presigned POSTs"))
+    }
+
+    async fn remove_bucket(&self, bucket: String) -> EmptyReqRes {
+        tokio::fs::remove_dir(self.path(&bucket, "")?).await.map_err(error).context("remove_bucket", &bucket, None)
+    }
+
+    async fn remove_object(&self, bucket: String, object_id: String) -> EmptyReqRes {
+        let path = self.path(&bucket, &object_id)?;
+        tokio::fs::remove_file(&path).await.map_err(error).context("remove_object", &bucket, Some(&object_id))?;
+        self.prune(&bucket, &path).await;
+        Ok(())
+    }
+
+    async fn create_bucket(&self, bucket: String) -> ReqRes<LocalBucket> {
+        tokio::fs::create_dir(self.path(&bucket, "")?).await.map_err(error).context("create_bucket", &bucket, None)?;
+        Ok(LocalBucket(bucket))
+    }
+
+    async fn copy_object(&self, src_bucket: String, src_object: String, dest_bucket: String, dest_object: String) -> ReqRes<LocalObject> {
+        self.copy(&src_bucket, &src_object, &dest_bucket, &dest_object).await.context("copy_object", &src_bucket, Some(&src_object))
+    }
+
+    async fn copy_object_with_options(&self, src_bucket: String, src_object: String, dest_bucket: String, dest_object: String, options: CopyOptions) -> ReqRes<LocalObject> {
+        if options.storage_class.is_some() {
+            return Err(ClientError::Unsupported("local files have no storage classes"))
+        }
+        self.copy(&src_bucket, &src_object, &dest_bucket, &dest_object).await.context("copy_object_with_options", &src_bucket, Some(&src_object))
+    }
+
+    async fn restore_object(&self, _bucket_name: String, _object_name: String, _days: u32, _tier: RetrievalTier) -> EmptyReqRes {
+        Err(ClientError::Unsupported("local files are never archived"))
+    }
+
+    async fn restore_status(&self, bucket_name: String, object_name: String) -> ReqRes<RestoreStatus> {
+        self.stat(&bucket_name, &object_name).await.context("restore_status", &bucket_name, Some(&object_name))?;
+        Ok(RestoreStatus::NotRequested)
+    }
+
+    async fn update_object_metadata(&self, _bucket: String, _object_id: String, _changes: MetadataChanges) -> ReqRes<LocalObject> {
+        Err(ClientError::Unsupported("local files have no metadata"))
+    }
+
+    async fn get_object_tags(&self, _bucket: String, _object_id: String) -> ReqRes<HashMap<String, String>> {
+        Err(ClientError::Unsupported("local files have no tags"))
+    }
+
+    async fn set_object_tags(&self, _bucket: String, _object_id: String, _tags: HashMap<String, String>) -> EmptyReqRes {
+        Err(ClientError::Unsupported("local files have no tags"))
+    }
+
+    async fn get_object_acl(&self, _bucket: String, _object_id: String) -> ReqRes<Vec<AclGrant>> {
+        Err(ClientError::Unsupported("local files have no ACLs"))
+    }
+
+    async fn set_object_acl(&self, _bucket: String, _object_id: String, _acl: AccessControl) -> EmptyReqRes {
+        Err(ClientError::Unsupported("local files have no ACLs"))
+    }
+
+    async fn append(&self, bucket: String, object_id: String, data: Bytes) -> EmptyReqRes {
+        let appended: EmptyReqRes = async {
+            let path = self.path(&bucket, &object_id)?;
+            if let Some(parent) = path.parent() {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+            let mut file = OpenOptions::new().create(true).append(true).open(&path).await?;
+            file.write_all(&data).await?;
+            file.sync_all().await?;
+            Ok(())
+        }.await;
+        appended.context("append", &bucket, Some(&object_id))
+    }
+
+    /// Writes the file, content type and Cache-Control are not stored
+    async fn upload_public(&self, bucket: String, object_id: String, data: Bytes, _content_type: Option<String>, _cache_control: String) -> EmptyReqRes {
+        self.write(&bucket, &object_id, &data, None).await.context("upload_public", &bucket, Some(&object_id))?;
+        Ok(())
+    }
+
+    fn public_url(&self, bucket: &str, object_id: &str) -> String {
+        format!("file://{}", self.root.join(bucket).join(object_id).display())
+    }
+
+    async fn list_buckets(&self, _max_results: Option<u32>, _page_token: Option<Cursor>) -> ReqRes<ListPage<LocalBucket>> {
+        let mut buckets = vec![];
+        let mut entries = tokio::fs::read_dir(&self.root).await.map_err(error)?;
+        while let Some(entry) = entries.next_entry().await? {
+            if let Ok(name) = entry.file_name().into_string() && entry.metadata().await?.is_dir() {
+                buckets.push(LocalBucket(name));
+            }
+        }
+        buckets.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(ListPage { items: buckets, prefixes: vec![], next_token: None })
+    }
+
+    async fn get_bucket(&self, bucket_name: String) -> ReqRes<LocalBucket> {
+        let metadata = tokio::fs::metadata(self.path(&bucket_name, "")?).await.map_err(error);
+        match metadata.context("get_bucket", &bucket_name, None)?.is_dir() {
+            true => Ok(LocalBucket(bucket_name)),
+            false => Err(ClientError::NotFound).context("get_bucket", &bucket_name, None)
+        }
+    }
+
+    async fn get_object(&self, bucket_name: String, object_name: String) -> ReqRes<LocalObject> {
+        self.stat(&bucket_name, &object_name).await.context("get_object", &bucket_name, Some(&object_name))
+    }
+
+    async fn stat_object(&self, bucket_name: String, object_name: String) -> ReqRes<LocalObject> {
+        self.stat(&bucket_name, &object_name).await.context("stat_object", &bucket_name, Some(&object_name))
+    }
+
+    async fn list_objects(&self, bucket_name: String, _max_results: Option<u32>, _page_token: Option<Cursor>) -> ReqRes<ListPage<LocalObject>> {
+        let items = self.walk(&bucket_name, "").await.context("list_objects", &bucket_name, None)?;
+        Ok(ListPage { items, prefixes: vec![], next_token: None })
+    }
+
+    async fn list_prefix(&self, bucket_name: String, prefix: String, _page_token: Option<Cursor>) -> ReqRes<ListPage<LocalObject>> {
+        let items = self.walk(&bucket_name, &prefix).await.context("list_prefix", &bucket_name, Some(&prefix))?;
+        Ok(ListPage { items, prefixes: vec![], next_token: None })
+    }
+
+    async fn list_projected(&self, bucket_name: String, prefix: String, _attributes: ListAttributes, page_token: Option<Cursor>) -> ReqRes<ListPage<LocalObject>> {
+        self.list_prefix(bucket_name, prefix, page_token).await
+    }
+
+    async fn list_delimited(&self, bucket_name: String, prefix: String, delimiter: String, _page_token: Option<Cursor>) -> ReqRes<ListPage<LocalObject>> {
+        let objects = self.walk(&bucket_name, &prefix).await.context("list_delimited", &bucket_name, Some(&prefix))?;
+        let (mut items, mut prefixes) = (vec![], BTreeSet::new());
+        for object in objects {
+            match object.name[prefix.len()..].find(&delimiter).filter(|_| !delimiter.is_empty()) {
+                Some(at) => {
+                    prefixes.insert(object.name[..prefix.len() + at + delimiter.len()].to_string());
+                }
+                None => items.push(object)
+            }
+        }
+        Ok(ListPage { items, prefixes: prefixes.into_iter().collect(), next_token: None })
+    }
+
+    async fn list_after(&self, bucket_name: String, prefix: String, start_after: String, max_results: u32) -> ReqRes<Vec<LocalObject>> {
+        let objects = self.walk(&bucket_name, &prefix).await.context("list_after", &bucket_name, Some(&prefix))?;
+        Ok(objects.into_iter().filter(|t| t.name > start_after).take(max_results as usize).collect())
+    }
+
+    async fn list_versions(&self, _bucket_name: String, _prefix: String, _page_token: Option<Cursor>) -> ReqRes<ListPage<ObjectVersion>> {
+        Err(ClientError::Unsupported("local files have no versions"))
+    }
+
+    async fn restore_object_version(&self, _bucket_name: String, _object_name: String, _version: String) -> ReqRes<LocalObject> {
+        Err(ClientError::Unsupported("local files have no versions"))
+    }
+
+    async fn compliance_records(&self, _bucket_name: String, _page_token: Option<Cursor>) -> ReqRes<ListPage<ComplianceRecord>> {
+        Err(ClientError::Unsupported("local files have no retention"))
+    }
+
+    async fn get_bucket_spec(&self, _bucket_name: String) -> ReqRes<BucketSpec> {
+        Err(ClientError::Unsupported("local directories have no bucket configuration"))
+    }
+
+    async fn set_lifecycle_rules(&self, _bucket_name: String, _rules: Vec<LifecycleRule>) -> EmptyReqRes {
+        Err(ClientError::Unsupported("local directories have no lifecycle rules"))
+    }
+
+    async fn get_object_lock(&self, _bucket_name: String, _object_name: String) -> ReqRes<ObjectLock> {
+        Err(ClientError::Unsupported("local files have no retention"))
+    }
+
+    async fn set_object_retention(&self, _bucket_name: String, _object_name: String, _retention: Option<Retention>) -> EmptyReqRes {
+        Err(ClientError::Unsupported("local files have no retention"))
+    }
+
+    async fn set_legal_hold(&self, _bucket_name: String, _object_name: String, _on: bool) -> EmptyReqRes {
+        Err(ClientError::Unsupported("local files have no legal holds"))
+    }
+
+    async fn get_bucket_labels(&self, _bucket_name: String) -> ReqRes<HashMap<String, String>> {
+        Err(ClientError::Unsupported("local directories have no labels"))
+    }
+
+    async fn set_bucket_labels(&self, _bucket_name: String, _labels: HashMap<String, String>) -> EmptyReqRes {
+        Err(ClientError::Unsupported("local directories have no labels"))
+    }
+
+    async fn get_bucket_policy(&self, _bucket_name: String) -> ReqRes<Option<BucketPolicy>> {
+        Err(ClientError::Unsupported("local directories have no policies"))
+    }
+
+    async fn set_bucket_policy(&self, _bucket_name: String, _policy: BucketPolicy) -> EmptyReqRes {
+        Err(ClientError::Unsupported("local directories have no policies"))
+    }
+
+    /// Buckets are single directory names
+    fn bucket_name_problems(&self, bucket_name: &str) -> Vec<BucketNameProblem> {
+        match bucket_name.chars().find(|t| matches!(t, '/' | '\\' | '\0')) {
+            Some(invalid) => vec![BucketNameProblem::InvalidCharacter(invalid)],
+            None if bucket_name.is_empty() => vec![BucketNameProblem::Length],
+            None if bucket_name == "." || bucket_name == ".." => vec![BucketNameProblem::Reserved],
+            None => vec![]
+        }
+    }
+}
+
+#[cfg(all(test, feature = "conformance"))]
+mod tests {
+    use crate::conformance::run_all;
+    use super::*;
+
+    #[tokio::test]
+    async fn passes_conformance() {
+        let root = std::env::temp_dir().join(format!("uni-stg-local-{}", std::process::id()));
+        tokio::fs::create_dir_all(root.join("bucket")).await.unwrap();
+        let report = run_all(&LocalClient::new(&root), "bucket").await;
+        let _ = tokio::fs::remove_dir_all(&root).await;
+        let failures: Vec<_> = report.failures().collect();
+        assert!(failures.is_empty(), "{failures:?}");
+    }
+}
@@ -0,0 +1,343 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use bytes::Bytes;
+use futures::StreamExt;
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
+use crate::delegate::delegate;
+use crate::encoding::slice_range;
+use crate::{ClientInterface, ClientObject, Context, CopyOptions, DownloadOptions, EmptyReqRes, MetadataChanges, ReqRes, UploadOptions};
+
+/// Objects larger than this are not cached by default
+pub const DEFAULT_MAX_OBJECT_SIZE: u64 = 1024 * 1024;
+
+type Id = (String, String);
+
+struct Entry {
+    data: Bytes,
+    stored: Instant,
+    /// Value of `Lru::clock` when the entry was last read, its key in `Lru::order`
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct Lru {
+    entries: HashMap<Id, Entry>,
+    /// Entries by last use, the first one is the least recently used
+    order: BTreeMap<u64, Id>,
+    clock: u64,
+    /// Number of invalidations, reads which overlap one do not store their (possibly outdated) data
+    invalidations: u64,
+}
+
+impl Lru {
+    fn remove(&mut self, id: &Id) {
+        if let Some(entry) = self.entries.remove(id) {
+            self.order.remove(&entry.last_used);
+        }
+    }
+
+    /// Marks an entry as used and returns it
+    fn touch(&mut self, id: &Id) -> Option<&Entry> {
+        self.clock += 1;
+        let clock = self.clock;
+        let entry = self.entries.get_mut(id)?;
+        self.order.remove(&entry.last_used);
+        entry.last_used = clock;
+        self.order.insert(clock, id.clone());
+        Some(entry)
+    }
+}
+
+/// Wraps a client and keeps recently read small objects (configs, manifests) in memory, for services which read them at high rates
+///
+/// Cached objects are served without any request until they are older than the TTL, so changes made by other
+/// writers are seen with a delay of up to the TTL. Writes through this client invalidate the cached copy once they completed.
+/// Once more than `capacity` objects are cached, the least recently read one is dropped.
+/// Ranged reads are only served from objects which are cached already, conditional or versioned downloads bypass the cache.
+pub struct MemoryCache<C: ClientInterface> {
+    inner: C,
+    capacity: usize,
+    ttl: Duration,
+    max_object_size: u64,
+    lru: Mutex<Lru>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl<C: ClientInterface> MemoryCache<C> {
+    /// Caches up to `capacity` objects for `ttl` each
+    pub fn new(inner: C, capacity: usize, ttl: Duration) -> Self {
+        Self { inner, capacity, ttl, max_object_size: DEFAULT_MAX_OBJECT_SIZE, lru: Mutex::default(), hits: AtomicU64::new(0), misses: AtomicU64::new(0) }
+    }
+
+    /// Objects larger than `max_object_size` are passed through (default: `DEFAULT_MAX_OBJECT_SIZE`)
+    pub fn with_max_object_size(mut self, max_object_size: u64) -> Self {
+        self.max_object_size = max_object_size;
+        self
+    }
+
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    /// Number of reads which were answered from memory
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// Number of reads which downloaded the object
+    pub fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+
+    /// Number of cached objects (including expired ones which were not dropped yet)
+    pub fn len(&self) -> usize {
+        self.lru().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drops every cached object
+    pub fn clear(&self) {
+        let mut lru = self.lru();
+        lru.entries.clear();
+        lru.order.clear();
+        lru.invalidations += 1;
+    }
+
+    /// Drops the cached copy of an object, for objects known to be changed by other writers
+    pub fn invalidate(&self, bucket: &str, key: &str) {
+        let mut lru = self.lru();
+        lru.remove(&(bucket.to_string(), key.to_string()));
+        lru.invalidations += 1;
+    }
+
+    fn lru(&self) -> std::sync::MutexGuard<'_, Lru> {
+        self.lru.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// The cached copy if it is not expired, marking it as used
+    fn cached(&self, bucket: &str, key: &str) -> Option<Bytes> {
+        let mut lru = self.lru();
+        let id = (bucket.to_string(), key.to_string());
+        if lru.entries.get(&id).is_some_and(|t| t.stored.elapsed() >= self.ttl) {
+            lru.remove(&id);
+            return None
+        }
+        lru.touch(&id).map(|t| t.data.clone())
+    }
+
+    /// Stores data read while the number of invalidations was `invalidations`, unless there was one meanwhile
+    fn store(&self, bucket: String, key: String, data: Bytes, invalidations: u64) {
+        if self.capacity == 0 || data.len() as u64 > self.max_object_size {
+            return
+        }
+        let mut lru = self.lru();
+        if lru.invalidations != invalidations {
+            return
+        }
+        let id = (bucket, key);
+        lru.remove(&id);
+        lru.entries.insert(id.clone(), Entry { data, stored: Instant::now(), last_used: 0 });
+        lru.touch(&id);
+        while lru.entries.len() > self.capacity {
+            let Some((_, oldest)) = lru.order.pop_first() else {
+                break
+            };
+            lru.entries.remove(&oldest);
+        }
+    }
+
+    /// Reads a whole object, from memory if the cached copy is not expired
+    async fn read(&self, bucket: String, key: String) -> ReqRes<Bytes> {
+        if let Some(data) = self.cached(&bucket, &key) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(data)
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let invalidations = self.lru().invalidations;
+        let data = self.inner.static_download_bytes(bucket.clone(), key.clone(), None, None).await?;
+        self.store(bucket, key, data.clone(), invalidations);
+        Ok(data)
+    }
+}
+
+impl<C: ClientInterface> ClientInterface for MemoryCache<C> {
+    async fn static_download_bytes(&self, bucket: String, object_id: String, starting: Option<u64>, ending: Option<u64>) -> ReqRes<Bytes> {
+        let ranged = starting.is_some() || ending.is_some();
+        if ranged {
+            return match self.cached(&bucket, &object_id) {
+                Some(data) => {
+                    self.hits.fetch_add(1, Ordering::Relaxed);
+                    Ok(slice_range(data, starting, ending))
+                }
+                None => self.inner.static_download_bytes(bucket, object_id, starting, ending).await
+            }
+        }
+        self.read(bucket, object_id).await
+    }
+
+    /// Conditional and versioned downloads bypass the cache
+    async fn download_with_options(&self, bucket: String, object_id: String, starting: Option<u64>, ending: Option<u64>, options: DownloadOptions) -> ReqRes<Bytes> {
+        if options.is_conditional() || options.version.is_some() {
+            return self.inner.download_with_options(bucket, object_id, starting, ending, options).await
+        }
+        self.static_download_bytes(bucket, object_id, starting, ending).await
+    }
+
+    /// Objects which are not cached are streamed without caching them
+    async fn download_to_writer(&self, bucket: String, object_id: String, starting: Option<u64>, ending: Option<u64>, mut w: impl AsyncWrite + Unpin + Send) -> ReqRes<u64> {
+        let Some(data) = self.cached(&bucket, &object_id) else {
+            return self.inner.download_to_writer(bucket, object_id, starting, ending, w).await
+        };
+        self.hits.fetch_add(1, Ordering::Relaxed);
+        let data = slice_range(data, starting, ending);
+        w.write_all(&data).await.context("download_to_writer", &bucket, Some(&object_id))?;
+        w.flush().await.context("download_to_writer", &bucket, Some(&object_id))?;
+        Ok(data.len() as u64)
+    }
+
+    async fn static_upload_bytes(&self, bucket: String, object_id: String, data: Bytes) -> ReqRes<impl ClientObject> {
+        let written = self.inner.static_upload_bytes(bucket.clone(), object_id.clone(), data).await;
+        self.invalidate(&bucket, &object_id);
+        written
+    }
+
+    async fn upload_with_options(&self, bucket: String, object_id: String, data: Bytes, options: UploadOptions) -> ReqRes<impl ClientObject> {
+        let written = self.inner.upload_with_options(bucket.clone(), object_id.clone(), data, options).await;
+        self.invalidate(&bucket, &object_id);
+        written
+    }
+
+    async fn upload_from_reader(&self, bucket: String, object_id: String, r: impl AsyncRead + Unpin + Send + Sync + 'static) -> EmptyReqRes {
+        let written = self.inner.upload_from_reader(bucket.clone(), object_id.clone(), r).await;
+        self.invalidate(&bucket, &object_id);
+        written
+    }
+
+    async fn append(&self, bucket: String, object_id: String, data: Bytes) -> EmptyReqRes {
+        let written = self.inner.append(bucket.clone(), object_id.clone(), data).await;
+        self.invalidate(&bucket, &object_id);
+        written
+    }
+
+    async fn upload_public(&self, bucket: String, object_id: String, data: Bytes, content_type: Option<String>, cache_control: String) -> EmptyReqRes {
+        let written = self.inner.upload_public(bucket.clone(), object_id.clone(), data, content_type, cache_control).await;
+        self.invalidate(&bucket, &object_id);
+        written
+    }
+
+    async fn remove_object(&self, bucket: String, object_id: String) -> EmptyReqRes {
+        let written = self.inner.remove_object(bucket.clone(), object_id.clone()).await;
+        self.invalidate(&bucket, &object_id);
+        written
+    }
+
+    async fn remove_objects(&self, bucket_name: String, keys: Vec<String>) -> ReqRes<Vec<(String, EmptyReqRes)>> {
+        let removed = self.inner.remove_objects(bucket_name.clone(), keys.clone()).await;
+        for key in &keys {
+            self.invalidate(&bucket_name, key);
+        }
+        removed
+    }
+
+    async fn copy_object(&self, src_bucket: String, src_object: String, dest_bucket: String, dest_object: String) -> ReqRes<impl ClientObject> {
+        let written = self.inner.copy_object(src_bucket, src_object, dest_bucket.clone(), dest_object.clone()).await;
+        self.invalidate(&dest_bucket, &dest_object);
+        written
+    }
+
+    async fn copy_object_with_options(&self, src_bucket: String, src_object: String, dest_bucket: String, dest_object: String, options: CopyOptions) -> ReqRes<impl ClientObject> {
+        let written = self.inner.copy_object_with_options(src_bucket, src_object, dest_bucket.clone(), dest_object.clone(), options).await;
+        self.invalidate(&dest_bucket, &dest_object);
+        written
+    }
+
+    /// Invalidates the destination with every update, the last one is sent once the copy completed
+    fn copy_object_progress(&self, src_bucket: String, src_object: String, dest_bucket: String, dest_object: String) -> impl futures::Stream<Item = ReqRes<crate::CopyProgress>> + Send {
+        self.inner.copy_object_progress(src_bucket, src_object, dest_bucket.clone(), dest_object.clone())
+            .inspect(move |_| self.invalidate(&dest_bucket, &dest_object))
+    }
+
+    async fn update_object_metadata(&self, bucket: String, object_id: String, changes: MetadataChanges) -> ReqRes<impl ClientObject> {
+        let written = self.inner.update_object_metadata(bucket.clone(), object_id.clone(), changes).await;
+        self.invalidate(&bucket, &object_id);
+        written
+    }
+
+    async fn restore_object_version(&self, bucket_name: String, object_name: String, version: String) -> ReqRes<impl ClientObject> {
+        let written = self.inner.restore_object_version(bucket_name.clone(), object_name.clone(), version).await;
+        self.invalidate(&bucket_name, &object_name);
+        written
+    }
+
+    delegate!(inner;
+        get_object_tags, set_object_tags, get_object_acl, set_object_acl, public_url, signed_url, presigned_post, remove_bucket, create_bucket, restore_object, restore_status,
+        list_buckets, get_bucket, get_object, stat_object, list_objects, list_prefix, list_projected, list_delimited, list_after, list_versions,
+        bucket_exists, bucket_name_problems, compliance_records, get_bucket_spec, get_lifecycle_rules, set_lifecycle_rules, get_bucket_policy, set_bucket_policy, get_bucket_labels, set_bucket_labels,
+        get_object_lock, set_object_retention, set_legal_hold
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::in_memory::InMemoryClient;
+    use super::*;
+
+    async fn put(client: &impl ClientInterface, key: &str, data: &'static [u8]) {
+        client.static_upload_bytes("bucket".to_string(), key.to_string(), Bytes::from_static(data)).await.unwrap();
+    }
+
+    async fn read(client: &MemoryCache<InMemoryClient>, key: &str, starting: Option<u64>, ending: Option<u64>) -> Bytes {
+        client.static_download_bytes("bucket".to_string(), key.to_string(), starting, ending).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn serves_and_invalidates_cached_objects() {
+        let client = MemoryCache::new(InMemoryClient::with_bucket("bucket"), 8, Duration::from_secs(60));
+        put(&client, "key", b"first").await;
+        assert_eq!(read(&client, "key", None, None).await, "first");
+        assert_eq!(read(&client, "key", None, None).await, "first");
+        assert_eq!(read(&client, "key", Some(1), Some(3)).await, "irs");
+        assert_eq!((client.misses(), client.hits()), (1, 2));
+        // Written behind the cache's back, the cached copy is served until the TTL passed
+        put(client.inner(), "key", b"second").await;
+        assert_eq!(read(&client, "key", None, None).await, "first");
+        put(&client, "key", b"third").await;
+        assert!(client.is_empty());
+        assert_eq!(read(&client, "key", None, None).await, "third");
+        client.remove_object("bucket".to_string(), "key".to_string()).await.unwrap();
+        assert!(client.is_empty());
+    }
+
+    #[tokio::test]
+    async fn expires_and_evicts() {
+        let expired = MemoryCache::new(InMemoryClient::with_bucket("bucket"), 8, Duration::ZERO);
+        put(&expired, "key", b"data").await;
+        read(&expired, "key", None, None).await;
+        read(&expired, "key", None, None).await;
+        assert_eq!((expired.misses(), expired.hits()), (2, 0));
+
+        let client = MemoryCache::new(InMemoryClient::with_bucket("bucket"), 2, Duration::from_secs(60)).with_max_object_size(4);
+        for key in ["a", "b", "c"] {
+            put(&client, key, b"four").await;
+        }
+        put(&client, "large", b"fives").await;
+        read(&client, "a", None, None).await;
+        read(&client, "b", None, None).await;
+        read(&client, "a", None, None).await;
+        read(&client, "c", None, None).await;
+        read(&client, "large", None, None).await;
+        assert_eq!(client.len(), 2);
+        let misses = client.misses();
+        read(&client, "a", None, None).await;
+        read(&client, "c", None, None).await;
+        assert_eq!(client.misses(), misses);
+        read(&client, "b", None, None).await;
+        assert_eq!(client.misses(), misses + 1);
+    }
+}
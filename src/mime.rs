@@ -0,0 +1,63 @@
+/// Content type of an object, guessed from the extension of its key and otherwise from the first bytes of its data
+/// With the `mime` feature, extensions are looked up in the `mime_guess` database first
+pub fn guess_content_type(key: &str, data: &[u8]) -> Option<String> {
+    #[cfg(feature = "mime")]
+    if let Some(guess) = mime_guess::from_path(key).first_raw() {
+        return Some(guess.to_string())
+    }
+    by_extension(key).or_else(|| sniff(data)).map(str::to_string)
+}
+
+/// Content type by file extension
+pub(crate) fn by_extension(path: &str) -> Option<&'static str> {
+    let extension = path.rsplit_once('.')?.1.to_ascii_lowercase();
+    Some(match extension.as_str() {
+        "html" | "htm" => "text/html; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "js" | "mjs" => "text/javascript; charset=utf-8",
+        "json" | "map" => "application/json",
+        "txt" => "text/plain; charset=utf-8",
+        "xml" => "application/xml",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "avif" => "image/avif",
+        "ico" => "image/x-icon",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        "ttf" => "font/ttf",
+        "wasm" => "application/wasm",
+        "pdf" => "application/pdf",
+        "webmanifest" => "application/manifest+json",
+        _ => return None
+    })
+}
+
+/// Number of bytes `guess_content_type` looks at
+#[cfg(feature = "google_cloud")]
+pub(crate) const SNIFF_LEN: usize = 12;
+
+/// Content type by magic bytes
+fn sniff(data: &[u8]) -> Option<&'static str> {
+    const SIGNATURES: &[(&[u8], &str)] = &[
+        (b"\x89PNG\r\n\x1a\n", "image/png"),
+        (b"\xff\xd8\xff", "image/jpeg"),
+        (b"GIF87a", "image/gif"),
+        (b"GIF89a", "image/gif"),
+        (b"%PDF-", "application/pdf"),
+        (b"PK\x03\x04", "application/zip"),
+        (b"\x1f\x8b", "application/gzip"),
+        (b"\0asm", "application/wasm"),
+        (b"wOFF", "font/woff"),
+        (b"wOF2", "font/woff2"),
+    ];
+    if let Some((_, content_type)) = SIGNATURES.iter().find(|(magic, _)| data.starts_with(magic)) {
+        return Some(*content_type)
+    }
+    if data.len() >= 12 && &data[..4] == b"RIFF" && &data[8..12] == b"WEBP" {
+        return Some("image/webp")
+    }
+    None
+}
@@ -0,0 +1,512 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use bytes::Bytes;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use crate::encoding::slice_range;
+use crate::{AccessControl, AclGrant, BucketNameProblem, BucketPolicy, BucketSpec, ClientBucket, ClientError, ClientInterface, ClientObject, ComplianceRecord, CopyOptions, Cursor, DownloadOptions, EmptyReqRes, LifecycleRule, ListAttributes, ListPage, MetadataChanges, ObjectLock, ObjectVersion, PostConstraints, PresignedPost, ReqRes, RestoreStatus, Retention, RetrievalTier, SignedUrlOptions, UploadOptions};
+
+/// Object returned by a `MockClient`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MockObject {
+    pub bucket_name: String,
+    pub name: String,
+    pub size: u64,
+    pub content_type: Option<String>,
+    pub last_modified: Option<SystemTime>,
+    pub etag: Option<String>,
+    pub custom_metadata: HashMap<String, String>,
+}
+
+impl MockObject {
+    pub fn new(bucket_name: &str, name: &str, size: u64) -> Self {
+        Self { bucket_name: bucket_name.to_string(), name: name.to_string(), size, ..Default::default() }
+    }
+}
+
+impl ClientObject for MockObject {
+    fn size(&self) -> u64 {
+        self.size
+    }
+
+    fn bucket_name(&self) -> String {
+        self.bucket_name.clone()
+    }
+
+    fn id(&self) -> String {
+        self.name.clone()
+    }
+
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn content_type(&self) -> Option<String> {
+        self.content_type.clone()
+    }
+
+    fn last_modified(&self) -> Option<SystemTime> {
+        self.last_modified
+    }
+
+    fn etag(&self) -> Option<String> {
+        self.etag.clone()
+    }
+
+    fn storage_class(&self) -> Option<String> {
+        None
+    }
+
+    fn custom_metadata(&self) -> HashMap<String, String> {
+        self.custom_metadata.clone()
+    }
+
+    fn checksum(&self) -> Option<String> {
+        None
+    }
+
+    fn version(&self) -> Option<String> {
+        self.etag.clone()
+    }
+}
+
+/// Bucket returned by a `MockClient`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MockBucket(pub String);
+
+impl ClientBucket for MockBucket {
+    fn id(&self) -> String {
+        self.0.clone()
+    }
+
+    fn name(&self) -> String {
+        self.0.clone()
+    }
+
+    fn location(&self) -> Option<String> {
+        None
+    }
+}
+
+/// A call a `MockClient` received
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MockCall {
+    /// Name of the `ClientInterface` method (example: 'static_download_bytes')
+    pub operation: &'static str,
+    pub bucket: String,
+    pub key: Option<String>,
+}
+
+#[derive(Clone)]
+enum Response {
+    Ok,
+    Bytes(Bytes),
+    Object(MockObject),
+    Objects(Vec<MockObject>),
+    Error(Arc<dyn Fn() -> ClientError + Send + Sync>),
+}
+
+struct Expectation {
+    operations: Vec<&'static str>,
+    bucket: String,
+    /// None matches every key
+    key: Option<String>,
+    /// None allows any number of calls
+    remaining: Option<usize>,
+    response: Response,
+}
+
+#[derive(Default)]
+struct State {
+    expectations: Vec<Expectation>,
+    calls: Vec<MockCall>,
+    unexpected: Vec<MockCall>,
+}
+
+const GET: &[&str] = &["static_download_bytes", "download_with_options", "download_to_writer"];
+const PUT: &[&str] = &["static_upload_bytes", "upload_with_options", "upload_from_reader"];
+const DELETE: &[&str] = &["remove_object"];
+const STAT: &[&str] = &["stat_object", "get_object"];
+const LIST: &[&str] = &["list_objects", "list_prefix", "list_projected", "list_delimited", "list_after"];
+
+/// Client for unit tests, which answers with scripted responses
+/// Example: `mock.expect_get("bucket", "key").returns(data)`, `mock.expect_put("bucket", "key").fails_with(|| ClientError::PreconditionFailed)`
+///
+/// Expectations are matched in the order they were added, calls without a matching expectation
+/// fail with `ClientError::Unsupported` and are reported by `verify`.
+#[derive(Clone, Default)]
+pub struct MockClient {
+    state: Arc<Mutex<State>>,
+}
+
+/// An expectation which is added once its response is set
+#[must_use = "the expectation is only added by returns, succeeds or fails_with"]
+pub struct MockExpectation<'a> {
+    client: &'a MockClient,
+    operations: Vec<&'static str>,
+    bucket: String,
+    key: Option<String>,
+    remaining: Option<usize>,
+}
+
+impl MockExpectation<'_> {
+    /// Matches only `n` calls (every call by default)
+    pub fn times(mut self, n: usize) -> Self {
+        self.remaining = Some(n);
+        self
+    }
+
+    /// Matches every key of the bucket
+    pub fn any_key(mut self) -> Self {
+        self.key = None;
+        self
+    }
+
+    /// Answers downloads with (the requested range of) `data`
+    pub fn returns(self, data: impl Into<Bytes>) -> MockClient {
+        self.respond(Response::Bytes(data.into()))
+    }
+
+    /// Answers stats and writes with `object`
+    pub fn returns_object(self, object: MockObject) -> MockClient {
+        self.respond(Response::Object(object))
+    }
+
+    /// Answers listings with a single page of `objects`
+    pub fn returns_objects(self, objects: Vec<MockObject>) -> MockClient {
+        self.respond(Response::Objects(objects))
+    }
+
+    /// Answers with success (writes return an object of the written size)
+    pub fn succeeds(self) -> MockClient {
+        self.respond(Response::Ok)
+    }
+
+    /// Answers with the error `error` creates
+    pub fn fails_with(self, error: impl Fn() -> ClientError + Send + Sync + 'static) -> MockClient {
+        self.respond(Response::Error(Arc::new(error)))
+    }
+
+    fn respond(self, response: Response) -> MockClient {
+        let expectation = Expectation { operations: self.operations, bucket: self.bucket, key: self.key, remaining: self.remaining, response };
+        self.client.lock().expectations.push(expectation);
+        self.client.clone()
+    }
+}
+
+impl MockClient {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn expect_on(&self, operations: &[&'static str], bucket: &str, key: Option<&str>) -> MockExpectation<'_> {
+        MockExpectation { client: self, operations: operations.to_vec(), bucket: bucket.to_string(), key: key.map(|t| t.to_string()), remaining: None }
+    }
+
+    /// Downloads (`static_download_bytes`, `download_with_options`, `download_to_writer`)
+    pub fn expect_get(&self, bucket: &str, key: &str) -> MockExpectation<'_> {
+        self.expect_on(GET, bucket, Some(key))
+    }
+
+    /// Uploads (`static_upload_bytes`, `upload_with_options`, `upload_from_reader`)
+    pub fn expect_put(&self, bucket: &str, key: &str) -> MockExpectation<'_> {
+        self.expect_on(PUT, bucket, Some(key))
+    }
+
+    pub fn expect_delete(&self, bucket: &str, key: &str) -> MockExpectation<'_> {
+        self.expect_on(DELETE, bucket, Some(key))
+    }
+
+    /// `stat_object` and `get_object`
+    pub fn expect_stat(&self, bucket: &str, key: &str) -> MockExpectation<'_> {
+        self.expect_on(STAT, bucket, Some(key))
+    }
+
+    /// Listings of the bucket (the key is the prefix, '' for `list_objects`)
+    pub fn expect_list(&self, bucket: &str, prefix: &str) -> MockExpectation<'_> {
+        self.expect_on(LIST, bucket, Some(prefix))
+    }
+
+    /// Any other method, by its name (example: 'set_object_tags'), `key` is None for bucket operations
+    pub fn expect(&self, operation: &'static str, bucket: &str, key: Option<&str>) -> MockExpectation<'_> {
+        self.expect_on(&[operation], bucket, key)
+    }
+
+    /// Every call received so far
+    pub fn calls(&self) -> Vec<MockCall> {
+        self.lock().calls.clone()
+    }
+
+    /// Fails with a description of every unexpected call and every expectation limited by `times` which was not used up
+    pub fn verify(&self) -> Result<(), Vec<String>> {
+        let state = self.lock();
+        let mut problems: Vec<String> = state.unexpected.iter().map(|t| format!("unexpected call {} on {}/{}", t.operation, t.bucket, t.key.as_deref().unwrap_or(""))).collect();
+        problems.extend(state.expectations.iter().filter(|t| t.remaining.is_some_and(|n| n > 0))
+            .map(|t| format!("{} more call(s) of {} on {}/{} expected", t.remaining.unwrap_or_default(), t.operations[0], t.bucket, t.key.as_deref().unwrap_or(""))));
+        if problems.is_empty() { Ok(()) } else { Err(problems) }
+    }
+
+    /// Records the call and returns the response of the first matching expectation
+    fn call(&self, operation: &'static str, bucket: &str, key: Option<&str>) -> ReqRes<Response> {
+        let mut state = self.lock();
+        let call = MockCall { operation, bucket: bucket.to_string(), key: key.map(|t| t.to_string()) };
+        state.calls.push(call.clone());
+        let expectation = state.expectations.iter_mut().find(|t| t.operations.contains(&operation) && t.bucket == bucket
+            && (t.key.is_none() || t.key.as_deref() == key) && t.remaining != Some(0));
+        match expectation {
+            Some(expectation) => {
+                if let Some(n) = expectation.remaining.as_mut() {
+                    *n -= 1;
+                }
+                match &expectation.response {
+                    Response::Error(error) => Err(error()),
+                    response => Ok(response.clone())
+                }
+            }
+            None => {
+                state.unexpected.push(call);
+                Err(ClientError::Unsupported("MockClient: no expectation matches the call"))
+            }
+        }
+    }
+
+    fn unit(&self, operation: &'static str, bucket: &str, key: Option<&str>) -> EmptyReqRes {
+        self.call(operation, bucket, key).map(|_| ())
+    }
+
+    fn bytes(&self, operation: &'static str, bucket: &str, key: &str, starting: Option<u64>, ending: Option<u64>) -> ReqRes<Bytes> {
+        match self.call(operation, bucket, Some(key))? {
+            Response::Bytes(data) => Ok(slice_range(data, starting, ending)),
+            _ => Ok(Bytes::new())
+        }
+    }
+
+    fn object(&self, operation: &'static str, bucket: &str, key: &str, size: u64) -> ReqRes<MockObject> {
+        match self.call(operation, bucket, Some(key))? {
+            Response::Object(object) => Ok(object),
+            Response::Bytes(data) => Ok(MockObject::new(bucket, key, data.len() as u64)),
+            _ => Ok(MockObject::new(bucket, key, size))
+        }
+    }
+
+    fn objects(&self, operation: &'static str, bucket: &str, prefix: &str) -> ReqRes<Vec<MockObject>> {
+        match self.call(operation, bucket, Some(prefix))? {
+            Response::Objects(objects) => Ok(objects),
+            Response::Object(object) => Ok(vec![object]),
+            _ => Ok(vec![])
+        }
+    }
+
+    fn page(&self, operation: &'static str, bucket: &str, prefix: &str) -> ReqRes<ListPage<MockObject>> {
+        Ok(ListPage { items: self.objects(operation, bucket, prefix)?, prefixes: vec![], next_token: None })
+    }
+}
+
+// Returns the concrete `MockObject` / `MockBucket`, so tests can compare them
+#[allow(refining_impl_trait)]
+impl ClientInterface for MockClient {
+    async fn static_download_bytes(&self, bucket: String, object_id: String, starting: Option<u64>, ending: Option<u64>) -> ReqRes<Bytes> {
+        self.bytes("static_download_bytes", &bucket, &object_id, starting, ending)
+    }
+
+    async fn static_upload_bytes(&self, bucket: String, object_id: String, data: Bytes) -> ReqRes<MockObject> {
+        self.object("static_upload_bytes", &bucket, &object_id, data.len() as u64)
+    }
+
+    async fn upload_with_options(&self, bucket: String, object_id: String, data: Bytes, _options: UploadOptions) -> ReqRes<MockObject> {
+        self.object("upload_with_options", &bucket, &object_id, data.len() as u64)
+    }
+
+    async fn download_with_options(&self, bucket: String, object_id: String, starting: Option<u64>, ending: Option<u64>, _options: DownloadOptions) -> ReqRes<Bytes> {
+        self.bytes("download_with_options", &bucket, &object_id, starting, ending)
+    }
+
+    async fn download_to_writer(&self, bucket: String, object_id: String, starting: Option<u64>, ending: Option<u64>, mut w: impl AsyncWrite + Unpin + Send) -> ReqRes<u64> {
+        let data = self.bytes("download_to_writer", &bucket, &object_id, starting, ending)?;
+        w.write_all(&data).await?;
+        w.flush().await?;
+        Ok(data.len() as u64)
+    }
+
+    async fn upload_from_reader(&self, bucket: String, object_id: String, mut r: impl AsyncRead + Unpin + Send + Sync + 'static) -> EmptyReqRes {
+        let mut data = vec![];
+        r.read_to_end(&mut data).await?;
+        self.unit("upload_from_reader", &bucket, Some(&object_id))
+    }
+
+    async fn signed_url(&self, bucket: String, object_id: String, _options: SignedUrlOptions) -> ReqRes<String> {
+        self.unit("signed_url", &bucket, Some(&object_id))?;
+        Ok(format!("mock://{bucket}/{object_id}"))
+    }
+
+    async fn presigned_post(&self, bucket: String, key_prefix: String, _constraints: PostConstraints) -> ReqRes<PresignedPost> {
+        self.unit("presigned_post", &bucket, Some(&key_prefix))?;
+        Ok(PresignedPost { url: format!("mock://{bucket}"), fields: HashMap::new() })
+    }
+
+    async fn remove_bucket(&self, bucket: String) -> EmptyReqRes {
+        self.unit("remove_bucket", &bucket, None)
+    }
+
+    async fn remove_object(&self, bucket: String, object_id: String) -> EmptyReqRes {
+        self.unit("remove_object", &bucket, Some(&object_id))
+    }
+
+    async fn create_bucket(&self, bucket: String) -> ReqRes<MockBucket> {
+        self.unit("create_bucket", &bucket, None)?;
+        Ok(MockBucket(bucket))
+    }
+
+    async fn copy_object(&self, src_bucket: String, src_object: String, dest_bucket: String, dest_object: String) -> ReqRes<MockObject> {
+        self.unit("copy_object", &src_bucket, Some(&src_object))?;
+        Ok(MockObject::new(&dest_bucket, &dest_object, 0))
+    }
+
+    async fn copy_object_with_options(&self, src_bucket: String, src_object: String, dest_bucket: String, dest_object: String, _options: CopyOptions) -> ReqRes<MockObject> {
+        self.unit("copy_object_with_options", &src_bucket, Some(&src_object))?;
+        Ok(MockObject::new(&dest_bucket, &dest_object, 0))
+    }
+
+    async fn restore_object(&self, bucket_name: String, object_name: String, _days: u32, _tier: RetrievalTier) -> EmptyReqRes {
+        self.unit("restore_object", &bucket_name, Some(&object_name))
+    }
+
+    async fn restore_status(&self, bucket_name: String, object_name: String) -> ReqRes<RestoreStatus> {
+        self.unit("restore_status", &bucket_name, Some(&object_name))?;
+        Ok(RestoreStatus::NotRequested)
+    }
+
+    async fn update_object_metadata(&self, bucket: String, object_id: String, _changes: MetadataChanges) -> ReqRes<MockObject> {
+        self.object("update_object_metadata", &bucket, &object_id, 0)
+    }
+
+    async fn get_object_tags(&self, bucket: String, object_id: String) -> ReqRes<HashMap<String, String>> {
+        self.unit("get_object_tags", &bucket, Some(&object_id))?;
+        Ok(HashMap::new())
+    }
+
+    async fn set_object_tags(&self, bucket: String, object_id: String, _tags: HashMap<String, String>) -> EmptyReqRes {
+        self.unit("set_object_tags", &bucket, Some(&object_id))
+    }
+
+    async fn get_object_acl(&self, bucket: String, object_id: String) -> ReqRes<Vec<AclGrant>> {
+        self.unit("get_object_acl", &bucket, Some(&object_id))?;
+        Ok(vec![])
+    }
+
+    async fn set_object_acl(&self, bucket: String, object_id: String, _acl: AccessControl) -> EmptyReqRes {
+        self.unit("set_object_acl", &bucket, Some(&object_id))
+    }
+
+    async fn append(&self, bucket: String, object_id: String, _data: Bytes) -> EmptyReqRes {
+        self.unit("append", &bucket, Some(&object_id))
+    }
+
+    async fn upload_public(&self, bucket: String, object_id: String, _data: Bytes, _content_type: Option<String>, _cache_control: String) -> EmptyReqRes {
+        self.unit("upload_public", &bucket, Some(&object_id))
+    }
+
+    fn public_url(&self, bucket: &str, object_id: &str) -> String {
+        format!("mock://{bucket}/{object_id}")
+    }
+
+    async fn list_buckets(&self, _max_results: Option<u32>, _page_token: Option<Cursor>) -> ReqRes<ListPage<MockBucket>> {
+        let buckets = self.objects("list_buckets", "", "")?;
+        Ok(ListPage { items: buckets.into_iter().map(|t| MockBucket(t.name)).collect(), prefixes: vec![], next_token: None })
+    }
+
+    async fn get_bucket(&self, bucket_name: String) -> ReqRes<MockBucket> {
+        self.unit("get_bucket", &bucket_name, None)?;
+        Ok(MockBucket(bucket_name))
+    }
+
+    async fn get_object(&self, bucket_name: String, object_name: String) -> ReqRes<MockObject> {
+        self.object("get_object", &bucket_name, &object_name, 0)
+    }
+
+    async fn stat_object(&self, bucket_name: String, object_name: String) -> ReqRes<MockObject> {
+        self.object("stat_object", &bucket_name, &object_name, 0)
+    }
+
+    async fn list_objects(&self, bucket_name: String, _max_results: Option<u32>, _page_token: Option<Cursor>) -> ReqRes<ListPage<MockObject>> {
+        self.page("list_objects", &bucket_name, "")
+    }
+
+    async fn list_prefix(&self, bucket_name: String, prefix: String, _page_token: Option<Cursor>) -> ReqRes<ListPage<MockObject>> {
+        self.page("list_prefix", &bucket_name, &prefix)
+    }
+
+    async fn list_projected(&self, bucket_name: String, prefix: String, _attributes: ListAttributes, _page_token: Option<Cursor>) -> ReqRes<ListPage<MockObject>> {
+        self.page("list_projected", &bucket_name, &prefix)
+    }
+
+    async fn list_delimited(&self, bucket_name: String, prefix: String, _delimiter: String, _page_token: Option<Cursor>) -> ReqRes<ListPage<MockObject>> {
+        self.page("list_delimited", &bucket_name, &prefix)
+    }
+
+    async fn list_after(&self, bucket_name: String, prefix: String, _start_after: String, _max_results: u32) -> ReqRes<Vec<MockObject>> {
+        self.objects("list_after", &bucket_name, &prefix)
+    }
+
+    async fn list_versions(&self, bucket_name: String, prefix: String, _page_token: Option<Cursor>) -> ReqRes<ListPage<ObjectVersion>> {
+        self.unit("list_versions", &bucket_name, Some(&prefix))?;
+        Ok(ListPage { items: vec![], prefixes: vec![], next_token: None })
+    }
+
+    async fn restore_object_version(&self, bucket_name: String, object_name: String, _version: String) -> ReqRes<MockObject> {
+        self.object("restore_object_version", &bucket_name, &object_name, 0)
+    }
+
+    async fn compliance_records(&self, bucket_name: String, _page_token: Option<Cursor>) -> ReqRes<ListPage<ComplianceRecord>> {
+        self.unit("compliance_records", &bucket_name, None)?;
+        Ok(ListPage { items: vec![], prefixes: vec![], next_token: None })
+    }
+
+    async fn get_bucket_spec(&self, bucket_name: String) -> ReqRes<BucketSpec> {
+        self.unit("get_bucket_spec", &bucket_name, None)?;
+        Ok(BucketSpec::default())
+    }
+
+    async fn set_lifecycle_rules(&self, bucket_name: String, _rules: Vec<LifecycleRule>) -> EmptyReqRes {
+        self.unit("set_lifecycle_rules", &bucket_name, None)
+    }
+
+    async fn get_object_lock(&self, bucket_name: String, object_name: String) -> ReqRes<ObjectLock> {
+        self.unit("get_object_lock", &bucket_name, Some(&object_name))?;
+        Ok(ObjectLock::default())
+    }
+
+    async fn set_object_retention(&self, bucket_name: String, object_name: String, _retention: Option<Retention>) -> EmptyReqRes {
+        self.unit("set_object_retention", &bucket_name, Some(&object_name))
+    }
+
+    async fn set_legal_hold(&self, bucket_name: String, object_name: String, _on: bool) -> EmptyReqRes {
+        self.unit("set_legal_hold", &bucket_name, Some(&object_name))
+    }
+
+    async fn get_bucket_labels(&self, bucket_name: String) -> ReqRes<HashMap<String, String>> {
+        self.unit("get_bucket_labels", &bucket_name, None)?;
+        Ok(HashMap::new())
+    }
+
+    async fn set_bucket_labels(&self, bucket_name: String, _labels: HashMap<String, String>) -> EmptyReqRes {
+        self.unit("set_bucket_labels", &bucket_name, None)
+    }
+
+    async fn get_bucket_policy(&self, bucket_name: String) -> ReqRes<Option<BucketPolicy>> {
+        self.unit("get_bucket_policy", &bucket_name, None)?;
+        Ok(None)
+    }
+
+    async fn set_bucket_policy(&self, bucket_name: String, _policy: BucketPolicy) -> EmptyReqRes {
+        self.unit("set_bucket_policy", &bucket_name, None)
+    }
+
+    fn bucket_name_problems(&self, _bucket_name: &str) -> Vec<BucketNameProblem> {
+        vec![]
+    }
+}
@@ -0,0 +1,132 @@
+use std::net::Ipv4Addr;
+use futures::{stream, StreamExt};
+use crate::{ClientInterface, ReqRes, CONCURRENCY};
+
+/// A naming rule a bucket name violates
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BucketNameProblem {
+    /// Too short or too long (3 to 63 characters, up to 222 with dots on Google-Cloud)
+    Length,
+    /// Only lowercase letters, digits, '-' and '.' are allowed ('_' on Google-Cloud as well)
+    InvalidCharacter(char),
+    /// Names have to start and end with a letter or digit
+    InvalidBoundary,
+    /// Dots may not follow each other
+    AdjacentDots,
+    /// Names may not look like an IP address (example: '192.168.5.4')
+    IpAddress,
+    /// The name uses a prefix or suffix reserved by the provider (example: 'xn--', 'goog')
+    Reserved,
+}
+
+/// Bucket naming rules of a provider
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NamingRules {
+    AwsS3,
+    GoogleCloud,
+}
+
+const AWS_RESERVED_PREFIXES: [&str; 3] = ["xn--", "sthree-", "amzn-s3-demo-"];
+const AWS_RESERVED_SUFFIXES: [&str; 5] = ["-s3alias", "--ol-s3", ".mrap", "--x-s3", "--table-s3"];
+
+impl NamingRules {
+    /// All rules `name` violates, empty if it is a valid bucket name
+    pub fn problems(self, name: &str) -> Vec<BucketNameProblem> {
+        let mut problems = vec![];
+        let length_ok = match self {
+            NamingRules::AwsS3 => (3..=63).contains(&name.len()),
+            // Every dot separated component is limited to 63 characters as well
+            NamingRules::GoogleCloud => (3..=63).contains(&name.len())
+                || (name.contains('.') && name.len() <= 222 && name.split('.').all(|t| t.len() <= 63)),
+        };
+        if !length_ok {
+            problems.push(BucketNameProblem::Length);
+        }
+        if let Some(c) = name.chars().find(|&c| !(c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '.'
+            || (c == '_' && self == NamingRules::GoogleCloud))) {
+            problems.push(BucketNameProblem::InvalidCharacter(c));
+        }
+        let boundary = |c: Option<char>| c.is_some_and(|c| c.is_ascii_lowercase() || c.is_ascii_digit());
+        if !boundary(name.chars().next()) || !boundary(name.chars().last()) {
+            problems.push(BucketNameProblem::InvalidBoundary);
+        }
+        if name.contains("..") {
+            problems.push(BucketNameProblem::AdjacentDots);
+        }
+        if name.parse::<Ipv4Addr>().is_ok() {
+            problems.push(BucketNameProblem::IpAddress);
+        }
+        let reserved = match self {
+            NamingRules::AwsS3 => AWS_RESERVED_PREFIXES.iter().any(|t| name.starts_with(t)) || AWS_RESERVED_SUFFIXES.iter().any(|t| name.ends_with(t)),
+            NamingRules::GoogleCloud => name.starts_with("goog") || name.contains("google"),
+        };
+        if reserved {
+            problems.push(BucketNameProblem::Reserved);
+        }
+        problems
+    }
+}
+
+/// Result of `ClientInterface::check_bucket_name`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BucketNameCheck {
+    pub name: String,
+    /// Empty if the name is valid
+    pub problems: Vec<BucketNameProblem>,
+    /// Whether nobody owns a bucket of this name, None if the name is invalid (and was not probed)
+    pub available: Option<bool>,
+    /// Valid and available variants of the name, only looked for if the name cannot be used
+    pub suggestions: Vec<String>,
+}
+
+impl BucketNameCheck {
+    /// Whether a bucket of this name can be created
+    pub fn usable(&self) -> bool {
+        self.problems.is_empty() && self.available == Some(true)
+    }
+}
+
+/// Lowercases the name, replaces invalid characters with '-' and trims it to leave room for a suffix
+fn sanitize(name: &str) -> String {
+    let name: String = name.chars().map(|c| match c.to_ascii_lowercase() {
+        c if c.is_ascii_lowercase() || c.is_ascii_digit() => c,
+        _ => '-'
+    }).take(56).collect();
+    let name = name.trim_matches('-');
+    if name.is_empty() { "bucket".to_string() } else { name.to_string() }
+}
+
+/// Buckets which exist but belong to somebody else are reported as taken (the probe is denied)
+async fn available<C: ClientInterface + ?Sized>(client: &C, name: String) -> ReqRes<bool> {
+    match client.bucket_exists(name).await {
+        Ok(exists) => Ok(!exists),
+        Err(e) if e.is_permission_denied() => Ok(false),
+        Err(e) => Err(e)
+    }
+}
+
+pub(crate) async fn check<C: ClientInterface + ?Sized>(client: &C, name: String, suggestions: usize) -> ReqRes<BucketNameCheck> {
+    let problems = client.bucket_name_problems(&name);
+    let is_available = if problems.is_empty() { Some(available(client, name.clone()).await?) } else { None };
+    let mut check = BucketNameCheck { name, problems, available: is_available, suggestions: vec![] };
+    if check.usable() || suggestions == 0 {
+        return Ok(check)
+    }
+    // Numbered variants of the sanitized name, a few more than requested as some of them may be taken
+    let base = sanitize(&check.name);
+    let candidates = (1..=suggestions.saturating_mul(3)).map(|i| format!("{base}-{i}"))
+        .filter(|t| client.bucket_name_problems(t).is_empty());
+    let probed: Vec<_> = stream::iter(candidates).map(|t| async move {
+        let available = available(client, t.clone()).await;
+        (t, available)
+    }).buffered(CONCURRENCY).collect().await;
+    for (candidate, available) in probed {
+        if available? {
+            check.suggestions.push(candidate);
+        }
+        if check.suggestions.len() == suggestions {
+            break
+        }
+    }
+    Ok(check)
+}
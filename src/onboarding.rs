@@ -0,0 +1,68 @@
+use serde::Serialize;
+use crate::compliance::PROVIDER_MANAGED;
+use crate::{Capability, ClientBucket, ClientInterface, ReqRes};
+
+/// Requirements a customer provided bucket has to meet
+#[derive(Debug, Clone, Default)]
+pub struct OnboardingRequirements {
+    /// Locations the bucket may be in (compared case-insensitively), None allows all
+    pub allowed_locations: Option<Vec<String>>,
+    /// New objects must be encrypted by default
+    pub require_encryption: bool,
+    /// New objects must be encrypted with a customer managed key by default
+    pub require_customer_key: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum OnboardingProblem {
+    /// An operation is not permitted
+    Missing(Capability),
+    /// The bucket is in a location which is not allowed (None if the location could not be read)
+    Location(Option<String>),
+    Unencrypted,
+    NoCustomerKey,
+}
+
+/// Result of validating a customer provided bucket
+#[derive(Debug, Clone, Serialize)]
+pub struct OnboardingReport {
+    pub bucket: String,
+    pub location: Option<String>,
+    /// Default encryption of the bucket (see `BucketSpec::encryption`)
+    pub encryption: Option<String>,
+    /// Operations which succeeded
+    pub capabilities: Vec<Capability>,
+    pub problems: Vec<OnboardingProblem>,
+}
+
+impl OnboardingReport {
+    /// Whether the bucket meets all requirements
+    pub fn is_ready(&self) -> bool {
+        self.problems.is_empty()
+    }
+
+    pub fn to_json(&self) -> ReqRes<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+/// Validates a bucket against `requirements`, see `ClientInterface::onboard_bucket`
+pub(crate) async fn onboard<C: ClientInterface + ?Sized>(client: &C, bucket: String, requirements: &OnboardingRequirements) -> OnboardingReport {
+    let capabilities: Vec<_> = client.probe_permissions(bucket.clone()).await.allowed().collect();
+    let mut problems: Vec<_> = [Capability::List, Capability::Put, Capability::Get, Capability::Delete, Capability::Sign].into_iter()
+        .filter(|t| !capabilities.contains(t))
+        .map(OnboardingProblem::Missing)
+        .collect();
+    let location = client.get_bucket(bucket.clone()).await.ok().and_then(|t| t.location());
+    if let Some(allowed) = &requirements.allowed_locations
+        && !location.as_ref().is_some_and(|l| allowed.iter().any(|t| t.eq_ignore_ascii_case(l))) {
+        problems.push(OnboardingProblem::Location(location.clone()));
+    }
+    let encryption = client.get_bucket_spec(bucket.clone()).await.ok().and_then(|t| t.encryption);
+    match &encryption {
+        None if requirements.require_encryption || requirements.require_customer_key => problems.push(OnboardingProblem::Unencrypted),
+        Some(encryption) if requirements.require_customer_key && PROVIDER_MANAGED.contains(&encryption.as_str()) => problems.push(OnboardingProblem::NoCustomerKey),
+        _ => {}
+    }
+    OnboardingReport { bucket, location, encryption, capabilities, problems }
+}
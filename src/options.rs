@@ -0,0 +1,243 @@
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+use std::time::{Duration, SystemTime};
+use crate::LifecycleRule;
+
+/// Canned access control applied to an uploaded object (supported by both providers)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ObjectAcl {
+    /// Only the owner has access
+    Private,
+    /// Anyone can read the object
+    PublicRead,
+    /// Every authenticated user can read the object
+    AuthenticatedRead,
+    /// The bucket owner can read the object
+    BucketOwnerRead,
+    /// The bucket owner has full control of the object
+    BucketOwnerFullControl,
+}
+
+/// Who an ACL grant applies to
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Grantee {
+    /// Anyone, including anonymous users
+    AllUsers,
+    /// Every authenticated user (of any account)
+    AuthenticatedUsers,
+    /// A user or service account by e-mail
+    Email(String),
+    /// Provider specific identifier (AWS-S3 canonical user ID or group URI, Google-Cloud entity such as 'project-owners-123')
+    Id(String),
+}
+
+impl Grantee {
+    /// Whether the grant makes the object public
+    pub fn is_public(&self) -> bool {
+        matches!(self, Grantee::AllUsers | Grantee::AuthenticatedUsers)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AclPermission {
+    Read,
+    /// Read the object, read and change its ACL
+    FullControl,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AclGrant {
+    pub grantee: Grantee,
+    pub permission: AclPermission,
+}
+
+/// Access control applied by `ClientInterface::set_object_acl`
+#[derive(Debug, Clone, PartialEq)]
+pub enum AccessControl {
+    Canned(ObjectAcl),
+    /// Replaces all grants, the owner of the object keeps full control
+    Grants(Vec<AclGrant>),
+}
+
+/// Options of `ClientInterface::upload_with_options`, fields which are None use the provider's defaults
+#[derive(Debug, Clone, Default)]
+pub struct UploadOptions {
+    /// Content-Type (example: 'image/png')
+    pub content_type: Option<String>,
+    /// User defined key-value pairs stored with the object
+    pub custom_metadata: HashMap<String, String>,
+    /// Cache-Control (example: 'public, max-age=3600')
+    pub cache_control: Option<String>,
+    /// Content-Encoding (example: 'gzip')
+    pub content_encoding: Option<String>,
+    /// Provider specific name of the storage class (example: 'STANDARD_IA' on AWS-S3, 'NEARLINE' on Google-Cloud)
+    pub storage_class: Option<String>,
+    pub acl: Option<ObjectAcl>,
+    pub precondition: Option<WritePrecondition>,
+}
+
+/// Options of `ClientInterface::ensure_bucket`, only applied to a bucket which is created
+#[derive(Debug, Clone, Default)]
+pub struct BucketOptions {
+    /// See `ClientInterface::set_bucket_labels` (ignored if empty)
+    pub labels: HashMap<String, String>,
+    /// See `ClientInterface::set_lifecycle_rules`
+    pub lifecycle_rules: Option<Vec<LifecycleRule>>,
+}
+
+/// Options of `ClientInterface::copy_object_with_options`, fields which are None are taken from the source object
+#[derive(Debug, Clone, Default)]
+pub struct CopyOptions {
+    /// Provider specific name of the storage class of the copy (example: 'GLACIER' on AWS-S3, 'ARCHIVE' on Google-Cloud)
+    pub storage_class: Option<String>,
+}
+
+/// Condition of a write, a write whose condition does not hold fails with `ClientError::PreconditionFailed`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WritePrecondition {
+    /// Only creates the object (AWS-S3 If-None-Match: *, Google-Cloud ifGenerationMatch=0)
+    DoesNotExist,
+    /// Only overwrites this version of the object (see `ClientObject::version`)
+    Matches(String),
+}
+
+/// Options of `ClientInterface::download_with_options`
+/// A download whose conditions are not met fails with `ClientError::NotModified` or `ClientError::PreconditionFailed`
+#[derive(Debug, Clone, Default)]
+pub struct DownloadOptions {
+    /// Only download if the object's ETag matches ('*' matches any object)
+    pub if_match: Option<String>,
+    /// Only download if the object's ETag differs, for cache validation ('*' only downloads missing objects)
+    pub if_none_match: Option<String>,
+    /// Only download if the object was modified after this time
+    pub if_modified_since: Option<SystemTime>,
+    /// Downloads a specific version (AWS-S3 version ID or Google-Cloud generation) instead of the live one
+    pub version: Option<String>,
+}
+
+impl DownloadOptions {
+    /// Whether any condition is set
+    pub fn is_conditional(&self) -> bool {
+        self.if_match.is_some() || self.if_none_match.is_some() || self.if_modified_since.is_some()
+    }
+}
+
+/// What a cached copy of an object is validated with (see `ClientInterface::download_if_modified`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CacheValidator {
+    /// ETag of the cached copy
+    ETag(String),
+    /// When the cached copy was fetched (or last modified)
+    ModifiedSince(SystemTime),
+}
+
+impl From<CacheValidator> for DownloadOptions {
+    fn from(value: CacheValidator) -> Self {
+        match value {
+            CacheValidator::ETag(etag) => DownloadOptions { if_none_match: Some(etag), ..Default::default() },
+            CacheValidator::ModifiedSince(time) => DownloadOptions { if_modified_since: Some(time), ..Default::default() },
+        }
+    }
+}
+
+/// HTTP method a signed URL is valid for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SignedUrlMethod {
+    #[default]
+    Get,
+    Put,
+    Head,
+    /// Removes the object, for handing out short-lived delete capabilities
+    Delete,
+}
+
+/// Options of `ClientInterface::signed_url`
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignedUrlOptions {
+    /// How long the URL stays valid (10 minutes by default, at most 7 days)
+    pub expires_in: Duration,
+    pub method: SignedUrlMethod,
+    /// Content-Type an upload has to be sent with (PUT), or which a download is served with (GET)
+    pub content_type: Option<String>,
+    /// Content-Disposition a download is served with (example: 'attachment; filename="report.pdf"')
+    pub response_content_disposition: Option<String>,
+    /// Headers which are signed, the request has to carry them with exactly these values
+    pub extra_headers: HashMap<String, String>,
+}
+
+impl Default for SignedUrlOptions {
+    fn default() -> Self {
+        Self {
+            expires_in: Duration::from_secs(600),
+            method: SignedUrlMethod::Get,
+            content_type: None,
+            response_content_disposition: None,
+            extra_headers: HashMap::new(),
+        }
+    }
+}
+
+impl SignedUrlOptions {
+    pub fn upload() -> Self {
+        Self { method: SignedUrlMethod::Put, ..Default::default() }
+    }
+
+    pub fn delete() -> Self {
+        Self { method: SignedUrlMethod::Delete, ..Default::default() }
+    }
+
+    /// Download which browsers save as `filename` instead of displaying it
+    pub fn attachment(filename: &str) -> Self {
+        let filename = filename.replace(['"', '\\'], "_");
+        Self { response_content_disposition: Some(format!("attachment; filename=\"{filename}\"")), ..Default::default() }
+    }
+}
+
+/// Restrictions of browser uploads through `ClientInterface::presigned_post`
+#[derive(Debug, Clone, PartialEq)]
+pub struct PostConstraints {
+    /// How long the form can be submitted (10 minutes by default, at most 7 days)
+    pub expires_in: Duration,
+    /// Accepted sizes of the uploaded file in bytes
+    pub content_length: Option<RangeInclusive<u64>>,
+    /// The form has to contain a Content-Type field starting with this (example: 'image/')
+    pub content_type_prefix: Option<String>,
+}
+
+impl Default for PostConstraints {
+    fn default() -> Self {
+        Self { expires_in: Duration::from_secs(600), content_length: None, content_type_prefix: None }
+    }
+}
+
+/// An HTML form upload, submitted as multipart/form-data to `url`
+/// All `fields` have to be sent before the file, which is sent as the last field named 'file'
+/// The 'key' field defaults to the key prefix followed by the name of the uploaded file, it may be changed to any key with that prefix
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PresignedPost {
+    pub url: String,
+    pub fields: HashMap<String, String>,
+}
+
+/// Changes applied by `ClientInterface::update_object_metadata`, fields which are None are kept
+#[derive(Debug, Clone, Default)]
+pub struct MetadataChanges {
+    pub content_type: Option<String>,
+    pub cache_control: Option<String>,
+    pub content_encoding: Option<String>,
+    pub content_disposition: Option<String>,
+    /// Custom metadata which is added (or overwritten)
+    pub custom_metadata: HashMap<String, String>,
+    /// Keys of custom metadata which are removed
+    pub remove_metadata: Vec<String>,
+}
+
+impl MetadataChanges {
+    /// Applies the custom metadata changes to the current custom metadata
+    #[cfg(any(feature = "google_cloud", feature = "aws_s3"))]
+    pub(crate) fn merge_custom(&self, mut current: HashMap<String, String>) -> HashMap<String, String> {
+        current.retain(|k, _| !self.remove_metadata.contains(k));
+        current.extend(self.custom_metadata.clone());
+        current
+    }
+}
@@ -0,0 +1,34 @@
+use std::collections::HashSet;
+use crate::{ClientInterface, ReqRes};
+
+/// Result of `ClientInterface::find_orphans`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Orphans {
+    /// Derived objects whose source no longer exists (example: thumbnails of deleted originals)
+    pub derived: Vec<String>,
+    /// Sources whose derived object is missing (example: originals which were never transcoded)
+    pub sources: Vec<String>,
+}
+
+impl Orphans {
+    pub fn is_empty(&self) -> bool {
+        self.derived.is_empty() && self.sources.is_empty()
+    }
+}
+
+pub(crate) async fn find<C: ClientInterface + ?Sized>(client: &C, bucket: String, primary_prefix: String, derived_prefix: String,
+                                                      key_mapping: impl Fn(&str) -> String) -> ReqRes<Orphans> {
+    let sources = client.list_prefix_names(bucket.clone(), primary_prefix).await?;
+    let derived: HashSet<String> = client.list_prefix_names(bucket, derived_prefix).await?.into_iter().collect();
+    let expected: Vec<(String, String)> = sources.into_iter().map(|t| {
+        let mapped = key_mapping(&t);
+        (t, mapped)
+    }).collect();
+    let expected_derived: HashSet<&str> = expected.iter().map(|(_, t)| t.as_str()).collect();
+    let mut orphans = Orphans {
+        derived: derived.iter().filter(|t| !expected_derived.contains(t.as_str())).cloned().collect(),
+        sources: expected.iter().filter(|(_, t)| !derived.contains(t)).map(|(t, _)| t.clone()).collect(),
+    };
+    orphans.derived.sort();
+    Ok(orphans)
+}
@@ -0,0 +1,386 @@
+use std::collections::HashMap;
+use std::future::{poll_fn, Future};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::time::Duration;
+use bytes::Bytes;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use crate::{AccessControl, AclGrant, BucketNameProblem, BucketPolicy, BucketSpec, ClientBucket, ClientInterface, ClientObject, ComplianceRecord, CopyOptions, Cursor, DownloadOptions, EmptyReqRes, LifecycleRule, ListAttributes, ListPage, MetadataChanges, ObjectLock, ObjectVersion, PostConstraints, PresignedPost, ReqRes, RestoreStatus, Retention, RetrievalTier, SignedUrlOptions, UploadOptions};
+
+#[derive(Default)]
+struct Gate {
+    paused: AtomicBool,
+    waiting: Mutex<Vec<Waker>>,
+    in_flight: AtomicUsize,
+    /// Transfers which stopped at a pause, these are counted in `in_flight` as well
+    parked: AtomicUsize,
+}
+
+impl Gate {
+    fn poll_open(&self, cx: &mut Context<'_>) -> Poll<()> {
+        if !self.paused.load(Ordering::SeqCst) {
+            return Poll::Ready(())
+        }
+        let mut waiting = self.waiting.lock().unwrap_or_else(|e| e.into_inner());
+        // Checked again while holding the lock, `resume` wakes the waiting tasks after clearing the flag
+        if !self.paused.load(Ordering::SeqCst) {
+            return Poll::Ready(())
+        }
+        waiting.push(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+/// Pauses and resumes the clients (and the readers / writers of their transfers) it was passed to
+/// Clones control the same clients, so one handle can quiesce all storage traffic of a process
+#[derive(Clone, Default)]
+pub struct PauseHandle(Arc<Gate>);
+
+impl PauseHandle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// New requests wait until `resume` is called, running transfers stop at their next chunk
+    pub fn pause(&self) {
+        self.0.paused.store(true, Ordering::SeqCst);
+    }
+
+    pub fn resume(&self) {
+        self.0.paused.store(false, Ordering::SeqCst);
+        for waker in self.0.waiting.lock().unwrap_or_else(|e| e.into_inner()).drain(..) {
+            waker.wake();
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.0.paused.load(Ordering::SeqCst)
+    }
+
+    /// Number of requests which were started and did not finish yet (including paused transfers)
+    pub fn in_flight(&self) -> usize {
+        self.0.in_flight.load(Ordering::SeqCst)
+    }
+
+    /// Number of transfers which stopped at a pause and wait for `resume`
+    pub fn parked(&self) -> usize {
+        self.0.parked.load(Ordering::SeqCst)
+    }
+
+    /// Waits until no request is running anymore (checked every `interval`), for example after `pause`
+    /// Transfers which stopped at the pause do not count, they keep their request open but send and receive nothing
+    pub async fn idle(&self, interval: Duration) {
+        while self.in_flight() > self.parked() {
+            tokio::time::sleep(interval).await;
+        }
+    }
+
+    /// Waits while paused, the request counts as running until the guard is dropped
+    async fn enter(&self) -> InFlight {
+        poll_fn(|cx| self.0.poll_open(cx)).await;
+        self.0.in_flight.fetch_add(1, Ordering::SeqCst);
+        InFlight(self.0.clone())
+    }
+}
+
+struct InFlight(Arc<Gate>);
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.0.in_flight.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Reader which stops reading while paused
+struct Gated<T> {
+    inner: T,
+    handle: PauseHandle,
+    /// Whether the transfer is counted in `Gate::parked`
+    parked: bool,
+}
+
+impl<T> Gated<T> {
+    fn new(inner: T, handle: PauseHandle) -> Self {
+        Self { inner, handle, parked: false }
+    }
+
+    /// Like `Gate::poll_open`, counts the transfer as parked while it waits
+    fn poll_open(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        let open = self.handle.0.poll_open(cx);
+        if open.is_pending() != self.parked {
+            self.parked = open.is_pending();
+            if self.parked {
+                self.handle.0.parked.fetch_add(1, Ordering::SeqCst);
+            } else {
+                self.handle.0.parked.fetch_sub(1, Ordering::SeqCst);
+            }
+        }
+        open
+    }
+}
+
+impl<T> Drop for Gated<T> {
+    fn drop(&mut self) {
+        if self.parked {
+            self.handle.0.parked.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for Gated<T> {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<std::io::Result<()>> {
+        if self.poll_open(cx).is_pending() {
+            return Poll::Pending
+        }
+        Pin::new(&mut self.inner).poll_read(cx, buf)
+    }
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for Gated<T> {
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<std::io::Result<usize>> {
+        if self.poll_open(cx).is_pending() {
+            return Poll::Pending
+        }
+        Pin::new(&mut self.inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+/// Wraps a client so its traffic can be paused (see `PauseHandle`), e.g. during incident response or provider maintenance
+///
+/// While paused, new requests wait before they are sent and streamed transfers stop between chunks,
+/// requests which were already sent complete normally.
+/// Daemons (`Watchdog`, `ChangeFeed`) pause with the client they use.
+/// Note: A transfer paused for long may be cut off by the provider, uploads which have to survive this should be checkpointed
+/// (see `UploadCheckpoint`) so they can be resumed.
+pub struct PausableClient<C: ClientInterface> {
+    inner: C,
+    handle: PauseHandle,
+}
+
+impl<C: ClientInterface> PausableClient<C> {
+    pub fn new(inner: C) -> Self {
+        Self { inner, handle: PauseHandle::new() }
+    }
+
+    /// Controlled by an existing handle, to pause several clients at once
+    pub fn with_handle(inner: C, handle: PauseHandle) -> Self {
+        Self { inner, handle }
+    }
+
+    pub fn handle(&self) -> &PauseHandle {
+        &self.handle
+    }
+
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    /// Runs a request once the client is not paused
+    async fn gated<T>(&self, request: impl Future<Output = T>) -> T {
+        let _in_flight = self.handle.enter().await;
+        request.await
+    }
+}
+
+impl<C: ClientInterface> ClientInterface for PausableClient<C> {
+    async fn static_download_bytes(&self, bucket: String, object_id: String, starting: Option<u64>, ending: Option<u64>) -> ReqRes<Bytes> {
+        self.gated(self.inner.static_download_bytes(bucket, object_id, starting, ending)).await
+    }
+
+    async fn static_upload_bytes(&self, bucket: String, object_id: String, data: Bytes) -> ReqRes<impl ClientObject> {
+        self.gated(self.inner.static_upload_bytes(bucket, object_id, data)).await
+    }
+
+    async fn upload_with_options(&self, bucket: String, object_id: String, data: Bytes, options: UploadOptions) -> ReqRes<impl ClientObject> {
+        self.gated(self.inner.upload_with_options(bucket, object_id, data, options)).await
+    }
+
+    async fn download_with_options(&self, bucket: String, object_id: String, starting: Option<u64>, ending: Option<u64>, options: DownloadOptions) -> ReqRes<Bytes> {
+        self.gated(self.inner.download_with_options(bucket, object_id, starting, ending, options)).await
+    }
+
+    async fn download_to_writer(&self, bucket: String, object_id: String, starting: Option<u64>, ending: Option<u64>, w: impl AsyncWrite + Unpin + Send) -> ReqRes<u64> {
+        let w = Gated::new(w, self.handle.clone());
+        self.gated(self.inner.download_to_writer(bucket, object_id, starting, ending, w)).await
+    }
+
+    async fn upload_from_reader(&self, bucket: String, object_id: String, r: impl AsyncRead + Unpin + Send + Sync + 'static) -> EmptyReqRes {
+        let r = Gated::new(r, self.handle.clone());
+        self.gated(self.inner.upload_from_reader(bucket, object_id, r)).await
+    }
+
+    async fn signed_url(&self, bucket: String, object_id: String, options: SignedUrlOptions) -> ReqRes<String> {
+        self.gated(self.inner.signed_url(bucket, object_id, options)).await
+    }
+
+    async fn presigned_post(&self, bucket: String, key_prefix: String, constraints: PostConstraints) -> ReqRes<PresignedPost> {
+        self.gated(self.inner.presigned_post(bucket, key_prefix, constraints)).await
+    }
+
+    async fn remove_bucket(&self, bucket: String) -> EmptyReqRes {
+        self.gated(self.inner.remove_bucket(bucket)).await
+    }
+
+    async fn remove_object(&self, bucket: String, object_id: String) -> EmptyReqRes {
+        self.gated(self.inner.remove_object(bucket, object_id)).await
+    }
+
+    async fn create_bucket(&self, bucket: String) -> ReqRes<impl ClientBucket> {
+        self.gated(self.inner.create_bucket(bucket)).await
+    }
+
+    async fn copy_object(&self, src_bucket: String, src_object: String, dest_bucket: String, dest_object: String) -> ReqRes<impl ClientObject> {
+        self.gated(self.inner.copy_object(src_bucket, src_object, dest_bucket, dest_object)).await
+    }
+
+    async fn copy_object_with_options(&self, src_bucket: String, src_object: String, dest_bucket: String, dest_object: String, options: CopyOptions) -> ReqRes<impl ClientObject> {
+        self.gated(self.inner.copy_object_with_options(src_bucket, src_object, dest_bucket, dest_object, options)).await
+    }
+
+    async fn restore_object(&self, bucket_name: String, object_name: String, days: u32, tier: RetrievalTier) -> EmptyReqRes {
+        self.gated(self.inner.restore_object(bucket_name, object_name, days, tier)).await
+    }
+
+    async fn restore_status(&self, bucket_name: String, object_name: String) -> ReqRes<RestoreStatus> {
+        self.gated(self.inner.restore_status(bucket_name, object_name)).await
+    }
+
+    async fn update_object_metadata(&self, bucket: String, object_id: String, changes: MetadataChanges) -> ReqRes<impl ClientObject> {
+        self.gated(self.inner.update_object_metadata(bucket, object_id, changes)).await
+    }
+
+    async fn get_object_tags(&self, bucket: String, object_id: String) -> ReqRes<HashMap<String, String>> {
+        self.gated(self.inner.get_object_tags(bucket, object_id)).await
+    }
+
+    async fn set_object_tags(&self, bucket: String, object_id: String, tags: HashMap<String, String>) -> EmptyReqRes {
+        self.gated(self.inner.set_object_tags(bucket, object_id, tags)).await
+    }
+
+    async fn get_object_acl(&self, bucket: String, object_id: String) -> ReqRes<Vec<AclGrant>> {
+        self.gated(self.inner.get_object_acl(bucket, object_id)).await
+    }
+
+    async fn set_object_acl(&self, bucket: String, object_id: String, acl: AccessControl) -> EmptyReqRes {
+        self.gated(self.inner.set_object_acl(bucket, object_id, acl)).await
+    }
+
+    async fn append(&self, bucket: String, object_id: String, data: Bytes) -> EmptyReqRes {
+        self.gated(self.inner.append(bucket, object_id, data)).await
+    }
+
+    async fn upload_public(&self, bucket: String, object_id: String, data: Bytes, content_type: Option<String>, cache_control: String) -> EmptyReqRes {
+        self.gated(self.inner.upload_public(bucket, object_id, data, content_type, cache_control)).await
+    }
+
+    /// Does not send a request, so it is never paused
+    fn public_url(&self, bucket: &str, object_id: &str) -> String {
+        self.inner.public_url(bucket, object_id)
+    }
+
+    async fn list_buckets(&self, max_results: Option<u32>, page_token: Option<Cursor>) -> ReqRes<ListPage<impl ClientBucket>> {
+        self.gated(self.inner.list_buckets(max_results, page_token)).await
+    }
+
+    async fn get_bucket(&self, bucket_name: String) -> ReqRes<impl ClientBucket> {
+        self.gated(self.inner.get_bucket(bucket_name)).await
+    }
+
+    async fn get_object(&self, bucket_name: String, object_name: String) -> ReqRes<impl ClientObject> {
+        self.gated(self.inner.get_object(bucket_name, object_name)).await
+    }
+
+    async fn stat_object(&self, bucket_name: String, object_name: String) -> ReqRes<impl ClientObject> {
+        self.gated(self.inner.stat_object(bucket_name, object_name)).await
+    }
+
+    async fn list_objects(&self, bucket_name: String, max_results: Option<u32>, page_token: Option<Cursor>) -> ReqRes<ListPage<impl ClientObject>> {
+        self.gated(self.inner.list_objects(bucket_name, max_results, page_token)).await
+    }
+
+    async fn list_prefix(&self, bucket_name: String, prefix: String, page_token: Option<Cursor>) -> ReqRes<ListPage<impl ClientObject>> {
+        self.gated(self.inner.list_prefix(bucket_name, prefix, page_token)).await
+    }
+
+    async fn list_projected(&self, bucket_name: String, prefix: String, attributes: ListAttributes, page_token: Option<Cursor>) -> ReqRes<ListPage<impl ClientObject>> {
+        self.gated(self.inner.list_projected(bucket_name, prefix, attributes, page_token)).await
+    }
+
+    async fn list_delimited(&self, bucket_name: String, prefix: String, delimiter: String, page_token: Option<Cursor>) -> ReqRes<ListPage<impl ClientObject>> {
+        self.gated(self.inner.list_delimited(bucket_name, prefix, delimiter, page_token)).await
+    }
+
+    async fn list_after(&self, bucket_name: String, prefix: String, start_after: String, max_results: u32) -> ReqRes<Vec<impl ClientObject>> {
+        self.gated(self.inner.list_after(bucket_name, prefix, start_after, max_results)).await
+    }
+
+    async fn list_versions(&self, bucket_name: String, prefix: String, page_token: Option<Cursor>) -> ReqRes<ListPage<ObjectVersion>> {
+        self.gated(self.inner.list_versions(bucket_name, prefix, page_token)).await
+    }
+
+    async fn restore_object_version(&self, bucket_name: String, object_name: String, version: String) -> ReqRes<impl ClientObject> {
+        self.gated(self.inner.restore_object_version(bucket_name, object_name, version)).await
+    }
+
+    async fn remove_objects(&self, bucket_name: String, keys: Vec<String>) -> ReqRes<Vec<(String, EmptyReqRes)>> {
+        self.gated(self.inner.remove_objects(bucket_name, keys)).await
+    }
+
+    async fn compliance_records(&self, bucket_name: String, page_token: Option<Cursor>) -> ReqRes<ListPage<ComplianceRecord>> {
+        self.gated(self.inner.compliance_records(bucket_name, page_token)).await
+    }
+
+    async fn get_bucket_spec(&self, bucket_name: String) -> ReqRes<BucketSpec> {
+        self.gated(self.inner.get_bucket_spec(bucket_name)).await
+    }
+
+    async fn get_lifecycle_rules(&self, bucket_name: String) -> ReqRes<Vec<LifecycleRule>> {
+        self.gated(self.inner.get_lifecycle_rules(bucket_name)).await
+    }
+
+    async fn set_lifecycle_rules(&self, bucket_name: String, rules: Vec<LifecycleRule>) -> EmptyReqRes {
+        self.gated(self.inner.set_lifecycle_rules(bucket_name, rules)).await
+    }
+
+    async fn get_object_lock(&self, bucket_name: String, object_name: String) -> ReqRes<ObjectLock> {
+        self.gated(self.inner.get_object_lock(bucket_name, object_name)).await
+    }
+
+    async fn set_object_retention(&self, bucket_name: String, object_name: String, retention: Option<Retention>) -> EmptyReqRes {
+        self.gated(self.inner.set_object_retention(bucket_name, object_name, retention)).await
+    }
+
+    async fn set_legal_hold(&self, bucket_name: String, object_name: String, on: bool) -> EmptyReqRes {
+        self.gated(self.inner.set_legal_hold(bucket_name, object_name, on)).await
+    }
+
+    async fn get_bucket_labels(&self, bucket_name: String) -> ReqRes<HashMap<String, String>> {
+        self.gated(self.inner.get_bucket_labels(bucket_name)).await
+    }
+
+    async fn set_bucket_labels(&self, bucket_name: String, labels: HashMap<String, String>) -> EmptyReqRes {
+        self.gated(self.inner.set_bucket_labels(bucket_name, labels)).await
+    }
+
+    async fn get_bucket_policy(&self, bucket_name: String) -> ReqRes<Option<BucketPolicy>> {
+        self.gated(self.inner.get_bucket_policy(bucket_name)).await
+    }
+
+    async fn set_bucket_policy(&self, bucket_name: String, policy: BucketPolicy) -> EmptyReqRes {
+        self.gated(self.inner.set_bucket_policy(bucket_name, policy)).await
+    }
+
+    fn bucket_name_problems(&self, bucket_name: &str) -> Vec<BucketNameProblem> {
+        self.inner.bucket_name_problems(bucket_name)
+    }
+}
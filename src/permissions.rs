@@ -0,0 +1,109 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+use serde::Serialize;
+use crate::{ClientError, ClientInterface, ReqRes};
+
+/// Objects written while probing a bucket are placed under this prefix (and removed again)
+pub const PROBE_PREFIX: &str = ".uni-stg/probe/";
+
+/// Operations which are probed by `ClientInterface::probe_permissions`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Capability {
+    /// List objects
+    List,
+    /// Read the bucket's metadata (location)
+    ReadBucket,
+    /// Read the bucket's configuration (versioning, lifecycle, CORS, encryption)
+    ReadConfig,
+    Put,
+    Stat,
+    Get,
+    Copy,
+    Delete,
+    /// Create signed download URLs
+    Sign,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum ProbeOutcome {
+    Allowed,
+    /// The provider rejected the request because of missing permissions
+    Denied,
+    /// The request failed for another reason (network, missing bucket, ...)
+    Failed,
+    /// Not attempted because an operation it depends on (Put) did not succeed
+    Untested,
+}
+
+/// Outcome of every probed operation on a bucket
+#[derive(Debug, Clone, Serialize)]
+pub struct PermissionReport {
+    pub bucket: String,
+    pub results: Vec<(Capability, ProbeOutcome)>,
+}
+
+impl PermissionReport {
+    pub fn outcome(&self, capability: Capability) -> ProbeOutcome {
+        self.results.iter().find(|(t, _)| *t == capability).map(|(_, t)| *t).unwrap_or(ProbeOutcome::Untested)
+    }
+
+    pub fn is_allowed(&self, capability: Capability) -> bool {
+        self.outcome(capability) == ProbeOutcome::Allowed
+    }
+
+    pub fn allowed(&self) -> impl Iterator<Item = Capability> + '_ {
+        self.results.iter().filter(|(_, t)| *t == ProbeOutcome::Allowed).map(|(t, _)| *t)
+    }
+
+    pub fn denied(&self) -> impl Iterator<Item = Capability> + '_ {
+        self.results.iter().filter(|(_, t)| *t == ProbeOutcome::Denied).map(|(t, _)| *t)
+    }
+
+    pub fn to_json(&self) -> ReqRes<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+fn outcome<T>(result: &Result<T, ClientError>) -> ProbeOutcome {
+    match result {
+        Ok(_) => ProbeOutcome::Allowed,
+        Err(e) if e.is_permission_denied() => ProbeOutcome::Denied,
+        Err(_) => ProbeOutcome::Failed
+    }
+}
+
+/// See `ClientInterface::probe_permissions`
+/// Objects which could be written but not deleted (Delete denied) are left behind under `PROBE_PREFIX`
+pub(crate) async fn probe<C: ClientInterface + ?Sized>(client: &C, bucket: String) -> PermissionReport {
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|t| t.as_nanos()).unwrap_or(0);
+    let key = format!("{PROBE_PREFIX}{nanos}");
+    let copy = format!("{key}.copy");
+    let data = key.clone().into_bytes();
+    let mut results = vec![
+        (Capability::List, outcome(&client.list_prefix(bucket.clone(), PROBE_PREFIX.to_string(), None).await)),
+        (Capability::ReadBucket, outcome(&client.get_bucket(bucket.clone()).await)),
+        (Capability::ReadConfig, outcome(&client.get_bucket_spec(bucket.clone()).await)),
+    ];
+    let put = outcome(&client.static_upload_bytes(bucket.clone(), key.clone(), data.clone().into()).await);
+    results.push((Capability::Put, put));
+    if put == ProbeOutcome::Allowed {
+        results.push((Capability::Stat, outcome(&client.stat_object(bucket.clone(), key.clone()).await)));
+        let get = match client.static_download_bytes(bucket.clone(), key.clone(), None, None).await {
+            Ok(t) if t != data => ProbeOutcome::Failed,
+            result => outcome(&result)
+        };
+        results.push((Capability::Get, get));
+        let copied = outcome(&client.copy_object(bucket.clone(), key.clone(), bucket.clone(), copy.clone()).await);
+        results.push((Capability::Copy, copied));
+        if copied == ProbeOutcome::Allowed {
+            let _ = client.remove_object(bucket.clone(), copy).await;
+        }
+        results.push((Capability::Delete, outcome(&client.remove_object(bucket.clone(), key.clone()).await)));
+    } else {
+        results.extend([Capability::Stat, Capability::Get, Capability::Copy, Capability::Delete].map(|t| (t, ProbeOutcome::Untested)));
+    }
+    results.push((Capability::Sign, match client.url_download_object(bucket.clone(), key).await {
+        Ok(t) if t.is_empty() => ProbeOutcome::Failed,
+        result => outcome(&result)
+    }));
+    PermissionReport { bucket, results }
+}
@@ -0,0 +1,44 @@
+use serde_json::Value;
+
+/// Access policy of a bucket in the model of its provider
+#[derive(Debug, Clone, PartialEq)]
+pub enum BucketPolicy {
+    /// AWS-S3 bucket policy document (JSON)
+    S3(Value),
+    /// Google-Cloud IAM policy
+    Iam(IamPolicy),
+}
+
+/// IAM policy of a Google-Cloud bucket
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IamPolicy {
+    pub bindings: Vec<IamBinding>,
+    /// ETag of the policy which was read, a policy set with an outdated ETag is rejected (empty to overwrite unconditionally)
+    pub etag: String,
+}
+
+/// Grants `role` (example: 'roles/storage.objectViewer') to `members` (example: 'user:alice@example.com', 'allUsers')
+#[derive(Debug, Clone, PartialEq)]
+pub struct IamBinding {
+    pub role: String,
+    pub members: Vec<String>,
+}
+
+impl IamPolicy {
+    /// Adds `member` to the binding of `role`, creating it if necessary
+    pub fn grant(&mut self, role: &str, member: &str) {
+        match self.bindings.iter_mut().find(|t| t.role == role) {
+            Some(binding) if binding.members.iter().any(|t| t == member) => {}
+            Some(binding) => binding.members.push(member.to_string()),
+            None => self.bindings.push(IamBinding { role: role.to_string(), members: vec![member.to_string()] }),
+        }
+    }
+
+    /// Removes `member` from the binding of `role`, bindings left without members are removed
+    pub fn revoke(&mut self, role: &str, member: &str) {
+        for binding in self.bindings.iter_mut().filter(|t| t.role == role) {
+            binding.members.retain(|t| t != member);
+        }
+        self.bindings.retain(|t| !t.members.is_empty());
+    }
+}
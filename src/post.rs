@@ -0,0 +1,52 @@
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use serde_json::{json, Map, Value};
+use crate::PostConstraints;
+
+/// Signing time formatted as '20240131T235959Z' and its date ('20240131')
+pub(crate) fn signing_time(time: SystemTime) -> (String, String) {
+    let (y, m, d, hh, mm, ss) = civil(time);
+    (format!("{y:04}{m:02}{d:02}T{hh:02}{mm:02}{ss:02}Z"), format!("{y:04}{m:02}{d:02}"))
+}
+
+/// Fields and conditions every provider requires, `fields` are the signing fields which also become conditions
+pub(crate) fn conditions(bucket: &str, key_prefix: &str, constraints: &PostConstraints, fields: &HashMap<String, String>) -> Vec<Value> {
+    let mut conditions = vec![json!({"bucket": bucket}), json!(["starts-with", "$key", key_prefix])];
+    conditions.extend(fields.iter().map(|(k, v)| Value::Object(Map::from_iter([(k.clone(), Value::from(v.as_str()))]))));
+    if let Some(range) = &constraints.content_length {
+        conditions.push(json!(["content-length-range", range.start(), range.end()]));
+    }
+    if let Some(prefix) = &constraints.content_type_prefix {
+        conditions.push(json!(["starts-with", "$Content-Type", prefix]));
+    }
+    conditions
+}
+
+/// Base64 of the policy document, which is what gets signed
+pub(crate) fn policy(expiration: SystemTime, conditions: Vec<Value>) -> String {
+    let (y, m, d, hh, mm, ss) = civil(expiration);
+    let document = json!({
+        "expiration": format!("{y:04}-{m:02}-{d:02}T{hh:02}:{mm:02}:{ss:02}Z"),
+        "conditions": conditions,
+    });
+    STANDARD.encode(document.to_string())
+}
+
+/// UTC year, month, day, hour, minute and second of `time`
+fn civil(time: SystemTime) -> (i64, u32, u32, u32, u32, u32) {
+    let secs = time.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    let (days, rem) = ((secs / 86400) as i64, secs % 86400);
+    // Days to civil date (Howard Hinnant's algorithm)
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let y = yoe + era * 400 + if m <= 2 { 1 } else { 0 };
+    (y, m, d, (rem / 3600) as u32, (rem % 3600 / 60) as u32, (rem % 60) as u32)
+}
@@ -0,0 +1,26 @@
+/// Progress of a server-side copy (see `ClientInterface::copy_object_progress`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CopyProgress {
+    /// Size of the object
+    pub total_bytes: u64,
+    pub bytes_copied: u64,
+    /// Google-Cloud token the next rewrite request continues with
+    pub rewrite_token: Option<String>,
+    /// Whether the copy is finished (only set on the last item)
+    pub done: bool,
+}
+
+impl CopyProgress {
+    /// A finished copy of an object of `size` bytes
+    pub fn finished(size: u64) -> Self {
+        Self { total_bytes: size, bytes_copied: size, rewrite_token: None, done: true }
+    }
+
+    /// Share of the object which was copied (1.0 for empty objects)
+    pub fn fraction(&self) -> f64 {
+        if self.total_bytes == 0 {
+            return 1.0
+        }
+        self.bytes_copied as f64 / self.total_bytes as f64
+    }
+}
@@ -0,0 +1,19 @@
+use sha2::{Digest, Sha256};
+use crate::encoding::hex;
+
+/// Cache-Control header of published objects: cached by browsers and CDNs for a year, never revalidated
+pub const IMMUTABLE_CACHE_CONTROL: &str = "public, max-age=31536000, immutable";
+
+/// A published object, see `ClientInterface::publish`
+#[derive(Debug, Clone, PartialEq)]
+pub struct PublicUrl {
+    pub url: String,
+    pub key: String,
+    /// False if the content had been published before
+    pub uploaded: bool,
+}
+
+/// Hex encoded SHA-256 hash of the data
+pub(crate) fn content_hash(data: &[u8]) -> String {
+    hex(&Sha256::digest(data))
+}
@@ -0,0 +1,64 @@
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use bytes::Bytes;
+use futures::{stream, StreamExt};
+use crate::{BulkReport, ClientError, ClientInterface, ClientObject, ErrorContext, MetadataChanges, ReqRes, UploadOptions, CONCURRENCY};
+
+/// Quarantined uploads are stored below this prefix (followed by their final key) until they are promoted or rejected
+pub const QUARANTINE_PREFIX: &str = ".uni-stg/quarantine/";
+/// Custom metadata key of the time (seconds since the Unix epoch) a quarantined upload expires at
+const EXPIRY_KEY: &str = "quarantine-expires";
+
+pub(crate) fn quarantined(key: &str) -> String {
+    format!("{QUARANTINE_PREFIX}{key}")
+}
+
+fn expired(object: &impl ClientObject, now: SystemTime) -> bool {
+    let now = now.duration_since(UNIX_EPOCH).map(|t| t.as_secs()).unwrap_or(0);
+    object.custom_metadata().get(EXPIRY_KEY).and_then(|t| t.parse::<u64>().ok()).is_some_and(|t| t <= now)
+}
+
+pub(crate) async fn upload<C: ClientInterface + ?Sized>(client: &C, bucket: String, key: String, data: Bytes, ttl: Duration) -> ReqRes<impl ClientObject> {
+    let expires = (SystemTime::now() + ttl).duration_since(UNIX_EPOCH).map(|t| t.as_secs()).unwrap_or(0);
+    let options = UploadOptions {
+        custom_metadata: HashMap::from([(EXPIRY_KEY.to_string(), expires.to_string())]),
+        ..Default::default()
+    };
+    client.upload_with_options(bucket, quarantined(&key), data, options).await
+}
+
+pub(crate) async fn promote<C: ClientInterface + ?Sized>(client: &C, bucket: String, key: String) -> ReqRes<impl ClientObject> {
+    let source = quarantined(&key);
+    let object = client.stat_object(bucket.clone(), source.clone()).await?;
+    if expired(&object, SystemTime::now()) {
+        client.remove_object(bucket.clone(), source).await?;
+        return Err(ClientError::QuarantineExpired.with_context(ErrorContext::new("promote", Some(&bucket), Some(&key))))
+    }
+    client.move_object(bucket.clone(), source, key.clone()).await?;
+    let changes = MetadataChanges { remove_metadata: vec![EXPIRY_KEY.to_string()], ..Default::default() };
+    client.update_object_metadata(bucket, key, changes).await
+}
+
+pub(crate) async fn purge<C: ClientInterface + ?Sized>(client: &C, bucket: String) -> ReqRes<BulkReport> {
+    let keys = client.list_prefix_names(bucket.clone(), QUARANTINE_PREFIX.to_string()).await?;
+    let now = SystemTime::now();
+    let bucket = &bucket;
+    // Listings do not include custom metadata, so every upload is looked at
+    let results = stream::iter(keys).map(|key| async move {
+        let result = match client.stat_object(bucket.clone(), key.clone()).await {
+            Ok(object) if expired(&object, now) => client.remove_object(bucket.clone(), key.clone()).await.map(|_| true),
+            Ok(_) => Ok(false),
+            Err(e) => Err(e)
+        };
+        (key, result)
+    }).buffer_unordered(CONCURRENCY).collect::<Vec<_>>().await;
+    let mut report = BulkReport::default();
+    for (key, result) in results {
+        match result {
+            Ok(true) => report.succeeded += 1,
+            Ok(false) => {}
+            Err(e) => report.failed.push((key, e))
+        }
+    }
+    Ok(report)
+}
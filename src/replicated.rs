@@ -0,0 +1,276 @@
+use std::collections::HashMap;
+use std::future::Future;
+use bytes::Bytes;
+use futures::{stream, StreamExt};
+use tokio::io::{AsyncRead, AsyncReadExt};
+use crate::delegate::delegate;
+use crate::{AccessControl, BucketPolicy, ClientBucket, ClientError, ClientInterface, ClientObject, Context, CopyOptions, EmptyReqRes, LifecycleRule, MetadataChanges, ReqRes, Retention, UploadOptions, CONCURRENCY};
+
+/// Number of replicas a write has to succeed on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReplicationPolicy {
+    /// Every replica
+    #[default]
+    All,
+    /// More than half of the replicas
+    Majority,
+    /// At least this many replicas (at least one, at most all)
+    AtLeast(usize),
+}
+
+impl ReplicationPolicy {
+    pub fn required(&self, replicas: usize) -> usize {
+        match self {
+            ReplicationPolicy::All => replicas,
+            ReplicationPolicy::Majority => replicas / 2 + 1,
+            ReplicationPolicy::AtLeast(n) => (*n).clamp(1, replicas.max(1)),
+        }
+    }
+}
+
+/// A write which failed on one replica
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplicaFailure {
+    pub operation: &'static str,
+    pub bucket: String,
+    pub object: Option<String>,
+    /// Index of the replica (0 is the primary)
+    pub replica: usize,
+    pub error: String,
+}
+
+/// Receives every write which failed on a replica, also if the write as a whole succeeded
+pub type FailureLog = Box<dyn Fn(&ReplicaFailure) + Send + Sync>;
+
+/// Writes to several replicas (buckets of the same name on other providers or regions) and reads from the primary
+///
+/// Writes and removals are sent to every replica at the same time and succeed once the `ReplicationPolicy` is met,
+/// the result of the first successful replica is returned. Replicas which missed a write stay behind until it is repeated
+/// (see `with_failure_log` and `VerifyingReadClient::repair`). Removing an object which is missing on a replica counts as removed there.
+/// Mixed providers are replicated through `UnifiedClient` (or `BoxedClient`).
+/// Note: Preconditions of writes are checked per replica, versions (`WritePrecondition::Matches`) usually differ between them.
+/// Archive restores, version restores, signed URLs and every read only use the primary.
+pub struct ReplicatedClient<C: ClientInterface> {
+    primary: C,
+    secondaries: Vec<C>,
+    policy: ReplicationPolicy,
+    log: Option<FailureLog>,
+}
+
+impl<C: ClientInterface> ReplicatedClient<C> {
+    pub fn new(primary: C, secondaries: Vec<C>) -> Self {
+        Self { primary, secondaries, policy: ReplicationPolicy::All, log: None }
+    }
+
+    pub fn with_policy(mut self, policy: ReplicationPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Sets the callback every failed replica write is recorded with
+    pub fn with_failure_log(mut self, log: impl Fn(&ReplicaFailure) + Send + Sync + 'static) -> Self {
+        self.log = Some(Box::new(log));
+        self
+    }
+
+    pub fn primary(&self) -> &C {
+        &self.primary
+    }
+
+    pub fn secondaries(&self) -> &[C] {
+        &self.secondaries
+    }
+
+    pub fn policy(&self) -> ReplicationPolicy {
+        self.policy
+    }
+
+    /// Sends a write to every replica and applies the policy to the results
+    async fn fan_out<'a, T, Fut: Future<Output = ReqRes<T>>>(&'a self, operation: &'static str, bucket: &str, object: Option<&str>, write: impl Fn(&'a C) -> Fut) -> ReqRes<T> {
+        let replicas = std::iter::once(&self.primary).chain(&self.secondaries);
+        let results = futures::future::join_all(replicas.map(write)).await;
+        let required = self.policy.required(results.len());
+        let (mut succeeded, mut first, mut error) = (0, None, None);
+        for (replica, result) in results.into_iter().enumerate() {
+            match result {
+                Ok(t) => {
+                    succeeded += 1;
+                    first = first.or(Some(t));
+                }
+                Err(e) => {
+                    if let Some(log) = &self.log {
+                        log(&ReplicaFailure { operation, bucket: bucket.to_string(), object: object.map(str::to_string), replica, error: e.to_string() });
+                    }
+                    error = error.or(Some(e));
+                }
+            }
+        }
+        match (first, error) {
+            (Some(t), _) if succeeded >= required => Ok(t),
+            (_, Some(e)) => Err(e),
+            (Some(t), None) => Ok(t),
+            (None, None) => Err(ClientError::Unsupported("a replicated client needs at least one replica"))
+        }
+    }
+}
+
+impl<C: ClientInterface> ClientInterface for ReplicatedClient<C> {
+    async fn static_upload_bytes(&self, bucket: String, object_id: String, data: Bytes) -> ReqRes<impl ClientObject> {
+        self.fan_out("static_upload_bytes", &bucket, Some(&object_id), |c| c.static_upload_bytes(bucket.clone(), object_id.clone(), data.clone())).await
+    }
+
+    async fn upload_with_options(&self, bucket: String, object_id: String, data: Bytes, options: UploadOptions) -> ReqRes<impl ClientObject> {
+        self.fan_out("upload_with_options", &bucket, Some(&object_id), |c| c.upload_with_options(bucket.clone(), object_id.clone(), data.clone(), options.clone())).await
+    }
+
+    /// The data is buffered, so it can be sent to every replica
+    async fn upload_from_reader(&self, bucket: String, object_id: String, mut r: impl AsyncRead + Unpin + Send + Sync + 'static) -> EmptyReqRes {
+        let mut data = vec![];
+        r.read_to_end(&mut data).await.context("upload_from_reader", &bucket, Some(&object_id))?;
+        self.static_upload_bytes(bucket, object_id, data.into()).await?;
+        Ok(())
+    }
+
+    async fn append(&self, bucket: String, object_id: String, data: Bytes) -> EmptyReqRes {
+        self.fan_out("append", &bucket, Some(&object_id), |c| c.append(bucket.clone(), object_id.clone(), data.clone())).await
+    }
+
+    async fn upload_public(&self, bucket: String, object_id: String, data: Bytes, content_type: Option<String>, cache_control: String) -> EmptyReqRes {
+        self.fan_out("upload_public", &bucket, Some(&object_id), |c| c.upload_public(bucket.clone(), object_id.clone(), data.clone(), content_type.clone(), cache_control.clone())).await
+    }
+
+    async fn update_object_metadata(&self, bucket: String, object_id: String, changes: MetadataChanges) -> ReqRes<impl ClientObject> {
+        self.fan_out("update_object_metadata", &bucket, Some(&object_id), |c| c.update_object_metadata(bucket.clone(), object_id.clone(), changes.clone())).await
+    }
+
+    async fn set_object_tags(&self, bucket: String, object_id: String, tags: HashMap<String, String>) -> EmptyReqRes {
+        self.fan_out("set_object_tags", &bucket, Some(&object_id), |c| c.set_object_tags(bucket.clone(), object_id.clone(), tags.clone())).await
+    }
+
+    async fn set_object_acl(&self, bucket: String, object_id: String, acl: AccessControl) -> EmptyReqRes {
+        self.fan_out("set_object_acl", &bucket, Some(&object_id), |c| c.set_object_acl(bucket.clone(), object_id.clone(), acl.clone())).await
+    }
+
+    async fn remove_object(&self, bucket: String, object_id: String) -> EmptyReqRes {
+        self.fan_out("remove_object", &bucket, Some(&object_id), |c| {
+            let (bucket, object_id) = (bucket.clone(), object_id.clone());
+            async move {
+                match c.remove_object(bucket, object_id).await {
+                    Err(e) if e.is_not_found() => Ok(()),
+                    removed => removed
+                }
+            }
+        }).await
+    }
+
+    /// Every object is removed (from every replica) on its own
+    async fn remove_objects(&self, bucket_name: String, keys: Vec<String>) -> ReqRes<Vec<(String, EmptyReqRes)>> {
+        Ok(stream::iter(keys).map(|key| {
+            let bucket_name = bucket_name.clone();
+            async move {
+                let removed = self.remove_object(bucket_name, key.clone()).await;
+                (key, removed)
+            }
+        }).buffered(CONCURRENCY).collect().await)
+    }
+
+    /// Every replica copies within itself
+    async fn copy_object(&self, src_bucket: String, src_object: String, dest_bucket: String, dest_object: String) -> ReqRes<impl ClientObject> {
+        self.fan_out("copy_object", &dest_bucket, Some(&dest_object), |c| c.copy_object(src_bucket.clone(), src_object.clone(), dest_bucket.clone(), dest_object.clone())).await
+    }
+
+    /// Every replica copies within itself
+    async fn copy_object_with_options(&self, src_bucket: String, src_object: String, dest_bucket: String, dest_object: String, options: CopyOptions) -> ReqRes<impl ClientObject> {
+        self.fan_out("copy_object_with_options", &dest_bucket, Some(&dest_object), |c| {
+            c.copy_object_with_options(src_bucket.clone(), src_object.clone(), dest_bucket.clone(), dest_object.clone(), options.clone())
+        }).await
+    }
+
+    async fn create_bucket(&self, bucket: String) -> ReqRes<impl ClientBucket> {
+        self.fan_out("create_bucket", &bucket, None, |c| c.create_bucket(bucket.clone())).await
+    }
+
+    async fn remove_bucket(&self, bucket: String) -> EmptyReqRes {
+        self.fan_out("remove_bucket", &bucket, None, |c| c.remove_bucket(bucket.clone())).await
+    }
+
+    async fn set_lifecycle_rules(&self, bucket_name: String, rules: Vec<LifecycleRule>) -> EmptyReqRes {
+        self.fan_out("set_lifecycle_rules", &bucket_name, None, |c| c.set_lifecycle_rules(bucket_name.clone(), rules.clone())).await
+    }
+
+    async fn set_bucket_policy(&self, bucket_name: String, policy: BucketPolicy) -> EmptyReqRes {
+        self.fan_out("set_bucket_policy", &bucket_name, None, |c| c.set_bucket_policy(bucket_name.clone(), policy.clone())).await
+    }
+
+    async fn set_bucket_labels(&self, bucket_name: String, labels: HashMap<String, String>) -> EmptyReqRes {
+        self.fan_out("set_bucket_labels", &bucket_name, None, |c| c.set_bucket_labels(bucket_name.clone(), labels.clone())).await
+    }
+
+    async fn set_object_retention(&self, bucket_name: String, object_name: String, retention: Option<Retention>) -> EmptyReqRes {
+        self.fan_out("set_object_retention", &bucket_name, Some(&object_name), |c| c.set_object_retention(bucket_name.clone(), object_name.clone(), retention)).await
+    }
+
+    async fn set_legal_hold(&self, bucket_name: String, object_name: String, on: bool) -> EmptyReqRes {
+        self.fan_out("set_legal_hold", &bucket_name, Some(&object_name), |c| c.set_legal_hold(bucket_name.clone(), object_name.clone(), on)).await
+    }
+
+    delegate!(primary;
+        static_download_bytes, download_with_options, download_to_writer, get_object_tags, get_object_acl, public_url, signed_url, presigned_post, restore_object, restore_status,
+        list_buckets, get_bucket, get_object, stat_object, list_objects, list_prefix, list_projected, list_delimited, list_after, list_versions, restore_object_version,
+        bucket_exists, bucket_name_problems, compliance_records, get_bucket_spec, get_lifecycle_rules, get_bucket_policy, get_bucket_labels, get_object_lock
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use crate::fault::{Fault, FaultInjecting};
+    use crate::in_memory::InMemoryClient;
+    use super::*;
+
+    /// Replicas of which the ones in `failing` reject every request
+    fn replicas(count: usize, failing: &[usize]) -> (FaultInjecting<InMemoryClient>, Vec<FaultInjecting<InMemoryClient>>) {
+        let mut replicas = (0..count).map(|i| {
+            let replica = FaultInjecting::with_seed(InMemoryClient::with_bucket("bucket"), 0);
+            if failing.contains(&i) { replica.on_all(Fault::new().error_rate(1.0)) } else { replica }
+        });
+        (replicas.next().unwrap(), replicas.collect())
+    }
+
+    async fn write(policy: ReplicationPolicy, failing: &[usize]) -> (EmptyReqRes, Vec<usize>) {
+        let (primary, secondaries) = replicas(3, failing);
+        let failed = Arc::new(Mutex::new(vec![]));
+        let log = failed.clone();
+        let client = ReplicatedClient::new(primary, secondaries).with_policy(policy)
+            .with_failure_log(move |failure| log.lock().unwrap().push(failure.replica));
+        let written = client.static_upload_bytes("bucket".to_string(), "key".to_string(), Bytes::from_static(b"data")).await.map(|_| ());
+        let mut failed = failed.lock().unwrap().clone();
+        failed.sort();
+        (written, failed)
+    }
+
+    #[tokio::test]
+    async fn applies_the_policy() {
+        assert_eq!((ReplicationPolicy::Majority.required(4), ReplicationPolicy::AtLeast(0).required(3), ReplicationPolicy::AtLeast(5).required(3)), (3, 1, 3));
+        let (written, failed) = write(ReplicationPolicy::All, &[]).await;
+        assert!(written.is_ok() && failed.is_empty());
+        let (written, failed) = write(ReplicationPolicy::All, &[2]).await;
+        assert!(written.unwrap_err().is_network());
+        assert_eq!(failed, [2]);
+        let (written, failed) = write(ReplicationPolicy::Majority, &[0]).await;
+        assert!(written.is_ok());
+        assert_eq!(failed, [0]);
+        assert!(write(ReplicationPolicy::Majority, &[0, 2]).await.0.is_err());
+        assert!(write(ReplicationPolicy::AtLeast(1), &[0, 2]).await.0.is_ok());
+        assert!(write(ReplicationPolicy::AtLeast(1), &[0, 1, 2]).await.0.is_err());
+    }
+
+    #[tokio::test]
+    async fn removes_objects_missing_on_replicas() {
+        let (primary, secondaries) = replicas(2, &[]);
+        let client = ReplicatedClient::new(primary, secondaries);
+        client.primary().static_upload_bytes("bucket".to_string(), "key".to_string(), Bytes::from_static(b"data")).await.unwrap();
+        client.remove_object("bucket".to_string(), "key".to_string()).await.unwrap();
+        assert!(client.stat_object("bucket".to_string(), "key".to_string()).await.is_err_and(|e| e.is_not_found()));
+    }
+}
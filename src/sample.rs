@@ -0,0 +1,58 @@
+use std::collections::BTreeSet;
+use futures::{stream, StreamExt};
+use crate::{ClientInterface, ClientObject, ReqRes, CONCURRENCY};
+
+/// Folder levels below the prefix which are explored with delimited listings
+const MAX_DEPTH: usize = 3;
+/// Range of characters (printable ASCII) the jump points are spread over
+const FIRST_CHAR: u8 = b' ';
+const LAST_CHAR: u8 = b'~';
+
+/// Picks `n` elements evenly spread over `items`
+fn spread<T: Clone>(items: &[T], n: usize) -> Vec<T> {
+    if items.len() <= n {
+        return items.to_vec()
+    }
+    (0..n).map(|i| items[i * items.len() / n].clone()).collect()
+}
+
+/// See `ClientInterface::sample_objects`
+pub(crate) async fn sample<C: ClientInterface + ?Sized>(client: &C, bucket: String, prefix: String, n: usize) -> ReqRes<Vec<String>> {
+    if n == 0 {
+        return Ok(vec![])
+    }
+    let mut names = BTreeSet::new();
+    // Descend into folders while there are fewer of them than samples
+    let mut folders = vec![prefix];
+    for _ in 0..MAX_DEPTH {
+        let mut children = vec![];
+        for folder in &folders {
+            let page = client.list_delimited(bucket.clone(), folder.clone(), "/".to_string(), None).await?;
+            names.extend(page.items.iter().map(|t| t.name()));
+            children.extend(page.prefixes);
+        }
+        if children.is_empty() {
+            break
+        }
+        folders = children;
+        if folders.len() >= n {
+            break
+        }
+    }
+    // Jump into every folder at points spread over the characters following the folder's name
+    let folders = spread(&folders, n);
+    let per_folder = n.div_ceil(folders.len()).max(1);
+    let jumps: Vec<(String, String)> = folders.iter().flat_map(|folder| {
+        let chars: Vec<u8> = (FIRST_CHAR..=LAST_CHAR).collect();
+        spread(&chars, per_folder).into_iter().map(move |c| (folder.clone(), format!("{folder}{}", c as char)))
+    }).collect();
+    let bucket = &bucket;
+    let found: Vec<ReqRes<Vec<String>>> = stream::iter(jumps).map(|(folder, start_after)| async move {
+        Ok(client.list_after(bucket.clone(), folder, start_after, 1).await?.iter().map(|t| t.name()).collect())
+    }).buffer_unordered(CONCURRENCY).collect().await;
+    for found in found {
+        names.extend(found?);
+    }
+    let names: Vec<String> = names.into_iter().collect();
+    Ok(spread(&names, n))
+}
@@ -0,0 +1,381 @@
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::time::{Duration, Instant};
+use bytes::Bytes;
+use tokio::io::AsyncWrite;
+use crate::delegate::delegate;
+use crate::{AccessControl, AclGrant, BucketSpec, ClientBucket, ClientInterface, ClientObject, CopyOptions, Cursor, EmptyReqRes, LifecycleRule, ListPage, MetadataChanges, ObjectLock, ReqRes, Retention, UploadOptions};
+
+/// How the results of the primary and the candidate compare
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ShadowOutcome {
+    /// Both succeeded with equivalent results
+    Match,
+    /// Both succeeded, but the results differ
+    Mismatch,
+    PrimaryFailed,
+    CandidateFailed,
+    BothFailed,
+}
+
+/// A single mirrored operation
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShadowRecord {
+    pub operation: &'static str,
+    pub bucket: String,
+    pub object: Option<String>,
+    pub outcome: ShadowOutcome,
+    pub primary_latency: Duration,
+    pub candidate_latency: Duration,
+}
+
+/// Receives every operation mirrored by a ShadowClient
+pub type ShadowLog = Box<dyn Fn(&ShadowRecord) + Send + Sync>;
+
+async fn timed<T>(future: impl Future<Output = T>) -> (T, Duration) {
+    let start = Instant::now();
+    let result = future.await;
+    (result, start.elapsed())
+}
+
+/// Names and sizes of listed objects, sorted
+fn listing<'a, T: ClientObject + 'a>(objects: impl IntoIterator<Item = &'a T>) -> Vec<(String, u64)> {
+    let mut listing: Vec<_> = objects.into_iter().map(|t| (t.name(), t.size())).collect();
+    listing.sort();
+    listing
+}
+
+/// Serves every request from a primary client and mirrors reads (and optionally writes) to a candidate client,
+/// comparing results and latency, to try out a new backend with real traffic before switching to it
+///
+/// Only results of the primary are returned, failures of the candidate are just recorded.
+/// Note: Both requests run concurrently, so a call takes as long as the slower of both backends.
+/// Streamed uploads, signed URLs, bucket policies, archive restores and conditional downloads (ETags, versions and policies differ between backends) are not mirrored,
+/// streamed downloads of the candidate are only compared by size.
+pub struct ShadowClient<P: ClientInterface, C: ClientInterface> {
+    primary: P,
+    candidate: C,
+    mirror_writes: bool,
+    log: Option<ShadowLog>,
+}
+
+impl<P: ClientInterface, C: ClientInterface> ShadowClient<P, C> {
+    pub fn new(primary: P, candidate: C) -> Self {
+        Self { primary, candidate, mirror_writes: false, log: None }
+    }
+
+    /// Also sends writes to the candidate (only do this if the candidate holds a copy of the primary's data)
+    pub fn with_mirrored_writes(mut self) -> Self {
+        self.mirror_writes = true;
+        self
+    }
+
+    /// Sets the callback every mirrored operation is recorded with
+    pub fn with_log(mut self, log: impl Fn(&ShadowRecord) + Send + Sync + 'static) -> Self {
+        self.log = Some(Box::new(log));
+        self
+    }
+
+    pub fn primary(&self) -> &P {
+        &self.primary
+    }
+
+    pub fn candidate(&self) -> &C {
+        &self.candidate
+    }
+
+    /// Runs both requests, records the comparison and returns the primary's result
+    async fn shadow<A, B>(&self, operation: &'static str, bucket: &str, object: Option<&str>,
+                          primary: impl Future<Output = ReqRes<A>>, candidate: impl Future<Output = ReqRes<B>>,
+                          same: impl FnOnce(&A, &B) -> bool) -> ReqRes<A> {
+        let ((primary, primary_latency), (candidate, candidate_latency)) = futures::join!(timed(primary), timed(candidate));
+        let outcome = match (&primary, &candidate) {
+            (Ok(a), Ok(b)) if same(a, b) => ShadowOutcome::Match,
+            (Ok(_), Ok(_)) => ShadowOutcome::Mismatch,
+            (Err(_), Ok(_)) => ShadowOutcome::PrimaryFailed,
+            (Ok(_), Err(_)) => ShadowOutcome::CandidateFailed,
+            (Err(_), Err(_)) => ShadowOutcome::BothFailed,
+        };
+        if let Some(log) = &self.log {
+            log(&ShadowRecord {
+                operation,
+                bucket: bucket.to_string(),
+                object: object.map(|t| t.to_string()),
+                outcome,
+                primary_latency,
+                candidate_latency,
+            });
+        }
+        primary
+    }
+}
+
+impl<P: ClientInterface, C: ClientInterface> ClientInterface for ShadowClient<P, C> {
+    async fn static_download_bytes(&self, bucket: String, object_id: String, starting: Option<u64>, ending: Option<u64>) -> ReqRes<Bytes> {
+        self.shadow("static_download_bytes", &bucket, Some(&object_id),
+                    self.primary.static_download_bytes(bucket.clone(), object_id.clone(), starting, ending),
+                    self.candidate.static_download_bytes(bucket.clone(), object_id.clone(), starting, ending),
+                    |a, b| a == b).await
+    }
+
+    async fn static_upload_bytes(&self, bucket: String, object_id: String, data: Bytes) -> ReqRes<impl ClientObject> {
+        if !self.mirror_writes {
+            return self.primary.static_upload_bytes(bucket, object_id, data).await
+        }
+        self.shadow("static_upload_bytes", &bucket, Some(&object_id),
+                    self.primary.static_upload_bytes(bucket.clone(), object_id.clone(), data.clone()),
+                    self.candidate.static_upload_bytes(bucket.clone(), object_id.clone(), data),
+                    |a, b| a.size() == b.size()).await
+    }
+
+    async fn upload_with_options(&self, bucket: String, object_id: String, data: Bytes, options: UploadOptions) -> ReqRes<impl ClientObject> {
+        if !self.mirror_writes {
+            return self.primary.upload_with_options(bucket, object_id, data, options).await
+        }
+        self.shadow("upload_with_options", &bucket, Some(&object_id),
+                    self.primary.upload_with_options(bucket.clone(), object_id.clone(), data.clone(), options.clone()),
+                    self.candidate.upload_with_options(bucket.clone(), object_id.clone(), data, options),
+                    |a, b| a.size() == b.size()).await
+    }
+
+    async fn download_to_writer(&self, bucket: String, object_id: String, starting: Option<u64>, ending: Option<u64>, w: impl AsyncWrite + Unpin + Send) -> ReqRes<u64> {
+        self.shadow("download_to_writer", &bucket, Some(&object_id),
+                    self.primary.download_to_writer(bucket.clone(), object_id.clone(), starting, ending, w),
+                    self.candidate.download_to_writer(bucket.clone(), object_id.clone(), starting, ending, tokio::io::sink()),
+                    |a, b| a == b).await
+    }
+
+    async fn append(&self, bucket: String, object_id: String, data: Bytes) -> EmptyReqRes {
+        if !self.mirror_writes {
+            return self.primary.append(bucket, object_id, data).await
+        }
+        self.shadow("append", &bucket, Some(&object_id),
+                    self.primary.append(bucket.clone(), object_id.clone(), data.clone()),
+                    self.candidate.append(bucket.clone(), object_id.clone(), data),
+                    |_, _| true).await
+    }
+
+    async fn update_object_metadata(&self, bucket: String, object_id: String, changes: MetadataChanges) -> ReqRes<impl ClientObject> {
+        if !self.mirror_writes {
+            return self.primary.update_object_metadata(bucket, object_id, changes).await
+        }
+        self.shadow("update_object_metadata", &bucket, Some(&object_id),
+                    self.primary.update_object_metadata(bucket.clone(), object_id.clone(), changes.clone()),
+                    self.candidate.update_object_metadata(bucket.clone(), object_id.clone(), changes),
+                    |a, b| a.content_type() == b.content_type() && a.custom_metadata() == b.custom_metadata()).await
+    }
+
+    async fn get_object_tags(&self, bucket: String, object_id: String) -> ReqRes<HashMap<String, String>> {
+        self.shadow("get_object_tags", &bucket, Some(&object_id),
+                    self.primary.get_object_tags(bucket.clone(), object_id.clone()),
+                    self.candidate.get_object_tags(bucket.clone(), object_id.clone()),
+                    |a, b| a == b).await
+    }
+
+    async fn set_object_tags(&self, bucket: String, object_id: String, tags: HashMap<String, String>) -> EmptyReqRes {
+        if !self.mirror_writes {
+            return self.primary.set_object_tags(bucket, object_id, tags).await
+        }
+        self.shadow("set_object_tags", &bucket, Some(&object_id),
+                    self.primary.set_object_tags(bucket.clone(), object_id.clone(), tags.clone()),
+                    self.candidate.set_object_tags(bucket.clone(), object_id.clone(), tags),
+                    |_, _| true).await
+    }
+
+    /// Only public grants are compared, identifiers of users differ between providers
+    async fn get_object_lock(&self, bucket_name: String, object_name: String) -> ReqRes<ObjectLock> {
+        self.shadow("get_object_lock", &bucket_name, Some(&object_name),
+                    self.primary.get_object_lock(bucket_name.clone(), object_name.clone()),
+                    self.candidate.get_object_lock(bucket_name.clone(), object_name.clone()),
+                    |a, b| a == b).await
+    }
+
+    async fn set_object_retention(&self, bucket_name: String, object_name: String, retention: Option<Retention>) -> EmptyReqRes {
+        if !self.mirror_writes {
+            return self.primary.set_object_retention(bucket_name, object_name, retention).await
+        }
+        self.shadow("set_object_retention", &bucket_name, Some(&object_name),
+                    self.primary.set_object_retention(bucket_name.clone(), object_name.clone(), retention),
+                    self.candidate.set_object_retention(bucket_name.clone(), object_name.clone(), retention),
+                    |_, _| true).await
+    }
+
+    async fn set_legal_hold(&self, bucket_name: String, object_name: String, on: bool) -> EmptyReqRes {
+        if !self.mirror_writes {
+            return self.primary.set_legal_hold(bucket_name, object_name, on).await
+        }
+        self.shadow("set_legal_hold", &bucket_name, Some(&object_name),
+                    self.primary.set_legal_hold(bucket_name.clone(), object_name.clone(), on),
+                    self.candidate.set_legal_hold(bucket_name.clone(), object_name.clone(), on),
+                    |_, _| true).await
+    }
+
+    async fn get_object_acl(&self, bucket: String, object_id: String) -> ReqRes<Vec<AclGrant>> {
+        let public = |grants: &Vec<AclGrant>| grants.iter().filter(|t| t.grantee.is_public()).cloned().collect::<HashSet<_>>();
+        self.shadow("get_object_acl", &bucket, Some(&object_id),
+                    self.primary.get_object_acl(bucket.clone(), object_id.clone()),
+                    self.candidate.get_object_acl(bucket.clone(), object_id.clone()),
+                    |a, b| public(a) == public(b)).await
+    }
+
+    async fn set_object_acl(&self, bucket: String, object_id: String, acl: AccessControl) -> EmptyReqRes {
+        if !self.mirror_writes {
+            return self.primary.set_object_acl(bucket, object_id, acl).await
+        }
+        self.shadow("set_object_acl", &bucket, Some(&object_id),
+                    self.primary.set_object_acl(bucket.clone(), object_id.clone(), acl.clone()),
+                    self.candidate.set_object_acl(bucket.clone(), object_id.clone(), acl),
+                    |_, _| true).await
+    }
+
+    async fn remove_bucket(&self, bucket: String) -> EmptyReqRes {
+        if !self.mirror_writes {
+            return self.primary.remove_bucket(bucket).await
+        }
+        self.shadow("remove_bucket", &bucket, None,
+                    self.primary.remove_bucket(bucket.clone()),
+                    self.candidate.remove_bucket(bucket.clone()),
+                    |_, _| true).await
+    }
+
+    async fn remove_object(&self, bucket: String, object_id: String) -> EmptyReqRes {
+        if !self.mirror_writes {
+            return self.primary.remove_object(bucket, object_id).await
+        }
+        self.shadow("remove_object", &bucket, Some(&object_id),
+                    self.primary.remove_object(bucket.clone(), object_id.clone()),
+                    self.candidate.remove_object(bucket.clone(), object_id.clone()),
+                    |_, _| true).await
+    }
+
+    async fn create_bucket(&self, bucket: String) -> ReqRes<impl ClientBucket> {
+        if !self.mirror_writes {
+            return self.primary.create_bucket(bucket).await
+        }
+        self.shadow("create_bucket", &bucket, None,
+                    self.primary.create_bucket(bucket.clone()),
+                    self.candidate.create_bucket(bucket.clone()),
+                    |a, b| a.name() == b.name()).await
+    }
+
+    async fn copy_object(&self, src_bucket: String, src_object: String, dest_bucket: String, dest_object: String) -> ReqRes<impl ClientObject> {
+        if !self.mirror_writes {
+            return self.primary.copy_object(src_bucket, src_object, dest_bucket, dest_object).await
+        }
+        self.shadow("copy_object", &dest_bucket, Some(&dest_object),
+                    self.primary.copy_object(src_bucket.clone(), src_object.clone(), dest_bucket.clone(), dest_object.clone()),
+                    self.candidate.copy_object(src_bucket.clone(), src_object.clone(), dest_bucket.clone(), dest_object.clone()),
+                    |a, b| a.size() == b.size()).await
+    }
+
+    /// Storage class names are provider specific, the candidate may reject them
+    async fn copy_object_with_options(&self, src_bucket: String, src_object: String, dest_bucket: String, dest_object: String, options: CopyOptions) -> ReqRes<impl ClientObject> {
+        if !self.mirror_writes {
+            return self.primary.copy_object_with_options(src_bucket, src_object, dest_bucket, dest_object, options).await
+        }
+        self.shadow("copy_object_with_options", &dest_bucket, Some(&dest_object),
+                    self.primary.copy_object_with_options(src_bucket.clone(), src_object.clone(), dest_bucket.clone(), dest_object.clone(), options.clone()),
+                    self.candidate.copy_object_with_options(src_bucket.clone(), src_object.clone(), dest_bucket.clone(), dest_object.clone(), options),
+                    |a, b| a.size() == b.size()).await
+    }
+
+    async fn get_object(&self, bucket_name: String, object_name: String) -> ReqRes<impl ClientObject> {
+        self.shadow("get_object", &bucket_name, Some(&object_name),
+                    self.primary.get_object(bucket_name.clone(), object_name.clone()),
+                    self.candidate.get_object(bucket_name.clone(), object_name.clone()),
+                    |a, b| a.size() == b.size()).await
+    }
+
+    async fn stat_object(&self, bucket_name: String, object_name: String) -> ReqRes<impl ClientObject> {
+        self.shadow("stat_object", &bucket_name, Some(&object_name),
+                    self.primary.stat_object(bucket_name.clone(), object_name.clone()),
+                    self.candidate.stat_object(bucket_name.clone(), object_name.clone()),
+                    |a, b| a.size() == b.size()).await
+    }
+
+    /// Page tokens are provider specific, so only the first page is mirrored
+    async fn list_objects(&self, bucket_name: String, max_results: Option<u32>, page_token: Option<Cursor>) -> ReqRes<ListPage<impl ClientObject>> {
+        if page_token.is_some() {
+            return self.primary.list_objects(bucket_name, max_results, page_token).await
+        }
+        self.shadow("list_objects", &bucket_name, None,
+                    self.primary.list_objects(bucket_name.clone(), max_results, None),
+                    self.candidate.list_objects(bucket_name.clone(), max_results, None),
+                    |a, b| listing(&a.items) == listing(&b.items)).await
+    }
+
+    /// Page tokens are provider specific, so only the first page is mirrored
+    async fn list_prefix(&self, bucket_name: String, prefix: String, page_token: Option<Cursor>) -> ReqRes<ListPage<impl ClientObject>> {
+        if page_token.is_some() {
+            return self.primary.list_prefix(bucket_name, prefix, page_token).await
+        }
+        self.shadow("list_prefix", &bucket_name, Some(&prefix),
+                    self.primary.list_prefix(bucket_name.clone(), prefix.clone(), None),
+                    self.candidate.list_prefix(bucket_name.clone(), prefix.clone(), None),
+                    |a, b| listing(&a.items) == listing(&b.items)).await
+    }
+
+    async fn remove_objects(&self, bucket_name: String, keys: Vec<String>) -> ReqRes<Vec<(String, EmptyReqRes)>> {
+        if !self.mirror_writes {
+            return self.primary.remove_objects(bucket_name, keys).await
+        }
+        self.shadow("remove_objects", &bucket_name, None,
+                    self.primary.remove_objects(bucket_name.clone(), keys.clone()),
+                    self.candidate.remove_objects(bucket_name.clone(), keys),
+                    |a, b| a.iter().filter(|(_, t)| t.is_ok()).count() == b.iter().filter(|(_, t)| t.is_ok()).count()).await
+    }
+
+    async fn bucket_exists(&self, bucket_name: String) -> ReqRes<bool> {
+        self.shadow("bucket_exists", &bucket_name, None,
+                    self.primary.bucket_exists(bucket_name.clone()),
+                    self.candidate.bucket_exists(bucket_name.clone()),
+                    |a, b| a == b).await
+    }
+
+    async fn get_bucket_spec(&self, bucket_name: String) -> ReqRes<BucketSpec> {
+        self.shadow("get_bucket_spec", &bucket_name, None,
+                    self.primary.get_bucket_spec(bucket_name.clone()),
+                    self.candidate.get_bucket_spec(bucket_name.clone()),
+                    |a, b| a == b).await
+    }
+
+    async fn get_lifecycle_rules(&self, bucket_name: String) -> ReqRes<Vec<LifecycleRule>> {
+        self.shadow("get_lifecycle_rules", &bucket_name, None,
+                    self.primary.get_lifecycle_rules(bucket_name.clone()),
+                    self.candidate.get_lifecycle_rules(bucket_name.clone()),
+                    |a, b| a == b).await
+    }
+
+    async fn get_bucket_labels(&self, bucket_name: String) -> ReqRes<HashMap<String, String>> {
+        self.shadow("get_bucket_labels", &bucket_name, None,
+                    self.primary.get_bucket_labels(bucket_name.clone()),
+                    self.candidate.get_bucket_labels(bucket_name.clone()),
+                    |a, b| a == b).await
+    }
+
+    async fn set_bucket_labels(&self, bucket_name: String, labels: HashMap<String, String>) -> EmptyReqRes {
+        if !self.mirror_writes {
+            return self.primary.set_bucket_labels(bucket_name, labels).await
+        }
+        self.shadow("set_bucket_labels", &bucket_name, None,
+                    self.primary.set_bucket_labels(bucket_name.clone(), labels.clone()),
+                    self.candidate.set_bucket_labels(bucket_name.clone(), labels),
+                    |_, _| true).await
+    }
+
+    async fn set_lifecycle_rules(&self, bucket_name: String, rules: Vec<LifecycleRule>) -> EmptyReqRes {
+        if !self.mirror_writes {
+            return self.primary.set_lifecycle_rules(bucket_name, rules).await
+        }
+        self.shadow("set_lifecycle_rules", &bucket_name, None,
+                    self.primary.set_lifecycle_rules(bucket_name.clone(), rules.clone()),
+                    self.candidate.set_lifecycle_rules(bucket_name.clone(), rules),
+                    |_, _| true).await
+    }
+
+    delegate!(primary;
+        download_with_options, upload_from_reader, upload_public, public_url, signed_url, presigned_post, list_buckets, get_bucket,
+        list_projected, list_delimited, list_after, list_versions, restore_object_version, restore_object, restore_status, compliance_records, get_bucket_policy, set_bucket_policy,
+        bucket_name_problems
+    );
+}
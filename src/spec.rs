@@ -0,0 +1,87 @@
+/// Desired configuration of a bucket
+/// Fields set to None are not checked
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BucketSpec {
+    /// Whether object versioning should be enabled
+    pub versioning: Option<bool>,
+    /// Lifecycle rules which should be present on the bucket
+    pub lifecycle_rules: Option<Vec<LifecycleRule>>,
+    /// CORS configuration of the bucket (compared as a whole)
+    pub cors: Option<Vec<CorsRule>>,
+    /// Default encryption of new objects (same values as `ComplianceRecord::encryption`)
+    pub encryption: Option<String>,
+}
+
+/// A single lifecycle rule
+#[derive(Debug, Clone, PartialEq)]
+pub struct LifecycleRule {
+    /// Only objects starting with this prefix are affected
+    pub prefix: Option<String>,
+    /// Minimum age (in days) of an object before the action is applied
+    pub age_days: Option<u32>,
+    /// What happens to matching objects
+    pub action: LifecycleAction,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LifecycleAction {
+    /// Delete (expire) the object
+    Delete,
+    /// Transition the object to another storage class
+    SetStorageClass(String),
+    /// Abort incomplete multipart uploads
+    AbortIncompleteMultipart,
+}
+
+/// A single CORS rule
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CorsRule {
+    pub origins: Vec<String>,
+    pub methods: Vec<String>,
+    pub response_headers: Vec<String>,
+    pub max_age_seconds: Option<i32>,
+}
+
+/// A difference between a bucket's actual configuration and a BucketSpec
+#[derive(Debug, Clone, PartialEq)]
+pub enum SpecDrift {
+    /// Versioning state differs (expected, actual)
+    Versioning { expected: bool, actual: bool },
+    /// A lifecycle rule of the spec is not present on the bucket
+    MissingLifecycleRule(LifecycleRule),
+    /// The bucket has a lifecycle rule which is not part of the spec
+    UnexpectedLifecycleRule(LifecycleRule),
+    /// CORS configuration differs
+    Cors { expected: Vec<CorsRule>, actual: Vec<CorsRule> },
+    /// Default encryption differs
+    Encryption { expected: String, actual: Option<String> },
+}
+
+impl BucketSpec {
+    /// Compares `actual` (as reported by the provider) against this spec
+    pub fn diff(&self, actual: &BucketSpec) -> Vec<SpecDrift> {
+        let mut drift = vec![];
+        if let Some(expected) = self.versioning {
+            let actual = actual.versioning.unwrap_or(false);
+            if expected != actual {
+                drift.push(SpecDrift::Versioning { expected, actual });
+            }
+        }
+        if let Some(expected) = &self.lifecycle_rules {
+            let actual = actual.lifecycle_rules.clone().unwrap_or_default();
+            drift.extend(expected.iter().filter(|r| !actual.contains(r)).cloned().map(SpecDrift::MissingLifecycleRule));
+            drift.extend(actual.iter().filter(|r| !expected.contains(r)).cloned().map(SpecDrift::UnexpectedLifecycleRule));
+        }
+        if let Some(expected) = &self.cors {
+            let actual = actual.cors.clone().unwrap_or_default();
+            if *expected != actual {
+                drift.push(SpecDrift::Cors { expected: expected.clone(), actual });
+            }
+        }
+        if let Some(expected) = &self.encryption
+            && Some(expected) != actual.encryption.as_ref() {
+            drift.push(SpecDrift::Encryption { expected: expected.clone(), actual: actual.encryption.clone() });
+        }
+        drift
+    }
+}
@@ -0,0 +1,391 @@
+use std::collections::HashMap;
+use std::time::SystemTime;
+use bytes::Bytes;
+use serde::Deserialize;
+use tokio::io::{AsyncRead, AsyncWrite};
+use crate::delegate::delegate;
+use crate::{AccessControl, AclGrant, ClientBucket, ClientInterface, ClientObject, CopyOptions, DownloadOptions, EmptyReqRes, MetadataChanges, ObjectLock, PostConstraints, PresignedPost, ReqRes, RestoreStatus, Retention, RetrievalTier, SignedUrlOptions, UploadOptions};
+
+/// Decides which objects a SplitClient serves from the new backend
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct SplitConfig {
+    /// Share of objects (0 - 100) which are routed to the new backend
+    pub percentage: u8,
+    /// Objects starting with one of these prefixes are always routed to the new backend
+    pub prefixes: Vec<String>,
+}
+
+impl SplitConfig {
+    /// Whether an object is routed to the new backend
+    /// The decision only depends on bucket and key, so an object always ends up on the same backend
+    pub fn routes_to_new(&self, bucket: &str, object: &str) -> bool {
+        if self.prefixes.iter().any(|t| object.starts_with(t.as_str())) {
+            return true
+        }
+        // FNV-1a, which (unlike the std hasher) is stable across builds
+        let hash = bucket.bytes().chain([b'/']).chain(object.bytes())
+            .fold(0xcbf29ce484222325u64, |hash, b| (hash ^ b as u64).wrapping_mul(0x100000001b3));
+        hash % 100 < self.percentage.min(100) as u64
+    }
+}
+
+/// An object or bucket of either backend of a SplitClient
+pub enum Routed<A, B> {
+    Old(A),
+    New(B),
+}
+
+impl<A: ClientObject, B: ClientObject> ClientObject for Routed<A, B> {
+    fn size(&self) -> u64 {
+        match self { Routed::Old(t) => t.size(), Routed::New(t) => t.size() }
+    }
+
+    fn bucket_name(&self) -> String {
+        match self { Routed::Old(t) => t.bucket_name(), Routed::New(t) => t.bucket_name() }
+    }
+
+    fn id(&self) -> String {
+        match self { Routed::Old(t) => t.id(), Routed::New(t) => t.id() }
+    }
+
+    fn name(&self) -> String {
+        match self { Routed::Old(t) => t.name(), Routed::New(t) => t.name() }
+    }
+
+    fn content_type(&self) -> Option<String> {
+        match self { Routed::Old(t) => t.content_type(), Routed::New(t) => t.content_type() }
+    }
+
+    fn last_modified(&self) -> Option<SystemTime> {
+        match self { Routed::Old(t) => t.last_modified(), Routed::New(t) => t.last_modified() }
+    }
+
+    fn etag(&self) -> Option<String> {
+        match self { Routed::Old(t) => t.etag(), Routed::New(t) => t.etag() }
+    }
+
+    fn storage_class(&self) -> Option<String> {
+        match self { Routed::Old(t) => t.storage_class(), Routed::New(t) => t.storage_class() }
+    }
+
+    fn custom_metadata(&self) -> HashMap<String, String> {
+        match self { Routed::Old(t) => t.custom_metadata(), Routed::New(t) => t.custom_metadata() }
+    }
+
+    fn checksum(&self) -> Option<String> {
+        match self { Routed::Old(t) => t.checksum(), Routed::New(t) => t.checksum() }
+    }
+
+    fn version(&self) -> Option<String> {
+        match self { Routed::Old(t) => t.version(), Routed::New(t) => t.version() }
+    }
+}
+
+impl<A: ClientBucket, B: ClientBucket> ClientBucket for Routed<A, B> {
+    fn id(&self) -> String {
+        match self { Routed::Old(t) => t.id(), Routed::New(t) => t.id() }
+    }
+
+    fn name(&self) -> String {
+        match self { Routed::Old(t) => t.name(), Routed::New(t) => t.name() }
+    }
+
+    fn location(&self) -> Option<String> {
+        match self { Routed::Old(t) => t.location(), Routed::New(t) => t.location() }
+    }
+}
+
+/// Routes object operations to a new backend according to a SplitConfig, for gradual migrations
+///
+/// Routed reads and changes of existing objects fall back to the old backend if the object is not present on the new one yet,
+/// other failures of the new backend are returned. Routed uploads only write to the new backend, routed copies fall back
+/// if the source is not present on the new backend.
+/// Bucket level operations and listings always use the old backend, removals are done on both backends.
+/// Lowering the percentage hides objects which were only written to the new backend.
+pub struct SplitClient<O: ClientInterface, N: ClientInterface> {
+    old: O,
+    new: N,
+    config: SplitConfig,
+}
+
+impl<O: ClientInterface, N: ClientInterface> SplitClient<O, N> {
+    pub fn new(old: O, new: N, config: SplitConfig) -> Self {
+        Self { old, new, config }
+    }
+
+    pub fn old(&self) -> &O {
+        &self.old
+    }
+
+    pub fn new_backend(&self) -> &N {
+        &self.new
+    }
+
+    pub fn config(&self) -> &SplitConfig {
+        &self.config
+    }
+}
+
+impl<O: ClientInterface, N: ClientInterface> ClientInterface for SplitClient<O, N> {
+    async fn static_download_bytes(&self, bucket: String, object_id: String, starting: Option<u64>, ending: Option<u64>) -> ReqRes<Bytes> {
+        if self.config.routes_to_new(&bucket, &object_id) {
+            match self.new.static_download_bytes(bucket.clone(), object_id.clone(), starting, ending).await {
+                Err(e) if e.is_not_found() => {}
+                data => return data
+            }
+        }
+        self.old.static_download_bytes(bucket, object_id, starting, ending).await
+    }
+
+    async fn download_with_options(&self, bucket: String, object_id: String, starting: Option<u64>, ending: Option<u64>, options: DownloadOptions) -> ReqRes<Bytes> {
+        if self.config.routes_to_new(&bucket, &object_id) {
+            match self.new.download_with_options(bucket.clone(), object_id.clone(), starting, ending, options.clone()).await {
+                Err(e) if e.is_not_found() => {}
+                data => return data
+            }
+        }
+        self.old.download_with_options(bucket, object_id, starting, ending, options).await
+    }
+
+    async fn static_upload_bytes(&self, bucket: String, object_id: String, data: Bytes) -> ReqRes<impl ClientObject> {
+        if self.config.routes_to_new(&bucket, &object_id) {
+            return Ok(Routed::New(self.new.static_upload_bytes(bucket, object_id, data).await?))
+        }
+        Ok(Routed::Old(self.old.static_upload_bytes(bucket, object_id, data).await?))
+    }
+
+    async fn upload_with_options(&self, bucket: String, object_id: String, data: Bytes, options: UploadOptions) -> ReqRes<impl ClientObject> {
+        if self.config.routes_to_new(&bucket, &object_id) {
+            return Ok(Routed::New(self.new.upload_with_options(bucket, object_id, data, options).await?))
+        }
+        Ok(Routed::Old(self.old.upload_with_options(bucket, object_id, data, options).await?))
+    }
+
+    /// The object is looked up first, so nothing is written to `w` before the backend is chosen
+    async fn download_to_writer(&self, bucket: String, object_id: String, starting: Option<u64>, ending: Option<u64>, w: impl AsyncWrite + Unpin + Send) -> ReqRes<u64> {
+        if self.config.routes_to_new(&bucket, &object_id) {
+            match self.new.stat_object(bucket.clone(), object_id.clone()).await {
+                Err(e) if e.is_not_found() => {}
+                Err(e) => return Err(e),
+                Ok(_) => return self.new.download_to_writer(bucket, object_id, starting, ending, w).await
+            }
+        }
+        self.old.download_to_writer(bucket, object_id, starting, ending, w).await
+    }
+
+    async fn upload_from_reader(&self, bucket: String, object_id: String, r: impl AsyncRead + Unpin + Send + Sync + 'static) -> EmptyReqRes {
+        if self.config.routes_to_new(&bucket, &object_id) {
+            return self.new.upload_from_reader(bucket, object_id, r).await
+        }
+        self.old.upload_from_reader(bucket, object_id, r).await
+    }
+
+    /// Appends to the old backend while the object only exists there, so its content is not split across backends
+    async fn append(&self, bucket: String, object_id: String, data: Bytes) -> EmptyReqRes {
+        if self.config.routes_to_new(&bucket, &object_id) {
+            let only_old = match self.new.stat_object(bucket.clone(), object_id.clone()).await {
+                Err(e) if e.is_not_found() => self.old.object_exists(bucket.clone(), object_id.clone()).await?,
+                Err(e) => return Err(e),
+                Ok(_) => false
+            };
+            if !only_old {
+                return self.new.append(bucket, object_id, data).await
+            }
+        }
+        self.old.append(bucket, object_id, data).await
+    }
+
+    async fn update_object_metadata(&self, bucket: String, object_id: String, changes: MetadataChanges) -> ReqRes<impl ClientObject> {
+        if self.config.routes_to_new(&bucket, &object_id) {
+            match self.new.update_object_metadata(bucket.clone(), object_id.clone(), changes.clone()).await {
+                Err(e) if e.is_not_found() => {}
+                object => return object.map(Routed::New)
+            }
+        }
+        Ok(Routed::Old(self.old.update_object_metadata(bucket, object_id, changes).await?))
+    }
+
+    async fn get_object_tags(&self, bucket: String, object_id: String) -> ReqRes<HashMap<String, String>> {
+        if self.config.routes_to_new(&bucket, &object_id) {
+            match self.new.get_object_tags(bucket.clone(), object_id.clone()).await {
+                Err(e) if e.is_not_found() => {}
+                tags => return tags
+            }
+        }
+        self.old.get_object_tags(bucket, object_id).await
+    }
+
+    async fn set_object_tags(&self, bucket: String, object_id: String, tags: HashMap<String, String>) -> EmptyReqRes {
+        if self.config.routes_to_new(&bucket, &object_id) {
+            match self.new.set_object_tags(bucket.clone(), object_id.clone(), tags.clone()).await {
+                Err(e) if e.is_not_found() => {}
+                changed => return changed
+            }
+        }
+        self.old.set_object_tags(bucket, object_id, tags).await
+    }
+
+    async fn get_object_lock(&self, bucket_name: String, object_name: String) -> ReqRes<ObjectLock> {
+        if self.config.routes_to_new(&bucket_name, &object_name) {
+            match self.new.get_object_lock(bucket_name.clone(), object_name.clone()).await {
+                Err(e) if e.is_not_found() => {}
+                lock => return lock
+            }
+        }
+        self.old.get_object_lock(bucket_name, object_name).await
+    }
+
+    async fn set_object_retention(&self, bucket_name: String, object_name: String, retention: Option<Retention>) -> EmptyReqRes {
+        if self.config.routes_to_new(&bucket_name, &object_name) {
+            match self.new.set_object_retention(bucket_name.clone(), object_name.clone(), retention).await {
+                Err(e) if e.is_not_found() => {}
+                changed => return changed
+            }
+        }
+        self.old.set_object_retention(bucket_name, object_name, retention).await
+    }
+
+    async fn set_legal_hold(&self, bucket_name: String, object_name: String, on: bool) -> EmptyReqRes {
+        if self.config.routes_to_new(&bucket_name, &object_name) {
+            match self.new.set_legal_hold(bucket_name.clone(), object_name.clone(), on).await {
+                Err(e) if e.is_not_found() => {}
+                changed => return changed
+            }
+        }
+        self.old.set_legal_hold(bucket_name, object_name, on).await
+    }
+
+    async fn get_object_acl(&self, bucket: String, object_id: String) -> ReqRes<Vec<AclGrant>> {
+        if self.config.routes_to_new(&bucket, &object_id) {
+            match self.new.get_object_acl(bucket.clone(), object_id.clone()).await {
+                Err(e) if e.is_not_found() => {}
+                grants => return grants
+            }
+        }
+        self.old.get_object_acl(bucket, object_id).await
+    }
+
+    async fn set_object_acl(&self, bucket: String, object_id: String, acl: AccessControl) -> EmptyReqRes {
+        if self.config.routes_to_new(&bucket, &object_id) {
+            match self.new.set_object_acl(bucket.clone(), object_id.clone(), acl.clone()).await {
+                Err(e) if e.is_not_found() => {}
+                changed => return changed
+            }
+        }
+        self.old.set_object_acl(bucket, object_id, acl).await
+    }
+
+    /// The URL does not fall back, objects which only exist on the old backend are not reachable through a download URL
+    async fn signed_url(&self, bucket: String, object_id: String, options: SignedUrlOptions) -> ReqRes<String> {
+        if self.config.routes_to_new(&bucket, &object_id) {
+            match self.new.signed_url(bucket.clone(), object_id.clone(), options.clone()).await {
+                Err(e) if e.is_not_found() => {}
+                url => return url
+            }
+        }
+        self.old.signed_url(bucket, object_id, options).await
+    }
+
+    /// Keys of the uploads are not known in advance, so only prefixes within `SplitConfig::prefixes` go to the new backend
+    async fn presigned_post(&self, bucket: String, key_prefix: String, constraints: PostConstraints) -> ReqRes<PresignedPost> {
+        if self.config.prefixes.iter().any(|t| key_prefix.starts_with(t.as_str())) {
+            return self.new.presigned_post(bucket, key_prefix, constraints).await
+        }
+        self.old.presigned_post(bucket, key_prefix, constraints).await
+    }
+
+    /// Does not fall back, so the object is always reachable through `public_url`
+    async fn upload_public(&self, bucket: String, object_id: String, data: Bytes, content_type: Option<String>, cache_control: String) -> EmptyReqRes {
+        if self.config.routes_to_new(&bucket, &object_id) {
+            return self.new.upload_public(bucket, object_id, data, content_type, cache_control).await
+        }
+        self.old.upload_public(bucket, object_id, data, content_type, cache_control).await
+    }
+
+    fn public_url(&self, bucket: &str, object_id: &str) -> String {
+        if self.config.routes_to_new(bucket, object_id) {
+            return self.new.public_url(bucket, object_id)
+        }
+        self.old.public_url(bucket, object_id)
+    }
+
+    /// Removed from both backends, so the old copy cannot reappear through a fallback
+    async fn remove_object(&self, bucket: String, object_id: String) -> EmptyReqRes {
+        let (new, old) = futures::join!(
+            self.new.remove_object(bucket.clone(), object_id.clone()),
+            self.old.remove_object(bucket, object_id)
+        );
+        match (new, old) {
+            (Err(e), _) if !e.is_not_found() => Err(e),
+            (Ok(()), Err(e)) if e.is_not_found() => Ok(()),
+            (_, old) => old
+        }
+    }
+
+    async fn copy_object(&self, src_bucket: String, src_object: String, dest_bucket: String, dest_object: String) -> ReqRes<impl ClientObject> {
+        if self.config.routes_to_new(&dest_bucket, &dest_object) {
+            match self.new.copy_object(src_bucket.clone(), src_object.clone(), dest_bucket.clone(), dest_object.clone()).await {
+                Err(e) if e.is_not_found() => {}
+                object => return object.map(Routed::New)
+            }
+        }
+        Ok(Routed::Old(self.old.copy_object(src_bucket, src_object, dest_bucket, dest_object).await?))
+    }
+
+    async fn copy_object_with_options(&self, src_bucket: String, src_object: String, dest_bucket: String, dest_object: String, options: CopyOptions) -> ReqRes<impl ClientObject> {
+        if self.config.routes_to_new(&dest_bucket, &dest_object) {
+            match self.new.copy_object_with_options(src_bucket.clone(), src_object.clone(), dest_bucket.clone(), dest_object.clone(), options.clone()).await {
+                Err(e) if e.is_not_found() => {}
+                object => return object.map(Routed::New)
+            }
+        }
+        Ok(Routed::Old(self.old.copy_object_with_options(src_bucket, src_object, dest_bucket, dest_object, options).await?))
+    }
+
+    async fn restore_object(&self, bucket_name: String, object_name: String, days: u32, tier: RetrievalTier) -> EmptyReqRes {
+        if self.config.routes_to_new(&bucket_name, &object_name) {
+            match self.new.restore_object(bucket_name.clone(), object_name.clone(), days, tier).await {
+                Err(e) if e.is_not_found() => {}
+                changed => return changed
+            }
+        }
+        self.old.restore_object(bucket_name, object_name, days, tier).await
+    }
+
+    async fn restore_status(&self, bucket_name: String, object_name: String) -> ReqRes<RestoreStatus> {
+        if self.config.routes_to_new(&bucket_name, &object_name) {
+            match self.new.restore_status(bucket_name.clone(), object_name.clone()).await {
+                Err(e) if e.is_not_found() => {}
+                status => return status
+            }
+        }
+        self.old.restore_status(bucket_name, object_name).await
+    }
+
+    async fn get_object(&self, bucket_name: String, object_name: String) -> ReqRes<impl ClientObject> {
+        if self.config.routes_to_new(&bucket_name, &object_name) {
+            match self.new.get_object(bucket_name.clone(), object_name.clone()).await {
+                Err(e) if e.is_not_found() => {}
+                object => return object.map(Routed::New)
+            }
+        }
+        Ok(Routed::Old(self.old.get_object(bucket_name, object_name).await?))
+    }
+
+    async fn stat_object(&self, bucket_name: String, object_name: String) -> ReqRes<impl ClientObject> {
+        if self.config.routes_to_new(&bucket_name, &object_name) {
+            match self.new.stat_object(bucket_name.clone(), object_name.clone()).await {
+                Err(e) if e.is_not_found() => {}
+                object => return object.map(Routed::New)
+            }
+        }
+        Ok(Routed::Old(self.old.stat_object(bucket_name, object_name).await?))
+    }
+
+    delegate!(old;
+        remove_bucket, create_bucket, list_buckets, get_bucket, list_objects, list_prefix, list_projected, list_delimited, list_after, list_versions, restore_object_version,
+        compliance_records, get_bucket_spec, get_lifecycle_rules, set_lifecycle_rules, get_bucket_policy, set_bucket_policy, get_bucket_labels, set_bucket_labels,
+        bucket_name_problems
+    );
+}
@@ -0,0 +1,72 @@
+use testcontainers::core::{IntoContainerPort, WaitFor};
+use testcontainers::runners::AsyncRunner;
+use testcontainers::{ContainerAsync, ContainerRequest, GenericImage, ImageExt};
+use crate::{ClientError, ReqRes};
+#[cfg(feature = "aws_s3")]
+use crate::{AWSClient, AWSConfig};
+#[cfg(feature = "google_cloud")]
+use crate::{GoogleCloud, GoogleCloudConfig};
+
+pub const MINIO_IMAGE: (&str, &str) = ("minio/minio", "RELEASE.2025-04-22T22-12-26Z");
+pub const FAKE_GCS_IMAGE: (&str, &str) = ("fsouza/fake-gcs-server", "1.52.2");
+/// Credentials of the root user of the MinIO container
+pub const MINIO_ACCESS_KEY: &str = "minioadmin";
+pub const MINIO_SECRET_KEY: &str = "minioadmin";
+
+/// A client for a storage emulator running in a container, the container is removed when this is dropped
+/// Needs a running Docker daemon.
+pub struct TestServer<C> {
+    client: C,
+    endpoint: String,
+    _container: ContainerAsync<GenericImage>,
+}
+
+impl<C> TestServer<C> {
+    pub fn client(&self) -> &C {
+        &self.client
+    }
+
+    /// Base URL of the emulator (example: 'http://127.0.0.1:49153')
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+}
+
+fn container_error(e: testcontainers::TestcontainersError) -> ClientError {
+    ClientError::Io(std::io::Error::other(e))
+}
+
+/// Starts `image` and returns it with the URL of `port`
+async fn start(image: ContainerRequest<GenericImage>, port: u16) -> ReqRes<(ContainerAsync<GenericImage>, String)> {
+    let container = image.start().await.map_err(container_error)?;
+    let host = container.get_host().await.map_err(container_error)?;
+    let port = container.get_host_port_ipv4(port.tcp()).await.map_err(container_error)?;
+    Ok((container, format!("http://{host}:{port}")))
+}
+
+/// Starts MinIO and returns an `AWSClient` for it (root user, region us-east-1, path-style addressing)
+#[cfg(feature = "aws_s3")]
+pub async fn minio() -> ReqRes<TestServer<AWSClient>> {
+    let image = GenericImage::new(MINIO_IMAGE.0, MINIO_IMAGE.1)
+        .with_exposed_port(9000.tcp())
+        .with_wait_for(WaitFor::message_on_stdout("API:"))
+        .with_env_var("MINIO_ROOT_USER", MINIO_ACCESS_KEY)
+        .with_env_var("MINIO_ROOT_PASSWORD", MINIO_SECRET_KEY)
+        .with_cmd(["server", "/data"]);
+    let (container, endpoint) = start(image, 9000).await?;
+    let config = AWSConfig::from_keys("us-east-1".to_string(), MINIO_ACCESS_KEY, MINIO_SECRET_KEY).await.with_endpoint(&endpoint);
+    Ok(TestServer { client: AWSClient::new(config), endpoint, _container: container })
+}
+
+/// Starts fake-gcs-server (over plain HTTP) and returns an anonymous `GoogleCloud` client for it
+/// Note: The emulator does not check signed URLs, IAM policies or ACLs
+#[cfg(feature = "google_cloud")]
+pub async fn fake_gcs() -> ReqRes<TestServer<GoogleCloud>> {
+    let image = GenericImage::new(FAKE_GCS_IMAGE.0, FAKE_GCS_IMAGE.1)
+        .with_exposed_port(4443.tcp())
+        .with_wait_for(WaitFor::message_on_stderr("server started at"))
+        .with_cmd(["-scheme", "http", "-port", "4443"]);
+    let (container, endpoint) = start(image, 4443).await?;
+    let config = GoogleCloudConfig::anonymous("test".to_string()).with_endpoint(&endpoint);
+    Ok(TestServer { client: GoogleCloud::new(config), endpoint, _container: container })
+}
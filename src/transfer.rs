@@ -0,0 +1,27 @@
+use crate::{ClientInterface, Defaults, ReqRes};
+
+/// Default size of the in-memory pipe between the download and the upload of a transfer (see `Defaults::transfer_buffer`)
+pub const TRANSFER_BUFFER: usize = 1024 * 1024;
+
+/// Streams an object from one client to another (example: GCS to AWS-S3) without buffering the whole object
+/// Returns the number of bytes transferred
+/// If the download fails after the upload was completed, the (truncated) destination object is removed again
+pub async fn transfer(src: &impl ClientInterface, src_bucket: String, src_object: String,
+                      dest: &impl ClientInterface, dest_bucket: String, dest_object: String) -> ReqRes<u64> {
+    let (writer, reader) = tokio::io::duplex(Defaults::get().transfer_buffer);
+    let (downloaded, uploaded) = futures::join!(
+        // The writer is dropped once the download is done, which ends the upload
+        src.download_to_writer(src_bucket, src_object, None, None, writer),
+        dest.upload_from_reader(dest_bucket.clone(), dest_object.clone(), reader)
+    );
+    match (downloaded, uploaded) {
+        (Ok(size), Ok(())) => Ok(size),
+        (Err(e), Ok(())) => {
+            // Best effort, the download error is more useful to the caller
+            let _ = dest.remove_object(dest_bucket, dest_object).await;
+            Err(e)
+        }
+        // An upload failure also breaks the pipe of the download, so it is the root cause
+        (_, Err(e)) => Err(e)
+    }
+}
@@ -0,0 +1,401 @@
+use std::collections::HashMap;
+use std::time::SystemTime;
+use bytes::Bytes;
+use futures::{Stream, StreamExt};
+use tokio::io::{AsyncRead, AsyncWrite};
+#[cfg(feature = "aws_s3")]
+use crate::aws_s3::AWSClient;
+#[cfg(feature = "google_cloud")]
+use crate::google_cloud::GoogleCloud;
+#[cfg(feature = "local")]
+use crate::local::LocalClient;
+use crate::{AccessControl, AclGrant, BucketNameProblem, BucketPolicy, BucketSpec, ClientBucket, ClientInterface, Credentials, StorageConfig, StorageUri, ClientObject, ComplianceRecord, CopyOptions, CopyProgress, Cursor, DownloadOptions, EmptyReqRes, LifecycleRule, ListAttributes, ListPage, MetadataChanges, ObjectLock, ObjectVersion, PostConstraints, PresignedPost, ReqRes, RestoreStatus, Retention, RetrievalTier, SignedUrlOptions, UploadOptions};
+
+/// Runs the same expression on whichever backend the client holds
+macro_rules! dispatch {
+    ($self:ident, $client:ident => $call:expr) => {
+        match $self {
+            #[cfg(feature = "google_cloud")]
+            UnifiedClient::GoogleCloud($client) => $call,
+            #[cfg(feature = "aws_s3")]
+            UnifiedClient::AWS($client) => $call,
+            #[cfg(feature = "local")]
+            UnifiedClient::Local($client) => $call,
+        }
+    };
+}
+
+/// Client of a provider chosen at runtime (example: from configuration), one variant per enabled backend
+#[derive(Clone)]
+// Clients are long-lived and shared, boxing the larger backend would only add an indirection to every call
+#[allow(clippy::large_enum_variant)]
+pub enum UnifiedClient {
+    #[cfg(feature = "google_cloud")]
+    GoogleCloud(GoogleCloud),
+    #[cfg(feature = "aws_s3")]
+    AWS(AWSClient),
+    #[cfg(feature = "local")]
+    Local(LocalClient),
+}
+
+#[cfg(feature = "google_cloud")]
+impl From<GoogleCloud> for UnifiedClient {
+    fn from(value: GoogleCloud) -> Self {
+        UnifiedClient::GoogleCloud(value)
+    }
+}
+
+#[cfg(feature = "aws_s3")]
+impl From<AWSClient> for UnifiedClient {
+    fn from(value: AWSClient) -> Self {
+        UnifiedClient::AWS(value)
+    }
+}
+
+#[cfg(feature = "local")]
+impl From<LocalClient> for UnifiedClient {
+    fn from(value: LocalClient) -> Self {
+        UnifiedClient::Local(value)
+    }
+}
+
+/// Object returned by a `UnifiedClient`, a snapshot of the backend's object
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnifiedObject {
+    pub size: u64,
+    pub bucket_name: String,
+    pub id: String,
+    pub name: String,
+    pub content_type: Option<String>,
+    pub last_modified: Option<SystemTime>,
+    pub etag: Option<String>,
+    pub storage_class: Option<String>,
+    pub custom_metadata: HashMap<String, String>,
+    pub checksum: Option<String>,
+    pub version: Option<String>,
+}
+
+impl UnifiedObject {
+    pub fn of(object: &impl ClientObject) -> Self {
+        Self {
+            size: object.size(),
+            bucket_name: object.bucket_name(),
+            id: object.id(),
+            name: object.name(),
+            content_type: object.content_type(),
+            last_modified: object.last_modified(),
+            etag: object.etag(),
+            storage_class: object.storage_class(),
+            custom_metadata: object.custom_metadata(),
+            checksum: object.checksum(),
+            version: object.version(),
+        }
+    }
+}
+
+impl ClientObject for UnifiedObject {
+    fn size(&self) -> u64 {
+        self.size
+    }
+
+    fn bucket_name(&self) -> String {
+        self.bucket_name.clone()
+    }
+
+    fn id(&self) -> String {
+        self.id.clone()
+    }
+
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn content_type(&self) -> Option<String> {
+        self.content_type.clone()
+    }
+
+    fn last_modified(&self) -> Option<SystemTime> {
+        self.last_modified
+    }
+
+    fn etag(&self) -> Option<String> {
+        self.etag.clone()
+    }
+
+    fn storage_class(&self) -> Option<String> {
+        self.storage_class.clone()
+    }
+
+    fn custom_metadata(&self) -> HashMap<String, String> {
+        self.custom_metadata.clone()
+    }
+
+    fn checksum(&self) -> Option<String> {
+        self.checksum.clone()
+    }
+
+    fn version(&self) -> Option<String> {
+        self.version.clone()
+    }
+}
+
+/// Bucket returned by a `UnifiedClient`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnifiedBucket {
+    pub id: String,
+    pub name: String,
+    pub location: Option<String>,
+}
+
+impl UnifiedBucket {
+    pub fn of(bucket: &impl ClientBucket) -> Self {
+        Self { id: bucket.id(), name: bucket.name(), location: bucket.location() }
+    }
+}
+
+impl ClientBucket for UnifiedBucket {
+    fn id(&self) -> String {
+        self.id.clone()
+    }
+
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn location(&self) -> Option<String> {
+        self.location.clone()
+    }
+}
+
+fn objects(page: ListPage<impl ClientObject>) -> ListPage<UnifiedObject> {
+    ListPage { items: page.items.iter().map(UnifiedObject::of).collect(), prefixes: page.prefixes, next_token: page.next_token }
+}
+
+impl UnifiedClient {
+    /// Builds the client of the URI's provider ('gs://' for Google-Cloud, 's3://' for AWS-S3, 'file://' for local files)
+    /// with the process-wide `Defaults`, and returns it with the parsed bucket and key
+    /// Google-Cloud clients built this way have no project, so listing and creating buckets needs a `GoogleCloudConfig`
+    /// 'file://' clients are rooted at '/', so 'file:///tmp/data.bin' has an empty bucket and the key 'tmp/data.bin'
+    /// Providers which are not enabled fail with `ClientError::InvalidConfig`
+    pub async fn from_uri(uri: &str, credentials: Credentials) -> ReqRes<(Self, StorageUri)> {
+        let uri = StorageUri::parse(uri)?;
+        let client = StorageConfig { provider: uri.scheme.clone(), credentials, ..Default::default() }.build().await?;
+        Ok((client, uri))
+    }
+
+    /// Name of the backend (example: 'aws_s3'), same as its feature
+    pub fn provider(&self) -> &'static str {
+        match self {
+            #[cfg(feature = "google_cloud")]
+            UnifiedClient::GoogleCloud(_) => "google_cloud",
+            #[cfg(feature = "aws_s3")]
+            UnifiedClient::AWS(_) => "aws_s3",
+            #[cfg(feature = "local")]
+            UnifiedClient::Local(_) => "local",
+        }
+    }
+}
+
+// Returns the concrete `UnifiedObject` / `UnifiedBucket` instead of opaque types
+#[allow(refining_impl_trait)]
+impl ClientInterface for UnifiedClient {
+    async fn static_download_bytes(&self, bucket: String, object_id: String, starting: Option<u64>, ending: Option<u64>) -> ReqRes<Bytes> {
+        dispatch!(self, c => c.static_download_bytes(bucket, object_id, starting, ending).await)
+    }
+
+    async fn static_upload_bytes(&self, bucket: String, object_id: String, data: Bytes) -> ReqRes<UnifiedObject> {
+        dispatch!(self, c => c.static_upload_bytes(bucket, object_id, data).await.map(|t| UnifiedObject::of(&t)))
+    }
+
+    async fn upload_with_options(&self, bucket: String, object_id: String, data: Bytes, options: UploadOptions) -> ReqRes<UnifiedObject> {
+        dispatch!(self, c => c.upload_with_options(bucket, object_id, data, options).await.map(|t| UnifiedObject::of(&t)))
+    }
+
+    async fn download_with_options(&self, bucket: String, object_id: String, starting: Option<u64>, ending: Option<u64>, options: DownloadOptions) -> ReqRes<Bytes> {
+        dispatch!(self, c => c.download_with_options(bucket, object_id, starting, ending, options).await)
+    }
+
+    async fn download_to_writer(&self, bucket: String, object_id: String, starting: Option<u64>, ending: Option<u64>, w: impl AsyncWrite + Unpin + Send) -> ReqRes<u64> {
+        dispatch!(self, c => c.download_to_writer(bucket, object_id, starting, ending, w).await)
+    }
+
+    async fn upload_from_reader(&self, bucket: String, object_id: String, r: impl AsyncRead + Unpin + Send + Sync + 'static) -> EmptyReqRes {
+        dispatch!(self, c => c.upload_from_reader(bucket, object_id, r).await)
+    }
+
+    async fn signed_url(&self, bucket: String, object_id: String, options: SignedUrlOptions) -> ReqRes<String> {
+        dispatch!(self, c => c.signed_url(bucket, object_id, options).await)
+    }
+
+    async fn presigned_post(&self, bucket: String, key_prefix: String, constraints: PostConstraints) -> ReqRes<PresignedPost> {
+        dispatch!(self, c => c.presigned_post(bucket, key_prefix, constraints).await)
+    }
+
+    async fn remove_bucket(&self, bucket: String) -> EmptyReqRes {
+        dispatch!(self, c => c.remove_bucket(bucket).await)
+    }
+
+    async fn remove_object(&self, bucket: String, object_id: String) -> EmptyReqRes {
+        dispatch!(self, c => c.remove_object(bucket, object_id).await)
+    }
+
+    async fn create_bucket(&self, bucket: String) -> ReqRes<UnifiedBucket> {
+        dispatch!(self, c => c.create_bucket(bucket).await.map(|t| UnifiedBucket::of(&t)))
+    }
+
+    async fn copy_object(&self, src_bucket: String, src_object: String, dest_bucket: String, dest_object: String) -> ReqRes<UnifiedObject> {
+        dispatch!(self, c => c.copy_object(src_bucket, src_object, dest_bucket, dest_object).await.map(|t| UnifiedObject::of(&t)))
+    }
+
+    async fn copy_object_with_options(&self, src_bucket: String, src_object: String, dest_bucket: String, dest_object: String, options: CopyOptions) -> ReqRes<UnifiedObject> {
+        dispatch!(self, c => c.copy_object_with_options(src_bucket, src_object, dest_bucket, dest_object, options).await.map(|t| UnifiedObject::of(&t)))
+    }
+
+    fn copy_object_progress(&self, src_bucket: String, src_object: String, dest_bucket: String, dest_object: String) -> impl Stream<Item = ReqRes<CopyProgress>> + Send {
+        dispatch!(self, c => c.copy_object_progress(src_bucket, src_object, dest_bucket, dest_object).boxed())
+    }
+
+    async fn restore_object(&self, bucket_name: String, object_name: String, days: u32, tier: RetrievalTier) -> EmptyReqRes {
+        dispatch!(self, c => c.restore_object(bucket_name, object_name, days, tier).await)
+    }
+
+    async fn restore_status(&self, bucket_name: String, object_name: String) -> ReqRes<RestoreStatus> {
+        dispatch!(self, c => c.restore_status(bucket_name, object_name).await)
+    }
+
+    async fn update_object_metadata(&self, bucket: String, object_id: String, changes: MetadataChanges) -> ReqRes<UnifiedObject> {
+        dispatch!(self, c => c.update_object_metadata(bucket, object_id, changes).await.map(|t| UnifiedObject::of(&t)))
+    }
+
+    async fn get_object_tags(&self, bucket: String, object_id: String) -> ReqRes<HashMap<String, String>> {
+        dispatch!(self, c => c.get_object_tags(bucket, object_id).await)
+    }
+
+    async fn set_object_tags(&self, bucket: String, object_id: String, tags: HashMap<String, String>) -> EmptyReqRes {
+        dispatch!(self, c => c.set_object_tags(bucket, object_id, tags).await)
+    }
+
+    async fn get_object_acl(&self, bucket: String, object_id: String) -> ReqRes<Vec<AclGrant>> {
+        dispatch!(self, c => c.get_object_acl(bucket, object_id).await)
+    }
+
+    async fn set_object_acl(&self, bucket: String, object_id: String, acl: AccessControl) -> EmptyReqRes {
+        dispatch!(self, c => c.set_object_acl(bucket, object_id, acl).await)
+    }
+
+    async fn append(&self, bucket: String, object_id: String, data: Bytes) -> EmptyReqRes {
+        dispatch!(self, c => c.append(bucket, object_id, data).await)
+    }
+
+    async fn upload_public(&self, bucket: String, object_id: String, data: Bytes, content_type: Option<String>, cache_control: String) -> EmptyReqRes {
+        dispatch!(self, c => c.upload_public(bucket, object_id, data, content_type, cache_control).await)
+    }
+
+    fn public_url(&self, bucket: &str, object_id: &str) -> String {
+        dispatch!(self, c => c.public_url(bucket, object_id))
+    }
+
+    async fn list_buckets(&self, max_results: Option<u32>, page_token: Option<Cursor>) -> ReqRes<ListPage<UnifiedBucket>> {
+        dispatch!(self, c => c.list_buckets(max_results, page_token).await.map(|page| ListPage {
+            items: page.items.iter().map(UnifiedBucket::of).collect(),
+            prefixes: page.prefixes,
+            next_token: page.next_token
+        }))
+    }
+
+    async fn get_bucket(&self, bucket_name: String) -> ReqRes<UnifiedBucket> {
+        dispatch!(self, c => c.get_bucket(bucket_name).await.map(|t| UnifiedBucket::of(&t)))
+    }
+
+    async fn get_object(&self, bucket_name: String, object_name: String) -> ReqRes<UnifiedObject> {
+        dispatch!(self, c => c.get_object(bucket_name, object_name).await.map(|t| UnifiedObject::of(&t)))
+    }
+
+    async fn stat_object(&self, bucket_name: String, object_name: String) -> ReqRes<UnifiedObject> {
+        dispatch!(self, c => c.stat_object(bucket_name, object_name).await.map(|t| UnifiedObject::of(&t)))
+    }
+
+    async fn list_objects(&self, bucket_name: String, max_results: Option<u32>, page_token: Option<Cursor>) -> ReqRes<ListPage<UnifiedObject>> {
+        dispatch!(self, c => c.list_objects(bucket_name, max_results, page_token).await.map(objects))
+    }
+
+    async fn list_prefix(&self, bucket_name: String, prefix: String, page_token: Option<Cursor>) -> ReqRes<ListPage<UnifiedObject>> {
+        dispatch!(self, c => c.list_prefix(bucket_name, prefix, page_token).await.map(objects))
+    }
+
+    async fn list_projected(&self, bucket_name: String, prefix: String, attributes: ListAttributes, page_token: Option<Cursor>) -> ReqRes<ListPage<UnifiedObject>> {
+        dispatch!(self, c => c.list_projected(bucket_name, prefix, attributes, page_token).await.map(objects))
+    }
+
+    async fn list_delimited(&self, bucket_name: String, prefix: String, delimiter: String, page_token: Option<Cursor>) -> ReqRes<ListPage<UnifiedObject>> {
+        dispatch!(self, c => c.list_delimited(bucket_name, prefix, delimiter, page_token).await.map(objects))
+    }
+
+    async fn list_after(&self, bucket_name: String, prefix: String, start_after: String, max_results: u32) -> ReqRes<Vec<UnifiedObject>> {
+        dispatch!(self, c => c.list_after(bucket_name, prefix, start_after, max_results).await.map(|items| items.iter().map(UnifiedObject::of).collect()))
+    }
+
+    async fn list_versions(&self, bucket_name: String, prefix: String, page_token: Option<Cursor>) -> ReqRes<ListPage<ObjectVersion>> {
+        dispatch!(self, c => c.list_versions(bucket_name, prefix, page_token).await)
+    }
+
+    async fn restore_object_version(&self, bucket_name: String, object_name: String, version: String) -> ReqRes<UnifiedObject> {
+        dispatch!(self, c => c.restore_object_version(bucket_name, object_name, version).await.map(|t| UnifiedObject::of(&t)))
+    }
+
+    async fn remove_objects(&self, bucket_name: String, keys: Vec<String>) -> ReqRes<Vec<(String, EmptyReqRes)>> {
+        dispatch!(self, c => c.remove_objects(bucket_name, keys).await)
+    }
+
+    async fn bucket_exists(&self, bucket_name: String) -> ReqRes<bool> {
+        dispatch!(self, c => c.bucket_exists(bucket_name).await)
+    }
+
+    async fn compliance_records(&self, bucket_name: String, page_token: Option<Cursor>) -> ReqRes<ListPage<ComplianceRecord>> {
+        dispatch!(self, c => c.compliance_records(bucket_name, page_token).await)
+    }
+
+    async fn get_bucket_spec(&self, bucket_name: String) -> ReqRes<BucketSpec> {
+        dispatch!(self, c => c.get_bucket_spec(bucket_name).await)
+    }
+
+    async fn get_lifecycle_rules(&self, bucket_name: String) -> ReqRes<Vec<LifecycleRule>> {
+        dispatch!(self, c => c.get_lifecycle_rules(bucket_name).await)
+    }
+
+    async fn set_lifecycle_rules(&self, bucket_name: String, rules: Vec<LifecycleRule>) -> EmptyReqRes {
+        dispatch!(self, c => c.set_lifecycle_rules(bucket_name, rules).await)
+    }
+
+    async fn get_object_lock(&self, bucket_name: String, object_name: String) -> ReqRes<ObjectLock> {
+        dispatch!(self, c => c.get_object_lock(bucket_name, object_name).await)
+    }
+
+    async fn set_object_retention(&self, bucket_name: String, object_name: String, retention: Option<Retention>) -> EmptyReqRes {
+        dispatch!(self, c => c.set_object_retention(bucket_name, object_name, retention).await)
+    }
+
+    async fn set_legal_hold(&self, bucket_name: String, object_name: String, on: bool) -> EmptyReqRes {
+        dispatch!(self, c => c.set_legal_hold(bucket_name, object_name, on).await)
+    }
+
+    async fn get_bucket_labels(&self, bucket_name: String) -> ReqRes<HashMap<String, String>> {
+        dispatch!(self, c => c.get_bucket_labels(bucket_name).await)
+    }
+
+    async fn set_bucket_labels(&self, bucket_name: String, labels: HashMap<String, String>) -> EmptyReqRes {
+        dispatch!(self, c => c.set_bucket_labels(bucket_name, labels).await)
+    }
+
+    async fn get_bucket_policy(&self, bucket_name: String) -> ReqRes<Option<BucketPolicy>> {
+        dispatch!(self, c => c.get_bucket_policy(bucket_name).await)
+    }
+
+    async fn set_bucket_policy(&self, bucket_name: String, policy: BucketPolicy) -> EmptyReqRes {
+        dispatch!(self, c => c.set_bucket_policy(bucket_name, policy).await)
+    }
+
+    fn bucket_name_problems(&self, bucket_name: &str) -> Vec<BucketNameProblem> {
+        dispatch!(self, c => c.bucket_name_problems(bucket_name))
+    }
+}
@@ -0,0 +1,44 @@
+use serde::Deserialize;
+use crate::{ClientError, ReqRes};
+
+/// Location parsed from a storage URI (example: 's3://bucket/path/to/key')
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StorageUri {
+    /// Lowercase scheme (example: 's3', 'gs', 'file')
+    pub scheme: String,
+    /// Empty for 'file://' URIs
+    pub bucket: String,
+    /// Key of the object or prefix, without the leading '/' (may be empty)
+    pub key: String,
+}
+
+impl StorageUri {
+    /// Fails with `ClientError::InvalidUri` if the scheme is missing or a bucket URI has no bucket
+    pub fn parse(uri: &str) -> ReqRes<Self> {
+        let invalid = || ClientError::InvalidUri(uri.to_string());
+        let (scheme, rest) = uri.split_once("://").ok_or_else(invalid)?;
+        if scheme.is_empty() || !scheme.chars().all(|t| t.is_ascii_alphanumeric() || matches!(t, '+' | '-' | '.')) {
+            return Err(invalid())
+        }
+        let scheme = scheme.to_ascii_lowercase();
+        let (bucket, key) = rest.split_once('/').unwrap_or((rest, ""));
+        if scheme != "file" && bucket.is_empty() {
+            return Err(invalid())
+        }
+        Ok(Self { scheme, bucket: bucket.to_string(), key: key.to_string() })
+    }
+}
+
+/// How a client built from a URI or `StorageConfig` authenticates (see `UnifiedClient::from_uri`)
+/// In configuration files: 'standard', 'anonymous' or `{ file = "path" }`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Credentials {
+    /// Default chain of the provider (application default credentials on Google-Cloud; environment, profile, IMDS on AWS-S3)
+    #[default]
+    Standard,
+    /// Unsigned requests, for public buckets
+    Anonymous,
+    /// Path of a Google-Cloud service account key file or an AWS-S3 shared credentials file
+    File(String),
+}
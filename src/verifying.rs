@@ -0,0 +1,217 @@
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::SystemTime;
+use bytes::Bytes;
+use tokio::io::AsyncWrite;
+use crate::{transfer, ClientError, ClientInterface, ClientObject, ErrorContext, ReqRes};
+
+/// Number of bytes compared at the start and end of an object by `VerifyingReadClient::check`
+pub const SAMPLE_SIZE: u64 = 64 * 1024;
+
+/// Result of comparing an object on two replicas
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReplicaStatus {
+    /// Both replicas hold the same data
+    Consistent,
+    MissingOnPrimary,
+    MissingOnSecondary,
+    /// Both replicas hold the object, but with different sizes
+    SizeDiffers { primary: u64, secondary: u64 },
+    /// Both replicas hold the object, but with different content
+    ContentDiffers,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Replica {
+    Primary,
+    Secondary,
+}
+
+/// Describes a repair done by `VerifyingReadClient::repair`
+#[derive(Debug, Clone, PartialEq)]
+pub struct RepairRecord {
+    pub bucket: String,
+    pub object: String,
+    /// State of the replicas before the repair
+    pub status: ReplicaStatus,
+    /// Replica which was overwritten
+    pub repaired: Replica,
+    /// Number of bytes copied
+    pub bytes: u64,
+    pub at: SystemTime,
+}
+
+/// Receives every repair done by a VerifyingReadClient
+pub type AuditLog = Box<dyn Fn(&RepairRecord) + Send + Sync>;
+
+/// Reads objects from two replicas (which may use different providers) and compares them
+/// Meant for critical reads of data which is deliberately stored twice to detect corruption
+pub struct VerifyingReadClient<P: ClientInterface, S: ClientInterface> {
+    primary: P,
+    secondary: S,
+    audit: Option<AuditLog>,
+}
+
+/// Computes the CRC32C of everything written to it, so objects can be checksummed without buffering them
+#[derive(Default)]
+struct Crc32cWriter {
+    crc: u32,
+}
+
+impl AsyncWrite for Crc32cWriter {
+    fn poll_write(mut self: Pin<&mut Self>, _: &mut Context<'_>, buf: &[u8]) -> Poll<std::io::Result<usize>> {
+        self.crc = crc32c::crc32c_append(self.crc, buf);
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// CRC32C of an object, computed while streaming it
+async fn streamed_crc32c(client: &impl ClientInterface, bucket: String, object: String) -> ReqRes<u32> {
+    let mut writer = Crc32cWriter::default();
+    client.download_to_writer(bucket, object, None, None, &mut writer).await?;
+    Ok(writer.crc)
+}
+
+/// Provider checksum which can be compared with one of another object (composite checksums of multipart uploads cannot)
+fn comparable_checksum(object: &impl ClientObject) -> Option<String> {
+    object.checksum().filter(|t| !t.contains('-'))
+}
+
+/// Converts a not-found error into None
+fn found<T>(result: ReqRes<T>) -> ReqRes<Option<T>> {
+    match result {
+        Ok(t) => Ok(Some(t)),
+        Err(e) if e.is_not_found() => Ok(None),
+        Err(e) => Err(e)
+    }
+}
+
+impl<P: ClientInterface, S: ClientInterface> VerifyingReadClient<P, S> {
+    pub fn new(primary: P, secondary: S) -> Self {
+        Self { primary, secondary, audit: None }
+    }
+
+    /// Sets the callback every repair is recorded with
+    pub fn with_audit_log(mut self, audit: impl Fn(&RepairRecord) + Send + Sync + 'static) -> Self {
+        self.audit = Some(Box::new(audit));
+        self
+    }
+
+    pub fn primary(&self) -> &P {
+        &self.primary
+    }
+
+    pub fn secondary(&self) -> &S {
+        &self.secondary
+    }
+
+    /// Downloads an object from both replicas and only returns it if they are identical
+    /// Fails with `ClientError::ReplicaDivergence` otherwise
+    pub async fn read(&self, bucket: String, object: String) -> ReqRes<Bytes> {
+        let (primary, secondary) = futures::join!(
+            self.primary.static_download_bytes(bucket.clone(), object.clone(), None, None),
+            self.secondary.static_download_bytes(bucket.clone(), object.clone(), None, None)
+        );
+        let status = match (found(primary)?, found(secondary)?) {
+            (Some(primary), Some(secondary)) if primary == secondary => return Ok(primary),
+            (Some(primary), Some(secondary)) if primary.len() != secondary.len() =>
+                ReplicaStatus::SizeDiffers { primary: primary.len() as u64, secondary: secondary.len() as u64 },
+            (Some(_), Some(_)) => ReplicaStatus::ContentDiffers,
+            (None, _) => ReplicaStatus::MissingOnPrimary,
+            (_, None) => ReplicaStatus::MissingOnSecondary,
+        };
+        Err(ClientError::ReplicaDivergence(status).with_context(ErrorContext::new("verified_read", Some(&bucket), Some(&object))))
+    }
+
+    /// Cheaply compares an object on both replicas without downloading it completely:
+    /// the sizes and the first and last `SAMPLE_SIZE` bytes have to match (see `verify` for a full comparison)
+    pub async fn check(&self, bucket: String, object: String) -> ReqRes<ReplicaStatus> {
+        let (primary, secondary) = futures::join!(
+            self.primary.stat_object(bucket.clone(), object.clone()),
+            self.secondary.stat_object(bucket.clone(), object.clone())
+        );
+        let (primary, secondary) = match (found(primary)?, found(secondary)?) {
+            (Some(primary), Some(secondary)) => (primary.size(), secondary.size()),
+            (None, _) => return Ok(ReplicaStatus::MissingOnPrimary),
+            (_, None) => return Ok(ReplicaStatus::MissingOnSecondary),
+        };
+        if primary != secondary {
+            return Ok(ReplicaStatus::SizeDiffers { primary, secondary })
+        }
+        if primary == 0 {
+            return Ok(ReplicaStatus::Consistent)
+        }
+        let sample = SAMPLE_SIZE.min(primary);
+        for (starting, ending) in [(Some(0), Some(sample - 1)), (None, Some(sample))] {
+            let (a, b) = futures::join!(
+                self.primary.static_download_bytes(bucket.clone(), object.clone(), starting, ending),
+                self.secondary.static_download_bytes(bucket.clone(), object.clone(), starting, ending)
+            );
+            if a? != b? {
+                return Ok(ReplicaStatus::ContentDiffers)
+            }
+        }
+        Ok(ReplicaStatus::Consistent)
+    }
+
+    /// Compares the full content of an object on both replicas
+    /// The checksums reported by the providers are compared if both have one of the same algorithm,
+    /// otherwise both replicas are streamed and their CRC32C is computed (nothing is buffered)
+    pub async fn verify(&self, bucket: String, object: String) -> ReqRes<ReplicaStatus> {
+        let (primary, secondary) = futures::join!(
+            self.primary.stat_object(bucket.clone(), object.clone()),
+            self.secondary.stat_object(bucket.clone(), object.clone())
+        );
+        let (primary, secondary) = match (found(primary)?, found(secondary)?) {
+            (Some(primary), Some(secondary)) => (primary, secondary),
+            (None, _) => return Ok(ReplicaStatus::MissingOnPrimary),
+            (_, None) => return Ok(ReplicaStatus::MissingOnSecondary),
+        };
+        if primary.size() != secondary.size() {
+            return Ok(ReplicaStatus::SizeDiffers { primary: primary.size(), secondary: secondary.size() })
+        }
+        let equal = match (comparable_checksum(&primary), comparable_checksum(&secondary)) {
+            (Some(a), Some(b)) if a.split_once(':').map(|t| t.0) == b.split_once(':').map(|t| t.0) => a == b,
+            _ => {
+                let (a, b) = futures::join!(
+                    streamed_crc32c(&self.primary, bucket.clone(), object.clone()),
+                    streamed_crc32c(&self.secondary, bucket.clone(), object.clone())
+                );
+                a? == b?
+            }
+        };
+        Ok(if equal { ReplicaStatus::Consistent } else { ReplicaStatus::ContentDiffers })
+    }
+
+    /// Overwrites a missing or diverged replica of an object with the healthy one (streamed, see `transfer`)
+    /// If both replicas hold the object but differ, the primary is considered healthy
+    /// The replicas are compared with `verify` before and after the repair, which is recorded in the audit log.
+    /// None is returned if nothing had to be repaired
+    pub async fn repair(&self, bucket: String, object: String) -> ReqRes<Option<RepairRecord>> {
+        let status = self.verify(bucket.clone(), object.clone()).await?;
+        let (repaired, bytes) = match status {
+            ReplicaStatus::Consistent => return Ok(None),
+            ReplicaStatus::MissingOnPrimary => {
+                (Replica::Primary, transfer(&self.secondary, bucket.clone(), object.clone(), &self.primary, bucket.clone(), object.clone()).await?)
+            }
+            _ => (Replica::Secondary, transfer(&self.primary, bucket.clone(), object.clone(), &self.secondary, bucket.clone(), object.clone()).await?)
+        };
+        let after = self.verify(bucket.clone(), object.clone()).await?;
+        if after != ReplicaStatus::Consistent {
+            return Err(ClientError::ReplicaDivergence(after).with_context(ErrorContext::new("repair", Some(&bucket), Some(&object))))
+        }
+        let record = RepairRecord { bucket, object, status, repaired, bytes, at: SystemTime::now() };
+        if let Some(audit) = &self.audit {
+            audit(&record);
+        }
+        Ok(Some(record))
+    }
+}
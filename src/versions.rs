@@ -0,0 +1,93 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::time::SystemTime;
+use crate::ClientObject;
+
+/// A single version (S3 version / GCS generation) of an object
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjectVersion {
+    pub bucket: String,
+    pub name: String,
+    /// Version ID (AWS-S3) or generation (GCS)
+    pub version: String,
+    pub size: u64,
+    /// When this version was written
+    pub created: SystemTime,
+    /// When this version stopped being the live one (if known)
+    pub superseded: Option<SystemTime>,
+    /// Whether this version is a delete marker (AWS-S3 only)
+    pub delete_marker: bool,
+}
+
+impl ClientObject for ObjectVersion {
+    fn size(&self) -> u64 {
+        self.size
+    }
+
+    fn bucket_name(&self) -> String {
+        self.bucket.clone()
+    }
+
+    fn id(&self) -> String {
+        self.version.clone()
+    }
+
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn content_type(&self) -> Option<String> {
+        None
+    }
+
+    fn last_modified(&self) -> Option<SystemTime> {
+        Some(self.created)
+    }
+
+    fn etag(&self) -> Option<String> {
+        None
+    }
+
+    fn storage_class(&self) -> Option<String> {
+        None
+    }
+
+    fn custom_metadata(&self) -> HashMap<String, String> {
+        HashMap::new()
+    }
+
+    fn checksum(&self) -> Option<String> {
+        None
+    }
+
+    /// Versions are not live objects, so they cannot be compared against
+    fn version(&self) -> Option<String> {
+        None
+    }
+}
+
+/// Picks the versions which were live at `timestamp`
+/// Missing `superseded` times are derived from the next newer version of the same object
+pub(crate) fn live_at(versions: Vec<ObjectVersion>, timestamp: SystemTime) -> Vec<ObjectVersion> {
+    let mut by_name: HashMap<String, Vec<ObjectVersion>> = HashMap::new();
+    for version in versions {
+        by_name.entry(version.name.clone()).or_default().push(version);
+    }
+    let mut live = vec![];
+    for (_, mut versions) in by_name {
+        versions.sort_by_key(|t| Reverse(t.created));
+        let mut newer: Option<SystemTime> = None;
+        for mut version in versions {
+            if version.superseded.is_none() {
+                version.superseded = newer;
+            }
+            newer = Some(version.created);
+            let alive = version.created <= timestamp && version.superseded.is_none_or(|t| t > timestamp);
+            if alive && !version.delete_marker {
+                live.push(version);
+            }
+        }
+    }
+    live.sort_by(|a, b| a.name.cmp(&b.name));
+    live
+}
@@ -0,0 +1,106 @@
+use std::time::{Duration, SystemTime};
+use futures::StreamExt;
+use crate::{ClientError, ClientInterface, ClientObject, ListOptions, ReqRes};
+
+/// A condition a Watchdog checks periodically
+#[derive(Debug, Clone, PartialEq)]
+pub enum WatchRule {
+    /// The newest object below `prefix` must not be older than `max_age`
+    Freshness { bucket: String, prefix: String, max_age: Duration },
+    /// All objects below `prefix` together must not be larger than `max_bytes`
+    MaxSize { bucket: String, prefix: String, max_bytes: u64 },
+    /// There must not be more than `max_objects` objects below `prefix`
+    MaxCount { bucket: String, prefix: String, max_objects: u64 },
+}
+
+/// A rule which does not hold
+#[derive(Debug, Clone, PartialEq)]
+pub enum WatchViolation {
+    /// The newest object (None if there is none) is too old
+    Stale { rule: WatchRule, newest: Option<SystemTime> },
+    TooLarge { rule: WatchRule, size: u64 },
+    TooMany { rule: WatchRule, count: u64 },
+}
+
+/// Passed to the callback of `Watchdog::run`
+pub enum WatchAlert {
+    Violation(WatchViolation),
+    /// A rule could not be checked
+    Failed { rule: WatchRule, error: ClientError },
+}
+
+/// Periodically checks rules against listings, for monitoring pipelines which write to a bucket
+pub struct Watchdog {
+    rules: Vec<WatchRule>,
+    interval: Duration,
+}
+
+impl Watchdog {
+    pub fn new(interval: Duration) -> Self {
+        Self { rules: vec![], interval }
+    }
+
+    pub fn rule(mut self, rule: WatchRule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// Checks a single rule, None if it holds
+    /// Freshness uses the creation times of `list_versions`, size and count rules list all objects below the prefix
+    pub async fn check_rule(&self, client: &impl ClientInterface, rule: &WatchRule) -> ReqRes<Option<WatchViolation>> {
+        match rule {
+            WatchRule::Freshness { bucket, prefix, max_age } => {
+                let mut newest: Option<SystemTime> = None;
+                let mut page_token = None;
+                loop {
+                    let page = client.list_versions(bucket.clone(), prefix.clone(), page_token).await?;
+                    newest = page.items.iter().filter(|t| !t.delete_marker).map(|t| t.created).chain(newest).max();
+                    page_token = page.next_token;
+                    if page_token.is_none() {
+                        break
+                    }
+                }
+                let fresh = newest.is_some_and(|t| t.elapsed().unwrap_or_default() <= *max_age);
+                Ok((!fresh).then(|| WatchViolation::Stale { rule: rule.clone(), newest }))
+            }
+            WatchRule::MaxSize { bucket, prefix, .. } | WatchRule::MaxCount { bucket, prefix, .. } => {
+                let options = ListOptions { prefix: Some(prefix.clone()), ..Default::default() };
+                let mut objects = std::pin::pin!(client.list_objects_stream(bucket.clone(), options));
+                let (mut size, mut count) = (0, 0);
+                while let Some(object) = objects.next().await {
+                    size += object?.size();
+                    count += 1;
+                }
+                Ok(match rule {
+                    WatchRule::MaxSize { max_bytes, .. } if size > *max_bytes => Some(WatchViolation::TooLarge { rule: rule.clone(), size }),
+                    WatchRule::MaxCount { max_objects, .. } if count > *max_objects => Some(WatchViolation::TooMany { rule: rule.clone(), count }),
+                    _ => None
+                })
+            }
+        }
+    }
+
+    /// Checks all rules once
+    pub async fn check(&self, client: &impl ClientInterface) -> Vec<WatchAlert> {
+        let mut alerts = vec![];
+        for rule in &self.rules {
+            match self.check_rule(client, rule).await {
+                Ok(Some(violation)) => alerts.push(WatchAlert::Violation(violation)),
+                Ok(None) => {}
+                Err(error) => alerts.push(WatchAlert::Failed { rule: rule.clone(), error })
+            }
+        }
+        alerts
+    }
+
+    /// Checks all rules every interval (forever) and passes every violation and failed check to `on_alert`
+    /// Webhooks can be called from the callback
+    pub async fn run(&self, client: &impl ClientInterface, mut on_alert: impl FnMut(WatchAlert)) {
+        loop {
+            for alert in self.check(client).await {
+                on_alert(alert);
+            }
+            tokio::time::sleep(self.interval).await;
+        }
+    }
+}